@@ -0,0 +1,10 @@
+-- Plausibility validation: flagged workouts are stored but held for admin review instead of scoring
+ALTER TABLE workout_data
+ADD COLUMN review_status VARCHAR(20) NOT NULL DEFAULT 'accepted'
+    CHECK (review_status IN ('accepted', 'pending_review', 'rejected')),
+ADD COLUMN validation_flags JSONB,
+ADD COLUMN reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+ADD COLUMN reviewed_at TIMESTAMPTZ;
+
+-- Admin review queue lookups
+CREATE INDEX idx_workout_data_pending_review ON workout_data(created_at) WHERE review_status = 'pending_review';
@@ -0,0 +1,28 @@
+-- Durable queue for workout uploads: the upload endpoint enqueues, a background worker processes with retries
+CREATE TABLE workout_processing_jobs (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    workout_uuid VARCHAR(255) NOT NULL,
+    -- The original upload request, cleared once the workout is stored
+    payload JSONB,
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'processing', 'completed', 'rejected', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 5,
+    run_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_at TIMESTAMPTZ,
+    last_error TEXT,
+    -- Set in the same transaction that stores the workout, so a retry never processes it twice
+    workout_data_id UUID REFERENCES workout_data(id) ON DELETE SET NULL,
+    result JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- Worker polling
+CREATE INDEX idx_workout_processing_jobs_runnable ON workout_processing_jobs(run_at) WHERE status IN ('queued', 'processing');
+CREATE INDEX idx_workout_processing_jobs_user_id ON workout_processing_jobs(user_id);
+
+-- A workout can only be in flight once
+CREATE UNIQUE INDEX idx_workout_processing_jobs_active_uuid ON workout_processing_jobs(workout_uuid) WHERE status IN ('queued', 'processing');
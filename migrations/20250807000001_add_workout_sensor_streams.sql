@@ -0,0 +1,21 @@
+-- Power, cadence, speed, distance and step streams recorded alongside heart rate, with
+-- summaries precomputed like the heart rate columns. A long ride records a sample a second per
+-- stream, so the samples get their own time-series table rather than JSON on the workout row,
+-- and summary queries never load them. Streams sampled at the same moment share a row.
+CREATE TABLE workout_sensor_samples (
+    workout_data_id UUID NOT NULL REFERENCES workout_data(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    power_watts INTEGER,
+    cadence INTEGER,
+    speed_mps DOUBLE PRECISION,
+    distance_meters DOUBLE PRECISION,
+    steps INTEGER,
+    PRIMARY KEY (workout_data_id, recorded_at)
+);
+
+ALTER TABLE workout_data
+ADD COLUMN avg_power INTEGER,
+ADD COLUMN max_power INTEGER,
+ADD COLUMN avg_cadence INTEGER,
+ADD COLUMN total_distance_meters DOUBLE PRECISION,
+ADD COLUMN total_steps INTEGER;
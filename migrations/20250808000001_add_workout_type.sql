@@ -0,0 +1,3 @@
+-- Activity type chosen on upload, drives the per-type scoring profile
+ALTER TABLE workout_data
+ADD COLUMN workout_type VARCHAR(20);
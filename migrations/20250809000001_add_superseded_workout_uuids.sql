@@ -0,0 +1,7 @@
+-- Recordings of the same activity from other devices that were merged into or replaced by this workout,
+-- so re-uploading them is detected as a duplicate
+ALTER TABLE workout_data
+ADD COLUMN superseded_workout_uuids TEXT[] NOT NULL DEFAULT '{}';
+
+-- Overlap lookups against a user's workouts in a time window
+CREATE INDEX idx_workout_data_user_window ON workout_data(user_id, workout_start, workout_end);
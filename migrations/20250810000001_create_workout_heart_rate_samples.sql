@@ -0,0 +1,77 @@
+-- Heart rate samples move out of the workout_data row into their own time-series table,
+-- so summary queries never load the raw series
+CREATE TABLE workout_heart_rate_samples (
+    workout_data_id UUID NOT NULL REFERENCES workout_data(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    heart_rate SMALLINT NOT NULL,
+    PRIMARY KEY (workout_data_id, recorded_at)
+);
+
+ALTER TABLE workout_data
+ADD COLUMN heart_rate_sample_count INTEGER NOT NULL DEFAULT 0;
+
+-- Casts that return NULL instead of aborting the migration on a malformed sample
+CREATE FUNCTION pg_temp.try_timestamptz(value TEXT) RETURNS TIMESTAMPTZ AS $$
+BEGIN
+    RETURN value::timestamptz;
+EXCEPTION WHEN others THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION pg_temp.try_heart_rate(value TEXT) RETURNS SMALLINT AS $$
+BEGIN
+    RETURN round(value::numeric)::smallint;
+EXCEPTION WHEN others THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Older uploads stored samples as {"hr": ...} instead of {"heart_rate": ...}
+CREATE TEMP TABLE backfill_samples AS
+SELECT wd.id AS workout_data_id,
+       pg_temp.try_timestamptz(sample->>'timestamp') AS recorded_at,
+       pg_temp.try_heart_rate(COALESCE(sample->>'heart_rate', sample->>'hr')) AS heart_rate
+FROM workout_data wd,
+     jsonb_array_elements(
+         CASE WHEN jsonb_typeof(wd.heart_rate_data) = 'array' THEN wd.heart_rate_data ELSE '[]'::jsonb END
+     ) AS sample
+WHERE jsonb_typeof(sample) = 'object';
+
+-- Samples that don't parse are skipped and counted rather than failing the whole backfill
+DO $$
+DECLARE
+    skipped BIGINT;
+BEGIN
+    SELECT COUNT(*) INTO skipped FROM backfill_samples WHERE recorded_at IS NULL OR heart_rate IS NULL;
+    IF skipped > 0 THEN
+        RAISE WARNING 'Skipped % heart rate samples that could not be parsed', skipped;
+    END IF;
+END;
+$$;
+
+INSERT INTO workout_heart_rate_samples (workout_data_id, recorded_at, heart_rate)
+SELECT workout_data_id, recorded_at, heart_rate
+FROM backfill_samples
+WHERE recorded_at IS NOT NULL
+AND heart_rate IS NOT NULL
+ON CONFLICT (workout_data_id, recorded_at) DO NOTHING;
+
+DROP TABLE backfill_samples;
+
+-- Summary columns are filled for every workout with samples, so nothing falls back to the raw series
+UPDATE workout_data wd
+SET heart_rate_sample_count = summary.sample_count,
+    avg_heart_rate = COALESCE(wd.avg_heart_rate, summary.avg_heart_rate),
+    max_heart_rate = COALESCE(wd.max_heart_rate, summary.max_heart_rate),
+    min_heart_rate = COALESCE(wd.min_heart_rate, summary.min_heart_rate)
+FROM (
+    SELECT workout_data_id,
+           COUNT(*)::int AS sample_count,
+           AVG(heart_rate)::int AS avg_heart_rate,
+           MAX(heart_rate)::int AS max_heart_rate,
+           MIN(heart_rate)::int AS min_heart_rate
+    FROM workout_heart_rate_samples
+    GROUP BY workout_data_id
+) AS summary
+WHERE wd.id = summary.workout_data_id;
@@ -0,0 +1,34 @@
+-- Soft caps on stat gains, a single row admins can tune without a redeploy
+CREATE TABLE stat_gain_caps (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    -- Minutes per zone and day scored at the full rate
+    zone1_daily_minutes REAL NOT NULL DEFAULT 120,
+    zone2_daily_minutes REAL NOT NULL DEFAULT 120,
+    zone3_daily_minutes REAL NOT NULL DEFAULT 90,
+    zone4_daily_minutes REAL NOT NULL DEFAULT 45,
+    zone5_daily_minutes REAL NOT NULL DEFAULT 20,
+    -- Share of each zone minute past its threshold that still scores
+    zone_diminished_rate REAL NOT NULL DEFAULT 0.5,
+    -- Stamina plus strength points per day and per week scored at the full rate
+    daily_soft_cap INTEGER NOT NULL DEFAULT 800,
+    weekly_soft_cap INTEGER NOT NULL DEFAULT 4000,
+    -- Share of points past a daily or weekly soft cap that still scores
+    over_cap_rate REAL NOT NULL DEFAULT 0.25,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO stat_gain_caps DEFAULT VALUES;
+
+-- What each user has been credited per day, the state the caps are applied against
+CREATE TABLE user_daily_stat_gains (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    zone1_minutes REAL NOT NULL DEFAULT 0,
+    zone2_minutes REAL NOT NULL DEFAULT 0,
+    zone3_minutes REAL NOT NULL DEFAULT 0,
+    zone4_minutes REAL NOT NULL DEFAULT 0,
+    zone5_minutes REAL NOT NULL DEFAULT 0,
+    stamina_gained INTEGER NOT NULL DEFAULT 0,
+    strength_gained INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, day)
+);
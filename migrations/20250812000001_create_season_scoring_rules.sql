@@ -0,0 +1,22 @@
+-- Scoring rules per season, so admins can tune scoring without a redeploy.
+-- Seasons without a row score with the built-in defaults.
+CREATE TABLE season_scoring_rules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    season_id UUID NOT NULL UNIQUE REFERENCES league_seasons(id) ON DELETE CASCADE,
+    -- Points per minute in each heart rate zone
+    zone1_stamina_per_min REAL NOT NULL DEFAULT 2,
+    zone2_stamina_per_min REAL NOT NULL DEFAULT 5,
+    zone3_stamina_per_min REAL NOT NULL DEFAULT 4,
+    zone4_stamina_per_min REAL NOT NULL DEFAULT 2,
+    zone5_stamina_per_min REAL NOT NULL DEFAULT 1,
+    zone1_strength_per_min REAL NOT NULL DEFAULT 0,
+    zone2_strength_per_min REAL NOT NULL DEFAULT 1,
+    zone3_strength_per_min REAL NOT NULL DEFAULT 3,
+    zone4_strength_per_min REAL NOT NULL DEFAULT 5,
+    zone5_strength_per_min REAL NOT NULL DEFAULT 8,
+    -- Live game score per stamina and strength point gained
+    stamina_score_weight REAL NOT NULL DEFAULT 1,
+    strength_score_weight REAL NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
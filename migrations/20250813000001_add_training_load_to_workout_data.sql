@@ -0,0 +1,7 @@
+-- Training load per workout, NULL for workouts without heart rate data or not scored yet
+ALTER TABLE workout_data
+    ADD COLUMN banister_trimp REAL,
+    ADD COLUMN edwards_trimp REAL,
+    ADD COLUMN intensity_factor REAL,
+    ADD COLUMN epoc_estimate REAL;
+
@@ -0,0 +1,22 @@
+-- Admin jobs that replay every stored workout with the current scoring.
+-- A dry run only reports per-user differences, applying one rewrites the stored stats.
+CREATE TABLE stat_recompute_jobs (
+    id UUID PRIMARY KEY,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    mode VARCHAR(10) NOT NULL CHECK (mode IN ('dry_run', 'apply')),
+    -- Also rescore live score events in games that were not evaluated yet
+    rebuild_live_games BOOLEAN NOT NULL DEFAULT false,
+    -- The dry run an apply job was approved from
+    dry_run_job_id UUID REFERENCES stat_recompute_jobs(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'completed', 'failed')),
+    report JSONB,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+-- A dry run is applied at most once
+CREATE UNIQUE INDEX idx_stat_recompute_jobs_dry_run ON stat_recompute_jobs(dry_run_job_id);
+CREATE INDEX idx_stat_recompute_jobs_created_at ON stat_recompute_jobs(created_at DESC);
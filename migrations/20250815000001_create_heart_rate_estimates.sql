@@ -0,0 +1,50 @@
+-- Max and resting heart rate estimated from workout history, and the zones that were in effect over time
+ALTER TABLE user_health_profiles
+    -- Apply estimates without asking the user first
+    ADD COLUMN auto_apply_heart_rate_estimates BOOLEAN NOT NULL DEFAULT false,
+    -- Highest heart rate seen in workouts, used instead of the age formula once applied
+    ADD COLUMN observed_max_heart_rate INTEGER;
+
+CREATE TABLE heart_rate_estimates (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    resting_heart_rate INTEGER NOT NULL,
+    max_heart_rate INTEGER NOT NULL,
+    -- What the user's zones were based on when the estimate was made
+    previous_resting_heart_rate INTEGER NOT NULL,
+    previous_max_heart_rate INTEGER NOT NULL,
+    zone1_max INTEGER NOT NULL,
+    zone2_max INTEGER NOT NULL,
+    zone3_max INTEGER NOT NULL,
+    zone4_max INTEGER NOT NULL,
+    zone5_max INTEGER NOT NULL,
+    workouts_considered INTEGER NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'proposed'
+        CHECK (status IN ('proposed', 'applied', 'dismissed', 'superseded')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMPTZ
+);
+
+-- At most one open proposal per user
+CREATE UNIQUE INDEX idx_heart_rate_estimates_proposed ON heart_rate_estimates(user_id) WHERE status = 'proposed';
+CREATE INDEX idx_heart_rate_estimates_user_id ON heart_rate_estimates(user_id, created_at DESC);
+
+CREATE TABLE heart_rate_zone_history (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    resting_heart_rate INTEGER NOT NULL,
+    max_heart_rate INTEGER NOT NULL,
+    zone1_max INTEGER NOT NULL,
+    zone2_max INTEGER NOT NULL,
+    zone3_max INTEGER NOT NULL,
+    zone4_max INTEGER NOT NULL,
+    zone5_max INTEGER NOT NULL,
+    -- 'profile' when the user edited their health profile, 'estimate' when an estimate was applied
+    source VARCHAR(20) NOT NULL CHECK (source IN ('profile', 'estimate')),
+    estimate_id UUID REFERENCES heart_rate_estimates(id) ON DELETE SET NULL,
+    effective_from TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    -- NULL for the zones in effect now
+    effective_until TIMESTAMPTZ
+);
+
+CREATE INDEX idx_heart_rate_zone_history_user_id ON heart_rate_zone_history(user_id, effective_from DESC);
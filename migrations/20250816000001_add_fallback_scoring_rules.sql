@@ -0,0 +1,8 @@
+-- Weights for scoring workouts without heart rate data from their calories and duration.
+-- The ceiling keeps them below what the same time earns with heart rate data.
+ALTER TABLE season_scoring_rules
+    ADD COLUMN fallback_stamina_per_min REAL NOT NULL DEFAULT 1,
+    ADD COLUMN fallback_strength_per_min REAL NOT NULL DEFAULT 0.5,
+    ADD COLUMN fallback_stamina_per_kcal REAL NOT NULL DEFAULT 0.02,
+    ADD COLUMN fallback_strength_per_kcal REAL NOT NULL DEFAULT 0.01,
+    ADD COLUMN fallback_max_points_per_min REAL NOT NULL DEFAULT 3;
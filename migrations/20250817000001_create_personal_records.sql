@@ -0,0 +1,16 @@
+-- Best values a user has reached, one row per kind of record
+CREATE TABLE personal_records (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    record_type VARCHAR(40) NOT NULL CHECK (record_type IN (
+        'longest_workout', 'most_high_zone_minutes', 'highest_workout_points',
+        'longest_daily_streak', 'longest_weekly_streak'
+    )),
+    value DOUBLE PRECISION NOT NULL,
+    -- The record this one beat, NULL for the first
+    previous_value DOUBLE PRECISION,
+    -- The workout that set the record
+    workout_data_id UUID REFERENCES workout_data(id) ON DELETE SET NULL,
+    achieved_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, record_type)
+);
@@ -0,0 +1,21 @@
+-- Challenges assigned to a user for one day, evaluated against every workout of that day
+CREATE TABLE daily_challenges (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    challenge_date DATE NOT NULL,
+    -- Template the challenge was made from, title and target are copied so template changes don't alter it
+    template_key VARCHAR(40) NOT NULL,
+    title VARCHAR(100) NOT NULL,
+    description TEXT NOT NULL,
+    target DOUBLE PRECISION NOT NULL CHECK (target > 0),
+    progress DOUBLE PRECISION NOT NULL DEFAULT 0,
+    stamina_bonus INTEGER NOT NULL DEFAULT 0 CHECK (stamina_bonus >= 0),
+    strength_bonus INTEGER NOT NULL DEFAULT 0 CHECK (strength_bonus >= 0),
+    completed_at TIMESTAMPTZ,
+    -- The workout that completed the challenge
+    completed_workout_id UUID REFERENCES workout_data(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, challenge_date, template_key)
+);
+
+CREATE INDEX idx_daily_challenges_user_date ON daily_challenges(user_id, challenge_date);
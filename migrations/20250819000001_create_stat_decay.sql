@@ -0,0 +1,35 @@
+-- Stat decay for inactive users, a single row admins can tune without a redeploy
+CREATE TABLE stat_decay_settings (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Days without an accepted workout before stats start to decay
+    idle_days INTEGER NOT NULL DEFAULT 14 CHECK (idle_days >= 1),
+    -- How many days before decay starts users are warned
+    warning_days INTEGER NOT NULL DEFAULT 3 CHECK (warning_days >= 0),
+    -- Share of the stats above the floor lost per day of decay
+    daily_decay_rate REAL NOT NULL DEFAULT 0.02,
+    -- Share of the stats a user earned that never decays
+    floor_ratio REAL NOT NULL DEFAULT 0.5,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO stat_decay_settings DEFAULT VALUES;
+
+-- Every decay applied to an avatar
+CREATE TABLE stat_decay_events (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    stamina_before INTEGER NOT NULL,
+    strength_before INTEGER NOT NULL,
+    -- Negative, what the decay took
+    stamina_change INTEGER NOT NULL,
+    strength_change INTEGER NOT NULL,
+    -- Last accepted workout, or registration for users who never trained
+    last_active_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stat_decay_events_user_id ON stat_decay_events(user_id, created_at DESC);
+
+-- When the user was last told their stats are about to decay
+ALTER TABLE user_avatars ADD COLUMN decay_warned_at TIMESTAMPTZ;
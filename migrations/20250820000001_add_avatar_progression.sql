@@ -0,0 +1,34 @@
+-- Levels earned through experience: every stat point gained from workouts and challenge bonuses,
+-- never reduced by stat decay
+ALTER TABLE user_avatars
+    ADD COLUMN experience INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN level INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN evolution_stage VARCHAR(20) NOT NULL DEFAULT 'novice';
+
+UPDATE user_avatars ua
+SET experience = GREATEST(
+    COALESCE((
+        SELECT SUM(wd.stamina_gained + wd.strength_gained)
+        FROM workout_data wd
+        WHERE wd.user_id = ua.user_id AND wd.review_status = 'accepted'
+    ), 0)
+    + COALESCE((
+        SELECT SUM(dc.stamina_bonus + dc.strength_bonus)
+        FROM daily_challenges dc
+        WHERE dc.user_id = ua.user_id AND dc.completed_at IS NOT NULL
+    ), 0),
+    0
+);
+
+-- Level n needs 50 * n * (n - 1) experience, see game::progression
+UPDATE user_avatars
+SET level = FLOOR((1 + SQRT(1 + 8 * experience / 100.0)) / 2)::int;
+
+UPDATE user_avatars
+SET evolution_stage = CASE
+    WHEN level >= 35 THEN 'legend'
+    WHEN level >= 20 THEN 'elite'
+    WHEN level >= 10 THEN 'veteran'
+    WHEN level >= 5 THEN 'adept'
+    ELSE 'novice'
+END;
@@ -0,0 +1,9 @@
+-- Exercises, sets, reps, load and RPE logged for strength sessions, and their summed volume load (reps x kg)
+ALTER TABLE workout_data
+    ADD COLUMN strength_exercises JSONB,
+    ADD COLUMN strength_volume_load_kg DOUBLE PRECISION;
+
+-- Strength points per kg of volume load, and the most a single set can earn
+ALTER TABLE season_scoring_rules
+    ADD COLUMN strength_per_volume_kg REAL NOT NULL DEFAULT 0.02,
+    ADD COLUMN strength_max_points_per_set REAL NOT NULL DEFAULT 25;
@@ -0,0 +1,32 @@
+-- Long workouts are uploaded in chunks: a session stages the heart rate samples
+-- until the client finalizes it, then it's queued as a single workout
+CREATE TABLE workout_upload_sessions (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    workout_uuid VARCHAR(255) NOT NULL,
+    -- The upload request without its heart rate samples, which are staged row by row
+    metadata JSONB NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'finalized')),
+    sample_count INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finalized_at TIMESTAMPTZ
+);
+
+-- A workout can only be uploaded through one open session at a time
+CREATE UNIQUE INDEX idx_workout_upload_sessions_open_uuid ON workout_upload_sessions(user_id, workout_uuid) WHERE status = 'open';
+-- Cleanup of abandoned sessions
+CREATE INDEX idx_workout_upload_sessions_updated_at ON workout_upload_sessions(updated_at);
+
+-- Resent chunks don't duplicate samples, a timestamp keeps its first reading
+CREATE TABLE workout_upload_session_samples (
+    session_id UUID NOT NULL REFERENCES workout_upload_sessions(id) ON DELETE CASCADE,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    heart_rate SMALLINT NOT NULL,
+    PRIMARY KEY (session_id, recorded_at)
+);
+
+-- Jobs of finalized sessions load the staged samples instead of carrying them in the payload
+ALTER TABLE workout_processing_jobs
+ADD COLUMN upload_session_id UUID REFERENCES workout_upload_sessions(id) ON DELETE SET NULL;
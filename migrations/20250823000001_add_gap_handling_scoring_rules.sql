@@ -0,0 +1,5 @@
+-- How gaps between heart rate samples are credited: the longest interval a single sample is
+-- credited with, and the longest gap interpolated between two samples instead of left unknown
+ALTER TABLE season_scoring_rules
+    ADD COLUMN max_credited_interval_sec REAL NOT NULL DEFAULT 60,
+    ADD COLUMN interpolate_up_to_sec REAL NOT NULL DEFAULT 300;
@@ -0,0 +1,4 @@
+-- The raw series moved to workout_heart_rate_samples in 20250810000001. Dropped in its own
+-- migration so the backfill's skipped-sample warnings can be checked before the JSON is gone.
+ALTER TABLE workout_data
+DROP COLUMN heart_rate_data;
@@ -0,0 +1,7 @@
+-- A dry run keeps the writes applying it makes, so an apply stores exactly what was reviewed
+ALTER TABLE stat_recompute_jobs
+ADD COLUMN plan JSONB;
+
+-- Running jobs refresh this. Jobs that stop refreshing it were interrupted and are failed.
+ALTER TABLE stat_recompute_jobs
+ADD COLUMN heartbeat_at TIMESTAMPTZ;
@@ -0,0 +1,13 @@
+-- Cumulative thresholds a user has passed, one row per milestone
+CREATE TABLE user_milestones (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    milestone_type VARCHAR(40) NOT NULL CHECK (milestone_type IN (
+        'workouts', 'training_hours', 'total_points'
+    )),
+    threshold BIGINT NOT NULL,
+    -- The workout that passed it
+    workout_data_id UUID REFERENCES workout_data(id) ON DELETE SET NULL,
+    reached_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, milestone_type, threshold)
+);
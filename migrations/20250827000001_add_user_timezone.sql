@@ -0,0 +1,2 @@
+-- IANA time zone the user's days are counted in, for daily challenges
+ALTER TABLE users ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
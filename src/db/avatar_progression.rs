@@ -0,0 +1,94 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::game::progression::DEFAULT_AVATAR_STYLE;
+use crate::models::profile::AvatarProgression;
+
+/// Avatar state right after its progression was saved
+#[derive(Debug)]
+pub struct SavedAvatarProgression {
+    pub previous_level: i32,
+    pub stamina: i32,
+    pub strength: i32,
+    pub previous_avatar_style: Option<String>,
+    pub avatar_style: Option<String>,
+}
+
+/// Experience a user has earned: every stat point from accepted workouts plus completed challenge bonuses.
+/// Stat decay doesn't take experience away.
+pub async fn get_total_experience(conn: &mut PgConnection, user_id: Uuid) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT GREATEST(
+            COALESCE((
+                SELECT SUM(wd.stamina_gained + wd.strength_gained)
+                FROM workout_data wd
+                WHERE wd.user_id = $1 AND wd.review_status = 'accepted'
+            ), 0)
+            + COALESCE((
+                SELECT SUM(dc.stamina_bonus + dc.strength_bonus)
+                FROM daily_challenges dc
+                WHERE dc.user_id = $1 AND dc.completed_at IS NOT NULL
+            ), 0),
+            0
+        )::int8 AS "experience!"
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// The stored experience of a user's avatar, `None` if they have no avatar yet
+pub async fn get_avatar_experience(pool: &PgPool, user_id: Uuid) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT experience::int8 AS "experience!" FROM user_avatars WHERE user_id = $1"#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Store the avatar's experience, level and evolution stage. A style the level no longer unlocks
+/// goes back to the default. Returns `None` if the user has no avatar.
+pub async fn save_avatar_progression(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    progression: &AvatarProgression,
+) -> Result<Option<SavedAvatarProgression>, sqlx::Error> {
+    sqlx::query_as!(
+        SavedAvatarProgression,
+        r#"
+        UPDATE user_avatars ua
+        SET experience = $1::int8, level = $2, evolution_stage = $3, updated_at = NOW(),
+            avatar_style = CASE
+                WHEN ua.avatar_style IS NULL OR ua.avatar_style = ANY($5) THEN ua.avatar_style
+                ELSE $6
+            END
+        FROM (SELECT level, avatar_style FROM user_avatars WHERE user_id = $4 FOR UPDATE) previous
+        WHERE ua.user_id = $4
+        RETURNING previous.level AS previous_level, ua.stamina, ua.strength,
+                  previous.avatar_style AS previous_avatar_style, ua.avatar_style
+        "#,
+        progression.experience,
+        progression.level,
+        progression.evolution_stage.as_str(),
+        user_id,
+        &progression.unlocked_styles,
+        DEFAULT_AVATAR_STYLE
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// Change the avatar's style. Returns `false` if the user has no avatar.
+pub async fn update_avatar_style(pool: &PgPool, user_id: Uuid, avatar_style: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE user_avatars SET avatar_style = $1, updated_at = NOW() WHERE user_id = $2",
+        avatar_style,
+        user_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,263 @@
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::workout_data::get_zone_minutes;
+use crate::game::daily_challenges::{ChallengeTemplate, DayActivity};
+use crate::models::profile::DailyChallenge;
+
+/// Where a workout falls in its owner's time zone
+#[derive(Debug, Clone, Copy)]
+pub struct WorkoutDay {
+    pub user_id: Uuid,
+    pub day: NaiveDate,
+    /// The owner's current day
+    pub today: NaiveDate,
+}
+
+/// Owner and day, in the owner's time zone, of an accepted workout. Unlike the UTC day of
+/// `workout_day` the stat caps use, this is the day the user sees their challenges for.
+pub async fn get_accepted_workout_day(
+    conn: &mut PgConnection,
+    workout_id: Uuid,
+) -> Result<Option<WorkoutDay>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT wd.user_id,
+               (COALESCE(wd.workout_start, wd.created_at) AT TIME ZONE u.timezone)::date AS "day!",
+               (NOW() AT TIME ZONE u.timezone)::date AS "today!"
+        FROM workout_data wd
+        JOIN users u ON u.id = wd.user_id
+        WHERE wd.id = $1 AND wd.review_status = 'accepted'
+        "#,
+        workout_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    Ok(row.map(|row| WorkoutDay { user_id: row.user_id, day: row.day, today: row.today }))
+}
+
+/// The user's current day in their time zone
+pub async fn get_user_today(conn: &mut PgConnection, user_id: Uuid) -> Result<NaiveDate, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT (NOW() AT TIME ZONE timezone)::date AS "today!" FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
+
+/// Users whose current day has started without challenges, with that day
+pub async fn get_users_due_challenges(pool: &PgPool) -> Result<Vec<(Uuid, NaiveDate)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT u.id, (NOW() AT TIME ZONE u.timezone)::date AS "today!"
+        FROM users u
+        WHERE NOT EXISTS (
+            SELECT 1 FROM daily_challenges dc
+            WHERE dc.user_id = u.id AND dc.challenge_date = (NOW() AT TIME ZONE u.timezone)::date
+        )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.id, row.today)).collect())
+}
+
+/// Give the user these challenges for the day, keeping any they already have
+pub async fn assign_daily_challenges(
+    pool: &PgPool,
+    user_id: Uuid,
+    day: NaiveDate,
+    templates: &[&ChallengeTemplate],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for template in templates {
+        sqlx::query!(
+            r#"
+            INSERT INTO daily_challenges (
+                id, user_id, challenge_date, template_key, title, description,
+                target, stamina_bonus, strength_bonus
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (user_id, challenge_date, template_key) DO NOTHING
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            day,
+            template.key,
+            template.title,
+            template.description,
+            template.target,
+            template.stamina_bonus,
+            template.strength_bonus
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+pub async fn get_daily_challenges(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+) -> Result<Vec<DailyChallenge>, sqlx::Error> {
+    sqlx::query_as!(
+        DailyChallenge,
+        r#"
+        SELECT id, user_id, challenge_date, template_key, title, description, target, progress,
+               stamina_bonus, strength_bonus, completed_at, completed_workout_id
+        FROM daily_challenges
+        WHERE user_id = $1 AND challenge_date = $2
+        ORDER BY template_key
+        "#,
+        user_id,
+        day
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Workouts, active minutes, calories and zone minutes of the user's accepted workouts on a day in their time zone
+pub async fn get_day_activity(conn: &mut PgConnection, user_id: Uuid, day: NaiveDate) -> Result<DayActivity, sqlx::Error> {
+    let bounds = sqlx::query!(
+        r#"
+        SELECT ($2::date::timestamp AT TIME ZONE timezone) AS "since!",
+               (($2::date + 1)::timestamp AT TIME ZONE timezone) AS "until!"
+        FROM users
+        WHERE id = $1
+        "#,
+        user_id,
+        day
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let (since, until) = (bounds.since, bounds.until);
+
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "workouts!",
+               COALESCE(SUM(duration_minutes), 0)::float8 AS "active_minutes!",
+               COALESCE(SUM(calories_burned), 0)::int8 AS "calories!"
+        FROM workout_data
+        WHERE user_id = $1
+        AND review_status = 'accepted'
+        AND COALESCE(workout_start, created_at) >= $2
+        AND COALESCE(workout_start, created_at) < $3
+        "#,
+        user_id,
+        since,
+        until
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(DayActivity {
+        workouts: row.workouts,
+        active_minutes: row.active_minutes,
+        calories: row.calories,
+        zone_minutes: get_zone_minutes(&mut *conn, user_id, since, until).await?,
+    })
+}
+
+pub async fn update_challenge_progress(
+    conn: &mut PgConnection,
+    challenge_id: Uuid,
+    progress: f64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE daily_challenges SET progress = $1 WHERE id = $2 AND completed_at IS NULL",
+        progress,
+        challenge_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Complete a challenge and add its bonus to the user's avatar.
+/// Returns `None` if the challenge was already completed, so the bonus is only ever awarded once.
+pub async fn complete_daily_challenge(
+    conn: &mut PgConnection,
+    challenge_id: Uuid,
+    progress: f64,
+    workout_id: Uuid,
+) -> Result<Option<DailyChallenge>, sqlx::Error> {
+    let completed = sqlx::query_as!(
+        DailyChallenge,
+        r#"
+        UPDATE daily_challenges
+        SET progress = $1, completed_at = NOW(), completed_workout_id = $2
+        WHERE id = $3 AND completed_at IS NULL
+        RETURNING id, user_id, challenge_date, template_key, title, description, target, progress,
+                  stamina_bonus, strength_bonus, completed_at, completed_workout_id
+        "#,
+        progress,
+        workout_id,
+        challenge_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(challenge) = &completed {
+        sqlx::query!(
+            r#"
+            UPDATE user_avatars
+            SET stamina = stamina + $1,
+                strength = strength + $2
+            WHERE user_id = $3
+            "#,
+            challenge.stamina_bonus,
+            challenge.strength_bonus,
+            challenge.user_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(completed)
+}
+
+/// Reopen a completed challenge the day's remaining workouts no longer reach and take its bonus
+/// back off the avatar, on the caller's transaction
+pub async fn reopen_daily_challenge(
+    conn: &mut PgConnection,
+    challenge_id: Uuid,
+    progress: f64,
+) -> Result<Option<DailyChallenge>, sqlx::Error> {
+    let reopened = sqlx::query_as!(
+        DailyChallenge,
+        r#"
+        UPDATE daily_challenges
+        SET progress = $1, completed_at = NULL, completed_workout_id = NULL
+        WHERE id = $2 AND completed_at IS NOT NULL
+        RETURNING id, user_id, challenge_date, template_key, title, description, target, progress,
+                  stamina_bonus, strength_bonus, completed_at, completed_workout_id
+        "#,
+        progress,
+        challenge_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(challenge) = &reopened {
+        sqlx::query!(
+            r#"
+            UPDATE user_avatars
+            SET stamina = GREATEST(stamina - $1, 0),
+                strength = GREATEST(strength - $2, 0)
+            WHERE user_id = $3
+            "#,
+            challenge.stamina_bonus,
+            challenge.strength_bonus,
+            challenge.user_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(reopened)
+}
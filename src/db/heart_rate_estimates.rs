@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::game::heart_rate_estimator::{EstimatedHeartRates, WorkoutHeartRateSummary, HIGH_PERCENTILE, LOW_PERCENTILE};
+use crate::models::profile::{HeartRateEstimate, HeartRateEstimateStatus, HeartRateZoneHistoryEntry};
+
+/// Where a user's heart rate zones came from
+#[derive(Debug, Clone, Copy)]
+pub enum HeartRateZoneSource {
+    Profile,
+    Estimate,
+}
+
+impl HeartRateZoneSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeartRateZoneSource::Profile => "profile",
+            HeartRateZoneSource::Estimate => "estimate",
+        }
+    }
+}
+
+/// Heart rate range of every accepted workout since `since`, per user
+pub async fn get_heart_rate_summaries(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+) -> Result<HashMap<Uuid, Vec<WorkoutHeartRateSummary>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT wd.user_id,
+               percentile_cont($2) WITHIN GROUP (ORDER BY s.heart_rate) AS "high!",
+               percentile_cont($3) WITHIN GROUP (ORDER BY s.heart_rate) AS "low!",
+               COUNT(*) AS "samples!"
+        FROM workout_heart_rate_samples s
+        JOIN workout_data wd ON wd.id = s.workout_data_id
+        WHERE wd.review_status = 'accepted'
+        AND COALESCE(wd.workout_start, wd.created_at) > $1
+        GROUP BY wd.user_id, wd.id
+        "#,
+        since,
+        HIGH_PERCENTILE,
+        LOW_PERCENTILE
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut summaries: HashMap<Uuid, Vec<WorkoutHeartRateSummary>> = HashMap::new();
+    for row in rows {
+        summaries.entry(row.user_id).or_default().push(WorkoutHeartRateSummary {
+            high: row.high,
+            low: row.low,
+            samples: row.samples,
+        });
+    }
+    Ok(summaries)
+}
+
+/// A user's most recent estimate, whatever became of it
+pub async fn get_latest_heart_rate_estimate(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<HeartRateEstimate>, sqlx::Error> {
+    sqlx::query_as!(
+        HeartRateEstimate,
+        r#"
+        SELECT id, user_id, resting_heart_rate, max_heart_rate, previous_resting_heart_rate,
+               previous_max_heart_rate, zone1_max, zone2_max, zone3_max, zone4_max, zone5_max,
+               workouts_considered, status, created_at, resolved_at
+        FROM heart_rate_estimates
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// The estimate waiting for the user to accept or dismiss it, if any
+pub async fn get_proposed_heart_rate_estimate(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Option<HeartRateEstimate>, sqlx::Error> {
+    sqlx::query_as!(
+        HeartRateEstimate,
+        r#"
+        SELECT id, user_id, resting_heart_rate, max_heart_rate, previous_resting_heart_rate,
+               previous_max_heart_rate, zone1_max, zone2_max, zone3_max, zone4_max, zone5_max,
+               workouts_considered, status, created_at, resolved_at
+        FROM heart_rate_estimates
+        WHERE user_id = $1 AND status = 'proposed'
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Store a new estimate as proposed, superseding the one the user hasn't decided on yet
+pub async fn insert_heart_rate_estimate(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    estimated: &EstimatedHeartRates,
+    previous_resting_heart_rate: i32,
+    previous_max_heart_rate: i32,
+    zone_maxes: [i32; 5],
+) -> Result<HeartRateEstimate, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE heart_rate_estimates
+        SET status = 'superseded', resolved_at = NOW()
+        WHERE user_id = $1 AND status = 'proposed'
+        "#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let [zone1_max, zone2_max, zone3_max, zone4_max, zone5_max] = zone_maxes;
+    sqlx::query_as!(
+        HeartRateEstimate,
+        r#"
+        INSERT INTO heart_rate_estimates (
+            id, user_id, resting_heart_rate, max_heart_rate, previous_resting_heart_rate,
+            previous_max_heart_rate, zone1_max, zone2_max, zone3_max, zone4_max, zone5_max,
+            workouts_considered, status
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING id, user_id, resting_heart_rate, max_heart_rate, previous_resting_heart_rate,
+                  previous_max_heart_rate, zone1_max, zone2_max, zone3_max, zone4_max, zone5_max,
+                  workouts_considered, status, created_at, resolved_at
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        estimated.resting_heart_rate,
+        estimated.max_heart_rate,
+        previous_resting_heart_rate,
+        previous_max_heart_rate,
+        zone1_max,
+        zone2_max,
+        zone3_max,
+        zone4_max,
+        zone5_max,
+        estimated.workouts_considered,
+        HeartRateEstimateStatus::Proposed.as_str()
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Settle a proposed estimate. Returns false if it was no longer proposed.
+pub async fn resolve_heart_rate_estimate(
+    conn: &mut PgConnection,
+    estimate_id: Uuid,
+    status: HeartRateEstimateStatus,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE heart_rate_estimates
+        SET status = $1, resolved_at = NOW()
+        WHERE id = $2 AND status = 'proposed'
+        "#,
+        status.as_str(),
+        estimate_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Put an estimate's heart rates and zones in the user's health profile and record them in the history
+pub async fn apply_heart_rate_estimate(
+    conn: &mut PgConnection,
+    estimate: &HeartRateEstimate,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_health_profiles (
+            user_id, resting_heart_rate, max_heart_rate, observed_max_heart_rate,
+            hr_zone_1_max, hr_zone_2_max, hr_zone_3_max, hr_zone_4_max, hr_zone_5_max, last_updated
+        )
+        VALUES ($1, $2, $3, $3, $4, $5, $6, $7, $8, NOW())
+        ON CONFLICT (user_id)
+        DO UPDATE SET
+            resting_heart_rate = $2,
+            max_heart_rate = $3,
+            observed_max_heart_rate = $3,
+            hr_zone_1_max = $4,
+            hr_zone_2_max = $5,
+            hr_zone_3_max = $6,
+            hr_zone_4_max = $7,
+            hr_zone_5_max = $8,
+            last_updated = NOW()
+        "#,
+        estimate.user_id,
+        estimate.resting_heart_rate,
+        estimate.max_heart_rate,
+        estimate.zone1_max,
+        estimate.zone2_max,
+        estimate.zone3_max,
+        estimate.zone4_max,
+        estimate.zone5_max
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    record_heart_rate_zones(
+        conn,
+        estimate.user_id,
+        estimate.resting_heart_rate,
+        estimate.max_heart_rate,
+        [estimate.zone1_max, estimate.zone2_max, estimate.zone3_max, estimate.zone4_max, estimate.zone5_max],
+        HeartRateZoneSource::Estimate,
+        Some(estimate.id),
+    )
+    .await
+}
+
+/// Record the zones that are in effect from now on, ending the previous ones
+pub async fn record_heart_rate_zones(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    resting_heart_rate: i32,
+    max_heart_rate: i32,
+    zone_maxes: [i32; 5],
+    source: HeartRateZoneSource,
+    estimate_id: Option<Uuid>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE heart_rate_zone_history
+        SET effective_until = NOW()
+        WHERE user_id = $1 AND effective_until IS NULL
+        "#,
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let [zone1_max, zone2_max, zone3_max, zone4_max, zone5_max] = zone_maxes;
+    sqlx::query!(
+        r#"
+        INSERT INTO heart_rate_zone_history (
+            id, user_id, resting_heart_rate, max_heart_rate,
+            zone1_max, zone2_max, zone3_max, zone4_max, zone5_max, source, estimate_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        resting_heart_rate,
+        max_heart_rate,
+        zone1_max,
+        zone2_max,
+        zone3_max,
+        zone4_max,
+        zone5_max,
+        source.as_str(),
+        estimate_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// The zones a user has had, newest first
+pub async fn get_heart_rate_zone_history(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> Result<Vec<HeartRateZoneHistoryEntry>, sqlx::Error> {
+    sqlx::query_as!(
+        HeartRateZoneHistoryEntry,
+        r#"
+        SELECT resting_heart_rate, max_heart_rate, zone1_max, zone2_max, zone3_max, zone4_max, zone5_max,
+               source, estimate_id, effective_from, effective_until
+        FROM heart_rate_zone_history
+        WHERE user_id = $1
+        ORDER BY effective_from DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Whether the user applies estimates without being asked
+pub async fn get_auto_apply_heart_rate_estimates(pool: &PgPool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    let auto_apply = sqlx::query_scalar!(
+        "SELECT auto_apply_heart_rate_estimates FROM user_health_profiles WHERE user_id = $1",
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(auto_apply.unwrap_or(false))
+}
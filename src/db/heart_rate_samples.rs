@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::workout_data::{HeartRateBucket, HeartRateData};
+
+/// Store a workout's heart rate samples, one row per timestamp.
+/// Repeated timestamps keep the first sample. Returns the number of samples stored.
+pub async fn insert_heart_rate_samples(
+    conn: &mut PgConnection,
+    workout_data_id: Uuid,
+    samples: &[HeartRateData],
+) -> Result<u64, sqlx::Error> {
+    if samples.is_empty() {
+        return Ok(0);
+    }
+
+    let (timestamps, heart_rates): (Vec<DateTime<Utc>>, Vec<i16>) = samples.iter()
+        .map(|sample| (sample.timestamp, sample.heart_rate.clamp(0, i16::MAX as i32) as i16))
+        .unzip();
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO workout_heart_rate_samples (workout_data_id, recorded_at, heart_rate)
+        SELECT $1, recorded_at, heart_rate
+        FROM UNNEST($2::timestamptz[], $3::smallint[]) AS samples(recorded_at, heart_rate)
+        ON CONFLICT (workout_data_id, recorded_at) DO NOTHING
+        "#,
+        workout_data_id,
+        &timestamps,
+        &heart_rates
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// All heart rate samples of a workout in time order
+pub async fn get_heart_rate_samples(
+    pool: &PgPool,
+    workout_data_id: Uuid,
+) -> Result<Vec<HeartRateData>, sqlx::Error> {
+    sqlx::query_as!(
+        HeartRateData,
+        r#"
+        SELECT recorded_at AS timestamp, heart_rate::int AS "heart_rate!"
+        FROM workout_heart_rate_samples
+        WHERE workout_data_id = $1
+        ORDER BY recorded_at
+        "#,
+        workout_data_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// Heart rate samples of several workouts at once, in time order per workout
+pub async fn get_heart_rate_samples_for_workouts(
+    conn: &mut PgConnection,
+    workout_data_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<HeartRateData>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT workout_data_id, recorded_at, heart_rate::int AS "heart_rate!"
+        FROM workout_heart_rate_samples
+        WHERE workout_data_id = ANY($1)
+        ORDER BY workout_data_id, recorded_at
+        "#,
+        workout_data_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut samples: HashMap<Uuid, Vec<HeartRateData>> = HashMap::new();
+    for row in rows {
+        samples.entry(row.workout_data_id).or_default().push(HeartRateData {
+            timestamp: row.recorded_at,
+            heart_rate: row.heart_rate,
+        });
+    }
+    Ok(samples)
+}
+
+/// A workout's heart rate averaged into buckets of `bucket_seconds`, for charts
+pub async fn get_downsampled_heart_rate(
+    pool: &PgPool,
+    workout_data_id: Uuid,
+    bucket_seconds: i64,
+) -> Result<Vec<HeartRateBucket>, sqlx::Error> {
+    sqlx::query_as!(
+        HeartRateBucket,
+        r#"
+        SELECT to_timestamp(floor(extract(epoch FROM recorded_at) / $2) * $2) AS "timestamp!",
+               AVG(heart_rate)::int AS "avg_heart_rate!",
+               MIN(heart_rate)::int AS "min_heart_rate!",
+               MAX(heart_rate)::int AS "max_heart_rate!",
+               COUNT(*)::int AS "samples!"
+        FROM workout_heart_rate_samples
+        WHERE workout_data_id = $1
+        GROUP BY 1
+        ORDER BY 1
+        "#,
+        workout_data_id,
+        bucket_seconds as f64
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Utc};
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 use uuid::Uuid;
 use tracing::{info, debug};
 
@@ -8,6 +8,23 @@ use crate::models::live_game::{
     LiveGameResponse, LiveGameScoreUpdate
 };
 
+/// A workout's score event in a live game that was not evaluated yet, with the game's score when it was read
+#[derive(Debug)]
+pub struct RescorableScoreEvent {
+    pub id: Uuid,
+    pub live_game_id: Uuid,
+    pub game_id: Uuid,
+    pub workout_data_id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub team_id: Uuid,
+    pub team_side: String,
+    pub score_points: i32,
+    pub power_contribution: i32,
+    pub home_score: i32,
+    pub away_score: i32,
+}
+
 #[derive(Debug)]
 pub struct LiveGameQueries {
     pool: PgPool,
@@ -173,21 +190,23 @@ impl LiveGameQueries {
         Ok(live_game)
     }
 
-    /// Update live game scores when a player contributes
+    /// Update live game scores when a player contributes.
+    /// Runs on the caller's connection so the update can be part of a larger transaction.
     pub async fn update_live_game_score(
         &self,
+        conn: &mut PgConnection,
         live_game_id: Uuid,
         update: &LiveGameScoreUpdate,
     ) -> Result<LiveGame, sqlx::Error> {
         info!("Updating live game {} score for user {}", live_game_id, update.username);
 
-        // Get current live game state
+        // Get current live game state, locking the row against concurrent score updates
         let current_game = sqlx::query_as!(
             LiveGame,
-            "SELECT * FROM live_games WHERE id = $1",
+            "SELECT * FROM live_games WHERE id = $1 FOR UPDATE",
             live_game_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *conn)
         .await?;
 
         // Determine which team the user belongs to and update accordingly
@@ -244,16 +263,16 @@ impl LiveGameQueries {
             team_side,
             live_game_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *conn)
         .await?;
 
         // Only update contributions and record events if there's actual score increase
         if update.score_increase > 0 || update.power_increase > 0 {
             // Update player contribution
-            self.update_player_contribution(live_game_id, update).await?;
+            self.update_player_contribution(&mut *conn, live_game_id, update).await?;
 
             // Record the score event
-            self.record_score_event(live_game_id, update, team_side).await?;
+            self.record_score_event(&mut *conn, live_game_id, update, team_side).await?;
         }
 
         debug!("Updated live game {}: {} {} - {} {}", 
@@ -267,9 +286,197 @@ impl LiveGameQueries {
         Ok(updated_game)
     }
 
+    /// Reverse the score events a workout produced in games that are still live.
+    /// Events in finished games are kept as history but unlinked from the workout.
+    /// Returns the corrected live games so the caller can broadcast them after committing.
+    pub async fn revert_workout_scores(
+        conn: &mut PgConnection,
+        workout_data_id: Uuid,
+    ) -> Result<Vec<LiveGame>, sqlx::Error> {
+        let events = sqlx::query!(
+            r#"
+            SELECT e.id, e.live_game_id, e.user_id, e.team_side, e.score_points, e.power_contribution
+            FROM live_score_events e
+            JOIN live_games lg ON lg.id = e.live_game_id
+            WHERE e.workout_data_id = $1
+            AND lg.is_active = true
+            AND lg.game_end_time > NOW()
+            ORDER BY e.live_game_id
+            FOR UPDATE OF lg, e
+            "#,
+            workout_data_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut updated_games: Vec<LiveGame> = Vec::new();
+        for event in events {
+            info!("Reverting score event {} in live game {}: -{} score, -{} power",
+                event.id, event.live_game_id, event.score_points, event.power_contribution);
+
+            let updated_game = sqlx::query_as!(
+                LiveGame,
+                r#"
+                UPDATE live_games
+                SET
+                    home_score = CASE WHEN $1 = 'home' THEN GREATEST(home_score - $2, 0) ELSE home_score END,
+                    away_score = CASE WHEN $1 = 'away' THEN GREATEST(away_score - $2, 0) ELSE away_score END,
+                    home_power = CASE WHEN $1 = 'home' THEN GREATEST(home_power - $3, 0) ELSE home_power END,
+                    away_power = CASE WHEN $1 = 'away' THEN GREATEST(away_power - $3, 0) ELSE away_power END,
+                    updated_at = NOW()
+                WHERE id = $4
+                RETURNING 
+                    id, game_id, home_team_id, home_team_name, away_team_id, away_team_name,
+                    home_score, away_score, home_power, away_power,
+                    game_start_time, game_end_time, last_score_time, last_scorer_id,
+                    last_scorer_name, last_scorer_team, is_active, created_at, updated_at
+                "#,
+                event.team_side,
+                event.score_points,
+                event.power_contribution,
+                event.live_game_id
+            )
+            .fetch_one(&mut *conn)
+            .await?;
+
+            sqlx::query!(
+                r#"
+                UPDATE live_player_contributions 
+                SET 
+                    current_power = GREATEST(current_power - $1, 0),
+                    total_score_contribution = GREATEST(total_score_contribution - $2, 0),
+                    contribution_count = GREATEST(contribution_count - 1, 0),
+                    updated_at = NOW()
+                WHERE live_game_id = $3 AND user_id = $4
+                "#,
+                event.power_contribution,
+                event.score_points,
+                event.live_game_id,
+                event.user_id
+            )
+            .execute(&mut *conn)
+            .await?;
+
+            sqlx::query!("DELETE FROM live_score_events WHERE id = $1", event.id)
+                .execute(&mut *conn)
+                .await?;
+
+            // Keep only the latest state per game
+            updated_games.retain(|game| game.id != updated_game.id);
+            updated_games.push(updated_game);
+        }
+
+        sqlx::query!(
+            "UPDATE live_score_events SET workout_data_id = NULL WHERE workout_data_id = $1",
+            workout_data_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(updated_games)
+    }
+
+    /// Score events produced by workouts in live games whose league game was not evaluated yet,
+    /// locked until the transaction ends
+    pub async fn get_rescorable_score_events(
+        &self,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<RescorableScoreEvent>, sqlx::Error> {
+        sqlx::query_as!(
+            RescorableScoreEvent,
+            r#"
+            SELECT e.id, e.live_game_id, lg.game_id, e.workout_data_id AS "workout_data_id!", e.user_id,
+                   e.username, e.team_id, e.team_side, e.score_points, e.power_contribution, lg.home_score, lg.away_score
+            FROM live_score_events e
+            JOIN live_games lg ON lg.id = e.live_game_id
+            JOIN league_games g ON g.id = lg.game_id
+            WHERE e.workout_data_id IS NOT NULL
+            AND g.status <> 'evaluated'
+            ORDER BY e.live_game_id, e.occurred_at
+            FOR UPDATE OF lg, e
+            "#
+        )
+        .fetch_all(conn)
+        .await
+    }
+
+    /// Replace what a score event counted for with a new score and stats, moving the game's
+    /// score and the player's contribution by the difference. Returns the corrected game.
+    pub async fn rescore_event(
+        &self,
+        conn: &mut PgConnection,
+        event: &RescorableScoreEvent,
+        update: &LiveGameScoreUpdate,
+    ) -> Result<LiveGame, sqlx::Error> {
+        let score_delta = update.score_increase - event.score_points;
+        let power_delta = update.power_increase - event.power_contribution;
+
+        sqlx::query!(
+            r#"
+            UPDATE live_score_events
+            SET score_points = $1, power_contribution = $2, stamina_gained = $3, strength_gained = $4,
+                description = $5
+            WHERE id = $6
+            "#,
+            update.score_increase,
+            update.power_increase,
+            update.stamina_gained,
+            update.strength_gained,
+            update.description,
+            event.id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        let updated_game = sqlx::query_as!(
+            LiveGame,
+            r#"
+            UPDATE live_games
+            SET
+                home_score = CASE WHEN $1 = 'home' THEN GREATEST(home_score + $2, 0) ELSE home_score END,
+                away_score = CASE WHEN $1 = 'away' THEN GREATEST(away_score + $2, 0) ELSE away_score END,
+                home_power = CASE WHEN $1 = 'home' THEN GREATEST(home_power + $3, 0) ELSE home_power END,
+                away_power = CASE WHEN $1 = 'away' THEN GREATEST(away_power + $3, 0) ELSE away_power END,
+                updated_at = NOW()
+            WHERE id = $4
+            RETURNING 
+                id, game_id, home_team_id, home_team_name, away_team_id, away_team_name,
+                home_score, away_score, home_power, away_power,
+                game_start_time, game_end_time, last_score_time, last_scorer_id,
+                last_scorer_name, last_scorer_team, is_active, created_at, updated_at
+            "#,
+            event.team_side,
+            score_delta,
+            power_delta,
+            event.live_game_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE live_player_contributions 
+            SET 
+                current_power = GREATEST(current_power + $1, 0),
+                total_score_contribution = GREATEST(total_score_contribution + $2, 0),
+                updated_at = NOW()
+            WHERE live_game_id = $3 AND user_id = $4
+            "#,
+            power_delta,
+            score_delta,
+            event.live_game_id,
+            event.user_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(updated_game)
+    }
+
     /// Update a player's contribution in a live game
     async fn update_player_contribution(
         &self,
+        conn: &mut PgConnection,
         live_game_id: Uuid,
         update: &LiveGameScoreUpdate,
     ) -> Result<(), sqlx::Error> {
@@ -290,7 +497,7 @@ impl LiveGameQueries {
             live_game_id,
             update.user_id
         )
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
 
         Ok(())
@@ -299,6 +506,7 @@ impl LiveGameQueries {
     /// Record a score event
     async fn record_score_event(
         &self,
+        conn: &mut PgConnection,
         live_game_id: Uuid,
         update: &LiveGameScoreUpdate,
         team_side: &str,
@@ -326,7 +534,7 @@ impl LiveGameQueries {
             update.description,
             update.workout_data_id
         )
-        .execute(&self.pool)
+        .execute(&mut *conn)
         .await?;
 
         Ok(())
@@ -1,2 +1,14 @@
 pub mod workout_data;
-pub mod live_game_queries;
\ No newline at end of file
+pub mod live_game_queries;
+pub mod workout_jobs;
+pub mod heart_rate_samples;
+pub mod sensor_samples;
+pub mod stat_gains;
+pub mod scoring_rules;
+pub mod stat_recompute_jobs;
+pub mod heart_rate_estimates;
+pub mod personal_records;
+pub mod daily_challenges;
+pub mod stat_decay;
+pub mod avatar_progression;
+pub mod workout_upload_sessions;
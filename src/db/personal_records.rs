@@ -0,0 +1,298 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::game::personal_records::{MilestoneType, RecordType};
+use crate::models::profile::{Milestone, PersonalRecord};
+
+/// What an accepted workout could set records with
+#[derive(Debug)]
+pub struct WorkoutRecordMetrics {
+    pub workout_id: Uuid,
+    pub user_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub duration_minutes: f64,
+    pub high_zone_minutes: f64,
+    pub points: f64,
+}
+
+/// Record metrics of a workout, `None` unless it exists and was accepted
+pub async fn get_workout_record_metrics(
+    pool: &PgPool,
+    workout_id: Uuid,
+) -> Result<Option<WorkoutRecordMetrics>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT wd.id, wd.user_id, COALESCE(wd.workout_start, wd.created_at) AS "started_at!",
+               COALESCE(EXTRACT(EPOCH FROM (wd.workout_end - wd.workout_start)) / 60.0,
+                        wd.duration_minutes::float8, 0)::float8 AS "duration_minutes!",
+               COALESCE((
+                   SELECT SUM((zone.value->>'minutes')::float8)
+                   FROM jsonb_array_elements(
+                       CASE WHEN jsonb_typeof(wd.heart_rate_zones) = 'array' THEN wd.heart_rate_zones ELSE '[]'::jsonb END
+                   ) AS zone
+                   WHERE zone.value->>'zone' IN ('Zone4', 'Zone5')
+               ), 0)::float8 AS "high_zone_minutes!",
+               COALESCE(wd.total_points_gained, 0)::float8 AS "points!"
+        FROM workout_data wd
+        WHERE wd.id = $1 AND wd.review_status = 'accepted'
+        "#,
+        workout_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| WorkoutRecordMetrics {
+        workout_id: row.id,
+        user_id: row.user_id,
+        started_at: row.started_at,
+        duration_minutes: row.duration_minutes,
+        high_zone_minutes: row.high_zone_minutes,
+        points: row.points,
+    }))
+}
+
+/// Record metrics of all the user's accepted workouts
+pub async fn get_user_record_metrics(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+) -> Result<Vec<WorkoutRecordMetrics>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT wd.id, wd.user_id, COALESCE(wd.workout_start, wd.created_at) AS "started_at!",
+               COALESCE(EXTRACT(EPOCH FROM (wd.workout_end - wd.workout_start)) / 60.0,
+                        wd.duration_minutes::float8, 0)::float8 AS "duration_minutes!",
+               COALESCE((
+                   SELECT SUM((zone.value->>'minutes')::float8)
+                   FROM jsonb_array_elements(
+                       CASE WHEN jsonb_typeof(wd.heart_rate_zones) = 'array' THEN wd.heart_rate_zones ELSE '[]'::jsonb END
+                   ) AS zone
+                   WHERE zone.value->>'zone' IN ('Zone4', 'Zone5')
+               ), 0)::float8 AS "high_zone_minutes!",
+               COALESCE(wd.total_points_gained, 0)::float8 AS "points!"
+        FROM workout_data wd
+        WHERE wd.user_id = $1 AND wd.review_status = 'accepted'
+        ORDER BY 3, wd.id
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| WorkoutRecordMetrics {
+        workout_id: row.id,
+        user_id: row.user_id,
+        started_at: row.started_at,
+        duration_minutes: row.duration_minutes,
+        high_zone_minutes: row.high_zone_minutes,
+        points: row.points,
+    }).collect())
+}
+
+/// Days (UTC) on which the user did an accepted workout, in ascending order
+pub async fn get_workout_days(conn: &mut PgConnection, user_id: Uuid) -> Result<Vec<NaiveDate>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT (COALESCE(workout_start, created_at) AT TIME ZONE 'UTC')::date AS "day!"
+        FROM workout_data
+        WHERE user_id = $1 AND review_status = 'accepted'
+        ORDER BY 1
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.day).collect())
+}
+
+pub async fn get_personal_records(conn: &mut PgConnection, user_id: Uuid) -> Result<Vec<PersonalRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        PersonalRecord,
+        r#"
+        SELECT id, user_id, record_type, value, previous_value, workout_data_id, achieved_at
+        FROM personal_records
+        WHERE user_id = $1
+        ORDER BY achieved_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Store `value` as the user's record if it beats the current one.
+/// Returns the new record, or `None` if the current one still stands.
+pub async fn save_personal_record(
+    pool: &PgPool,
+    user_id: Uuid,
+    record_type: RecordType,
+    value: f64,
+    workout_id: Uuid,
+) -> Result<Option<PersonalRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        PersonalRecord,
+        r#"
+        INSERT INTO personal_records (id, user_id, record_type, value, workout_data_id)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, record_type) DO UPDATE
+        SET previous_value = personal_records.value,
+            value = EXCLUDED.value,
+            workout_data_id = EXCLUDED.workout_data_id,
+            achieved_at = NOW()
+        WHERE personal_records.value < EXCLUDED.value
+        RETURNING id, user_id, record_type, value, previous_value, workout_data_id, achieved_at
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        record_type.as_str(),
+        value,
+        workout_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Overwrite a record with a recomputed value, for when the workout that set it is gone
+pub async fn replace_personal_record(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    record_type: RecordType,
+    value: f64,
+    workout_id: Option<Uuid>,
+    achieved_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO personal_records (id, user_id, record_type, value, workout_data_id, achieved_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id, record_type) DO UPDATE
+        SET previous_value = NULL,
+            value = EXCLUDED.value,
+            workout_data_id = EXCLUDED.workout_data_id,
+            achieved_at = EXCLUDED.achieved_at
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        record_type.as_str(),
+        value,
+        workout_id,
+        achieved_at
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_personal_record(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    record_type: RecordType,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM personal_records WHERE user_id = $1 AND record_type = $2",
+        user_id,
+        record_type.as_str()
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Running totals over the user's accepted workouts
+#[derive(Debug, Default)]
+pub struct MilestoneTotals {
+    pub workouts: f64,
+    pub training_hours: f64,
+    pub points: f64,
+}
+
+impl MilestoneTotals {
+    pub fn total(&self, milestone_type: MilestoneType) -> f64 {
+        match milestone_type {
+            MilestoneType::Workouts => self.workouts,
+            MilestoneType::TrainingHours => self.training_hours,
+            MilestoneType::TotalPoints => self.points,
+        }
+    }
+}
+
+pub async fn get_milestone_totals(conn: &mut PgConnection, user_id: Uuid) -> Result<MilestoneTotals, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COUNT(*)::float8 AS "workouts!",
+               COALESCE(SUM(COALESCE(EXTRACT(EPOCH FROM (workout_end - workout_start)) / 3600.0,
+                                     duration_minutes / 60.0::float8, 0)), 0)::float8 AS "training_hours!",
+               COALESCE(SUM(total_points_gained), 0)::float8 AS "points!"
+        FROM workout_data
+        WHERE user_id = $1 AND review_status = 'accepted'
+        "#,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(MilestoneTotals {
+        workouts: row.workouts,
+        training_hours: row.training_hours,
+        points: row.points,
+    })
+}
+
+pub async fn get_milestones(conn: &mut PgConnection, user_id: Uuid) -> Result<Vec<Milestone>, sqlx::Error> {
+    sqlx::query_as!(
+        Milestone,
+        r#"
+        SELECT id, user_id, milestone_type, threshold, workout_data_id, reached_at
+        FROM user_milestones
+        WHERE user_id = $1
+        ORDER BY reached_at DESC, threshold DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Store the thresholds the user has reached. Returns the ones that are new.
+pub async fn save_milestones(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    milestone_type: MilestoneType,
+    thresholds: &[i64],
+    workout_id: Uuid,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO user_milestones (id, user_id, milestone_type, threshold, workout_data_id)
+        SELECT gen_random_uuid(), $1, $2, threshold, $4
+        FROM UNNEST($3::bigint[]) AS threshold
+        ON CONFLICT (user_id, milestone_type, threshold) DO NOTHING
+        RETURNING threshold
+        "#,
+        user_id,
+        milestone_type.as_str(),
+        thresholds,
+        workout_id
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Drop milestones the user's totals no longer reach
+pub async fn delete_unreached_milestones(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    milestone_type: MilestoneType,
+    total: f64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM user_milestones WHERE user_id = $1 AND milestone_type = $2 AND threshold > $3::float8",
+        user_id,
+        milestone_type.as_str(),
+        total
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
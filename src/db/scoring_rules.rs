@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::game::ScoringRules;
+
+/// A season's own scoring rules, if it has any
+pub async fn get_season_scoring_rules(pool: &PgPool, season_id: Uuid) -> Result<Option<ScoringRules>, sqlx::Error> {
+    sqlx::query_as!(
+        ScoringRules,
+        r#"
+        SELECT season_id AS "season_id?",
+               zone1_stamina_per_min, zone2_stamina_per_min, zone3_stamina_per_min,
+               zone4_stamina_per_min, zone5_stamina_per_min,
+               zone1_strength_per_min, zone2_strength_per_min, zone3_strength_per_min,
+               zone4_strength_per_min, zone5_strength_per_min,
+               stamina_score_weight, strength_score_weight,
+               fallback_stamina_per_min, fallback_strength_per_min, fallback_stamina_per_kcal,
+               fallback_strength_per_kcal, fallback_max_points_per_min,
+               strength_per_volume_kg, strength_max_points_per_set,
+               max_credited_interval_sec, interpolate_up_to_sec, updated_at AS "updated_at?"
+        FROM season_scoring_rules
+        WHERE season_id = $1
+        "#,
+        season_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// The rules a game scores with: those of its season, or the defaults
+pub async fn get_game_scoring_rules(conn: &mut PgConnection, game_id: Uuid) -> Result<ScoringRules, sqlx::Error> {
+    let rules = sqlx::query_as!(
+        ScoringRules,
+        r#"
+        SELECT r.season_id AS "season_id?",
+               r.zone1_stamina_per_min, r.zone2_stamina_per_min, r.zone3_stamina_per_min,
+               r.zone4_stamina_per_min, r.zone5_stamina_per_min,
+               r.zone1_strength_per_min, r.zone2_strength_per_min, r.zone3_strength_per_min,
+               r.zone4_strength_per_min, r.zone5_strength_per_min,
+               r.stamina_score_weight, r.strength_score_weight,
+               r.fallback_stamina_per_min, r.fallback_strength_per_min, r.fallback_stamina_per_kcal,
+               r.fallback_strength_per_kcal, r.fallback_max_points_per_min,
+               r.strength_per_volume_kg, r.strength_max_points_per_set,
+               r.max_credited_interval_sec, r.interpolate_up_to_sec, r.updated_at AS "updated_at?"
+        FROM league_games g
+        JOIN season_scoring_rules r ON r.season_id = g.season_id
+        WHERE g.id = $1
+        "#,
+        game_id
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(rules.unwrap_or_default())
+}
+
+/// The rules a user's avatar scores with at `at`: those of the latest season running then that
+/// one of their teams plays in, or the defaults
+pub async fn get_user_scoring_rules(conn: &mut PgConnection, user_id: Uuid, at: DateTime<Utc>) -> Result<ScoringRules, sqlx::Error> {
+    let rules = sqlx::query_as!(
+        ScoringRules,
+        r#"
+        SELECT r.season_id AS "season_id?",
+               r.zone1_stamina_per_min, r.zone2_stamina_per_min, r.zone3_stamina_per_min,
+               r.zone4_stamina_per_min, r.zone5_stamina_per_min,
+               r.zone1_strength_per_min, r.zone2_strength_per_min, r.zone3_strength_per_min,
+               r.zone4_strength_per_min, r.zone5_strength_per_min,
+               r.stamina_score_weight, r.strength_score_weight,
+               r.fallback_stamina_per_min, r.fallback_strength_per_min, r.fallback_stamina_per_kcal,
+               r.fallback_strength_per_kcal, r.fallback_max_points_per_min,
+               r.strength_per_volume_kg, r.strength_max_points_per_set,
+               r.max_credited_interval_sec, r.interpolate_up_to_sec, r.updated_at AS "updated_at?"
+        FROM season_scoring_rules r
+        WHERE r.season_id = (
+            SELECT ls.id
+            FROM team_members tm
+            JOIN league_teams lt ON lt.team_id = tm.team_id
+            JOIN league_seasons ls ON ls.id = lt.season_id
+            WHERE tm.user_id = $1 AND tm.status = 'active'
+              AND ls.start_date <= $2 AND ls.end_date >= $2
+            ORDER BY ls.start_date DESC, ls.id
+            LIMIT 1
+        )
+        "#,
+        user_id,
+        at
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(rules.unwrap_or_default())
+}
+
+/// The live games each workout counted towards. Workouts that counted towards no game are left out.
+pub async fn get_scoring_game_ids(conn: &mut PgConnection, workout_data_ids: &[Uuid]) -> Result<HashMap<Uuid, Vec<Uuid>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT DISTINCT e.workout_data_id AS "workout_data_id!", lg.game_id
+        FROM live_score_events e
+        JOIN live_games lg ON lg.id = e.live_game_id
+        WHERE e.workout_data_id = ANY($1)
+        "#,
+        workout_data_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut game_ids: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for row in rows {
+        game_ids.entry(row.workout_data_id).or_default().push(row.game_id);
+    }
+    Ok(game_ids)
+}
+
+/// Give a season its own scoring rules. Fails with a unique violation if it already has some.
+pub async fn create_season_scoring_rules(
+    pool: &PgPool,
+    season_id: Uuid,
+    rules: &ScoringRules,
+) -> Result<ScoringRules, sqlx::Error> {
+    sqlx::query_as!(
+        ScoringRules,
+        r#"
+        INSERT INTO season_scoring_rules (
+            season_id, zone1_stamina_per_min, zone2_stamina_per_min, zone3_stamina_per_min,
+            zone4_stamina_per_min, zone5_stamina_per_min, zone1_strength_per_min, zone2_strength_per_min,
+            zone3_strength_per_min, zone4_strength_per_min, zone5_strength_per_min,
+            stamina_score_weight, strength_score_weight, fallback_stamina_per_min, fallback_strength_per_min,
+            fallback_stamina_per_kcal, fallback_strength_per_kcal, fallback_max_points_per_min,
+            strength_per_volume_kg, strength_max_points_per_set, max_credited_interval_sec, interpolate_up_to_sec
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)
+        RETURNING season_id AS "season_id?",
+                  zone1_stamina_per_min, zone2_stamina_per_min, zone3_stamina_per_min,
+                  zone4_stamina_per_min, zone5_stamina_per_min,
+                  zone1_strength_per_min, zone2_strength_per_min, zone3_strength_per_min,
+                  zone4_strength_per_min, zone5_strength_per_min,
+                  stamina_score_weight, strength_score_weight,
+               fallback_stamina_per_min, fallback_strength_per_min, fallback_stamina_per_kcal,
+               fallback_strength_per_kcal, fallback_max_points_per_min,
+               strength_per_volume_kg, strength_max_points_per_set,
+               max_credited_interval_sec, interpolate_up_to_sec, updated_at AS "updated_at?"
+        "#,
+        season_id,
+        rules.zone1_stamina_per_min,
+        rules.zone2_stamina_per_min,
+        rules.zone3_stamina_per_min,
+        rules.zone4_stamina_per_min,
+        rules.zone5_stamina_per_min,
+        rules.zone1_strength_per_min,
+        rules.zone2_strength_per_min,
+        rules.zone3_strength_per_min,
+        rules.zone4_strength_per_min,
+        rules.zone5_strength_per_min,
+        rules.stamina_score_weight,
+        rules.strength_score_weight,
+        rules.fallback_stamina_per_min,
+        rules.fallback_strength_per_min,
+        rules.fallback_stamina_per_kcal,
+        rules.fallback_strength_per_kcal,
+        rules.fallback_max_points_per_min,
+        rules.strength_per_volume_kg,
+        rules.strength_max_points_per_set,
+        rules.max_credited_interval_sec,
+        rules.interpolate_up_to_sec
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Replace a season's scoring rules, `None` if it has none yet
+pub async fn update_season_scoring_rules(
+    pool: &PgPool,
+    season_id: Uuid,
+    rules: &ScoringRules,
+) -> Result<Option<ScoringRules>, sqlx::Error> {
+    sqlx::query_as!(
+        ScoringRules,
+        r#"
+        UPDATE season_scoring_rules
+        SET zone1_stamina_per_min = $2, zone2_stamina_per_min = $3, zone3_stamina_per_min = $4,
+            zone4_stamina_per_min = $5, zone5_stamina_per_min = $6, zone1_strength_per_min = $7,
+            zone2_strength_per_min = $8, zone3_strength_per_min = $9, zone4_strength_per_min = $10,
+            zone5_strength_per_min = $11, stamina_score_weight = $12, strength_score_weight = $13,
+            fallback_stamina_per_min = $14, fallback_strength_per_min = $15, fallback_stamina_per_kcal = $16,
+            fallback_strength_per_kcal = $17, fallback_max_points_per_min = $18,
+            strength_per_volume_kg = $19, strength_max_points_per_set = $20,
+            max_credited_interval_sec = $21, interpolate_up_to_sec = $22, updated_at = NOW()
+        WHERE season_id = $1
+        RETURNING season_id AS "season_id?",
+                  zone1_stamina_per_min, zone2_stamina_per_min, zone3_stamina_per_min,
+                  zone4_stamina_per_min, zone5_stamina_per_min,
+                  zone1_strength_per_min, zone2_strength_per_min, zone3_strength_per_min,
+                  zone4_strength_per_min, zone5_strength_per_min,
+                  stamina_score_weight, strength_score_weight,
+               fallback_stamina_per_min, fallback_strength_per_min, fallback_stamina_per_kcal,
+               fallback_strength_per_kcal, fallback_max_points_per_min,
+               strength_per_volume_kg, strength_max_points_per_set,
+               max_credited_interval_sec, interpolate_up_to_sec, updated_at AS "updated_at?"
+        "#,
+        season_id,
+        rules.zone1_stamina_per_min,
+        rules.zone2_stamina_per_min,
+        rules.zone3_stamina_per_min,
+        rules.zone4_stamina_per_min,
+        rules.zone5_stamina_per_min,
+        rules.zone1_strength_per_min,
+        rules.zone2_strength_per_min,
+        rules.zone3_strength_per_min,
+        rules.zone4_strength_per_min,
+        rules.zone5_strength_per_min,
+        rules.stamina_score_weight,
+        rules.strength_score_weight,
+        rules.fallback_stamina_per_min,
+        rules.fallback_strength_per_min,
+        rules.fallback_stamina_per_kcal,
+        rules.fallback_strength_per_kcal,
+        rules.fallback_max_points_per_min,
+        rules.strength_per_volume_kg,
+        rules.strength_max_points_per_set,
+        rules.max_credited_interval_sec,
+        rules.interpolate_up_to_sec
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Drop a season's scoring rules so it goes back to the defaults. Returns whether it had any.
+pub async fn delete_season_scoring_rules(pool: &PgPool, season_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!("DELETE FROM season_scoring_rules WHERE season_id = $1", season_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,125 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::models::workout_data::{
+    CadenceData, DistanceData, PowerData, SpeedData, StepsData, WorkoutDataSyncRequest, WorkoutStreams,
+};
+
+/// Values of the streams sampled at one moment
+#[derive(Default)]
+struct SensorSample {
+    power_watts: Option<i32>,
+    cadence: Option<i32>,
+    speed_mps: Option<f64>,
+    distance_meters: Option<f64>,
+    steps: Option<i32>,
+}
+
+/// Store a workout's power, cadence, speed, distance and step streams, one row per timestamp.
+/// Repeated timestamps within a stream keep the first sample. Returns the number of rows stored.
+pub async fn insert_sensor_samples(
+    conn: &mut PgConnection,
+    workout_data_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+) -> Result<u64, sqlx::Error> {
+    let mut samples: BTreeMap<DateTime<Utc>, SensorSample> = BTreeMap::new();
+    for sample in data.power.iter().flatten() {
+        samples.entry(sample.timestamp).or_default().power_watts.get_or_insert(sample.watts);
+    }
+    for sample in data.cadence.iter().flatten() {
+        samples.entry(sample.timestamp).or_default().cadence.get_or_insert(sample.cadence);
+    }
+    for sample in data.speed.iter().flatten() {
+        samples.entry(sample.timestamp).or_default().speed_mps.get_or_insert(sample.meters_per_second);
+    }
+    for sample in data.distance.iter().flatten() {
+        samples.entry(sample.timestamp).or_default().distance_meters.get_or_insert(sample.meters);
+    }
+    for sample in data.steps.iter().flatten() {
+        samples.entry(sample.timestamp).or_default().steps.get_or_insert(sample.steps);
+    }
+    if samples.is_empty() {
+        return Ok(0);
+    }
+
+    let timestamps: Vec<DateTime<Utc>> = samples.keys().copied().collect();
+    let power_watts: Vec<Option<i32>> = samples.values().map(|sample| sample.power_watts).collect();
+    let cadence: Vec<Option<i32>> = samples.values().map(|sample| sample.cadence).collect();
+    let speed_mps: Vec<Option<f64>> = samples.values().map(|sample| sample.speed_mps).collect();
+    let distance_meters: Vec<Option<f64>> = samples.values().map(|sample| sample.distance_meters).collect();
+    let steps: Vec<Option<i32>> = samples.values().map(|sample| sample.steps).collect();
+
+    let result = sqlx::query!(
+        r#"
+        INSERT INTO workout_sensor_samples (
+            workout_data_id, recorded_at, power_watts, cadence, speed_mps, distance_meters, steps
+        )
+        SELECT $1, recorded_at, power_watts, cadence, speed_mps, distance_meters, steps
+        FROM UNNEST($2::timestamptz[], $3::int[], $4::int[], $5::float8[], $6::float8[], $7::int[])
+            AS samples(recorded_at, power_watts, cadence, speed_mps, distance_meters, steps)
+        "#,
+        workout_data_id,
+        &timestamps,
+        &power_watts as &[Option<i32>],
+        &cadence as &[Option<i32>],
+        &speed_mps as &[Option<f64>],
+        &distance_meters as &[Option<f64>],
+        &steps as &[Option<i32>]
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// A workout's sensor streams in time order, each `None` if it wasn't recorded
+pub async fn get_sensor_streams(
+    conn: &mut PgConnection,
+    workout_data_id: Uuid,
+) -> Result<WorkoutStreams, sqlx::Error> {
+    let mut streams = get_sensor_streams_for_workouts(conn, &[workout_data_id]).await?;
+    Ok(streams.remove(&workout_data_id).unwrap_or_default())
+}
+
+/// Sensor streams of several workouts at once, in time order per workout
+pub async fn get_sensor_streams_for_workouts(
+    conn: &mut PgConnection,
+    workout_data_ids: &[Uuid],
+) -> Result<HashMap<Uuid, WorkoutStreams>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT workout_data_id, recorded_at, power_watts, cadence, speed_mps, distance_meters, steps
+        FROM workout_sensor_samples
+        WHERE workout_data_id = ANY($1)
+        ORDER BY workout_data_id, recorded_at
+        "#,
+        workout_data_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    let mut streams: HashMap<Uuid, WorkoutStreams> = HashMap::new();
+    for row in rows {
+        let workout = streams.entry(row.workout_data_id).or_default();
+        let timestamp = row.recorded_at;
+        if let Some(watts) = row.power_watts {
+            workout.power.get_or_insert_with(Vec::new).push(PowerData { timestamp, watts });
+        }
+        if let Some(cadence) = row.cadence {
+            workout.cadence.get_or_insert_with(Vec::new).push(CadenceData { timestamp, cadence });
+        }
+        if let Some(meters_per_second) = row.speed_mps {
+            workout.speed.get_or_insert_with(Vec::new).push(SpeedData { timestamp, meters_per_second });
+        }
+        if let Some(meters) = row.distance_meters {
+            workout.distance.get_or_insert_with(Vec::new).push(DistanceData { timestamp, meters });
+        }
+        if let Some(steps) = row.steps {
+            workout.steps.get_or_insert_with(Vec::new).push(StepsData { timestamp, steps });
+        }
+    }
+    Ok(streams)
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::game::stat_decay::stat_decay;
+use crate::models::game::{StatDecayEvent, StatDecaySettings};
+
+/// A user who hasn't done an accepted workout for a while
+#[derive(Debug)]
+pub struct InactiveUser {
+    pub user_id: Uuid,
+    /// Last accepted workout, or registration for users who never trained
+    pub last_active_at: DateTime<Utc>,
+    pub decay_warned_at: Option<DateTime<Utc>>,
+    pub last_decayed_at: Option<DateTime<Utc>>,
+}
+
+pub async fn get_stat_decay_settings(conn: &mut PgConnection) -> Result<StatDecaySettings, sqlx::Error> {
+    sqlx::query_as!(
+        StatDecaySettings,
+        r#"
+        SELECT enabled, idle_days, warning_days, daily_decay_rate, floor_ratio, updated_at AS "updated_at?"
+        FROM stat_decay_settings
+        "#
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Replace the stat decay settings
+pub async fn update_stat_decay_settings(
+    pool: &PgPool,
+    settings: &StatDecaySettings,
+) -> Result<StatDecaySettings, sqlx::Error> {
+    sqlx::query_as!(
+        StatDecaySettings,
+        r#"
+        UPDATE stat_decay_settings
+        SET enabled = $1, idle_days = $2, warning_days = $3, daily_decay_rate = $4, floor_ratio = $5,
+            updated_at = NOW()
+        RETURNING enabled, idle_days, warning_days, daily_decay_rate, floor_ratio, updated_at AS "updated_at?"
+        "#,
+        settings.enabled,
+        settings.idle_days,
+        settings.warning_days,
+        settings.daily_decay_rate,
+        settings.floor_ratio
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Users whose last activity was before `inactive_since`
+pub async fn get_inactive_users(
+    pool: &PgPool,
+    inactive_since: DateTime<Utc>,
+) -> Result<Vec<InactiveUser>, sqlx::Error> {
+    sqlx::query_as!(
+        InactiveUser,
+        r#"
+        SELECT ua.user_id,
+               COALESCE(activity.last_workout_at, u.created_at) AS "last_active_at!",
+               ua.decay_warned_at,
+               (SELECT MAX(e.created_at) FROM stat_decay_events e WHERE e.user_id = ua.user_id) AS last_decayed_at
+        FROM user_avatars ua
+        JOIN users u ON u.id = ua.user_id
+        LEFT JOIN LATERAL (
+            SELECT MAX(COALESCE(wd.workout_start, wd.created_at)) AS last_workout_at
+            FROM workout_data wd
+            WHERE wd.user_id = ua.user_id AND wd.review_status = 'accepted'
+        ) activity ON TRUE
+        WHERE COALESCE(activity.last_workout_at, u.created_at) < $1
+        ORDER BY ua.user_id
+        "#,
+        inactive_since
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn mark_decay_warned(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query!("UPDATE user_avatars SET decay_warned_at = NOW() WHERE user_id = $1", user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Decay a user's stats once and record it. Returns `None` if the stats are at the floor
+/// or the user trained since `last_active_at`.
+pub async fn apply_stat_decay(
+    pool: &PgPool,
+    user_id: Uuid,
+    last_active_at: DateTime<Utc>,
+    settings: &StatDecaySettings,
+) -> Result<Option<StatDecayEvent>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let avatar = sqlx::query!(
+        "SELECT stamina, strength FROM user_avatars WHERE user_id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let trained_since = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM workout_data
+            WHERE user_id = $1 AND review_status = 'accepted'
+            AND COALESCE(workout_start, created_at) > $2
+        ) AS "exists!"
+        "#,
+        user_id,
+        last_active_at
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if trained_since {
+        return Ok(None);
+    }
+
+    let (decayed_stamina, decayed_strength) = get_decayed_stats(&mut tx, user_id).await?;
+
+    let stamina_decay = stat_decay(avatar.stamina, decayed_stamina, settings);
+    let strength_decay = stat_decay(avatar.strength, decayed_strength, settings);
+    if stamina_decay == 0 && strength_decay == 0 {
+        return Ok(None);
+    }
+
+    sqlx::query!(
+        r#"
+        UPDATE user_avatars
+        SET stamina = stamina - $1, strength = strength - $2, updated_at = NOW()
+        WHERE user_id = $3
+        "#,
+        stamina_decay,
+        strength_decay,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let event = sqlx::query_as!(
+        StatDecayEvent,
+        r#"
+        INSERT INTO stat_decay_events (
+            id, user_id, stamina_before, strength_before, stamina_change, strength_change, last_active_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, user_id, stamina_before, strength_before, stamina_change, strength_change,
+                  last_active_at, created_at
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        avatar.stamina,
+        avatar.strength,
+        -stamina_decay,
+        -strength_decay,
+        last_active_at
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some(event))
+}
+
+/// Stamina and strength decay has taken from a user so far
+pub async fn get_decayed_stats(conn: &mut PgConnection, user_id: Uuid) -> Result<(i32, i32), sqlx::Error> {
+    let decayed = sqlx::query!(
+        r#"
+        SELECT COALESCE(-SUM(stamina_change), 0)::int4 AS "stamina!",
+               COALESCE(-SUM(strength_change), 0)::int4 AS "strength!"
+        FROM stat_decay_events
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .fetch_one(conn)
+    .await?;
+    Ok((decayed.stamina, decayed.strength))
+}
+
+/// Every decay applied to a user, newest first
+pub async fn get_stat_decay_events(pool: &PgPool, user_id: Uuid) -> Result<Vec<StatDecayEvent>, sqlx::Error> {
+    sqlx::query_as!(
+        StatDecayEvent,
+        r#"
+        SELECT id, user_id, stamina_before, strength_before, stamina_change, strength_change,
+               last_active_at, created_at
+        FROM stat_decay_events
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        "#,
+        user_id
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,176 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::game::StatCaps;
+
+/// What a user has been credited on one day, the state stat caps are applied against
+#[derive(Debug, Default, Clone)]
+pub struct DailyStatGains {
+    /// Minutes in Zone1 to Zone5
+    pub zone_minutes: [f32; 5],
+    pub stamina_gained: i32,
+    pub strength_gained: i32,
+}
+
+impl DailyStatGains {
+    pub fn points(&self) -> i32 {
+        self.stamina_gained + self.strength_gained
+    }
+}
+
+/// The current stat gain caps
+pub async fn get_stat_caps(conn: &mut PgConnection) -> Result<StatCaps, sqlx::Error> {
+    sqlx::query_as!(
+        StatCaps,
+        r#"
+        SELECT zone1_daily_minutes, zone2_daily_minutes, zone3_daily_minutes, zone4_daily_minutes,
+               zone5_daily_minutes, zone_diminished_rate, daily_soft_cap, weekly_soft_cap, over_cap_rate,
+               updated_at AS "updated_at?"
+        FROM stat_gain_caps
+        "#
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Replace the stat gain caps
+pub async fn update_stat_caps(pool: &PgPool, caps: &StatCaps) -> Result<StatCaps, sqlx::Error> {
+    sqlx::query_as!(
+        StatCaps,
+        r#"
+        UPDATE stat_gain_caps
+        SET zone1_daily_minutes = $1, zone2_daily_minutes = $2, zone3_daily_minutes = $3,
+            zone4_daily_minutes = $4, zone5_daily_minutes = $5, zone_diminished_rate = $6,
+            daily_soft_cap = $7, weekly_soft_cap = $8, over_cap_rate = $9, updated_at = NOW()
+        RETURNING zone1_daily_minutes, zone2_daily_minutes, zone3_daily_minutes, zone4_daily_minutes,
+                  zone5_daily_minutes, zone_diminished_rate, daily_soft_cap, weekly_soft_cap, over_cap_rate,
+                  updated_at AS "updated_at?"
+        "#,
+        caps.zone1_daily_minutes,
+        caps.zone2_daily_minutes,
+        caps.zone3_daily_minutes,
+        caps.zone4_daily_minutes,
+        caps.zone5_daily_minutes,
+        caps.zone_diminished_rate,
+        caps.daily_soft_cap,
+        caps.weekly_soft_cap,
+        caps.over_cap_rate
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// A user's gains on a day, locked until the transaction ends so concurrent uploads are capped one at a time
+pub async fn lock_daily_stat_gains(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+) -> Result<DailyStatGains, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_daily_stat_gains (user_id, day)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id, day) DO NOTHING
+        "#,
+        user_id,
+        day
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let row = sqlx::query!(
+        r#"
+        SELECT zone1_minutes, zone2_minutes, zone3_minutes, zone4_minutes, zone5_minutes,
+               stamina_gained, strength_gained
+        FROM user_daily_stat_gains
+        WHERE user_id = $1 AND day = $2
+        FOR UPDATE
+        "#,
+        user_id,
+        day
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(DailyStatGains {
+        zone_minutes: [row.zone1_minutes, row.zone2_minutes, row.zone3_minutes, row.zone4_minutes, row.zone5_minutes],
+        stamina_gained: row.stamina_gained,
+        strength_gained: row.strength_gained,
+    })
+}
+
+/// Stamina plus strength a user gained in the Monday-to-Sunday week containing `day`
+pub async fn get_weekly_points(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+) -> Result<i32, sqlx::Error> {
+    let week_start = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+
+    let points = sqlx::query_scalar!(
+        r#"
+        SELECT COALESCE(SUM(stamina_gained + strength_gained), 0)::int AS "points!"
+        FROM user_daily_stat_gains
+        WHERE user_id = $1 AND day >= $2 AND day < $3
+        "#,
+        user_id,
+        week_start,
+        week_start + Duration::days(7)
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(points)
+}
+
+/// Add to a user's gains on a day. Negative amounts take back a retracted workout, never below zero.
+pub async fn add_daily_stat_gains(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+    gains: &DailyStatGains,
+) -> Result<(), sqlx::Error> {
+    let [zone1, zone2, zone3, zone4, zone5] = gains.zone_minutes;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO user_daily_stat_gains (
+            user_id, day, zone1_minutes, zone2_minutes, zone3_minutes, zone4_minutes, zone5_minutes,
+            stamina_gained, strength_gained
+        )
+        VALUES ($1, $2, GREATEST($3::real, 0), GREATEST($4::real, 0), GREATEST($5::real, 0), GREATEST($6::real, 0), GREATEST($7::real, 0),
+                GREATEST($8, 0), GREATEST($9, 0))
+        ON CONFLICT (user_id, day) DO UPDATE
+        SET zone1_minutes = GREATEST(user_daily_stat_gains.zone1_minutes + $3::real, 0),
+            zone2_minutes = GREATEST(user_daily_stat_gains.zone2_minutes + $4::real, 0),
+            zone3_minutes = GREATEST(user_daily_stat_gains.zone3_minutes + $5::real, 0),
+            zone4_minutes = GREATEST(user_daily_stat_gains.zone4_minutes + $6::real, 0),
+            zone5_minutes = GREATEST(user_daily_stat_gains.zone5_minutes + $7::real, 0),
+            stamina_gained = GREATEST(user_daily_stat_gains.stamina_gained + $8, 0),
+            strength_gained = GREATEST(user_daily_stat_gains.strength_gained + $9, 0)
+        "#,
+        user_id,
+        day,
+        zone1,
+        zone2,
+        zone3,
+        zone4,
+        zone5,
+        gains.stamina_gained,
+        gains.strength_gained
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Forget every user's daily gains, before they are rebuilt from a replay of all workouts
+pub async fn clear_daily_stat_gains(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM user_daily_stat_gains")
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
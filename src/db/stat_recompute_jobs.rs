@@ -0,0 +1,155 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::game::{StatRecomputeJob, StatRecomputeMode, StatRecomputeReport, StatRecomputeStatus};
+
+/// Queue a stat recompute job. Fails with a unique violation if the dry run was already applied.
+pub async fn create_stat_recompute_job(
+    pool: &PgPool,
+    requested_by: Uuid,
+    mode: StatRecomputeMode,
+    rebuild_live_games: bool,
+    dry_run_job_id: Option<Uuid>,
+) -> Result<StatRecomputeJob, sqlx::Error> {
+    sqlx::query_as!(
+        StatRecomputeJob,
+        r#"
+        INSERT INTO stat_recompute_jobs (id, requested_by, mode, rebuild_live_games, dry_run_job_id, status)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, requested_by, mode, rebuild_live_games, dry_run_job_id, status, report, error,
+                  created_at, started_at, completed_at
+        "#,
+        Uuid::new_v4(),
+        requested_by,
+        mode.as_str(),
+        rebuild_live_games,
+        dry_run_job_id,
+        StatRecomputeStatus::Queued.as_str()
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_stat_recompute_job(pool: &PgPool, job_id: Uuid) -> Result<Option<StatRecomputeJob>, sqlx::Error> {
+    sqlx::query_as!(
+        StatRecomputeJob,
+        r#"
+        SELECT id, requested_by, mode, rebuild_live_games, dry_run_job_id, status, report, error,
+               created_at, started_at, completed_at
+        FROM stat_recompute_jobs
+        WHERE id = $1
+        "#,
+        job_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Mark a queued job as running. Returns false if it was failed as interrupted in the meantime.
+pub async fn start_stat_recompute_job(pool: &PgPool, job_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE stat_recompute_jobs
+        SET status = 'running', started_at = NOW(), heartbeat_at = NOW()
+        WHERE id = $1 AND status = 'queued'
+        "#,
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Show that a running job is still alive. Returns false once it no longer runs.
+pub async fn touch_stat_recompute_job(pool: &PgPool, job_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE stat_recompute_jobs SET heartbeat_at = NOW() WHERE id = $1 AND status = 'running'",
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Finish a running job with its report and, for a dry run, the writes applying it makes
+pub async fn finish_stat_recompute_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    report: &StatRecomputeReport,
+    plan: Option<&serde_json::Value>,
+) -> Result<(), sqlx::Error> {
+    let report = serde_json::to_value(report)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize recompute report: {}", e)))?;
+
+    sqlx::query!(
+        r#"
+        UPDATE stat_recompute_jobs
+        SET status = 'completed', report = $1, plan = $2, completed_at = NOW()
+        WHERE id = $3 AND status = 'running'
+        "#,
+        report,
+        plan,
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Give up on a running job. Nothing it computed was stored.
+pub async fn fail_stat_recompute_job(pool: &PgPool, job_id: Uuid, error: &str) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE stat_recompute_jobs
+        SET status = 'failed', error = $1, completed_at = NOW()
+        WHERE id = $2 AND status = 'running'
+        "#,
+        error,
+        job_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Fail jobs whose instance stopped before finishing them: running jobs that stopped refreshing
+/// their heartbeat and queued jobs that never started. Returns the number failed.
+pub async fn fail_interrupted_stat_recompute_jobs(pool: &PgPool, stale_seconds: f64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE stat_recompute_jobs
+        SET status = 'failed', error = 'Interrupted before it finished', completed_at = NOW()
+        WHERE status IN ('queued', 'running')
+        AND COALESCE(heartbeat_at, created_at) < NOW() - make_interval(secs => $1)
+        "#,
+        stale_seconds
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// The reviewed report and plan of the dry run an apply job was approved from
+pub async fn get_reviewed_dry_run(
+    pool: &PgPool,
+    apply_job_id: Uuid,
+) -> Result<Option<(serde_json::Value, serde_json::Value)>, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT d.report, d.plan
+        FROM stat_recompute_jobs a
+        JOIN stat_recompute_jobs d ON d.id = a.dry_run_job_id
+        WHERE a.id = $1
+        "#,
+        apply_job_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|row| row.report.zip(row.plan)))
+}
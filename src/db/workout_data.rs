@@ -1,9 +1,45 @@
-use sqlx::{Pool, Postgres};
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{PgConnection, Pool, Postgres};
 use uuid::Uuid;
 use serde_json::json;
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::heart_rate_samples::{get_heart_rate_samples, get_heart_rate_samples_for_workouts, insert_heart_rate_samples};
+use crate::db::sensor_samples::{get_sensor_streams, get_sensor_streams_for_workouts, insert_sensor_samples};
+use crate::game::stats_calculator::StatChanges;
+use crate::models::workout_data::{
+    WorkoutDataSyncRequest, HeartRateData, PowerData, CadenceData, DistanceData, StepsData,
+    ValidationIssue, WeeklyStats, MonthlyTrend, WorkoutReviewStatus, WorkoutStreams, WorkoutType,
+    ExportedWorkout, StrengthExercise, strength_volume_load,
+};
 
-use crate::models::workout_data::{WorkoutDataSyncRequest, HeartRateData};
+/// A stored workout whose time window overlaps a new upload
+#[derive(Debug)]
+pub struct OverlappingWorkout {
+    pub id: Uuid,
+    pub device_id: String,
+    pub workout_uuid: String,
+    pub workout_start: DateTime<Utc>,
+    pub workout_end: DateTime<Utc>,
+    pub heart_rate: Vec<HeartRateData>,
+    pub calories_burned: Option<i32>,
+    pub workout_type: Option<WorkoutType>,
+    pub streams: WorkoutStreams,
+    pub strength_exercises: Option<Vec<StrengthExercise>>,
+    pub superseded_workout_uuids: Vec<String>,
+    /// Whether the workout was scored, rather than held for review
+    pub accepted: bool,
+}
+
+/// An accepted workout rebuilt into the upload it was scored from, with what it was credited
+#[derive(Debug)]
+pub struct ScoredWorkout {
+    pub id: Uuid,
+    pub stamina_gained: i32,
+    pub strength_gained: i32,
+    pub data: WorkoutDataSyncRequest,
+}
 
 /// Calculate duration in minutes from start/end times
 fn calculate_duration_minutes(data: &WorkoutDataSyncRequest) -> Option<i32> {
@@ -40,9 +76,54 @@ fn calculate_min_heart_rate(heart_rate_data: &[HeartRateData]) -> Option<i32> {
     heart_rate_data.iter().map(|hr| hr.heart_rate).reduce(i32::min)
 }
 
+/// Read the `strength_exercises` JSONB column
+pub fn parse_strength_exercises(json: Option<serde_json::Value>) -> Option<Vec<StrengthExercise>> {
+    json.and_then(|json| serde_json::from_value(json).ok())
+}
+
+/// Calculate average and maximum power from power meter data
+fn calculate_power_summary(power_data: &[PowerData]) -> (Option<i32>, Option<i32>) {
+    if power_data.is_empty() {
+        return (None, None);
+    }
+
+    let sum: i64 = power_data.iter().map(|sample| sample.watts as i64).sum();
+    let max = power_data.iter().map(|sample| sample.watts).reduce(i32::max);
+    (Some((sum / power_data.len() as i64) as i32), max)
+}
+
+/// Calculate average cadence, ignoring zero samples recorded while coasting or standing still
+fn calculate_avg_cadence(cadence_data: &[CadenceData]) -> Option<i32> {
+    let moving: Vec<i32> = cadence_data.iter()
+        .map(|sample| sample.cadence)
+        .filter(|cadence| *cadence > 0)
+        .collect();
+    if moving.is_empty() {
+        return None;
+    }
+
+    Some(moving.iter().sum::<i32>() / moving.len() as i32)
+}
+
+/// Distance samples are cumulative, so the total is the furthest one
+fn calculate_total_distance(distance_data: &[DistanceData]) -> Option<f64> {
+    distance_data.iter().map(|sample| sample.meters).reduce(f64::max)
+}
+
+/// Summed in i64 so a long step stream can't overflow, then saturated to the column's range
+fn calculate_total_steps(steps_data: &[StepsData]) -> Option<i32> {
+    if steps_data.is_empty() {
+        return None;
+    }
+    let total: i64 = steps_data.iter().map(|sample| sample.steps as i64).sum();
+    Some(total.clamp(0, i32::MAX as i64) as i32)
+}
+
+/// Insert a workout together with the stats it earned, its review state and its heart rate samples.
+/// Takes a connection so the insert can share a transaction with the avatar and live game updates.
 #[tracing::instrument(
     name = "Insert workout data into database",
-    skip(pool, data),
+    skip(conn, data, stat_changes, validation_flags, superseded_workout_uuids),
     fields(
         user_id = %user_id,
         workout_uuid = ?data.workout_uuid,
@@ -50,9 +131,13 @@ fn calculate_min_heart_rate(heart_rate_data: &[HeartRateData]) -> Option<i32> {
     )
 )]
 pub async fn insert_workout_data(
-    pool: &Pool<Postgres>,
+    conn: &mut PgConnection,
     user_id: Uuid,
     data: &WorkoutDataSyncRequest,
+    stat_changes: &StatChanges,
+    review_status: WorkoutReviewStatus,
+    validation_flags: &[ValidationIssue],
+    superseded_workout_uuids: &[String],
 ) -> Result<Uuid, sqlx::Error> {
     tracing::info!("Attempting to insert workout data for user");
     
@@ -68,20 +153,50 @@ pub async fn insert_workout_data(
     } else {
         (None, None, None)
     };
+
+    let (avg_power, max_power) = data.power.as_deref()
+        .map(calculate_power_summary)
+        .unwrap_or((None, None));
+    let avg_cadence = data.cadence.as_deref().and_then(calculate_avg_cadence);
+    let total_distance_meters = data.distance.as_deref().and_then(calculate_total_distance);
+    let total_steps = data.steps.as_deref().and_then(calculate_total_steps);
+    let strength_volume_load_kg = data.strength_exercises.as_deref().map(strength_volume_load);
+
+    let heart_rate_samples = data.heart_rate.as_deref().unwrap_or_default();
+    let heart_rate_sample_count = heart_rate_samples.iter()
+        .map(|sample| sample.timestamp)
+        .collect::<HashSet<_>>()
+        .len() as i32;
+
+    let zone_breakdown_json = stat_changes.zone_breakdown.as_ref()
+        .map(|breakdown| serde_json::to_value(breakdown).unwrap_or(serde_json::Value::Null));
+    let training_load = stat_changes.training_load;
+    let validation_flags_json = if validation_flags.is_empty() {
+        None
+    } else {
+        Some(json!(validation_flags))
+    };
     
     let record = sqlx::query!(
         r#"
         INSERT INTO workout_data (
-            user_id, device_id, heart_rate_data, 
+            user_id, device_id, heart_rate_sample_count,
             calories_burned, workout_uuid, workout_start, workout_end,
-            duration_minutes, avg_heart_rate, max_heart_rate, min_heart_rate
+            duration_minutes, avg_heart_rate, max_heart_rate, min_heart_rate,
+            heart_rate_zones, stamina_gained, strength_gained, total_points_gained,
+            review_status, validation_flags,
+            avg_power, max_power, avg_cadence, total_distance_meters, total_steps,
+            workout_type, superseded_workout_uuids,
+            banister_trimp, edwards_trimp, intensity_factor, epoc_estimate,
+            strength_exercises, strength_volume_load_kg
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17,
+                $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28, $29, $30)
         RETURNING id
         "#,
         user_id,
         &data.device_id,
-        json!(data.heart_rate),
+        heart_rate_sample_count,
         data.calories_burned,
         data.workout_uuid,
         data.workout_start,
@@ -89,9 +204,28 @@ pub async fn insert_workout_data(
         duration_minutes,
         avg_heart_rate,
         max_heart_rate,
-        min_heart_rate
+        min_heart_rate,
+        zone_breakdown_json,
+        stat_changes.stamina_change,
+        stat_changes.strength_change,
+        stat_changes.stamina_change + stat_changes.strength_change,
+        review_status.as_str(),
+        validation_flags_json,
+        avg_power,
+        max_power,
+        avg_cadence,
+        total_distance_meters,
+        total_steps,
+        data.workout_type.map(|workout_type| workout_type.as_str()),
+        superseded_workout_uuids,
+        training_load.map(|load| load.banister_trimp),
+        training_load.map(|load| load.edwards_trimp),
+        training_load.map(|load| load.intensity_factor),
+        training_load.map(|load| load.epoc_estimate),
+        data.strength_exercises.as_ref().map(|exercises| json!(exercises)),
+        strength_volume_load_kg
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *conn)
     .await
     .map_err(|e| {
         // Check if this is a unique constraint violation
@@ -125,6 +259,9 @@ pub async fn insert_workout_data(
         e
     })?;
     
+    insert_heart_rate_samples(conn, record.id, heart_rate_samples).await?;
+    insert_sensor_samples(conn, record.id, data).await?;
+
     tracing::info!("Successfully inserted workout data with id: {}", record.id);
     Ok(record.id)
 }
@@ -147,7 +284,7 @@ pub async fn check_workout_uuid_exists(
     let record = sqlx::query!(
         r#"
         SELECT id FROM workout_data 
-        WHERE user_id = $1 AND workout_uuid = $2
+        WHERE user_id = $1 AND (workout_uuid = $2 OR $2 = ANY(superseded_workout_uuids))
         "#,
         user_id,
         workout_uuid
@@ -159,4 +296,447 @@ pub async fn check_workout_uuid_exists(
     tracing::info!("Workout UUID check result: exists={}", exists);
     
     Ok(exists)
-}
\ No newline at end of file
+}
+#[tracing::instrument(
+    name = "Get synced workout UUIDs",
+    skip(pool, workout_uuids),
+    fields(
+        user_id = %user_id,
+        uuid_count = workout_uuids.len()
+    )
+)]
+pub async fn get_synced_workout_uuids(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    workout_uuids: &[String],
+) -> Result<Vec<String>, sqlx::Error> {
+    // Recordings merged into or replaced by another workout count as synced too
+    let existing_uuids = sqlx::query_scalar!(
+        r#"
+        SELECT requested.workout_uuid AS "workout_uuid!"
+        FROM UNNEST($2::text[]) AS requested(workout_uuid)
+        WHERE EXISTS (
+            SELECT 1 FROM workout_data
+            WHERE user_id = $1
+            AND (workout_uuid = requested.workout_uuid OR requested.workout_uuid = ANY(superseded_workout_uuids))
+        )
+        "#,
+        user_id,
+        workout_uuids
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(existing_uuids)
+}
+
+/// Hold the user's workout lock until the caller's transaction ends, so their uploads are checked
+/// for overlaps and stored one at a time
+pub async fn lock_user_workouts(conn: &mut PgConnection, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))")
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Find the user's stored workouts whose time window overlaps `start`..`end`.
+/// Rejected workouts never scored, so they aren't considered.
+pub async fn find_overlapping_workouts(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<OverlappingWorkout>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, device_id, workout_uuid, workout_start AS "workout_start!", workout_end AS "workout_end!",
+               calories_burned, workout_type, superseded_workout_uuids,
+               strength_exercises, review_status = 'accepted' AS "accepted!"
+        FROM workout_data
+        WHERE user_id = $1
+        AND review_status <> 'rejected'
+        AND workout_start IS NOT NULL AND workout_end IS NOT NULL
+        AND workout_start < $3 AND workout_end > $2
+        ORDER BY workout_start
+        "#,
+        user_id,
+        start,
+        end
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let workout_ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+    let mut heart_rate = get_heart_rate_samples_for_workouts(&mut *conn, &workout_ids).await?;
+    let mut streams = get_sensor_streams_for_workouts(conn, &workout_ids).await?;
+
+    Ok(rows.into_iter().map(|row| OverlappingWorkout {
+        id: row.id,
+        device_id: row.device_id,
+        workout_uuid: row.workout_uuid,
+        workout_start: row.workout_start,
+        workout_end: row.workout_end,
+        heart_rate: heart_rate.remove(&row.id).unwrap_or_default(),
+        calories_burned: row.calories_burned,
+        workout_type: row.workout_type.as_deref().map(WorkoutType::from_name),
+        streams: streams.remove(&row.id).unwrap_or_default(),
+        strength_exercises: parse_strength_exercises(row.strength_exercises),
+        superseded_workout_uuids: row.superseded_workout_uuids,
+        accepted: row.accepted,
+    }).collect())
+}
+
+/// A user's summed training load over the acute and chronic windows ending at `until`,
+/// counting accepted workouts only. Sums Edwards TRIMP if `edwards` is set, Banister TRIMP otherwise.
+pub async fn get_training_load_sums(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    edwards: bool,
+    until: DateTime<Utc>,
+    acute_days: i64,
+    chronic_days: i64,
+) -> Result<(f64, f64), sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(load) FILTER (WHERE started_at > $3::timestamptz - make_interval(days => $4)), 0) AS "acute!",
+            COALESCE(SUM(load), 0) AS "chronic!"
+        FROM (
+            SELECT CASE WHEN $2 THEN edwards_trimp ELSE banister_trimp END::float8 AS load,
+                   COALESCE(workout_start, created_at) AS started_at
+            FROM workout_data
+            WHERE user_id = $1
+            AND review_status = 'accepted'
+            AND COALESCE(workout_start, created_at) > $3::timestamptz - make_interval(days => $5)
+            AND COALESCE(workout_start, created_at) <= $3
+        ) loads
+        "#,
+        user_id,
+        edwards,
+        until,
+        acute_days as i32,
+        chronic_days as i32
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok((row.acute, row.chronic))
+}
+
+/// Minutes per heart rate zone across the user's accepted workouts started in the window,
+/// summed from their stored zone breakdowns and keyed by zone ("Zone1" to "Zone5")
+pub async fn get_zone_minutes(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<HashMap<String, f64>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT zone.value->>'zone' AS "zone!", SUM((zone.value->>'minutes')::float8) AS "minutes!"
+        FROM workout_data wd
+        CROSS JOIN LATERAL jsonb_array_elements(wd.heart_rate_zones) AS zone
+        WHERE wd.user_id = $1
+        AND wd.review_status = 'accepted'
+        AND jsonb_typeof(wd.heart_rate_zones) = 'array'
+        AND COALESCE(wd.workout_start, wd.created_at) >= $2
+        AND COALESCE(wd.workout_start, wd.created_at) < $3
+        GROUP BY zone.value->>'zone'
+        "#,
+        user_id,
+        since,
+        until
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.zone, row.minutes)).collect())
+}
+
+/// Calories, exercise time and sessions of the user's accepted workouts started since `since`.
+/// Strength workouts, and untyped ones that built more strength than stamina, count as strength sessions.
+pub async fn get_weekly_stats(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<WeeklyStats, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            COALESCE(SUM(calories_burned), 0)::int4 AS "total_calories!",
+            COALESCE(SUM(duration_minutes), 0)::int4 AS "total_exercise_time!",
+            COUNT(*) FILTER (WHERE is_strength)::int4 AS "strength_sessions!",
+            COUNT(*) FILTER (WHERE NOT is_strength)::int4 AS "cardio_sessions!"
+        FROM (
+            SELECT calories_burned, duration_minutes,
+                   workout_type = 'strength'
+                   OR (COALESCE(workout_type, 'other') NOT IN ('run', 'ride', 'swim', 'walk')
+                       AND strength_gained > stamina_gained) AS is_strength
+            FROM workout_data
+            WHERE user_id = $1
+            AND review_status = 'accepted'
+            AND COALESCE(workout_start, created_at) >= $2
+        ) workouts
+        "#,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(WeeklyStats {
+        total_calories: row.total_calories,
+        total_exercise_time: row.total_exercise_time,
+        strength_sessions: row.strength_sessions,
+        cardio_sessions: row.cardio_sessions,
+    })
+}
+
+/// Stamina and strength the user gained from accepted workouts started since `since`
+pub async fn get_stat_gains_since(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<MonthlyTrend, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT COALESCE(SUM(stamina_gained), 0)::int4 AS "stamina_gain!",
+               COALESCE(SUM(strength_gained), 0)::int4 AS "strength_gain!"
+        FROM workout_data
+        WHERE user_id = $1
+        AND review_status = 'accepted'
+        AND COALESCE(workout_start, created_at) >= $2
+        "#,
+        user_id,
+        since
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(MonthlyTrend {
+        stamina_gain: row.stamina_gain,
+        strength_gain: row.strength_gain,
+    })
+}
+
+/// A page of the user's workouts started in `from`..`to`, oldest first, without their samples;
+/// see `load_exported_workout_samples`. Pages continue after the `(workout_date, id)` of the last
+/// workout of the previous page.
+pub async fn get_exported_workouts(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> Result<Vec<ExportedWorkout>, sqlx::Error> {
+    query_exported_workouts(pool, user_id, None, from, to, after, limit).await
+}
+
+/// One of the user's workouts with its samples, `None` if it isn't theirs
+pub async fn get_exported_workout(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    workout_id: Uuid,
+) -> Result<Option<ExportedWorkout>, sqlx::Error> {
+    let workouts = query_exported_workouts(pool, user_id, Some(workout_id), None, None, None, 1).await?;
+    let Some(mut workout) = workouts.into_iter().next() else {
+        return Ok(None);
+    };
+    load_exported_workout_samples(pool, &mut workout).await?;
+    Ok(Some(workout))
+}
+
+/// Fill in the heart rate and sensor streams of an exported workout
+pub async fn load_exported_workout_samples(
+    pool: &Pool<Postgres>,
+    workout: &mut ExportedWorkout,
+) -> Result<(), sqlx::Error> {
+    workout.streams = get_sensor_streams(&mut *pool.acquire().await?, workout.id).await?;
+    workout.heart_rate = get_heart_rate_samples(pool, workout.id).await?;
+    Ok(())
+}
+
+async fn query_exported_workouts(
+    pool: &Pool<Postgres>,
+    user_id: Uuid,
+    workout_id: Option<Uuid>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> Result<Vec<ExportedWorkout>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, workout_uuid, device_id, COALESCE(workout_start, created_at) AS "workout_date!",
+               workout_start, workout_end, workout_type, duration_minutes, calories_burned,
+               avg_heart_rate, max_heart_rate, min_heart_rate, total_distance_meters,
+               stamina_gained, strength_gained, review_status
+        FROM workout_data
+        WHERE user_id = $1
+        AND ($2::uuid IS NULL OR id = $2)
+        AND ($3::timestamptz IS NULL OR COALESCE(workout_start, created_at) >= $3)
+        AND ($4::timestamptz IS NULL OR COALESCE(workout_start, created_at) < $4)
+        AND ($5::timestamptz IS NULL OR (COALESCE(workout_start, created_at), id) > ($5, $6::uuid))
+        ORDER BY COALESCE(workout_start, created_at), id
+        LIMIT $7
+        "#,
+        user_id,
+        workout_id,
+        from,
+        to,
+        after.map(|(workout_date, _)| workout_date),
+        after.map(|(_, id)| id),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| ExportedWorkout {
+        id: row.id,
+        workout_uuid: row.workout_uuid,
+        device_id: row.device_id,
+        workout_date: row.workout_date,
+        workout_start: row.workout_start,
+        workout_end: row.workout_end,
+        workout_type: row.workout_type,
+        duration_minutes: row.duration_minutes,
+        calories_burned: row.calories_burned,
+        avg_heart_rate: row.avg_heart_rate,
+        max_heart_rate: row.max_heart_rate,
+        min_heart_rate: row.min_heart_rate,
+        total_distance_meters: row.total_distance_meters,
+        stamina_gained: row.stamina_gained,
+        strength_gained: row.strength_gained,
+        review_status: row.review_status,
+        heart_rate: Vec::new(),
+        streams: WorkoutStreams::default(),
+    }).collect())
+}
+
+/// Users with at least one accepted workout
+pub async fn get_users_with_accepted_workouts(conn: &mut PgConnection) -> Result<Vec<(Uuid, String)>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT u.id, u.username
+        FROM users u
+        WHERE EXISTS (SELECT 1 FROM workout_data wd WHERE wd.user_id = u.id AND wd.review_status = 'accepted')
+        ORDER BY u.username
+        "#
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| (row.id, row.username)).collect())
+}
+
+/// Ids of a user's accepted workouts in the order they happened, by start time and then upload time
+pub async fn get_accepted_workout_ids(conn: &mut PgConnection, user_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT id
+        FROM workout_data
+        WHERE user_id = $1 AND review_status = 'accepted'
+        ORDER BY COALESCE(workout_start, created_at), created_at
+        "#,
+        user_id
+    )
+    .fetch_all(conn)
+    .await
+}
+
+/// A stored workout rebuilt into the upload it was scored from, with its heart rate samples
+pub async fn get_scored_workout(conn: &mut PgConnection, workout_data_id: Uuid) -> Result<ScoredWorkout, sqlx::Error> {
+    let row = sqlx::query!(
+        r#"
+        SELECT id, device_id, calories_burned, workout_uuid, workout_start, workout_end, created_at,
+               workout_type, stamina_gained, strength_gained, strength_exercises
+        FROM workout_data
+        WHERE id = $1
+        "#,
+        workout_data_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let mut heart_rate = get_heart_rate_samples_for_workouts(&mut *conn, &[row.id]).await?;
+    let streams = get_sensor_streams(conn, row.id).await?;
+    Ok(ScoredWorkout {
+        id: row.id,
+        stamina_gained: row.stamina_gained,
+        strength_gained: row.strength_gained,
+        data: WorkoutDataSyncRequest {
+            device_id: row.device_id,
+            timestamp: row.created_at,
+            heart_rate: heart_rate.remove(&row.id),
+            calories_burned: row.calories_burned,
+            workout_uuid: row.workout_uuid,
+            workout_start: row.workout_start,
+            workout_end: row.workout_end,
+            workout_type: row.workout_type.as_deref().map(WorkoutType::from_name),
+            overlap_policy: None,
+            power: streams.power,
+            cadence: streams.cadence,
+            speed: streams.speed,
+            distance: streams.distance,
+            steps: streams.steps,
+            strength_exercises: parse_strength_exercises(row.strength_exercises),
+        },
+    })
+}
+
+/// Digest of every accepted workout and what it's credited, to tell whether any was stored,
+/// retracted or rescored since it was taken
+pub async fn accepted_workouts_fingerprint(conn: &mut PgConnection) -> Result<String, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT md5(COALESCE(string_agg(id::text || ':' || stamina_gained || ':' || strength_gained, ',' ORDER BY id), ''))
+            AS "fingerprint!"
+        FROM workout_data
+        WHERE review_status = 'accepted'
+        "#
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Overwrite the stats a workout was credited with
+pub async fn update_workout_stats(
+    conn: &mut PgConnection,
+    workout_data_id: Uuid,
+    stat_changes: &StatChanges,
+) -> Result<(), sqlx::Error> {
+    let zone_breakdown_json = stat_changes.zone_breakdown.as_ref()
+        .map(|breakdown| serde_json::to_value(breakdown).unwrap_or(serde_json::Value::Null));
+
+    sqlx::query!(
+        r#"
+        UPDATE workout_data
+        SET heart_rate_zones = $1,
+            stamina_gained = $2,
+            strength_gained = $3,
+            total_points_gained = $4,
+            banister_trimp = $5,
+            edwards_trimp = $6,
+            intensity_factor = $7,
+            epoc_estimate = $8,
+            updated_at = NOW()
+        WHERE id = $9
+        "#,
+        zone_breakdown_json,
+        stat_changes.stamina_change,
+        stat_changes.strength_change,
+        stat_changes.stamina_change + stat_changes.strength_change,
+        stat_changes.training_load.map(|load| load.banister_trimp),
+        stat_changes.training_load.map(|load| load.edwards_trimp),
+        stat_changes.training_load.map(|load| load.intensity_factor),
+        stat_changes.training_load.map(|load| load.epoc_estimate),
+        workout_data_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::workout_data::{WorkoutDataSyncRequest, WorkoutJobStatus, WorkoutProcessingJob};
+
+/// A job claimed by a queue worker, with what it needs to run the upload pipeline
+#[derive(Debug)]
+pub struct ClaimedWorkoutJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub username: String,
+    pub workout_uuid: String,
+    pub payload: Option<serde_json::Value>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub workout_data_id: Option<Uuid>,
+    /// Upload session whose staged heart rate samples belong to the payload
+    pub upload_session_id: Option<Uuid>,
+}
+
+/// Queue a workout upload. Fails with a unique violation if the same workout is already queued.
+pub async fn enqueue_workout_job(
+    pool: &PgPool,
+    user_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+) -> Result<Uuid, sqlx::Error> {
+    let payload = serde_json::to_value(data)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize workout payload: {}", e)))?;
+
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO workout_processing_jobs (id, user_id, workout_uuid, payload, status)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        data.workout_uuid,
+        payload,
+        WorkoutJobStatus::Queued.as_str()
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Queue the workout of a finalized upload session. The payload carries the metadata only,
+/// the worker loads the staged samples. Fails with a unique violation if the workout is already queued.
+pub async fn enqueue_upload_session_job(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    upload_session_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+) -> Result<Uuid, sqlx::Error> {
+    let payload = serde_json::to_value(data)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize workout payload: {}", e)))?;
+
+    sqlx::query_scalar!(
+        r#"
+        INSERT INTO workout_processing_jobs (id, user_id, workout_uuid, payload, status, upload_session_id)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        data.workout_uuid,
+        payload,
+        WorkoutJobStatus::Queued.as_str(),
+        upload_session_id
+    )
+    .fetch_one(conn)
+    .await
+}
+
+/// Claim the next due job, including jobs whose worker died without finishing them.
+/// `SKIP LOCKED` lets several workers and app instances poll the same table.
+pub async fn claim_next_workout_job(
+    pool: &PgPool,
+    stale_lock_seconds: f64,
+) -> Result<Option<ClaimedWorkoutJob>, sqlx::Error> {
+    sqlx::query_as!(
+        ClaimedWorkoutJob,
+        r#"
+        WITH next_job AS (
+            SELECT id
+            FROM workout_processing_jobs
+            WHERE (status = 'queued' AND run_at <= NOW())
+               OR (status = 'processing' AND locked_at < NOW() - make_interval(secs => $1))
+            ORDER BY run_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        UPDATE workout_processing_jobs j
+        SET status = 'processing',
+            attempts = j.attempts + 1,
+            locked_at = NOW(),
+            updated_at = NOW()
+        FROM next_job, users u
+        WHERE j.id = next_job.id AND u.id = j.user_id
+        RETURNING j.id, j.user_id, u.username, j.workout_uuid, j.payload,
+                  j.attempts, j.max_attempts, j.workout_data_id, j.upload_session_id
+        "#,
+        stale_lock_seconds
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record the stored workout on the job inside the transaction that stores it
+pub async fn link_workout_to_job(
+    conn: &mut PgConnection,
+    job_id: Uuid,
+    workout_data_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE workout_processing_jobs SET workout_data_id = $1, updated_at = NOW() WHERE id = $2",
+        workout_data_id,
+        job_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Keep a running job's lock fresh so it isn't taken for the job of a dead worker.
+/// Returns false if the attempt no longer holds the job.
+pub async fn touch_workout_job_lock(
+    pool: &PgPool,
+    job_id: Uuid,
+    attempt: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE workout_processing_jobs
+        SET locked_at = NOW()
+        WHERE id = $1 AND attempts = $2 AND status = 'processing'
+        "#,
+        job_id,
+        attempt
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Finish a job as completed or rejected. The payload is dropped since the outcome is final.
+/// Only the attempt that holds the job can finish it; returns false if a later attempt took over.
+pub async fn finish_workout_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    attempt: i32,
+    status: WorkoutJobStatus,
+    result: &serde_json::Value,
+) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query!(
+        r#"
+        UPDATE workout_processing_jobs
+        SET status = $1, result = $2, payload = NULL, locked_at = NULL,
+            completed_at = NOW(), updated_at = NOW()
+        WHERE id = $3 AND attempts = $4 AND status = 'processing'
+        "#,
+        status.as_str(),
+        result,
+        job_id,
+        attempt
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(updated.rows_affected() > 0)
+}
+
+/// Put a failed job back in the queue to run again after `delay_seconds`.
+/// Returns false if a later attempt took over the job.
+pub async fn retry_workout_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    attempt: i32,
+    error: &str,
+    delay_seconds: f64,
+) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query!(
+        r#"
+        UPDATE workout_processing_jobs
+        SET status = 'queued', last_error = $1, locked_at = NULL,
+            run_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+        WHERE id = $3 AND attempts = $4 AND status = 'processing'
+        "#,
+        error,
+        delay_seconds,
+        job_id,
+        attempt
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(updated.rows_affected() > 0)
+}
+
+/// Give up on a job. The payload is kept so the upload can be inspected or re-queued, and so are the
+/// staged samples of an upload session until the session is cleaned up as abandoned.
+/// Returns false if a later attempt took over the job.
+pub async fn fail_workout_job(
+    pool: &PgPool,
+    job_id: Uuid,
+    attempt: i32,
+    error: &str,
+) -> Result<bool, sqlx::Error> {
+    let updated = sqlx::query!(
+        r#"
+        UPDATE workout_processing_jobs
+        SET status = 'failed', last_error = $1, locked_at = NULL,
+            completed_at = NOW(), updated_at = NOW()
+        WHERE id = $2 AND attempts = $3 AND status = 'processing'
+        "#,
+        error,
+        job_id,
+        attempt
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(updated.rows_affected() > 0)
+}
+
+/// Fetch a job if it belongs to the user
+pub async fn get_workout_job_for_user(
+    pool: &PgPool,
+    job_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<WorkoutProcessingJob>, sqlx::Error> {
+    sqlx::query_as!(
+        WorkoutProcessingJob,
+        r#"
+        SELECT id, user_id, workout_uuid, status, attempts, max_attempts, last_error,
+               workout_data_id, result, created_at, updated_at, completed_at
+        FROM workout_processing_jobs
+        WHERE id = $1 AND user_id = $2
+        "#,
+        job_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
@@ -0,0 +1,344 @@
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::workout_data::{HeartRateData, UploadSessionStatus, WorkoutDataSyncRequest, WorkoutUploadSession};
+
+/// Open an upload session for a workout. Returns `None` if the user already has one open for it.
+pub async fn create_upload_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+) -> Result<Option<WorkoutUploadSession>, sqlx::Error> {
+    let metadata = serde_json::to_value(data)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize workout metadata: {}", e)))?;
+
+    sqlx::query_as!(
+        WorkoutUploadSession,
+        r#"
+        INSERT INTO workout_upload_sessions (id, user_id, workout_uuid, metadata, status)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (user_id, workout_uuid) WHERE status = 'open' DO NOTHING
+        RETURNING id, user_id, workout_uuid, metadata, status, sample_count,
+                  created_at, updated_at, finalized_at
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        data.workout_uuid,
+        metadata,
+        UploadSessionStatus::Open.as_str()
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// The user's open session for a workout, if any
+pub async fn get_open_upload_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    workout_uuid: &str,
+) -> Result<Option<WorkoutUploadSession>, sqlx::Error> {
+    sqlx::query_as!(
+        WorkoutUploadSession,
+        r#"
+        SELECT id, user_id, workout_uuid, metadata, status, sample_count,
+               created_at, updated_at, finalized_at
+        FROM workout_upload_sessions
+        WHERE user_id = $1 AND workout_uuid = $2 AND status = 'open'
+        "#,
+        user_id,
+        workout_uuid
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Fetch a session if it belongs to the user
+pub async fn get_upload_session_for_user(
+    pool: &PgPool,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<WorkoutUploadSession>, sqlx::Error> {
+    sqlx::query_as!(
+        WorkoutUploadSession,
+        r#"
+        SELECT id, user_id, workout_uuid, metadata, status, sample_count,
+               created_at, updated_at, finalized_at
+        FROM workout_upload_sessions
+        WHERE id = $1 AND user_id = $2
+        "#,
+        session_id,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Fetch and lock a session if it belongs to the user, so chunks and finalizing don't interleave
+pub async fn lock_upload_session_for_user(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<Option<WorkoutUploadSession>, sqlx::Error> {
+    sqlx::query_as!(
+        WorkoutUploadSession,
+        r#"
+        SELECT id, user_id, workout_uuid, metadata, status, sample_count,
+               created_at, updated_at, finalized_at
+        FROM workout_upload_sessions
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        session_id,
+        user_id
+    )
+    .fetch_optional(conn)
+    .await
+}
+
+/// Stage a chunk of samples. Timestamps already staged keep their first reading.
+/// Returns the number of new samples.
+pub async fn insert_upload_session_samples(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    samples: &[HeartRateData],
+) -> Result<u64, sqlx::Error> {
+    if samples.is_empty() {
+        return Ok(0);
+    }
+
+    let (timestamps, heart_rates): (Vec<DateTime<Utc>>, Vec<i16>) = samples.iter()
+        .map(|sample| (sample.timestamp, sample.heart_rate.clamp(0, i16::MAX as i32) as i16))
+        .unzip();
+
+    let inserted = sqlx::query!(
+        r#"
+        INSERT INTO workout_upload_session_samples (session_id, recorded_at, heart_rate)
+        SELECT $1, recorded_at, heart_rate
+        FROM UNNEST($2::timestamptz[], $3::smallint[]) AS samples(recorded_at, heart_rate)
+        ON CONFLICT (session_id, recorded_at) DO NOTHING
+        "#,
+        session_id,
+        &timestamps,
+        &heart_rates
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+        r#"
+        UPDATE workout_upload_sessions
+        SET sample_count = sample_count + $1, updated_at = NOW()
+        WHERE id = $2
+        "#,
+        inserted as i32,
+        session_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(inserted)
+}
+
+/// Stream a session's staged samples in time order, without loading them all at once
+pub fn stream_upload_session_samples(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+) -> BoxStream<'_, Result<HeartRateData, sqlx::Error>> {
+    sqlx::query_as!(
+        HeartRateData,
+        r#"
+        SELECT recorded_at AS timestamp, heart_rate::int AS "heart_rate!"
+        FROM workout_upload_session_samples
+        WHERE session_id = $1
+        ORDER BY recorded_at
+        "#,
+        session_id
+    )
+    .fetch(conn)
+}
+
+/// Stream a session's staged samples recorded between `from` and `to`, in time order
+pub fn stream_upload_session_samples_between(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> BoxStream<'_, Result<HeartRateData, sqlx::Error>> {
+    sqlx::query_as!(
+        HeartRateData,
+        r#"
+        SELECT recorded_at AS timestamp, heart_rate::int AS "heart_rate!"
+        FROM workout_upload_session_samples
+        WHERE session_id = $1 AND recorded_at BETWEEN $2 AND $3
+        ORDER BY recorded_at
+        "#,
+        session_id,
+        from,
+        to
+    )
+    .fetch(conn)
+}
+
+/// Times of a session's first and last staged samples
+pub async fn get_upload_session_sample_range(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+) -> Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>), sqlx::Error> {
+    let range = sqlx::query!(
+        r#"
+        SELECT MIN(recorded_at) AS first_sample_at, MAX(recorded_at) AS last_sample_at
+        FROM workout_upload_session_samples
+        WHERE session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok((range.first_sample_at, range.last_sample_at))
+}
+
+/// Fold a stored workout's heart rate samples into a session's staged ones, for a session upload
+/// merged with another device's recording of the activity. Where both devices read within a second
+/// of each other the stored sample is kept. Returns the number of stored samples added.
+pub async fn merge_workout_samples_into_upload_session(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    workout_data_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query!(
+        r#"
+        DELETE FROM workout_upload_session_samples s
+        USING workout_heart_rate_samples w
+        WHERE s.session_id = $1 AND w.workout_data_id = $2
+          AND s.recorded_at > w.recorded_at - INTERVAL '1 second'
+          AND s.recorded_at < w.recorded_at + INTERVAL '1 second'
+        "#,
+        session_id,
+        workout_data_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let merged = sqlx::query!(
+        r#"
+        INSERT INTO workout_upload_session_samples (session_id, recorded_at, heart_rate)
+        SELECT $1, recorded_at, heart_rate
+        FROM workout_heart_rate_samples
+        WHERE workout_data_id = $2
+        ON CONFLICT (session_id, recorded_at) DO NOTHING
+        "#,
+        session_id,
+        workout_data_id
+    )
+    .execute(conn)
+    .await?
+    .rows_affected();
+
+    Ok(merged)
+}
+
+/// Copy a session's staged samples to a stored workout's heart rate and fill in its heart rate
+/// summary from them, without reading them out of the database. Returns the number of samples stored.
+pub async fn store_upload_session_samples(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+    workout_data_id: Uuid,
+) -> Result<u64, sqlx::Error> {
+    let stored = sqlx::query!(
+        r#"
+        INSERT INTO workout_heart_rate_samples (workout_data_id, recorded_at, heart_rate)
+        SELECT $1, recorded_at, heart_rate
+        FROM workout_upload_session_samples
+        WHERE session_id = $2
+        ON CONFLICT (workout_data_id, recorded_at) DO NOTHING
+        "#,
+        workout_data_id,
+        session_id
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    sqlx::query!(
+        r#"
+        UPDATE workout_data wd
+        SET heart_rate_sample_count = summary.sample_count,
+            avg_heart_rate = summary.avg_heart_rate,
+            max_heart_rate = summary.max_heart_rate,
+            min_heart_rate = summary.min_heart_rate
+        FROM (
+            SELECT COUNT(*)::int AS sample_count,
+                   (SUM(heart_rate) / NULLIF(COUNT(*), 0))::int AS avg_heart_rate,
+                   MAX(heart_rate)::int AS max_heart_rate,
+                   MIN(heart_rate)::int AS min_heart_rate
+            FROM workout_heart_rate_samples
+            WHERE workout_data_id = $1
+        ) summary
+        WHERE wd.id = $1
+        "#,
+        workout_data_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(stored)
+}
+
+/// Close a session to further samples once it's queued
+pub async fn finalize_upload_session(
+    conn: &mut PgConnection,
+    session_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        r#"
+        UPDATE workout_upload_sessions
+        SET status = $1, finalized_at = NOW(), updated_at = NOW()
+        WHERE id = $2
+        "#,
+        UploadSessionStatus::Finalized.as_str(),
+        session_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Drop a session and its staged samples
+pub async fn delete_upload_session(
+    pool: &PgPool,
+    session_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM workout_upload_sessions WHERE id = $1", session_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Drop sessions untouched for `idle_hours` that are still open, or finalized without a job
+/// left to load their samples. Returns the number dropped.
+pub async fn delete_abandoned_upload_sessions(
+    pool: &PgPool,
+    idle_hours: i32,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM workout_upload_sessions s
+        WHERE s.updated_at < NOW() - make_interval(hours => $1)
+          AND NOT EXISTS (
+              SELECT 1 FROM workout_processing_jobs j
+              WHERE j.upload_session_id = s.id AND j.status IN ('queued', 'processing')
+          )
+        "#,
+        idle_hours
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
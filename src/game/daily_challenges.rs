@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+/// Challenges assigned to each user per day
+pub const CHALLENGES_PER_DAY: usize = 3;
+
+/// What a challenge measures over the day's accepted workouts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChallengeMetric {
+    /// Minutes in this zone or higher
+    ZoneMinutes { min_zone: u8 },
+    Workouts,
+    ActiveMinutes,
+    Calories,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChallengeTemplate {
+    pub key: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub metric: ChallengeMetric,
+    pub target: f64,
+    pub stamina_bonus: i32,
+    pub strength_bonus: i32,
+}
+
+pub const CHALLENGE_TEMPLATES: [ChallengeTemplate; 6] = [
+    ChallengeTemplate {
+        key: "zone2_30",
+        title: "Base builder",
+        description: "Spend 30 minutes in Zone 2 or higher",
+        metric: ChallengeMetric::ZoneMinutes { min_zone: 2 },
+        target: 30.0,
+        stamina_bonus: 4,
+        strength_bonus: 0,
+    },
+    ChallengeTemplate {
+        key: "zone3_20",
+        title: "Aerobic push",
+        description: "Spend 20 minutes in Zone 3 or higher",
+        metric: ChallengeMetric::ZoneMinutes { min_zone: 3 },
+        target: 20.0,
+        stamina_bonus: 5,
+        strength_bonus: 0,
+    },
+    ChallengeTemplate {
+        key: "zone4_10",
+        title: "Threshold work",
+        description: "Spend 10 minutes in Zone 4 or higher",
+        metric: ChallengeMetric::ZoneMinutes { min_zone: 4 },
+        target: 10.0,
+        stamina_bonus: 3,
+        strength_bonus: 3,
+    },
+    ChallengeTemplate {
+        key: "two_workouts",
+        title: "Double session",
+        description: "Complete two workouts today",
+        metric: ChallengeMetric::Workouts,
+        target: 2.0,
+        stamina_bonus: 3,
+        strength_bonus: 3,
+    },
+    ChallengeTemplate {
+        key: "active_45",
+        title: "Keep moving",
+        description: "Log 45 active minutes",
+        metric: ChallengeMetric::ActiveMinutes,
+        target: 45.0,
+        stamina_bonus: 4,
+        strength_bonus: 1,
+    },
+    ChallengeTemplate {
+        key: "calories_400",
+        title: "Burn it",
+        description: "Burn 400 kcal in workouts",
+        metric: ChallengeMetric::Calories,
+        target: 400.0,
+        stamina_bonus: 2,
+        strength_bonus: 4,
+    },
+];
+
+/// What the user did on one day
+#[derive(Debug, Default, Clone)]
+pub struct DayActivity {
+    pub workouts: i64,
+    pub active_minutes: f64,
+    pub calories: i64,
+    /// Minutes per zone name ("Zone1" to "Zone5")
+    pub zone_minutes: HashMap<String, f64>,
+}
+
+impl ChallengeMetric {
+    /// How far the day's activity got towards the metric's target
+    pub fn progress(&self, activity: &DayActivity) -> f64 {
+        match self {
+            ChallengeMetric::ZoneMinutes { min_zone } => activity.zone_minutes.iter()
+                .filter(|(zone, _)| zone_number(zone).is_some_and(|zone| zone >= *min_zone))
+                .map(|(_, minutes)| minutes)
+                .sum(),
+            ChallengeMetric::Workouts => activity.workouts as f64,
+            ChallengeMetric::ActiveMinutes => activity.active_minutes,
+            ChallengeMetric::Calories => activity.calories as f64,
+        }
+    }
+}
+
+pub fn find_template(key: &str) -> Option<&'static ChallengeTemplate> {
+    CHALLENGE_TEMPLATES.iter().find(|template| template.key == key)
+}
+
+/// The templates a user gets on a day. The pick varies per user and day but is stable,
+/// so assigning twice gives the same challenges, also across releases and servers.
+/// The day is the user's, in their time zone.
+pub fn pick_templates(user_id: Uuid, day: NaiveDate) -> Vec<&'static ChallengeTemplate> {
+    let mut templates: Vec<(u64, &'static ChallengeTemplate)> = CHALLENGE_TEMPLATES.iter()
+        .map(|template| {
+            let seed = format!("{}|{}|{}", user_id, day, template.key);
+            (fnv1a(seed.as_bytes()), template)
+        })
+        .collect();
+    templates.sort_by_key(|(rank, _)| *rank);
+    templates.into_iter().take(CHALLENGES_PER_DAY).map(|(_, template)| template).collect()
+}
+
+/// 64 bit FNV-1a, fixed unlike the standard library's hashers, which may change between Rust versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3))
+}
+
+fn zone_number(zone: &str) -> Option<u8> {
+    zone.strip_prefix("Zone").and_then(|number| number.parse().ok())
+}
@@ -0,0 +1,67 @@
+/// Workouts needed before heart rates are estimated
+pub const MIN_WORKOUTS: usize = 3;
+/// Workouts with fewer samples say too little about the user's range
+pub const MIN_SAMPLES_PER_WORKOUT: i64 = 60;
+/// Smaller differences from the current values aren't worth moving the zones for
+pub const MIN_CHANGE_BPM: i32 = 3;
+/// Percentiles of a workout's samples taken as its high and low, so sensor spikes and dropouts don't count
+pub const HIGH_PERCENTILE: f64 = 0.99;
+pub const LOW_PERCENTILE: f64 = 0.05;
+
+/// A workout's heart rate range
+#[derive(Debug, Clone, Copy)]
+pub struct WorkoutHeartRateSummary {
+    pub high: f64,
+    pub low: f64,
+    pub samples: i64,
+}
+
+/// Heart rates estimated from workout history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EstimatedHeartRates {
+    pub resting_heart_rate: i32,
+    pub max_heart_rate: i32,
+    pub workouts_considered: i32,
+}
+
+/// Estimate max and resting heart rate from a user's recent workouts.
+///
+/// A workout shows the heart rate reaches at least its high, so the max only ever goes up.
+/// Its low is an upper bound on the resting heart rate, so the resting heart rate only ever goes down.
+/// Returns `None` without enough workouts or when neither moves by at least `MIN_CHANGE_BPM`.
+pub fn estimate_heart_rates(
+    workouts: &[WorkoutHeartRateSummary],
+    current_resting_heart_rate: i32,
+    current_max_heart_rate: i32,
+) -> Option<EstimatedHeartRates> {
+    let usable: Vec<&WorkoutHeartRateSummary> = workouts.iter()
+        .filter(|workout| workout.samples >= MIN_SAMPLES_PER_WORKOUT)
+        .collect();
+    if usable.len() < MIN_WORKOUTS {
+        return None;
+    }
+
+    let observed_max = usable.iter().map(|workout| workout.high).fold(f64::MIN, f64::max).round() as i32;
+    let observed_low = usable.iter().map(|workout| workout.low).fold(f64::MAX, f64::min).round() as i32;
+
+    let max_heart_rate = if observed_max >= current_max_heart_rate + MIN_CHANGE_BPM {
+        observed_max
+    } else {
+        current_max_heart_rate
+    };
+    let resting_heart_rate = if observed_low <= current_resting_heart_rate - MIN_CHANGE_BPM {
+        observed_low
+    } else {
+        current_resting_heart_rate
+    };
+
+    if max_heart_rate == current_max_heart_rate && resting_heart_rate == current_resting_heart_rate {
+        return None;
+    }
+
+    Some(EstimatedHeartRates {
+        resting_heart_rate,
+        max_heart_rate,
+        workouts_considered: usable.len() as i32,
+    })
+}
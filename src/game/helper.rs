@@ -1,9 +1,9 @@
-use sqlx::{Pool, Postgres, Error};
+use sqlx::{Error, PgConnection};
 use uuid::Uuid;
 
 use crate::models::workout_data::{UserProfile, Gender, HeartRateZones};
 
-pub async fn get_user_profile(pool: &Pool<Postgres>, user_id: Uuid) -> Result<UserProfile, Error> {
+pub async fn get_user_profile(conn: &mut PgConnection, user_id: Uuid) -> Result<UserProfile, Error> {
     tracing::info!("🔍 Fetching health profile for user: {}", user_id);
     let result = sqlx::query!(
         r#"
@@ -14,7 +14,7 @@ pub async fn get_user_profile(pool: &Pool<Postgres>, user_id: Uuid) -> Result<Us
         "#,
         user_id
     )
-    .fetch_optional(pool)
+    .fetch_optional(conn)
     .await?;
 
     match result {
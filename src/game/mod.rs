@@ -1,3 +1,9 @@
 pub mod stats_calculator;
+pub mod stat_caps;
 pub mod helper;
-pub mod game_evaluator;
\ No newline at end of file
+pub mod game_evaluator;
+pub mod heart_rate_estimator;
+pub mod personal_records;
+pub mod daily_challenges;
+pub mod stat_decay;
+pub mod progression;
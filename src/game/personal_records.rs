@@ -0,0 +1,137 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::models::profile::TrainingStreak;
+
+/// Kinds of personal records kept per user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    LongestWorkout,
+    /// Minutes in zones 4 and 5 in a single workout
+    MostHighZoneMinutes,
+    HighestWorkoutPoints,
+    LongestDailyStreak,
+    LongestWeeklyStreak,
+}
+
+impl RecordType {
+    pub const ALL: [RecordType; 5] = [
+        RecordType::LongestWorkout,
+        RecordType::MostHighZoneMinutes,
+        RecordType::HighestWorkoutPoints,
+        RecordType::LongestDailyStreak,
+        RecordType::LongestWeeklyStreak,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordType::LongestWorkout => "longest_workout",
+            RecordType::MostHighZoneMinutes => "most_high_zone_minutes",
+            RecordType::HighestWorkoutPoints => "highest_workout_points",
+            RecordType::LongestDailyStreak => "longest_daily_streak",
+            RecordType::LongestWeeklyStreak => "longest_weekly_streak",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            RecordType::LongestWorkout => "Longest workout",
+            RecordType::MostHighZoneMinutes => "Most Zone 4-5 minutes",
+            RecordType::HighestWorkoutPoints => "Highest workout points",
+            RecordType::LongestDailyStreak => "Longest daily streak",
+            RecordType::LongestWeeklyStreak => "Longest weekly streak",
+        }
+    }
+
+    /// A record value as shown to the user
+    pub fn format_value(&self, value: f64) -> String {
+        match self {
+            RecordType::LongestWorkout | RecordType::MostHighZoneMinutes => format!("{:.0} min", value),
+            RecordType::HighestWorkoutPoints => format!("{:.0} points", value),
+            RecordType::LongestDailyStreak => format!("{:.0} days", value),
+            RecordType::LongestWeeklyStreak => format!("{:.0} weeks", value),
+        }
+    }
+}
+
+/// Running totals that reach a milestone at each of their thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneType {
+    /// Accepted workouts
+    Workouts,
+    TrainingHours,
+    /// Stamina plus strength gained over all workouts
+    TotalPoints,
+}
+
+impl MilestoneType {
+    pub const ALL: [MilestoneType; 3] = [
+        MilestoneType::Workouts,
+        MilestoneType::TrainingHours,
+        MilestoneType::TotalPoints,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MilestoneType::Workouts => "workouts",
+            MilestoneType::TrainingHours => "training_hours",
+            MilestoneType::TotalPoints => "total_points",
+        }
+    }
+
+    pub fn thresholds(&self) -> &'static [i64] {
+        match self {
+            MilestoneType::Workouts => &[1, 10, 25, 50, 100, 250, 500, 1000],
+            MilestoneType::TrainingHours => &[10, 25, 50, 100, 250, 500, 1000],
+            MilestoneType::TotalPoints => &[1_000, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000],
+        }
+    }
+
+    /// A milestone as shown to the user
+    pub fn title(&self, threshold: i64) -> String {
+        match self {
+            MilestoneType::Workouts if threshold == 1 => "First workout".to_string(),
+            MilestoneType::Workouts => format!("{} workouts", threshold),
+            MilestoneType::TrainingHours => format!("{} hours of training", threshold),
+            MilestoneType::TotalPoints => format!("{} points earned", threshold),
+        }
+    }
+}
+
+/// Streak of consecutive days with a workout, from the days the user trained on in ascending order.
+/// The current streak is still alive if the user trained today or yesterday.
+pub fn daily_streak(workout_days: &[NaiveDate], today: NaiveDate) -> TrainingStreak {
+    streak(workout_days, today, Duration::days(1))
+}
+
+/// Streak of consecutive weeks, Monday to Sunday, with a workout.
+/// The current streak is still alive if the user trained this week or last week.
+pub fn weekly_streak(workout_days: &[NaiveDate], today: NaiveDate) -> TrainingStreak {
+    let mut weeks: Vec<NaiveDate> = workout_days.iter().map(|day| week_start(*day)).collect();
+    weeks.dedup();
+    streak(&weeks, week_start(today), Duration::weeks(1))
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday() as i64)
+}
+
+fn streak(periods: &[NaiveDate], current_period: NaiveDate, step: Duration) -> TrainingStreak {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for period in periods {
+        run = match previous {
+            Some(previous) if *period == previous + step => run + 1,
+            Some(previous) if *period == previous => run,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*period);
+    }
+
+    let current = match previous {
+        Some(last) if last >= current_period - step => run,
+        _ => 0,
+    };
+    TrainingStreak { current, longest }
+}
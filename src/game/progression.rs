@@ -0,0 +1,69 @@
+use crate::models::profile::{AvatarProgression, EvolutionStage};
+
+/// Level n needs `LEVEL_EXPERIENCE_STEP / 2 * n * (n - 1)` experience: 100 for level 2, 300 for 3, 600 for 4, ...
+pub const LEVEL_EXPERIENCE_STEP: i64 = 100;
+
+/// Style every avatar starts with
+pub const DEFAULT_AVATAR_STYLE: &str = "warrior";
+
+/// Avatar styles and the level that unlocks them
+pub const AVATAR_STYLES: [(&str, i32); 7] = [
+    (DEFAULT_AVATAR_STYLE, 1),
+    ("ranger", 3),
+    ("monk", 5),
+    ("knight", 8),
+    ("samurai", 12),
+    ("champion", 20),
+    ("titan", 35),
+];
+
+pub fn evolution_stage(level: i32) -> EvolutionStage {
+    match level {
+        35.. => EvolutionStage::Legend,
+        20.. => EvolutionStage::Elite,
+        10.. => EvolutionStage::Veteran,
+        5.. => EvolutionStage::Adept,
+        _ => EvolutionStage::Novice,
+    }
+}
+
+/// Level, evolution stage and unlocked styles that come with an amount of experience
+pub fn avatar_progression(experience: i64) -> AvatarProgression {
+    let level = level_for_experience(experience);
+    AvatarProgression {
+        level,
+        experience,
+        level_experience: experience_for_level(level),
+        next_level_experience: experience_for_level(level + 1),
+        evolution_stage: evolution_stage(level),
+        unlocked_styles: unlocked_styles(level),
+    }
+}
+
+/// Experience needed to reach a level
+pub fn experience_for_level(level: i32) -> i64 {
+    let level = level.max(1) as i64;
+    LEVEL_EXPERIENCE_STEP / 2 * level * (level - 1)
+}
+
+pub fn level_for_experience(experience: i64) -> i32 {
+    let mut level = 1;
+    while experience_for_level(level + 1) <= experience {
+        level += 1;
+    }
+    level
+}
+
+pub fn unlocked_styles(level: i32) -> Vec<String> {
+    AVATAR_STYLES.iter()
+        .filter(|(_, unlock_level)| *unlock_level <= level)
+        .map(|(style, _)| style.to_string())
+        .collect()
+}
+
+/// Level that unlocks a style, `None` for unknown styles
+pub fn style_unlock_level(style: &str) -> Option<i32> {
+    AVATAR_STYLES.iter()
+        .find(|(name, _)| *name == style)
+        .map(|(_, unlock_level)| *unlock_level)
+}
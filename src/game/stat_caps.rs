@@ -0,0 +1,155 @@
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::db::stat_gains::{add_daily_stat_gains, get_stat_caps, get_weekly_points, lock_daily_stat_gains, DailyStatGains};
+use crate::game::stats_calculator::{StatChanges, ZoneBreakdown};
+use crate::models::game::StatCaps;
+
+/// Cap a workout's stat changes against the user's gains that day and week, and record what it was credited.
+/// `game_changes`, the same workout scored with the rules of other seasons' games, are capped alike
+/// but not recorded. Runs on the caller's transaction, which keeps the user's day locked until the workout is stored.
+pub async fn apply_stat_caps(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+    changes: &mut StatChanges,
+    game_changes: impl Iterator<Item = &mut StatChanges>,
+) -> Result<(), sqlx::Error> {
+    let caps = get_stat_caps(&mut *conn).await?;
+    let today = lock_daily_stat_gains(&mut *conn, user_id, day).await?;
+    let week_points = get_weekly_points(&mut *conn, user_id, day).await?;
+
+    for changes_in_game in game_changes {
+        cap_stat_changes(changes_in_game, &caps, &today, week_points);
+    }
+    let zone_minutes = cap_stat_changes(changes, &caps, &today, week_points);
+
+    add_daily_stat_gains(conn, user_id, day, &DailyStatGains {
+        zone_minutes,
+        stamina_gained: changes.stamina_change,
+        strength_gained: changes.strength_change,
+    }).await
+}
+
+/// Take a retracted workout's credited minutes and points back out of the user's day
+pub async fn release_stat_caps(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    day: NaiveDate,
+    stamina_gained: i32,
+    strength_gained: i32,
+    zone_breakdown: &[ZoneBreakdown],
+) -> Result<(), sqlx::Error> {
+    let mut zone_minutes = [0.0; 5];
+    for zone in zone_breakdown {
+        if let Some(index) = zone_index(&zone.zone) {
+            zone_minutes[index] -= zone.minutes;
+        }
+    }
+
+    add_daily_stat_gains(conn, user_id, day, &DailyStatGains {
+        zone_minutes,
+        stamina_gained: -stamina_gained,
+        strength_gained: -strength_gained,
+    }).await
+}
+
+/// Apply diminishing returns to zone minutes past the daily threshold, then the daily and weekly
+/// soft caps on points. Explains every cut in the reasoning. Returns the workout's minutes per zone.
+pub fn cap_stat_changes(
+    changes: &mut StatChanges,
+    caps: &StatCaps,
+    today: &DailyStatGains,
+    week_points: i32,
+) -> [f32; 5] {
+    let mut zone_minutes = [0.0; 5];
+    let mut stamina = changes.stamina_change as f32;
+    let mut strength = changes.strength_change as f32;
+    let mut reasoning = Vec::new();
+
+    for zone in changes.zone_breakdown.iter_mut().flatten() {
+        let Some(index) = zone_index(&zone.zone) else {
+            continue;
+        };
+        zone_minutes[index] += zone.minutes;
+
+        let threshold = caps.zone_daily_minutes(index);
+        let full_minutes = (threshold - today.zone_minutes[index]).clamp(0.0, zone.minutes);
+        let over_minutes = zone.minutes - full_minutes;
+        if over_minutes <= 0.0 {
+            continue;
+        }
+
+        let factor = (full_minutes + over_minutes * caps.zone_diminished_rate) / zone.minutes;
+        let zone_stamina = (zone.stamina_gained as f32 * factor) as i32;
+        let zone_strength = (zone.strength_gained as f32 * factor) as i32;
+        stamina -= (zone.stamina_gained - zone_stamina) as f32;
+        strength -= (zone.strength_gained - zone_strength) as f32;
+        zone.stamina_gained = zone_stamina;
+        zone.strength_gained = zone_strength;
+
+        reasoning.push(format!(
+            "{} past {:.0} min today: {:.1} min scored at {:.0}%",
+            zone.zone, threshold, over_minutes, caps.zone_diminished_rate * 100.0
+        ));
+    }
+
+    let points = stamina + strength;
+    if points > 0.0 {
+        let (after_daily, over_daily) = soft_cap(points, today.points(), caps.daily_soft_cap, caps.over_cap_rate);
+        if over_daily > 0.0 {
+            reasoning.push(format!(
+                "Daily soft cap of {} points reached: {:.0} points scored at {:.0}%",
+                caps.daily_soft_cap, over_daily, caps.over_cap_rate * 100.0
+            ));
+        }
+
+        let (after_weekly, over_weekly) = soft_cap(after_daily, week_points, caps.weekly_soft_cap, caps.over_cap_rate);
+        if over_weekly > 0.0 {
+            reasoning.push(format!(
+                "Weekly soft cap of {} points reached: {:.0} points scored at {:.0}%",
+                caps.weekly_soft_cap, over_weekly, caps.over_cap_rate * 100.0
+            ));
+        }
+
+        let factor = after_weekly / points;
+        stamina *= factor;
+        strength *= factor;
+        if factor < 1.0 {
+            for zone in changes.zone_breakdown.iter_mut().flatten() {
+                zone.stamina_gained = (zone.stamina_gained as f32 * factor) as i32;
+                zone.strength_gained = (zone.strength_gained as f32 * factor) as i32;
+            }
+        }
+    }
+
+    if !reasoning.is_empty() {
+        tracing::info!("🧢 Stat caps cut gains from +{} stamina, +{} strength to +{:.0}, +{:.0}",
+            changes.stamina_change, changes.strength_change, stamina, strength);
+    }
+    changes.stamina_change = stamina.round() as i32;
+    changes.strength_change = strength.round() as i32;
+    changes.reasoning.extend(reasoning);
+
+    zone_minutes
+}
+
+/// Points that still score once `already` points were gained against `cap`, and how many went past it
+fn soft_cap(points: f32, already: i32, cap: i32, over_cap_rate: f32) -> (f32, f32) {
+    let full = ((cap - already) as f32).clamp(0.0, points);
+    let over = points - full;
+    (full + over * over_cap_rate, over)
+}
+
+/// Position of a zone breakdown entry, Zone1 first
+fn zone_index(zone: &str) -> Option<usize> {
+    match zone {
+        "Zone1" => Some(0),
+        "Zone2" => Some(1),
+        "Zone3" => Some(2),
+        "Zone4" => Some(3),
+        "Zone5" => Some(4),
+        _ => None,
+    }
+}
@@ -0,0 +1,30 @@
+use crate::models::game::StatDecaySettings;
+
+/// How much of one stat decays in a run.
+///
+/// The floor is `floor_ratio` of what the user earned, counting what earlier decay took back,
+/// so a long break never wipes out more than the configured share of their progress.
+/// Anything above the floor loses at least one point, so small stats still reach it.
+pub fn stat_decay(current: i32, already_decayed: i32, settings: &StatDecaySettings) -> i32 {
+    let floor = decay_floor(current + already_decayed, settings);
+    let above_floor = current - floor;
+    if above_floor <= 0 || settings.daily_decay_rate <= 0.0 {
+        return 0;
+    }
+
+    let decay = (above_floor as f32 * settings.daily_decay_rate).round() as i32;
+    decay.clamp(1, above_floor)
+}
+
+/// A stat after taking back the `gained` points of a retracted workout.
+///
+/// Decay may already have taken part of those points, so the stat drops no lower than the floor
+/// of what's left earned without the workout, the same floor decay stops at.
+pub fn retracted_stat(current: i32, gained: i32, already_decayed: i32, settings: &StatDecaySettings) -> i32 {
+    let floor = decay_floor(current + already_decayed - gained, settings);
+    (current - gained).max(floor).max(0)
+}
+
+fn decay_floor(earned: i32, settings: &StatDecaySettings) -> i32 {
+    (earned as f32 * settings.floor_ratio).ceil() as i32
+}
@@ -1,11 +1,13 @@
 use serde::{Serialize, Deserialize};
-use sqlx::{Pool, Postgres};
+use sqlx::{PgConnection, Pool, Postgres};
 use uuid::Uuid;
 
 use crate::models::game::*;
-use crate::models::workout_data::{WorkoutDataSyncRequest, HeartRateData, HeartRateZones, ZoneName};
+use crate::models::workout_data::{
+    strength_volume_load, WorkoutDataSyncRequest, HeartRateData, HeartRateZones, StrengthExercise, TrainingLoad, WorkoutType,
+};
 use crate::game::helper::{get_user_profile, calc_max_heart_rate};
-use crate::workout::workout_analyzer::WorkoutAnalyzer;
+use crate::workout::workout_analyzer::{GapHandling, HeartRateReserve, WorkoutAnalyzer};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ZoneBreakdown {
@@ -23,39 +25,112 @@ pub struct StatChanges {
     pub strength_change: i32,
     pub reasoning: Vec<String>,
     pub zone_breakdown: Option<Vec<ZoneBreakdown>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_load: Option<TrainingLoad>,
 }
 
 pub struct StatCalculator;
 
 impl StatCalculator {
-    /// Calculate stat changes based on HRR zones from heart rate and calories
-    pub async fn calculate_stat_changes(pool: &Pool<Postgres>, user_id: Uuid, workout_data: &WorkoutDataSyncRequest) -> StatChanges {
+    /// Calculate stat changes based on HRR zones from heart rate, using the season's zone points
+    /// weighted by the scoring profile of the workout type. Workouts without heart rate data
+    /// fall back to calories and duration. Strength sessions logged as sets take their strength
+    /// from the volume load instead.
+    pub async fn calculate_stat_changes(pool: &Pool<Postgres>, user_id: Uuid, workout_data: &WorkoutDataSyncRequest, rules: &ScoringRules) -> Result<StatChanges, sqlx::Error> {
+        Self::calculate_stat_changes_with_analysis(&mut *pool.acquire().await?, user_id, workout_data, None, rules).await
+    }
+
+    /// `calculate_stat_changes` on the caller's connection, reusing an analysis of the heart rate made
+    /// while it was streamed, see `calculate_stat_changes_with_profile`
+    pub async fn calculate_stat_changes_with_analysis(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        workout_data: &WorkoutDataSyncRequest,
+        analysis: Option<&WorkoutAnalyzer>,
+        rules: &ScoringRules,
+    ) -> Result<StatChanges, sqlx::Error> {
+        let has_heart_rate = analysis.is_some() || workout_data.heart_rate.as_ref().is_some_and(|samples| !samples.is_empty());
+        let heart_rate_profile = if has_heart_rate {
+            Some(Self::user_heart_rate_zones(conn, user_id).await?)
+        } else {
+            None
+        };
+        Ok(Self::calculate_stat_changes_with_profile(heart_rate_profile.as_ref(), analysis, workout_data, rules))
+    }
+
+    /// `calculate_stat_changes` with the user's heart rate zones and reserve already loaded.
+    /// They're only needed for workouts with heart rate data. An analysis of the samples made while
+    /// they were streamed is scored instead of analyzing them again, if it used the rules' gap handling,
+    /// which also scores samples that never made it into the payload.
+    pub fn calculate_stat_changes_with_profile(
+        heart_rate_profile: Option<&(HeartRateZones, HeartRateReserve)>,
+        analysis: Option<&WorkoutAnalyzer>,
+        workout_data: &WorkoutDataSyncRequest,
+        rules: &ScoringRules,
+    ) -> StatChanges {
         let mut changes = StatChanges {
             stamina_change: 0,
             strength_change: 0,
             reasoning: Vec::new(),
             zone_breakdown: None,
+            training_load: None,
         };
 
-        if let Some(heart_rate) = &workout_data.heart_rate {
-            let stats_changes = Self::calc_stats_hhr_based(pool, user_id, heart_rate).await;
+        let workout_type = workout_data.workout_type.unwrap_or_default();
+        let heart_rate = workout_data.heart_rate.as_ref().filter(|samples| !samples.is_empty());
+        let analysis = analysis.filter(|analysis| analysis.gap_handling() == GapHandling::from_rules(rules));
+        let heart_rate_stats = heart_rate_profile.and_then(|(heart_rate_zones, heart_rate_reserve)| match (analysis, heart_rate) {
+            (Some(analysis), _) => Some(Self::calc_stats_from_analysis(analysis, heart_rate_zones, heart_rate_reserve, workout_type, rules)),
+            (None, Some(heart_rate)) => Self::calc_stats_hhr_based(heart_rate, heart_rate_zones, heart_rate_reserve, workout_type, rules),
+            (None, None) => None,
+        });
+        if let Some(stats_changes) = heart_rate_stats {
             changes.stamina_change += stats_changes.stamina_change;
             changes.strength_change += stats_changes.strength_change;
             changes.zone_breakdown = stats_changes.zone_breakdown;
+            changes.training_load = stats_changes.training_load;
+            changes.reasoning.extend(stats_changes.reasoning);
+        } else {
+            // Heart rate too short to analyze is scored like none at all
+            if heart_rate.is_some() && analysis.is_none() {
+                changes.reasoning.push("Too few heart rate samples to analyze".to_string());
+            }
+            let stats_changes = Self::calc_stats_fallback(workout_data, workout_type, rules);
+            changes.stamina_change += stats_changes.stamina_change;
+            changes.strength_change += stats_changes.strength_change;
+            changes.reasoning.extend(stats_changes.reasoning);
+        }
+
+        if let Some(exercises) = workout_data.strength_exercises.as_deref().filter(|exercises| !exercises.is_empty()) {
+            let stats_changes = Self::calc_strength_from_volume_load(exercises, rules);
+            // The sets replace whatever strength the heart rate zones would have given
+            let replaced_source = if changes.zone_breakdown.is_some() { "heart rate zones" } else { "calories and duration" };
+            for zone in changes.zone_breakdown.iter_mut().flatten() {
+                zone.strength_gained = 0;
+            }
+            changes.reasoning.push(format!(
+                "Logged sets replace the {} strength from {}", changes.strength_change, replaced_source
+            ));
+            changes.strength_change = stats_changes.strength_change;
+            changes.reasoning.extend(stats_changes.reasoning);
+        }
+
+        if workout_type != WorkoutType::Other {
+            changes.reasoning.push(format!("Scored as {} workout", workout_type.as_str()));
+        }
+        if rules.season_id.is_some() {
+            changes.reasoning.push("Scored with the season's scoring rules".to_string());
         }
         changes
     }
 
-    /// Calculate base stats from HRR zones based on heart rate
-    async fn calc_stats_hhr_based(pool: &Pool<Postgres>, user_id: Uuid, heart_rate: &Vec<HeartRateData>) -> StatChanges {
-        let mut changes = StatChanges {
-            stamina_change: 0,
-            strength_change: 0,
-            reasoning: Vec::new(),
-            zone_breakdown: None,
-        };
-
-        let user_profile = get_user_profile(pool, user_id).await.unwrap();
+    /// The user's heart rate zones, stored or calculated from their profile, and their heart rate reserve
+    pub async fn user_heart_rate_zones(conn: &mut PgConnection, user_id: Uuid) -> Result<(HeartRateZones, HeartRateReserve), sqlx::Error> {
+        let user_profile = get_user_profile(conn, user_id).await?;
+        let max_heart_rate = user_profile.max_heart_rate.unwrap_or_else(|| 
+            calc_max_heart_rate(user_profile.age, user_profile.gender)
+        );
+        let resting_heart_rate = user_profile.resting_heart_rate.unwrap_or(60);
         
         // Use stored heart rate zones if available, otherwise calculate them
         let heart_rate_zones = if let Some(stored_zones) = user_profile.stored_heart_rate_zones {
@@ -63,10 +138,6 @@ impl StatCalculator {
             stored_zones
         } else {
             tracing::info!("⚠️ No stored zones found, calculating heart rate zones");
-            let max_heart_rate = user_profile.max_heart_rate.unwrap_or_else(|| 
-                calc_max_heart_rate(user_profile.age, user_profile.gender)
-            );
-            let resting_heart_rate = user_profile.resting_heart_rate.unwrap_or(60);
             let hrr = max_heart_rate - resting_heart_rate;
             
             tracing::info!("💓 Heart rate calculation: max_hr={}, resting_hr={}, hrr={}", 
@@ -74,7 +145,22 @@ impl StatCalculator {
             
             HeartRateZones::new(hrr, resting_heart_rate, max_heart_rate)
         };
-        
+
+        Ok((heart_rate_zones, HeartRateReserve {
+            resting_heart_rate,
+            max_heart_rate,
+            gender: user_profile.gender,
+        }))
+    }
+
+    /// Calculate base stats from HRR zones based on heart rate, `None` if there's too little to analyze
+    fn calc_stats_hhr_based(
+        heart_rate: &[HeartRateData],
+        heart_rate_zones: &HeartRateZones,
+        heart_rate_reserve: &HeartRateReserve,
+        workout_type: WorkoutType,
+        rules: &ScoringRules,
+    ) -> Option<StatChanges> {
         tracing::info!("📊 Processing {} heart rate data points", heart_rate.len());
         if !heart_rate.is_empty() {
             let avg_hr: i32 = heart_rate.iter().map(|hr| hr.heart_rate).sum::<i32>() / heart_rate.len() as i32;
@@ -85,45 +171,171 @@ impl StatCalculator {
             );
         }
         
-        if let Some(workout_analysis) = WorkoutAnalyzer::new(heart_rate, &heart_rate_zones) {
+        if let Some(workout_analysis) = WorkoutAnalyzer::with_gap_handling(heart_rate, heart_rate_zones, GapHandling::from_rules(rules)) {
             tracing::info!("✅ WorkoutAnalyzer created successfully");
-            for (zone, minutes) in &workout_analysis.zone_durations {
-                tracing::info!("📈 Zone {:?}: {:.1} minutes", zone, minutes);
+            Some(Self::calc_stats_from_analysis(&workout_analysis, heart_rate_zones, heart_rate_reserve, workout_type, rules))
+        } else {
+            tracing::warn!("⚠️ WorkoutAnalyzer returned None - scoring from calories and duration");
+            None
+        }
+    }
+
+    /// Calculate base stats from the zone durations and training load of analyzed heart rate
+    fn calc_stats_from_analysis(
+        workout_analysis: &WorkoutAnalyzer,
+        heart_rate_zones: &HeartRateZones,
+        heart_rate_reserve: &HeartRateReserve,
+        workout_type: WorkoutType,
+        rules: &ScoringRules,
+    ) -> StatChanges {
+        let mut changes = StatChanges {
+            stamina_change: 0,
+            strength_change: 0,
+            reasoning: Vec::new(),
+            zone_breakdown: None,
+            training_load: None,
+        };
+
+        for (zone, minutes) in &workout_analysis.zone_durations {
+            tracing::info!("📈 Zone {:?}: {:.1} minutes", zone, minutes);
+        }
+        let profile = ScoringProfile::for_workout_type(workout_type);
+        let (points_changes, zone_breakdown) = Self::calc_points_and_breakdown_from_workout_analysis(workout_analysis, heart_rate_zones, &profile, rules);
+        changes.stamina_change += points_changes.stamina_change;
+        changes.strength_change += points_changes.strength_change;
+        changes.zone_breakdown = Some(zone_breakdown);
+
+        let training_load = workout_analysis.training_load(heart_rate_reserve);
+        tracing::info!("🏋️ Training load: Banister TRIMP {:.1}, Edwards TRIMP {:.1}, IF {:.2}, EPOC {:.0} ml/kg",
+            training_load.banister_trimp, training_load.edwards_trimp,
+            training_load.intensity_factor, training_load.epoc_estimate);
+        changes.training_load = Some(training_load);
+
+        // Add zone distribution info
+        for (zone, minutes) in &workout_analysis.zone_durations {
+            if *minutes > 0.5 { // Only show zones with significant time
+                changes.reasoning.push(format!(
+                    "{:?}: {:.1} min", zone, minutes
+                ));
             }
-            let (points_changes, zone_breakdown) = Self::calc_points_and_breakdown_from_workout_analysis(&workout_analysis, &heart_rate_zones);
-            changes.stamina_change += points_changes.stamina_change;
-            changes.strength_change += points_changes.strength_change;
-            changes.zone_breakdown = Some(zone_breakdown);
-
-            // Add zone distribution info
-            for (zone, minutes) in &workout_analysis.zone_durations {
-                if *minutes > 0.5 { // Only show zones with significant time
-                    changes.reasoning.push(format!(
-                        "{:?}: {:.1} min", zone, minutes
-                    ));
-                }
+        }
+
+        changes.reasoning.push(format!(
+            "Avg HR: {:.0} bpm, Peak HR: {:.0} bpm", 
+            workout_analysis.avg_heart_rate, workout_analysis.peak_heart_rate
+        ));
+
+        // Gaps in the heart rate data aren't scored
+        if workout_analysis.unknown_duration_min > 0.0 {
+            changes.reasoning.push(format!(
+                "Heart rate coverage {:.0}%: {:.1} min without heart rate data not scored",
+                workout_analysis.coverage_percent, workout_analysis.unknown_duration_min
+            ));
+            tracing::info!("🕳️ {:.1} min of heart rate gaps discarded ({:.0}% coverage)",
+                workout_analysis.unknown_duration_min, workout_analysis.coverage_percent);
+        }
+        
+        tracing::info!("🎯 Final stat changes: stamina +{}, strength +{}", 
+            changes.stamina_change, changes.strength_change);
+        changes
+    }
+
+    /// Calculate stats from calories and duration for workouts without heart rate data,
+    /// kept under the season's per-minute ceiling for them
+    pub fn calc_stats_fallback(workout_data: &WorkoutDataSyncRequest, workout_type: WorkoutType, rules: &ScoringRules) -> StatChanges {
+        let mut changes = StatChanges {
+            stamina_change: 0,
+            strength_change: 0,
+            reasoning: vec!["No heart rate data: scored from calories and duration".to_string()],
+            zone_breakdown: None,
+            training_load: None,
+        };
+
+        let minutes = match (workout_data.workout_start, workout_data.workout_end) {
+            (Some(start), Some(end)) if end > start => (end - start).num_seconds() as f32 / 60.0,
+            _ => {
+                tracing::info!("⚠️ No heart rate data and no workout duration - no stats calculated");
+                changes.reasoning.push("No workout duration, nothing scored".to_string());
+                return changes;
             }
+        };
+        let calories = workout_data.calories_burned.unwrap_or(0).max(0) as f32;
 
+        let profile = ScoringProfile::for_workout_type(workout_type);
+        let mut stamina = (minutes * rules.fallback_stamina_per_min + calories * rules.fallback_stamina_per_kcal)
+            * profile.stamina_multiplier;
+        let mut strength = (minutes * rules.fallback_strength_per_min + calories * rules.fallback_strength_per_kcal)
+            * profile.strength_multiplier
+            + minutes * profile.base_strength_per_min;
+
+        let ceiling = minutes * rules.fallback_max_points_per_min;
+        if stamina + strength > ceiling {
+            let scale = ceiling / (stamina + strength);
+            stamina *= scale;
+            strength *= scale;
             changes.reasoning.push(format!(
-                "Avg HR: {:.0} bpm, Peak HR: {:.0} bpm", 
-                workout_analysis.avg_heart_rate, workout_analysis.peak_heart_rate
+                "Limited to {:.1} points per minute without heart rate data", rules.fallback_max_points_per_min
             ));
-            
-            tracing::info!("🎯 Final stat changes: stamina +{}, strength +{}", 
-                changes.stamina_change, changes.strength_change);
-        } else {
-            tracing::error!("❌ WorkoutAnalyzer::new() returned None - no stats calculated");
         }
 
+        changes.stamina_change = stamina as i32;
+        changes.strength_change = strength as i32;
+        changes.reasoning.push(format!("Duration: {:.1} min, Calories: {:.0} kcal", minutes, calories));
+
+        tracing::info!("🔥 Fallback stat changes from {:.1} min and {:.0} kcal: stamina +{}, strength +{}",
+            minutes, calories, changes.stamina_change, changes.strength_change);
+        changes
+    }
+
+    /// Calculate strength from the volume load (reps x kg) of a strength session's sets, weighted by
+    /// their RPE and kept under the season's per-set ceiling
+    pub fn calc_strength_from_volume_load(exercises: &[StrengthExercise], rules: &ScoringRules) -> StatChanges {
+        let mut changes = StatChanges {
+            stamina_change: 0,
+            strength_change: 0,
+            reasoning: Vec::new(),
+            zone_breakdown: None,
+            training_load: None,
+        };
+
+        let mut strength = 0.0;
+        let mut sets = 0;
+        let mut capped_sets = 0;
+        for set in exercises.iter().flat_map(|exercise| &exercise.sets) {
+            let effort = set.rpe
+                .map(|rpe| 1.0 + (rpe - STRENGTH_REFERENCE_RPE) * 0.1)
+                .unwrap_or(1.0)
+                .clamp(0.5, 1.2);
+            let points = set.volume_load() as f32 * rules.strength_per_volume_kg * effort;
+            if points > rules.strength_max_points_per_set {
+                capped_sets += 1;
+            }
+            strength += points.min(rules.strength_max_points_per_set);
+            sets += 1;
+        }
+
+        let volume_load = strength_volume_load(exercises);
+        changes.strength_change = strength as i32;
+        changes.reasoning.push(format!(
+            "Strength from volume load: {:.0} kg over {} sets of {} exercises", volume_load, sets, exercises.len()
+        ));
+        if capped_sets > 0 {
+            changes.reasoning.push(format!(
+                "{} sets limited to {:.0} strength each", capped_sets, rules.strength_max_points_per_set
+            ));
+        }
+
+        tracing::info!("🏋️ Volume load {:.0} kg over {} sets: strength +{}", volume_load, sets, changes.strength_change);
         changes
     }
 
-    fn calc_points_and_breakdown_from_workout_analysis(workout_analysis: &WorkoutAnalyzer, heart_rate_zones: &HeartRateZones) -> (StatChanges, Vec<ZoneBreakdown>) {
+    fn calc_points_and_breakdown_from_workout_analysis(workout_analysis: &WorkoutAnalyzer, heart_rate_zones: &HeartRateZones, profile: &ScoringProfile, rules: &ScoringRules) -> (StatChanges, Vec<ZoneBreakdown>) {
         let mut changes = StatChanges {
             stamina_change: 0,
             strength_change: 0,
             reasoning: Vec::new(),
             zone_breakdown: None,
+            training_load: None,
         };
 
         let mut total_stamina = 0.0;
@@ -131,16 +343,11 @@ impl StatCalculator {
         let mut zone_breakdown = Vec::new();
 
         for (zone, duration_minutes) in &workout_analysis.zone_durations {
-            let (stamina_per_min, strength_per_min) = match zone {
-                ZoneName::Zone1 => (ZONE_1_STAMINA_POINTS_PER_MIN, ZONE_1_STRENGTH_POINTS_PER_MIN),
-                ZoneName::Zone2 => (ZONE_2_STAMINA_POINTS_PER_MIN, ZONE_2_STRENGTH_POINTS_PER_MIN),
-                ZoneName::Zone3 => (ZONE_3_STAMINA_POINTS_PER_MIN, ZONE_3_STRENGTH_POINTS_PER_MIN),
-                ZoneName::Zone4 => (ZONE_4_STAMINA_POINTS_PER_MIN, ZONE_4_STRENGTH_POINTS_PER_MIN),
-                ZoneName::Zone5 => (ZONE_5_STAMINA_POINTS_PER_MIN, ZONE_5_STRENGTH_POINTS_PER_MIN),
-            };
+            let (stamina_per_min, strength_per_min) = rules.zone_points_per_min(*zone);
             
-            let zone_stamina = (duration_minutes * stamina_per_min as f32) as i32;
-            let zone_strength = (duration_minutes * strength_per_min as f32) as i32;
+            let zone_stamina = (duration_minutes * stamina_per_min * profile.stamina_multiplier) as i32;
+            let zone_strength = (duration_minutes
+                * (strength_per_min * profile.strength_multiplier + profile.base_strength_per_min)) as i32;
             
             total_stamina += zone_stamina as f32;
             total_strength += zone_strength as f32;
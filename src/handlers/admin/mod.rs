@@ -2,4 +2,5 @@ pub mod user_handler;
 pub mod team_handler;
 pub mod league_handler;
 pub mod game_management_handler;
-pub mod workout_handler;
\ No newline at end of file
+pub mod workout_handler;
+pub mod scoring_handler;
@@ -0,0 +1,362 @@
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::scoring_rules::{
+    create_season_scoring_rules, delete_season_scoring_rules, get_season_scoring_rules, update_season_scoring_rules,
+};
+use crate::db::stat_decay::{get_stat_decay_events, get_stat_decay_settings, update_stat_decay_settings};
+use crate::db::stat_gains::{get_stat_caps, update_stat_caps};
+use crate::db::stat_recompute_jobs::{create_stat_recompute_job, get_stat_recompute_job};
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::game::{
+    ScoringRules, StartStatRecomputeRequest, StatCaps, StatDecaySettings, StatRecomputeJob, StatRecomputeMode,
+    StatRecomputeStatus,
+};
+use crate::services::{StatDecayService, StatRecomputeService};
+
+// GET /admin/scoring/caps - Get the daily and weekly stat caps
+pub async fn get_caps(
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat caps")
+    })?;
+
+    let caps = get_stat_caps(&mut conn).await.map_err(|e| {
+        tracing::error!("Failed to fetch stat caps: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat caps")
+    })?;
+
+    Ok(HttpResponse::Ok().json(caps))
+}
+
+// PUT /admin/scoring/caps - Replace the daily and weekly stat caps
+pub async fn update_caps(
+    pool: web::Data<PgPool>,
+    body: web::Json<StatCaps>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let errors = body.validate();
+    if !errors.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid stat caps",
+            "details": errors
+        })));
+    }
+
+    let caps = update_stat_caps(pool.get_ref(), &body).await.map_err(|e| {
+        tracing::error!("Failed to update stat caps: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to update stat caps")
+    })?;
+
+    tracing::info!("🧢 Stat caps updated: daily {} points, weekly {} points", caps.daily_soft_cap, caps.weekly_soft_cap);
+    Ok(HttpResponse::Ok().json(caps))
+}
+
+// GET /admin/scoring/decay - Get the inactivity decay settings
+pub async fn get_decay_settings(
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat decay settings")
+    })?;
+
+    let settings = get_stat_decay_settings(&mut conn).await.map_err(|e| {
+        tracing::error!("Failed to fetch stat decay settings: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat decay settings")
+    })?;
+
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+// PUT /admin/scoring/decay - Replace the inactivity decay settings
+pub async fn update_decay_settings(
+    pool: web::Data<PgPool>,
+    body: web::Json<StatDecaySettings>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let errors = body.validate();
+    if !errors.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "error": "Invalid stat decay settings",
+            "details": errors
+        })));
+    }
+
+    let settings = update_stat_decay_settings(pool.get_ref(), &body).await.map_err(|e| {
+        tracing::error!("Failed to update stat decay settings: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to update stat decay settings")
+    })?;
+
+    tracing::info!("📉 Stat decay settings updated: {} idle days, {} daily rate, {} floor",
+        settings.idle_days, settings.daily_decay_rate, settings.floor_ratio);
+    Ok(HttpResponse::Ok().json(settings))
+}
+
+// POST /admin/scoring/decay/run - Warn and decay inactive users now instead of waiting for the nightly run
+pub async fn run_decay(
+    pool: web::Data<PgPool>,
+    redis: Option<web::Data<Arc<redis::Client>>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let summary = StatDecayService::new(pool.get_ref().clone(), redis.map(|redis| redis.get_ref().clone()))
+        .run()
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to run stat decay: {}", e);
+            actix_web::error::ErrorInternalServerError("Failed to run stat decay")
+        })?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Stat decay completed", summary)))
+}
+
+// GET /admin/users/{id}/stat-decay - Every decay applied to a user's stats
+pub async fn get_user_decay_events(
+    pool: web::Data<PgPool>,
+    user_id: web::Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let events = get_stat_decay_events(pool.get_ref(), user_id.into_inner()).await.map_err(|e| {
+        tracing::error!("Failed to fetch stat decay events: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat decay events")
+    })?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success("Stat decay events", events)))
+}
+
+// GET /admin/leagues/{league_id}/seasons/{season_id}/scoring-rules - Get a season's scoring rules
+pub async fn get_season_rules(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (league_id, season_id) = path.into_inner();
+    if !season_exists(&pool, league_id, season_id).await? {
+        return Ok(season_not_found());
+    }
+
+    let rules = get_season_scoring_rules(pool.get_ref(), season_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch scoring rules for season {}: {}", season_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch scoring rules")
+    })?;
+
+    match rules {
+        Some(rules) => Ok(rules_response(rules, "Scoring rules")),
+        None => Ok(rules_not_found()),
+    }
+}
+
+// POST /admin/leagues/{league_id}/seasons/{season_id}/scoring-rules - Give a season its own scoring rules
+pub async fn create_season_rules(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<ScoringRules>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (league_id, season_id) = path.into_inner();
+    if let Some(response) = invalid_rules(&body) {
+        return Ok(response);
+    }
+    if !season_exists(&pool, league_id, season_id).await? {
+        return Ok(season_not_found());
+    }
+
+    match create_season_scoring_rules(pool.get_ref(), season_id, &body).await {
+        Ok(rules) => {
+            tracing::info!("📏 Scoring rules created for season {}", season_id);
+            Ok(HttpResponse::Created().json(ApiResponse::success("Scoring rules created", rules)))
+        }
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Season already has scoring rules"
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create scoring rules for season {}: {}", season_id, e);
+            Err(actix_web::error::ErrorInternalServerError("Failed to create scoring rules"))
+        }
+    }
+}
+
+// PUT /admin/leagues/{league_id}/seasons/{season_id}/scoring-rules - Replace a season's scoring rules
+pub async fn update_season_rules(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    body: web::Json<ScoringRules>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (league_id, season_id) = path.into_inner();
+    if let Some(response) = invalid_rules(&body) {
+        return Ok(response);
+    }
+    if !season_exists(&pool, league_id, season_id).await? {
+        return Ok(season_not_found());
+    }
+
+    let rules = update_season_scoring_rules(pool.get_ref(), season_id, &body).await.map_err(|e| {
+        tracing::error!("Failed to update scoring rules for season {}: {}", season_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to update scoring rules")
+    })?;
+
+    match rules {
+        Some(rules) => {
+            tracing::info!("📏 Scoring rules updated for season {}", season_id);
+            Ok(rules_response(rules, "Scoring rules updated"))
+        }
+        None => Ok(rules_not_found()),
+    }
+}
+
+// DELETE /admin/leagues/{league_id}/seasons/{season_id}/scoring-rules - Go back to the default scoring
+pub async fn delete_season_rules(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (league_id, season_id) = path.into_inner();
+    if !season_exists(&pool, league_id, season_id).await? {
+        return Ok(season_not_found());
+    }
+
+    let deleted = delete_season_scoring_rules(pool.get_ref(), season_id).await.map_err(|e| {
+        tracing::error!("Failed to delete scoring rules for season {}: {}", season_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to delete scoring rules")
+    })?;
+
+    if !deleted {
+        return Ok(rules_not_found());
+    }
+
+    tracing::info!("📏 Scoring rules deleted for season {}, back to the defaults", season_id);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(
+        "Scoring rules deleted, the season uses the default scoring",
+        serde_json::json!({ "season_id": season_id }),
+    )))
+}
+
+// POST /admin/scoring/recompute - Start a dry run that reports what replaying every workout would change
+pub async fn start_recompute(
+    pool: web::Data<PgPool>,
+    recompute_service: web::Data<StatRecomputeService>,
+    body: Option<web::Json<StartStatRecomputeRequest>>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let admin_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid admin ID"))?;
+    let rebuild_live_games = body.map(|body| body.rebuild_live_games).unwrap_or_default();
+
+    let job = create_stat_recompute_job(pool.get_ref(), admin_id, StatRecomputeMode::DryRun, rebuild_live_games, None)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create stat recompute job: {}", e);
+            actix_web::error::ErrorInternalServerError("Failed to start stat recompute")
+        })?;
+
+    recompute_service.into_inner().spawn_job(job.id, StatRecomputeMode::DryRun, rebuild_live_games);
+    Ok(recompute_accepted(job, "Dry run started"))
+}
+
+// GET /admin/scoring/recompute/{job_id} - Get a stat recompute job with its report once completed
+pub async fn get_recompute(
+    pool: web::Data<PgPool>,
+    job_id: web::Path<Uuid>,
+) -> Result<HttpResponse, actix_web::Error> {
+    match fetch_recompute_job(&pool, job_id.into_inner()).await? {
+        Some(job) => Ok(HttpResponse::Ok().json(ApiResponse::success("Stat recompute job", job))),
+        None => Ok(recompute_job_not_found()),
+    }
+}
+
+// POST /admin/scoring/recompute/{job_id}/apply - Apply a completed dry run with the same options
+pub async fn apply_recompute(
+    pool: web::Data<PgPool>,
+    recompute_service: web::Data<StatRecomputeService>,
+    job_id: web::Path<Uuid>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let admin_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid admin ID"))?;
+    let Some(dry_run) = fetch_recompute_job(&pool, job_id.into_inner()).await? else {
+        return Ok(recompute_job_not_found());
+    };
+
+    if dry_run.mode != StatRecomputeMode::DryRun.as_str() || dry_run.status != StatRecomputeStatus::Completed.as_str() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Only a completed dry run can be applied"
+        })));
+    }
+
+    let job = match create_stat_recompute_job(
+        pool.get_ref(), admin_id, StatRecomputeMode::Apply, dry_run.rebuild_live_games, Some(dry_run.id),
+    ).await {
+        Ok(job) => job,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(HttpResponse::Conflict().json(serde_json::json!({
+                "error": "Dry run was already applied"
+            })));
+        }
+        Err(e) => {
+            tracing::error!("Failed to create stat recompute job: {}", e);
+            return Err(actix_web::error::ErrorInternalServerError("Failed to apply stat recompute"));
+        }
+    };
+
+    tracing::info!("🔄 Applying stat recompute dry run {} as job {}", dry_run.id, job.id);
+    recompute_service.into_inner().spawn_job(job.id, StatRecomputeMode::Apply, job.rebuild_live_games);
+    Ok(recompute_accepted(job, "Recompute started"))
+}
+
+async fn fetch_recompute_job(pool: &PgPool, job_id: Uuid) -> Result<Option<StatRecomputeJob>, actix_web::Error> {
+    get_stat_recompute_job(pool, job_id).await.map_err(|e| {
+        tracing::error!("Failed to fetch stat recompute job {}: {}", job_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch stat recompute job")
+    })
+}
+
+fn recompute_accepted(job: StatRecomputeJob, message: &str) -> HttpResponse {
+    HttpResponse::Accepted().json(ApiResponse::success(message, job))
+}
+
+fn recompute_job_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Stat recompute job not found"
+    }))
+}
+
+async fn season_exists(pool: &PgPool, league_id: Uuid, season_id: Uuid) -> Result<bool, actix_web::Error> {
+    let season = sqlx::query!(
+        "SELECT id FROM league_seasons WHERE league_id = $1 AND id = $2",
+        league_id,
+        season_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        tracing::error!("Database error checking season: {}", e);
+        actix_web::error::ErrorInternalServerError("Database error")
+    })?;
+
+    Ok(season.is_some())
+}
+
+fn invalid_rules(rules: &ScoringRules) -> Option<HttpResponse> {
+    let errors = rules.validate();
+    (!errors.is_empty()).then(|| HttpResponse::BadRequest().json(serde_json::json!({
+        "error": "Invalid scoring rules",
+        "details": errors
+    })))
+}
+
+fn rules_response(rules: ScoringRules, message: &str) -> HttpResponse {
+    HttpResponse::Ok().json(ApiResponse::success(message, rules))
+}
+
+fn season_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Season not found"
+    }))
+}
+
+fn rules_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Season has no scoring rules, the default scoring applies"
+    }))
+}
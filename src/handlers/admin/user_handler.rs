@@ -4,6 +4,8 @@ use uuid::Uuid;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::services::HeartRateEstimationService;
+
 #[derive(Serialize)]
 pub struct AdminUserResponse {
     pub id: Uuid,
@@ -456,4 +458,23 @@ pub async fn delete_user(
     };
 
     Ok(HttpResponse::Ok().json(response))
-}
\ No newline at end of file
+}
+
+// POST /admin/heart-rate-estimates/run - Estimate heart rates from workout history now instead of waiting for the nightly run
+pub async fn run_heart_rate_estimation(
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse> {
+    let summary = HeartRateEstimationService::new(pool.get_ref().clone())
+        .run()
+        .await
+        .map_err(|e| {
+            eprintln!("Database error estimating heart rates: {}", e);
+            actix_web::error::ErrorInternalServerError("Failed to estimate heart rates")
+        })?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse {
+        data: summary,
+        success: true,
+        message: Some("Heart rate estimation completed".to_string()),
+    }))
+}
@@ -2,12 +2,18 @@ use actix_web::{web, HttpResponse};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
+use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::models::workout_data::HeartRateData;
+use crate::db::heart_rate_samples::get_heart_rate_samples;
+use crate::db::sensor_samples::get_sensor_streams;
+use crate::handlers::workout_data::upload_workout_data::approve_flagged_workout;
+use crate::middleware::auth::Claims;
+use crate::models::workout_data::{HeartRateData, WorkoutStreams};
+use crate::services::live_game_service::LiveGameService;
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
-struct AdminWorkoutData {
+pub struct AdminWorkoutData {
     pub id: Uuid,
     pub user_id: Uuid,
     pub username: String,
@@ -17,6 +23,8 @@ struct AdminWorkoutData {
     pub workout_uuid: Option<String>,
     pub workout_start: Option<DateTime<Utc>>,
     pub workout_end: Option<DateTime<Utc>>,
+    pub review_status: String,
+    pub validation_flags: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -27,10 +35,20 @@ struct AdminWorkoutDetail {
     pub username: String,
     pub device_id: String,
     pub heart_rate: Option<Vec<HeartRateData>>,
+    #[serde(flatten)]
+    pub streams: WorkoutStreams,
     pub calories_burned: Option<i32>,
+    pub avg_power: Option<i32>,
+    pub max_power: Option<i32>,
+    pub avg_cadence: Option<i32>,
+    pub total_distance_meters: Option<f64>,
+    pub total_steps: Option<i32>,
     pub workout_uuid: Option<String>,
     pub workout_start: Option<DateTime<Utc>>,
     pub workout_end: Option<DateTime<Utc>>,
+    pub workout_type: Option<String>,
+    pub review_status: String,
+    pub validation_flags: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -40,6 +58,8 @@ pub struct AdminWorkoutQuery {
     pub offset: Option<i64>,
     pub user_id: Option<Uuid>,
     pub username: Option<String>,
+    /// e.g. "pending_review" to list the review queue
+    pub review_status: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -62,11 +82,13 @@ pub async fn get_all_workouts(
         JOIN users u ON u.id = wd.user_id
         WHERE ($1::uuid IS NULL OR wd.user_id = $1)
         AND ($2::text IS NULL OR LOWER(u.username) LIKE LOWER(CONCAT('%', $2, '%')))
+        AND ($3::text IS NULL OR wd.review_status = $3)
     "#;
 
     let total = sqlx::query_scalar::<_, i64>(count_query)
         .bind(query.user_id)
         .bind(&query.username)
+        .bind(&query.review_status)
         .fetch_one(pool.get_ref())
         .await
         .map_err(|e| {
@@ -81,16 +103,19 @@ pub async fn get_all_workouts(
             wd.user_id,
             u.username,
             wd.device_id,
-            COALESCE(jsonb_array_length(wd.heart_rate_data), 0) as heart_rate_count,
+            wd.heart_rate_sample_count as heart_rate_count,
             wd.calories_burned,
             wd.workout_uuid,
             wd.workout_start,
             wd.workout_end,
+            wd.review_status,
+            wd.validation_flags,
             wd.created_at
         FROM workout_data wd
         JOIN users u ON u.id = wd.user_id
         WHERE ($1::uuid IS NULL OR wd.user_id = $1)
         AND ($2::text IS NULL OR LOWER(u.username) LIKE LOWER(CONCAT('%', $2, '%')))
+        AND ($5::text IS NULL OR wd.review_status = $5)
         ORDER BY wd.created_at DESC
         LIMIT $3 OFFSET $4
     "#;
@@ -100,6 +125,7 @@ pub async fn get_all_workouts(
         .bind(&query.username)
         .bind(limit)
         .bind(offset)
+        .bind(&query.review_status)
         .fetch_all(pool.get_ref())
         .await
         .map_err(|e| {
@@ -120,11 +146,18 @@ pub async fn get_workout_detail(
             wd.user_id,
             u.username,
             wd.device_id,
-            wd.heart_rate_data,
             wd.calories_burned,
+            wd.avg_power,
+            wd.max_power,
+            wd.avg_cadence,
+            wd.total_distance_meters,
+            wd.total_steps,
             wd.workout_uuid,
             wd.workout_start,
             wd.workout_end,
+            wd.workout_type,
+            wd.review_status,
+            wd.validation_flags,
             wd.created_at
         FROM workout_data wd
         JOIN users u ON u.id = wd.user_id
@@ -145,36 +178,23 @@ pub async fn get_workout_detail(
             }
         })?;
 
-    let heart_rate_json: Option<serde_json::Value> = row.get("heart_rate_data");
-    let heart_rate = heart_rate_json.and_then(|json| {
-        // Try to deserialize the heart rate data
-        // Handle both possible formats: {heart_rate: ...} and {hr: ...}
-        if let serde_json::Value::Array(arr) = json {
-            let mut heart_rate_vec = Vec::new();
-            for item in arr {
-                if let serde_json::Value::Object(obj) = item {
-                    if let (Some(timestamp), Some(hr_value)) = (
-                        obj.get("timestamp").and_then(|t| t.as_str()),
-                        obj.get("heart_rate").or_else(|| obj.get("hr")).and_then(|h| h.as_i64())
-                    ) {
-                        if let Ok(ts) = DateTime::parse_from_rfc3339(timestamp) {
-                            heart_rate_vec.push(HeartRateData {
-                                timestamp: ts.with_timezone(&Utc),
-                                heart_rate: hr_value as i32,
-                            });
-                        }
-                    }
-                }
-            }
-            if !heart_rate_vec.is_empty() {
-                Some(heart_rate_vec)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    });
+    let heart_rate = get_heart_rate_samples(pool.get_ref(), row.get("id"))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch workout heart rate samples: {}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch workout detail")
+        })?;
+    let heart_rate = (!heart_rate.is_empty()).then_some(heart_rate);
+    let mut conn = pool.acquire().await.map_err(|e| {
+        tracing::error!("Failed to acquire connection: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to fetch workout detail")
+    })?;
+    let streams = get_sensor_streams(&mut conn, row.get("id"))
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fetch workout sensor streams: {}", e);
+            actix_web::error::ErrorInternalServerError("Failed to fetch workout detail")
+        })?;
 
     let workout = AdminWorkoutDetail {
         id: row.get("id"),
@@ -182,10 +202,19 @@ pub async fn get_workout_detail(
         username: row.get("username"),
         device_id: row.get("device_id"),
         heart_rate,
+        streams,
         calories_burned: row.get("calories_burned"),
+        avg_power: row.get("avg_power"),
+        max_power: row.get("max_power"),
+        avg_cadence: row.get("avg_cadence"),
+        total_distance_meters: row.get("total_distance_meters"),
+        total_steps: row.get("total_steps"),
         workout_uuid: row.get("workout_uuid"),
         workout_start: row.get("workout_start"),
         workout_end: row.get("workout_end"),
+        workout_type: row.get("workout_type"),
+        review_status: row.get("review_status"),
+        validation_flags: row.get("validation_flags"),
         created_at: row.get("created_at"),
     };
 
@@ -265,4 +294,89 @@ pub async fn bulk_delete_workouts(
         "message": format!("{} workouts deleted successfully", deleted_count),
         "deleted_count": deleted_count
     })))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkoutReviewDecision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkoutReviewRequest {
+    pub decision: WorkoutReviewDecision,
+}
+
+/// Resolve a workout held for review: approving scores it, rejecting keeps it unscored
+pub async fn review_workout(
+    pool: web::Data<PgPool>,
+    redis: Option<web::Data<Arc<redis::Client>>>,
+    live_game_service: Option<web::Data<LiveGameService>>,
+    workout_id: web::Path<Uuid>,
+    body: web::Json<WorkoutReviewRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let workout_id = workout_id.into_inner();
+    let reviewer_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| actix_web::error::ErrorBadRequest("Invalid admin ID"))?;
+
+    match body.decision {
+        WorkoutReviewDecision::Approve => {
+            let stat_changes = approve_flagged_workout(
+                pool.get_ref(),
+                redis.as_ref().map(|r| r.get_ref()),
+                live_game_service.as_ref().map(|s| s.get_ref()),
+                workout_id,
+                reviewer_id,
+            )
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => {
+                    actix_web::error::ErrorNotFound("Workout not found or not pending review")
+                }
+                _ => {
+                    tracing::error!("Failed to approve workout {}: {}", workout_id, e);
+                    actix_web::error::ErrorInternalServerError("Failed to approve workout")
+                }
+            })?;
+
+            tracing::info!("Admin {} approved workout: {}", claims.username, workout_id);
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Workout approved and scored",
+                "workout_id": workout_id,
+                "review_status": "accepted",
+                "stamina_change": stat_changes.stamina_change,
+                "strength_change": stat_changes.strength_change
+            })))
+        }
+        WorkoutReviewDecision::Reject => {
+            let result = sqlx::query(
+                "UPDATE workout_data
+                 SET review_status = 'rejected', reviewed_by = $1, reviewed_at = NOW()
+                 WHERE id = $2 AND review_status = 'pending_review'"
+            )
+            .bind(reviewer_id)
+            .bind(workout_id)
+            .execute(pool.get_ref())
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to reject workout {}: {}", workout_id, e);
+                actix_web::error::ErrorInternalServerError("Failed to reject workout")
+            })?;
+
+            if result.rows_affected() == 0 {
+                return Err(actix_web::error::ErrorNotFound("Workout not found or not pending review"));
+            }
+
+            tracing::info!("Admin {} rejected workout: {}", claims.username, workout_id);
+
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "message": "Workout rejected",
+                "workout_id": workout_id,
+                "review_status": "rejected"
+            })))
+        }
+    }
 }
\ No newline at end of file
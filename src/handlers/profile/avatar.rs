@@ -0,0 +1,77 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use uuid::Uuid;
+use sqlx::PgPool;
+
+use crate::db::avatar_progression::{get_avatar_experience, update_avatar_style};
+use crate::game::progression::{avatar_progression, style_unlock_level};
+use crate::middleware::auth::Claims;
+use crate::models::profile::UpdateAvatarStyleRequest;
+
+#[tracing::instrument(
+    name = "Update avatar style",
+    skip(pool, claims, data),
+    fields(username = %claims.username)
+)]
+pub async fn change_avatar_style(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+    data: web::Json<UpdateAvatarStyleRequest>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    let Some(unlock_level) = style_unlock_level(&data.avatar_style) else {
+        return HttpResponse::BadRequest().json(json!({
+            "error": format!("Unknown avatar style '{}'", data.avatar_style)
+        }));
+    };
+
+    let experience = match get_avatar_experience(&pool, user_id).await {
+        Ok(Some(experience)) => experience,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(json!({
+                "error": "Avatar not found"
+            }));
+        }
+        Err(e) => {
+            tracing::error!("Database error fetching avatar experience: {}", e);
+            return HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update avatar style"
+            }));
+        }
+    };
+
+    let progression = avatar_progression(experience);
+    if progression.level < unlock_level {
+        return HttpResponse::Forbidden().json(json!({
+            "error": format!("Avatar style '{}' unlocks at level {}", data.avatar_style, unlock_level)
+        }));
+    }
+
+    match update_avatar_style(&pool, user_id, &data.avatar_style).await {
+        Ok(_) => {
+            tracing::info!("🎨 {} switched to the {} avatar style", claims.username, data.avatar_style);
+            HttpResponse::Ok().json(json!({
+                "success": true,
+                "data": {
+                    "avatar_style": data.avatar_style,
+                    "progression": progression,
+                }
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Database error updating avatar style: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update avatar style"
+            }))
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use uuid::Uuid;
+use sqlx::PgPool;
+
+use crate::middleware::auth::Claims;
+use crate::services::DailyChallengeService;
+
+#[tracing::instrument(
+    name = "Get daily challenges",
+    skip(pool, claims),
+    fields(username = %claims.username)
+)]
+pub async fn get_todays_challenges(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    let service = DailyChallengeService::new(pool.get_ref().clone(), None);
+    match service.todays_challenges(user_id).await {
+        Ok((today, challenges)) => HttpResponse::Ok().json(json!({
+            "success": true,
+            "data": {
+                "date": today,
+                "challenges": challenges,
+            }
+        })),
+        Err(e) => {
+            tracing::error!("Database error fetching daily challenges: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch daily challenges"
+            }))
+        }
+    }
+}
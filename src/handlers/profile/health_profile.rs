@@ -5,7 +5,8 @@ use sqlx::PgPool;
 
 use crate::middleware::auth::Claims;
 use crate::models::profile::{HealthProfileResponse, UpdateHealthProfileRequest};
-use crate::models::workout_data::{HeartRateZones, ZoneName};
+use crate::db::heart_rate_estimates::{record_heart_rate_zones, HeartRateZoneSource};
+use crate::models::workout_data::HeartRateZones;
 
 #[tracing::instrument(
     name = "Get health profile",
@@ -29,7 +30,8 @@ pub async fn get_health_profile(
     match sqlx::query_as!(
         HealthProfileResponse,
         r#"
-        SELECT id, user_id, age, gender, resting_heart_rate, weight, height, last_updated
+        SELECT id, user_id, age, gender, resting_heart_rate, weight, height,
+               auto_apply_heart_rate_estimates, last_updated
         FROM user_health_profiles 
         WHERE user_id = $1
         "#,
@@ -116,8 +118,10 @@ pub async fn update_health_profile(
 
     let result = sqlx::query!(
         r#"
-        INSERT INTO user_health_profiles (user_id, age, gender, resting_heart_rate, weight, height, last_updated)
-        VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        INSERT INTO user_health_profiles (
+            user_id, age, gender, resting_heart_rate, weight, height, auto_apply_heart_rate_estimates, last_updated
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, false), NOW())
         ON CONFLICT (user_id) 
         DO UPDATE SET 
             age = COALESCE($2, user_health_profiles.age),
@@ -125,15 +129,18 @@ pub async fn update_health_profile(
             resting_heart_rate = COALESCE($4, user_health_profiles.resting_heart_rate),
             weight = COALESCE($5, user_health_profiles.weight),
             height = COALESCE($6, user_health_profiles.height),
+            auto_apply_heart_rate_estimates = COALESCE($7, user_health_profiles.auto_apply_heart_rate_estimates),
             last_updated = NOW()
-        RETURNING id, age, resting_heart_rate
+        RETURNING id, age, resting_heart_rate, observed_max_heart_rate, max_heart_rate,
+                  hr_zone_1_max, hr_zone_2_max, hr_zone_3_max, hr_zone_4_max, hr_zone_5_max
         "#,
         user_id,
         profile_data.age,
         profile_data.gender.as_deref(),
         profile_data.resting_heart_rate,
         profile_data.weight,
-        profile_data.height
+        profile_data.height,
+        profile_data.auto_apply_heart_rate_estimates
     )
     .fetch_one(&**pool)
     .await;
@@ -144,35 +151,27 @@ pub async fn update_health_profile(
             
             // Calculate and store heart rate zones if we have age and resting heart rate
             if let (Some(age), Some(resting_heart_rate)) = (profile_record.age, profile_record.resting_heart_rate) {
-                // Calculate max heart rate using 220 - age formula
-                let max_heart_rate = 220 - age;
+                // Calculate max heart rate using 220 - age formula, unless workouts showed a higher one
+                let max_heart_rate = profile_record.observed_max_heart_rate.unwrap_or(220 - age);
                 let hhr = max_heart_rate - resting_heart_rate; // Heart Rate Reserve
                 
                 // Calculate zone thresholds using HeartRateZones
                 let zones = HeartRateZones::new(hhr, resting_heart_rate, max_heart_rate);
+                let zone_maxes = zones.zone_maxes();
+                let stored_zone_maxes = [
+                    profile_record.hr_zone_1_max,
+                    profile_record.hr_zone_2_max,
+                    profile_record.hr_zone_3_max,
+                    profile_record.hr_zone_4_max,
+                    profile_record.hr_zone_5_max,
+                ];
+                let unchanged = profile_record.max_heart_rate == Some(max_heart_rate)
+                    && stored_zone_maxes == zone_maxes.map(Some);
                 
                 // Update the profile with calculated zone thresholds
-                if let Err(e) = sqlx::query!(
-                    r#"
-                    UPDATE user_health_profiles 
-                    SET max_heart_rate = $1,
-                        hr_zone_1_max = $2,
-                        hr_zone_2_max = $3,
-                        hr_zone_3_max = $4,
-                        hr_zone_4_max = $5,
-                        hr_zone_5_max = $6
-                    WHERE user_id = $7
-                    "#,
-                    max_heart_rate,
-                    zones.zones.get(&ZoneName::Zone1).map(|z| z.high),
-                    zones.zones.get(&ZoneName::Zone2).map(|z| z.high),
-                    zones.zones.get(&ZoneName::Zone3).map(|z| z.high),
-                    zones.zones.get(&ZoneName::Zone4).map(|z| z.high),
-                    zones.zones.get(&ZoneName::Zone5).map(|z| z.high),
-                    user_id
-                )
-                .execute(&**pool)
-                .await {
+                if unchanged {
+                    tracing::debug!("Heart rate zones unchanged for user: {}", claims.username);
+                } else if let Err(e) = store_heart_rate_zones(&pool, user_id, resting_heart_rate, max_heart_rate, zone_maxes).await {
                     tracing::error!("Failed to update heart rate zones: {}", e);
                     // Continue execution - zones are optional
                 } else {
@@ -184,7 +183,8 @@ pub async fn update_health_profile(
             match sqlx::query_as!(
                 HealthProfileResponse,
                 r#"
-                SELECT id, user_id, age, gender, resting_heart_rate, weight, height, last_updated
+                SELECT id, user_id, age, gender, resting_heart_rate, weight, height,
+                       auto_apply_heart_rate_estimates, last_updated
                 FROM user_health_profiles 
                 WHERE user_id = $1
                 "#,
@@ -213,4 +213,44 @@ pub async fn update_health_profile(
             }))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Store zones calculated from the profile and record them in the zone history
+async fn store_heart_rate_zones(
+    pool: &PgPool,
+    user_id: Uuid,
+    resting_heart_rate: i32,
+    max_heart_rate: i32,
+    zone_maxes: [i32; 5],
+) -> Result<(), sqlx::Error> {
+    let [zone1_max, zone2_max, zone3_max, zone4_max, zone5_max] = zone_maxes;
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE user_health_profiles 
+        SET max_heart_rate = $1,
+            hr_zone_1_max = $2,
+            hr_zone_2_max = $3,
+            hr_zone_3_max = $4,
+            hr_zone_4_max = $5,
+            hr_zone_5_max = $6
+        WHERE user_id = $7
+        "#,
+        max_heart_rate,
+        zone1_max,
+        zone2_max,
+        zone3_max,
+        zone4_max,
+        zone5_max,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    record_heart_rate_zones(
+        &mut tx, user_id, resting_heart_rate, max_heart_rate, zone_maxes, HeartRateZoneSource::Profile, None,
+    ).await?;
+
+    tx.commit().await
+}
@@ -0,0 +1,141 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use uuid::Uuid;
+use sqlx::PgPool;
+
+use crate::db::heart_rate_estimates::{
+    apply_heart_rate_estimate, get_heart_rate_zone_history, get_proposed_heart_rate_estimate,
+    resolve_heart_rate_estimate,
+};
+use crate::game::helper::{calc_max_heart_rate, get_user_profile};
+use crate::middleware::auth::Claims;
+use crate::models::profile::{HeartRateEstimate, HeartRateEstimateStatus};
+use crate::models::workout_data::HeartRateZones;
+
+#[tracing::instrument(
+    name = "Get heart rate zones",
+    skip(pool, claims),
+    fields(username = %claims.username)
+)]
+pub async fn get_heart_rate_zones(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    let result = async {
+        let profile = get_user_profile(&mut *pool.acquire().await?, user_id).await?;
+        let pending_estimate = get_proposed_heart_rate_estimate(&pool, user_id).await?;
+        let history = get_heart_rate_zone_history(&pool, user_id).await?;
+        Ok::<_, sqlx::Error>((profile, pending_estimate, history))
+    }.await;
+
+    match result {
+        Ok((profile, pending_estimate, history)) => {
+            let resting_heart_rate = profile.resting_heart_rate.unwrap_or(60);
+            let max_heart_rate = profile.max_heart_rate
+                .unwrap_or_else(|| calc_max_heart_rate(profile.age, profile.gender));
+            let zones = profile.stored_heart_rate_zones.unwrap_or_else(|| {
+                HeartRateZones::new(max_heart_rate - resting_heart_rate, resting_heart_rate, max_heart_rate)
+            });
+
+            HttpResponse::Ok().json(json!({
+                "success": true,
+                "data": {
+                    "current": {
+                        "resting_heart_rate": resting_heart_rate,
+                        "max_heart_rate": max_heart_rate,
+                        "zone_maxes": zones.zone_maxes(),
+                    },
+                    "pending_estimate": pending_estimate,
+                    "history": history,
+                }
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Database error fetching heart rate zones: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch heart rate zones"
+            }))
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Review heart rate estimate",
+    skip(pool, claims),
+    fields(username = %claims.username)
+)]
+pub async fn review_heart_rate_estimate(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+    accept: bool,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+    let status = if accept { HeartRateEstimateStatus::Applied } else { HeartRateEstimateStatus::Dismissed };
+
+    match settle_proposed_estimate(&pool, user_id, status).await {
+        Ok(Some(estimate)) => {
+            tracing::info!("Heart rate estimate {} {} by user: {}", estimate.id, estimate.status, claims.username);
+            let message = if accept {
+                "Heart rate estimate applied to your zones"
+            } else {
+                "Heart rate estimate dismissed"
+            };
+            HttpResponse::Ok().json(json!({
+                "success": true,
+                "data": estimate,
+                "message": message
+            }))
+        }
+        Ok(None) => HttpResponse::NotFound().json(json!({
+            "error": "No heart rate estimate to review"
+        })),
+        Err(e) => {
+            tracing::error!("Database error reviewing heart rate estimate: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to review heart rate estimate"
+            }))
+        }
+    }
+}
+
+/// Apply or dismiss the user's proposed estimate, `None` if there is none
+async fn settle_proposed_estimate(
+    pool: &PgPool,
+    user_id: Uuid,
+    status: HeartRateEstimateStatus,
+) -> Result<Option<HeartRateEstimate>, sqlx::Error> {
+    let Some(mut estimate) = get_proposed_heart_rate_estimate(pool, user_id).await? else {
+        return Ok(None);
+    };
+
+    let mut tx = pool.begin().await?;
+    // Superseded by a newer estimate in the meantime
+    if !resolve_heart_rate_estimate(&mut tx, estimate.id, status).await? {
+        return Ok(None);
+    }
+    if status == HeartRateEstimateStatus::Applied {
+        apply_heart_rate_estimate(&mut tx, &estimate).await?;
+    }
+    tx.commit().await?;
+
+    estimate.status = status.as_str().to_string();
+    Ok(Some(estimate))
+}
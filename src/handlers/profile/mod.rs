@@ -1,2 +1,7 @@
 pub mod profile;
-pub mod health_profile;
\ No newline at end of file
+pub mod health_profile;
+pub mod heart_rate_zones;
+pub mod personal_records;
+pub mod daily_challenges;
+pub mod avatar;
+pub mod timezone;
@@ -0,0 +1,59 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+use sqlx::PgPool;
+
+use crate::db::personal_records::{get_milestones, get_personal_records, get_workout_days};
+use crate::game::personal_records::{daily_streak, weekly_streak};
+use crate::middleware::auth::Claims;
+use crate::models::profile::PersonalRecordsResponse;
+
+#[tracing::instrument(
+    name = "Get personal records",
+    skip(pool, claims),
+    fields(username = %claims.username)
+)]
+pub async fn get_user_personal_records(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        let records = get_personal_records(&mut conn, user_id).await?;
+        let milestones = get_milestones(&mut conn, user_id).await?;
+        let workout_days = get_workout_days(&mut conn, user_id).await?;
+        Ok::<_, sqlx::Error>((records, milestones, workout_days))
+    }.await;
+
+    match result {
+        Ok((records, milestones, workout_days)) => {
+            let today = Utc::now().date_naive();
+            HttpResponse::Ok().json(json!({
+                "success": true,
+                "data": PersonalRecordsResponse {
+                    records,
+                    milestones,
+                    daily_streak: daily_streak(&workout_days, today),
+                    weekly_streak: weekly_streak(&workout_days, today),
+                }
+            }))
+        }
+        Err(e) => {
+            tracing::error!("Database error fetching personal records: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to fetch personal records"
+            }))
+        }
+    }
+}
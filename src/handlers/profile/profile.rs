@@ -3,6 +3,7 @@ use serde_json::json;
 use uuid::Uuid;
 use sqlx::PgPool;
 
+use crate::game::progression::avatar_progression;
 use crate::middleware::auth::Claims;
 use crate::models::profile::{UserProfileResponse, GameStats};
 use crate::models::common::ApiResponse;
@@ -57,9 +58,9 @@ pub async fn get_user_profile(
     tracing::info!("Fetching avatar stats for: {}", user_id);
 
     // Get user game stats (avatar stats)
-    let game_stats = match sqlx::query!(
+    let (game_stats, experience) = match sqlx::query!(
         r#"
-        SELECT stamina, strength, avatar_style
+        SELECT stamina, strength, avatar_style, experience
         FROM user_avatars 
         WHERE user_id = $1
         "#,
@@ -68,26 +69,26 @@ pub async fn get_user_profile(
     .fetch_optional(&**pool)
     .await
     {
-        Ok(Some(avatar)) => GameStats {
+        Ok(Some(avatar)) => (GameStats {
             stamina: avatar.stamina,
             strength: avatar.strength,
-        },
+        }, avatar.experience as i64),
         Ok(None) => {
             // Create default avatar if none exists
             match create_default_avatar(&pool, user_id).await {
-                Ok(stats) => stats,
-                Err(_) => GameStats {
+                Ok(stats) => (stats, 0),
+                Err(_) => (GameStats {
                     stamina: 50,
                     strength: 50,
-                }
+                }, 0)
             }
         }
         Err(e) => {
             tracing::error!("Database error fetching avatar: {}", e);
-            GameStats {
+            (GameStats {
                 stamina: 50,
                 strength: 50,
-            }
+            }, 0)
         }
     };
 
@@ -120,6 +121,7 @@ pub async fn get_user_profile(
         stats: game_stats,
         rank,
         avatar_style,
+        progression: avatar_progression(experience),
         total_stats,
         created_at: user_info.created_at,
         last_login: None,
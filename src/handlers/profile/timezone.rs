@@ -0,0 +1,63 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use uuid::Uuid;
+use sqlx::PgPool;
+
+use crate::middleware::auth::Claims;
+use crate::models::profile::UpdateTimezoneRequest;
+
+#[tracing::instrument(
+    name = "Update timezone",
+    skip(pool, claims, data),
+    fields(username = %claims.username)
+)]
+pub async fn change_timezone(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+    data: web::Json<UpdateTimezoneRequest>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::BadRequest().json(json!({
+                "error": "Invalid user ID"
+            }));
+        }
+    };
+
+    // Only names Postgres can count days in
+    let updated = sqlx::query_scalar!(
+        r#"
+        UPDATE users
+        SET timezone = $1, updated_at = NOW()
+        WHERE id = $2 AND EXISTS (SELECT 1 FROM pg_timezone_names WHERE name = $1)
+        RETURNING timezone
+        "#,
+        data.timezone,
+        user_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match updated {
+        Ok(Some(timezone)) => {
+            tracing::info!("🌍 {} switched to the {} time zone", claims.username, timezone);
+            HttpResponse::Ok().json(json!({
+                "success": true,
+                "data": {
+                    "timezone": timezone,
+                }
+            }))
+        }
+        Ok(None) => HttpResponse::BadRequest().json(json!({
+            "error": format!("Unknown time zone '{}'", data.timezone)
+        })),
+        Err(e) => {
+            tracing::error!("Database error updating timezone: {}", e);
+            HttpResponse::InternalServerError().json(json!({
+                "error": "Failed to update timezone"
+            }))
+        }
+    }
+}
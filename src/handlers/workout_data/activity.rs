@@ -1,13 +1,31 @@
 use actix_web::{web, HttpResponse};
+use serde::Deserialize;
 use serde_json::json;
 use uuid::Uuid;
 use sqlx::PgPool;
 use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
 
+use crate::db::workout_data::{get_stat_gains_since, get_weekly_stats, get_zone_minutes};
+use crate::league::seasons::SeasonService;
 use crate::middleware::auth::Claims;
 use crate::models::workout_data::{ActivitySummaryResponse, WeeklyStats, MonthlyTrend};
 
+/// Zones with the labels the activity summary uses for them
+const ZONE_LABELS: [(&str, &str); 5] = [
+    ("Zone1", "Zone 1 (Active Recovery)"),
+    ("Zone2", "Zone 2 (Aerobic Base)"),
+    ("Zone3", "Zone 3 (Aerobic)"),
+    ("Zone4", "Zone 4 (Threshold)"),
+    ("Zone5", "Zone 5 (VO2 Max)"),
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ActivitySummaryQuery {
+    /// Window of the zone distribution: "7d", "30d" (default) or "season"
+    pub window: Option<String>,
+}
+
 #[tracing::instrument(
     name = "Get user activity summary",
     skip(pool, claims),
@@ -15,7 +33,8 @@ use crate::models::workout_data::{ActivitySummaryResponse, WeeklyStats, MonthlyT
 )]
 pub async fn get_activity_summary(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    claims: web::ReqData<Claims>,
+    query: web::Query<ActivitySummaryQuery>
 ) -> HttpResponse {
     let user_id = match Uuid::parse_str(&claims.sub) {
         Ok(id) => id,
@@ -32,6 +51,31 @@ pub async fn get_activity_summary(
     let week_ago = now - Duration::days(7);
     let month_ago = now - Duration::days(30);
 
+    let zone_window = query.window.as_deref().unwrap_or("30d");
+    let (zone_since, zone_until) = match zone_window {
+        "7d" => (week_ago, now),
+        "30d" => (month_ago, now),
+        "season" => match SeasonService::new(pool.get_ref().clone()).get_current_season_for_user(user_id).await {
+            Ok(Some(season)) => (season.start_date, season.end_date.min(now)),
+            Ok(None) => {
+                return HttpResponse::NotFound().json(json!({
+                    "error": "Your team isn't playing in a season"
+                }));
+            }
+            Err(e) => {
+                tracing::error!("Failed to get current season: {}", e);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to get activity summary"
+                }));
+            }
+        },
+        other => {
+            return HttpResponse::BadRequest().json(json!({
+                "error": format!("Unknown window '{}', use 7d, 30d or season", other)
+            }));
+        }
+    };
+
     // Get recent workout count (last 7 days)
     let recent_workouts = match sqlx::query!(
         r#"
@@ -74,8 +118,8 @@ pub async fn get_activity_summary(
         }
     };
 
-    // Get zone distribution (mock data for now - would need heart rate time series)
-    let zone_distribution = generate_mock_zone_distribution();
+    // Get zone distribution from the stored zone breakdowns of the workouts in the window
+    let zone_distribution = calculate_zone_distribution(&pool, user_id, zone_since, zone_until).await;
 
     // Get last sync time
     let last_sync = match sqlx::query!(
@@ -103,6 +147,7 @@ pub async fn get_activity_summary(
         recent_workouts,
         total_sessions,
         zone_distribution,
+        zone_window: zone_window.to_string(),
         last_sync,
         weekly_stats,
         monthly_trend,
@@ -115,101 +160,43 @@ pub async fn get_activity_summary(
     }))
 }
 
-fn generate_mock_zone_distribution() -> HashMap<String, f32> {
-    let mut zones = HashMap::new();
-    zones.insert("Zone 1 (Active Recovery)".to_string(), 28.5);
-    zones.insert("Zone 2 (Aerobic Base)".to_string(), 45.2);
-    zones.insert("Zone 3 (Aerobic)".to_string(), 22.8);
-    zones.insert("Zone 4 (Threshold)".to_string(), 15.3);
-    zones.insert("Zone 5 (VO2 Max)".to_string(), 8.7);
-    zones
-}
-
-async fn calculate_weekly_stats(pool: &PgPool, user_id: Uuid, since: DateTime<Utc>) -> WeeklyStats {
-    // Get total calories burned this week
-    let total_calories = match sqlx::query!(
-        r#"
-        SELECT SUM(calories_burned) as total_calories
-        FROM workout_data 
-        WHERE user_id = $1 
-        AND created_at >= $2
-        AND calories_burned IS NOT NULL
-        "#,
-        user_id,
-        since
-    )
-    .fetch_one(pool)
-    .await
-    {
-        Ok(row) => row.total_calories.unwrap_or(0),
-        Err(_) => 0,
+async fn calculate_zone_distribution(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> HashMap<String, f32> {
+    let zone_minutes = async { get_zone_minutes(&mut *pool.acquire().await?, user_id, since, until).await }.await;
+    let zone_minutes = match zone_minutes {
+        Ok(zone_minutes) => zone_minutes,
+        Err(e) => {
+            tracing::error!("Failed to get zone minutes: {}", e);
+            HashMap::new()
+        }
     };
 
-    // Estimate exercise time (very simplified - 1 calorie = ~1 minute)
-    let total_exercise_time = total_calories / 5; // More realistic ratio
-
-    // Count different session types (simplified based on calorie burn)
-    let session_counts = match sqlx::query!(
-        r#"
-        SELECT 
-            COUNT(CASE WHEN calories_burned > 400 THEN 1 END) as high_intensity,
-            COUNT(CASE WHEN calories_burned BETWEEN 200 AND 400 THEN 1 END) as moderate_intensity
-        FROM workout_data 
-        WHERE user_id = $1 
-        AND created_at >= $2
-        AND calories_burned > 200
-        "#,
-        user_id,
-        since
-    )
-    .fetch_one(pool)
-    .await
-    {
-        Ok(row) => (
-            row.high_intensity.unwrap_or(0) as i32,
-            row.moderate_intensity.unwrap_or(0) as i32
-        ),
-        Err(_) => (0, 0),
-    };
+    ZONE_LABELS.iter()
+        .map(|(zone, label)| (label.to_string(), zone_minutes.get(*zone).copied().unwrap_or(0.0) as f32))
+        .collect()
+}
 
-    WeeklyStats {
-        total_calories: total_calories as i32,
-        total_exercise_time: total_exercise_time as i32,
-        strength_sessions: session_counts.0, // High intensity assumed as strength
-        cardio_sessions: session_counts.1,   // Moderate intensity assumed as cardio
+async fn calculate_weekly_stats(pool: &PgPool, user_id: Uuid, since: DateTime<Utc>) -> WeeklyStats {
+    match get_weekly_stats(pool, user_id, since).await {
+        Ok(weekly_stats) => weekly_stats,
+        Err(e) => {
+            tracing::error!("Failed to get weekly stats: {}", e);
+            WeeklyStats::default()
+        }
     }
 }
 
 async fn calculate_monthly_trend(pool: &PgPool, user_id: Uuid, since: DateTime<Utc>) -> MonthlyTrend {
-    // Get stat changes from the last month
-    // This would need to be implemented with a proper stat_changes table
-    // For now, we'll estimate based on activity level
-    
-    let activity_count = match sqlx::query!(
-        r#"
-        SELECT COUNT(*) as count
-        FROM workout_data 
-        WHERE user_id = $1 
-        AND created_at >= $2
-        AND calories_burned > 200
-        "#,
-        user_id,
-        since
-    )
-    .fetch_one(pool)
-    .await
-    {
-        Ok(row) => row.count.unwrap_or(0) as i32,
-        Err(_) => 0,
-    };
-
-    // Estimate gains based on activity (very simplified)
-    let stamina_gain = (activity_count * 2).min(50); // Max 50 points gain per month
-    let strength_gain = activity_count.min(30); // Max 30 points gain per month
-    
-    MonthlyTrend {
-        stamina_gain,
-        strength_gain,
+    match get_stat_gains_since(pool, user_id, since).await {
+        Ok(monthly_trend) => monthly_trend,
+        Err(e) => {
+            tracing::error!("Failed to get monthly trend: {}", e);
+            MonthlyTrend::default()
+        }
     }
 }
 
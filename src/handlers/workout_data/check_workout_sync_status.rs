@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::db::workout_data::get_synced_workout_uuids;
 use crate::middleware::auth::Claims;
 use crate::models::common::ApiResponse;
 
@@ -47,18 +48,7 @@ pub async fn check_workout_sync_status(
     let workout_uuids = &request.workout_uuids;
 
     // Query to find which workout UUIDs already exist for this user
-    let existing_uuids = match sqlx::query_scalar!(
-        r#"
-        SELECT workout_uuid 
-        FROM workout_data 
-        WHERE user_id = $1 
-        AND workout_uuid = ANY($2)
-        "#,
-        user_id,
-        workout_uuids
-    )
-    .fetch_all(pool.get_ref())
-    .await {
+    let existing_uuids = match get_synced_workout_uuids(pool.get_ref(), user_id, workout_uuids).await {
         Ok(uuids) => uuids,
         Err(e) => {
             tracing::error!("Database error checking workout sync status: {:?}", e);
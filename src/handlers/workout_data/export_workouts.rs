@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+
+use actix_web::{http::header, web, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::workout_data::{get_exported_workout, get_exported_workouts, load_exported_workout_samples};
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::workout_data::ExportedWorkout;
+use crate::workout::file_export::{csv_rows, ndjson_line, tcx, WorkoutExportFormat, CSV_HEADER};
+
+/// Workout summaries loaded per query of an export. Their samples are loaded and written one
+/// workout at a time, so no more than one workout's samples are ever held in memory.
+const EXPORT_PAGE_SIZE: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct WorkoutExportQuery {
+    /// "csv" (default) or "ndjson"
+    pub format: Option<String>,
+    /// Only workouts started at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only workouts started before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SingleWorkoutExportQuery {
+    /// "tcx" (default). GPX is refused, as positions aren't recorded and GPX track points need them.
+    pub format: Option<String>,
+}
+
+/// Where a streamed export has got to
+struct ExportCursor {
+    pool: PgPool,
+    user_id: Uuid,
+    format: WorkoutExportFormat,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    after: Option<(DateTime<Utc>, Uuid)>,
+    /// Workouts of the last page still to be written
+    pending: VecDeque<ExportedWorkout>,
+    done: bool,
+}
+
+#[tracing::instrument(
+    name = "Export workout history",
+    skip(pool, query, claims),
+    fields(username = %claims.username)
+)]
+pub async fn export_workout_history(
+    query: web::Query<WorkoutExportQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    let format = match query.format.as_deref().map(WorkoutExportFormat::from_name) {
+        None => WorkoutExportFormat::Csv,
+        Some(Some(format)) if !format.is_single_workout() => format,
+        Some(_) => {
+            return HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("Unknown export format, use csv or ndjson")
+            );
+        }
+    };
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from >= to {
+            return HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("'from' must be before 'to'")
+            );
+        }
+    }
+
+    tracing::info!("📦 Exporting workouts of {} as {}", claims.username, format.as_str());
+
+    let cursor = ExportCursor {
+        pool: pool.get_ref().clone(),
+        user_id,
+        format,
+        from: query.from,
+        to: query.to,
+        after: None,
+        pending: VecDeque::new(),
+        done: false,
+    };
+    let header_row = (format == WorkoutExportFormat::Csv)
+        .then(|| Ok(web::Bytes::from_static(CSV_HEADER.as_bytes())));
+    let body = futures::stream::iter(header_row).chain(futures::stream::unfold(cursor, next_export_page));
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"workouts.{}\"", format.as_str()),
+        ))
+        .streaming(body)
+}
+
+/// Write the next workout of an export with its samples, loading the next page of workouts once
+/// the last one is used up, and end the stream after the last workout or on an error
+async fn next_export_page(
+    mut cursor: ExportCursor,
+) -> Option<(Result<web::Bytes, actix_web::Error>, ExportCursor)> {
+    if cursor.pending.is_empty() {
+        if cursor.done {
+            return None;
+        }
+        match get_exported_workouts(&cursor.pool, cursor.user_id, cursor.from, cursor.to, cursor.after, EXPORT_PAGE_SIZE).await {
+            Ok(workouts) if workouts.is_empty() => return None,
+            Ok(workouts) => {
+                cursor.done = (workouts.len() as i64) < EXPORT_PAGE_SIZE;
+                cursor.after = workouts.last().map(|workout| (workout.workout_date, workout.id));
+                cursor.pending = workouts.into();
+            }
+            Err(e) => return Some(export_failed(cursor, e)),
+        }
+    }
+
+    let mut workout = cursor.pending.pop_front()?;
+    if let Err(e) = load_exported_workout_samples(&cursor.pool, &mut workout).await {
+        return Some(export_failed(cursor, e));
+    }
+    let chunk = match cursor.format {
+        WorkoutExportFormat::Csv => csv_rows(&workout),
+        _ => ndjson_line(&workout),
+    };
+    Some((Ok(web::Bytes::from(chunk)), cursor))
+}
+
+/// End an export whose workouts couldn't be loaded
+fn export_failed(
+    mut cursor: ExportCursor,
+    e: sqlx::Error,
+) -> (Result<web::Bytes, actix_web::Error>, ExportCursor) {
+    // The status line is already sent, so all that's left is to cut the response short
+    tracing::error!("❌ Failed to export workouts for user {}: {}", cursor.user_id, e);
+    cursor.done = true;
+    cursor.pending.clear();
+    (Err(actix_web::error::ErrorInternalServerError("Failed to export workouts")), cursor)
+}
+
+#[tracing::instrument(
+    name = "Export workout",
+    skip(pool, query, claims),
+    fields(
+        username = %claims.username,
+        workout_id = %workout_id
+    )
+)]
+pub async fn export_workout(
+    workout_id: web::Path<Uuid>,
+    query: web::Query<SingleWorkoutExportQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let workout_id = workout_id.into_inner();
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    let format = match query.format.as_deref().map(WorkoutExportFormat::from_name) {
+        None | Some(Some(WorkoutExportFormat::Tcx)) => WorkoutExportFormat::Tcx,
+        Some(Some(WorkoutExportFormat::Gpx)) => {
+            return HttpResponse::UnprocessableEntity().json(
+                ApiResponse::<()>::error("GPX track points need positions, which aren't recorded; use tcx")
+            );
+        }
+        Some(_) => {
+            return HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error("Unknown export format, use tcx")
+            );
+        }
+    };
+
+    let workout = match get_exported_workout(pool.get_ref(), user_id, workout_id).await {
+        Ok(Some(workout)) => workout,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Workout not found")
+            );
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to load workout {} for export: {}", workout_id, e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to export workout")
+            );
+        }
+    };
+
+    let body = tcx::write(&workout);
+    tracing::info!("📦 Exported workout {} of {} as {}", workout_id, claims.username, format.as_str());
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"workout-{}.{}\"", workout_id, format.as_str()),
+        ))
+        .body(body)
+}
@@ -0,0 +1,104 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::db::heart_rate_samples::get_downsampled_heart_rate;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+
+/// Points returned when the client doesn't ask for a resolution
+const DEFAULT_CHART_POINTS: i64 = 300;
+/// Upper bound on the resolution a client can ask for
+const MAX_CHART_POINTS: i64 = 2000;
+
+#[derive(Debug, Deserialize)]
+pub struct HeartRateSeriesQuery {
+    /// Roughly how many points the chart needs, 300 if omitted
+    pub points: Option<i64>,
+}
+
+#[tracing::instrument(
+    name = "Get workout heart rate series",
+    skip(pool, query, claims),
+    fields(
+        username = %claims.username,
+        workout_id = %workout_id
+    )
+)]
+pub async fn get_heart_rate_series(
+    workout_id: web::Path<Uuid>,
+    query: web::Query<HeartRateSeriesQuery>,
+    pool: web::Data<sqlx::PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let workout_id = workout_id.into_inner();
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    let span = match sqlx::query!(
+        r#"
+        SELECT wd.heart_rate_sample_count,
+               MIN(s.recorded_at) AS first_sample,
+               MAX(s.recorded_at) AS last_sample
+        FROM workout_data wd
+        LEFT JOIN workout_heart_rate_samples s ON s.workout_data_id = wd.id
+        WHERE wd.id = $1 AND wd.user_id = $2
+        GROUP BY wd.id
+        "#,
+        workout_id,
+        user_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(span)) => span,
+        Ok(None) => {
+            return HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Workout not found")
+            );
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to look up workout {} for {}: {}", workout_id, claims.username, e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch heart rate series")
+            );
+        }
+    };
+
+    let points = query.points.unwrap_or(DEFAULT_CHART_POINTS).clamp(1, MAX_CHART_POINTS);
+    let span_seconds = match (span.first_sample, span.last_sample) {
+        (Some(first), Some(last)) => (last - first).num_seconds(),
+        _ => 0,
+    };
+    // Round up so the series never has many more buckets than requested
+    let bucket_seconds = (span_seconds / points + i64::from(span_seconds % points != 0)).max(1);
+
+    match get_downsampled_heart_rate(pool.get_ref(), workout_id, bucket_seconds).await {
+        Ok(series) => {
+            tracing::info!("📈 Returning {} heart rate points from {} samples for workout {}",
+                series.len(), span.heart_rate_sample_count, workout_id);
+
+            HttpResponse::Ok().json(ApiResponse::success("Heart rate series retrieved", json!({
+                "workout_id": workout_id,
+                "sample_count": span.heart_rate_sample_count,
+                "bucket_seconds": bucket_seconds,
+                "series": series,
+            })))
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to downsample heart rate for workout {}: {}", workout_id, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch heart rate series")
+            )
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use actix_web::{web, HttpResponse};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::handlers::workout_data::upload_workout_data::queue_workout_upload;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::workout_data::{OverlapPolicy, WorkoutDataSyncRequest};
+use crate::services::workout_queue_service::WorkoutQueueService;
+use crate::workout::file_import::parse_workout_file;
+
+/// Maximum accepted size of an uploaded workout file
+pub const MAX_WORKOUT_FILE_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct WorkoutFileImportQuery {
+    /// Source device, e.g. "garmin" or "wahoo". Defaults to the detected file format.
+    pub device_id: Option<String>,
+    /// What to do when the file duplicates a workout recorded on another device
+    pub overlap_policy: Option<OverlapPolicy>,
+}
+
+#[tracing::instrument(
+    name = "Import workout file",
+    skip(body, query, pool, workout_queue, claims),
+    fields(
+        username = %claims.username,
+        file_size = body.len()
+    )
+)]
+pub async fn import_workout_file(
+    body: web::Bytes,
+    query: web::Query<WorkoutFileImportQuery>,
+    pool: web::Data<sqlx::PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    tracing::info!("📁 Importing workout file ({} bytes) for user: {}", body.len(), claims.username);
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    let imported = match parse_workout_file(&body, user_id) {
+        Ok(imported) => imported,
+        Err(e) => {
+            tracing::warn!("⚠️ Could not parse workout file for {}: {}", claims.username, e);
+            return HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error(e)
+            );
+        }
+    };
+
+    tracing::info!("📊 Parsed {} file for {}: {} heart rate samples from {} to {}",
+        imported.format.as_str(), claims.username, imported.heart_rate.len(),
+        imported.workout_start, imported.workout_end);
+
+    let workout = WorkoutDataSyncRequest {
+        device_id: query.device_id.clone().unwrap_or_else(|| imported.format.as_str().to_string()),
+        timestamp: imported.workout_end,
+        heart_rate: if imported.heart_rate.is_empty() { None } else { Some(imported.heart_rate) },
+        calories_burned: imported.calories_burned,
+        workout_uuid: imported.workout_uuid,
+        workout_start: Some(imported.workout_start),
+        workout_end: Some(imported.workout_end),
+        workout_type: imported.workout_type,
+        overlap_policy: query.overlap_policy,
+        power: if imported.power.is_empty() { None } else { Some(imported.power) },
+        cadence: if imported.cadence.is_empty() { None } else { Some(imported.cadence) },
+        speed: if imported.speed.is_empty() { None } else { Some(imported.speed) },
+        distance: if imported.distance.is_empty() { None } else { Some(imported.distance) },
+        steps: None,
+        strength_exercises: None,
+    };
+
+    // The file is parsed here so a broken one is refused right away, the workout is processed by the queue
+    queue_workout_upload(&pool, &workout_queue, user_id, &claims.username, &workout).await
+}
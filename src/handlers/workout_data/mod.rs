@@ -1,4 +1,12 @@
 pub mod upload_workout_data;
+pub mod upload_workout_data_batch;
+pub mod import_workout_file;
+pub mod retract_workout;
+pub mod workout_job_status;
 pub mod activity;
 pub mod workout_history;
-pub mod check_workout_sync_status;
\ No newline at end of file
+pub mod heart_rate_series;
+pub mod check_workout_sync_status;
+pub mod training_load;
+pub mod export_workouts;
+pub mod upload_session;
@@ -0,0 +1,202 @@
+use actix_web::{web, HttpResponse};
+use serde_json::json;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::db::daily_challenges::get_accepted_workout_day;
+use crate::db::stat_decay::{get_decayed_stats, get_stat_decay_settings};
+use crate::db::workout_data::lock_user_workouts;
+use crate::game::stat_caps::release_stat_caps;
+use crate::game::stat_decay::retracted_stat;
+use crate::game::stats_calculator::ZoneBreakdown;
+use crate::handlers::workout_data::upload_workout_data::workout_day;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::live_game::LiveGame;
+use crate::services::avatar_progression_service::AvatarProgressionService;
+use crate::services::daily_challenge_service::DailyChallengeService;
+use crate::services::live_game_service::LiveGameService;
+use crate::services::personal_record_service::PersonalRecordService;
+
+#[tracing::instrument(
+    name = "Retract workout",
+    skip(pool, live_game_service, claims),
+    fields(
+        username = %claims.username,
+        workout_id = %workout_id
+    )
+)]
+pub async fn retract_workout(
+    workout_id: web::Path<Uuid>,
+    pool: web::Data<sqlx::PgPool>,
+    live_game_service: web::Data<LiveGameService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let workout_id = workout_id.into_inner();
+    tracing::info!("↩️ Retracting workout {} for user: {}", workout_id, claims.username);
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    match retract_user_workout(&pool, &live_game_service, user_id, workout_id).await {
+        Ok(Some((stamina_reverted, strength_reverted, live_games_corrected))) => {
+            tracing::info!("✅ Retracted workout {} for {}: -{} stamina, -{} strength, {} live game(s) corrected",
+                workout_id, claims.username, stamina_reverted, strength_reverted, live_games_corrected);
+
+            // Retracting only ever lowers the level, so there's no level-up to announce
+            let progression_service = AvatarProgressionService::new(pool.get_ref().clone(), None);
+            if let Err(e) = progression_service.sync(user_id, &claims.username).await {
+                tracing::error!("❌ Failed to update avatar progression for {}: {}", claims.username, e);
+            }
+
+            HttpResponse::Ok().json(ApiResponse::success("Workout retracted", json!({
+                "workout_id": workout_id,
+                "stamina_reverted": stamina_reverted,
+                "strength_reverted": strength_reverted,
+                "live_games_corrected": live_games_corrected,
+            })))
+        }
+        Ok(None) => {
+            HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Workout not found")
+            )
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to retract workout {} for {}: {}", workout_id, claims.username, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to retract workout")
+            )
+        }
+    }
+}
+
+/// Scores taken back from a deleted workout
+pub struct ReversedWorkout {
+    pub stamina_reverted: i32,
+    pub strength_reverted: i32,
+    /// Live games whose scores were corrected, to broadcast once the transaction commits
+    pub corrected_games: Vec<LiveGame>,
+}
+
+/// Delete a user's own workout and reverse everything it scored, in one transaction.
+/// Returns the reverted stamina, strength and number of corrected live games, or None if
+/// the workout doesn't exist or belongs to someone else.
+async fn retract_user_workout(
+    pool: &sqlx::PgPool,
+    live_game_service: &LiveGameService,
+    user_id: Uuid,
+    workout_id: Uuid,
+) -> Result<Option<(i32, i32, usize)>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(reversed) = reverse_and_delete_workout(&mut tx, user_id, workout_id).await? else {
+        return Ok(None);
+    };
+
+    tx.commit().await?;
+
+    // Broadcast corrected scores only once the reversal is committed
+    for game in &reversed.corrected_games {
+        if let Err(e) = live_game_service.broadcast_live_score_update(game).await {
+            tracing::error!("❌ Failed to broadcast corrected live score for game {}: {}", game.game_id, e);
+        }
+    }
+
+    Ok(Some((reversed.stamina_reverted, reversed.strength_reverted, reversed.corrected_games.len())))
+}
+
+/// Take back a workout's avatar stats, live game scores, challenge bonuses and records and delete it, on the caller's transaction.
+/// Returns None if the workout doesn't exist or belongs to someone else.
+pub async fn reverse_and_delete_workout(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    workout_id: Uuid,
+) -> Result<Option<ReversedWorkout>, sqlx::Error> {
+    // Like uploads and recompute, this takes the user's workout lock, then the daily gains, then
+    // the avatar, then the live games. The lock is reentrant, so an upload replacing a recording
+    // it already holds the lock for can call this too.
+    lock_user_workouts(&mut *conn, user_id).await?;
+
+    let workout = sqlx::query!(
+        r#"
+        SELECT stamina_gained, strength_gained, heart_rate_zones, workout_start, created_at
+        FROM workout_data
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        "#,
+        workout_id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    let Some(workout) = workout else {
+        return Ok(None);
+    };
+
+    // Free up the day's stat caps for the user's other workouts
+    let zone_breakdown: Vec<ZoneBreakdown> = workout.heart_rate_zones
+        .and_then(|zones| serde_json::from_value(zones).ok())
+        .unwrap_or_default();
+    release_stat_caps(
+        &mut *conn,
+        user_id,
+        workout_day(workout.workout_start, workout.created_at),
+        workout.stamina_gained,
+        workout.strength_gained,
+        &zone_breakdown,
+    ).await?;
+
+    // Decay may already have taken part of the gains, so the stats drop no lower than its floor
+    let avatar = sqlx::query!(
+        "SELECT stamina, strength FROM user_avatars WHERE user_id = $1 FOR UPDATE",
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let decay_settings = get_stat_decay_settings(&mut *conn).await?;
+    let (decayed_stamina, decayed_strength) = get_decayed_stats(&mut *conn, user_id).await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE user_avatars
+        SET stamina = $1,
+            strength = $2
+        WHERE user_id = $3
+        "#,
+        retracted_stat(avatar.stamina, workout.stamina_gained, decayed_stamina, &decay_settings),
+        retracted_stat(avatar.strength, workout.strength_gained, decayed_strength, &decay_settings),
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let corrected_games = LiveGameService::revert_workout_scores(&mut *conn, workout_id).await?;
+
+    let challenge_day = get_accepted_workout_day(&mut *conn, workout_id).await?;
+
+    sqlx::query!("DELETE FROM workout_data WHERE id = $1", workout_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // The day's challenges are re-evaluated against the workouts left that day
+    if let Some(challenge_day) = challenge_day {
+        DailyChallengeService::reevaluate_day(&mut *conn, user_id, challenge_day.day).await?;
+    }
+
+    // Records it held fall back to the next best workout
+    PersonalRecordService::recompute_user(&mut *conn, user_id).await?;
+
+    Ok(Some(ReversedWorkout {
+        stamina_reverted: workout.stamina_gained,
+        strength_reverted: workout.strength_gained,
+        corrected_games,
+    }))
+}
@@ -0,0 +1,98 @@
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::db::workout_data::get_training_load_sums;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+
+/// Days of recent load compared against the longer baseline
+const ACUTE_DAYS: i64 = 7;
+/// Days of load the body is adapted to
+const CHRONIC_DAYS: i64 = 28;
+
+#[derive(Debug, Deserialize)]
+pub struct TrainingLoadQuery {
+    /// "banister" (default) or "edwards"
+    pub metric: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrainingLoadRatio {
+    pub metric: &'static str,
+    pub acute_days: i64,
+    pub chronic_days: i64,
+    /// Average daily load over the acute window
+    pub acute_load: f64,
+    /// Average daily load over the chronic window
+    pub chronic_load: f64,
+    /// Acute over chronic load, missing until there is a chronic load
+    pub ratio: Option<f64>,
+    pub status: &'static str,
+}
+
+impl TrainingLoadRatio {
+    fn new(metric: &'static str, acute_sum: f64, chronic_sum: f64) -> Self {
+        let acute_load = acute_sum / ACUTE_DAYS as f64;
+        let chronic_load = chronic_sum / CHRONIC_DAYS as f64;
+        let ratio = (chronic_load > 0.0).then(|| acute_load / chronic_load);
+
+        // Bands commonly used for injury risk, 0.8-1.3 being the sweet spot
+        let status = match ratio {
+            None => "no_baseline",
+            Some(ratio) if ratio < 0.8 => "undertraining",
+            Some(ratio) if ratio <= 1.3 => "optimal",
+            Some(ratio) if ratio <= 1.5 => "elevated",
+            Some(_) => "high_risk",
+        };
+
+        Self { metric, acute_days: ACUTE_DAYS, chronic_days: CHRONIC_DAYS, acute_load, chronic_load, ratio, status }
+    }
+}
+
+#[tracing::instrument(
+    name = "Get training load ratio",
+    skip(pool, query, claims),
+    fields(username = %claims.username)
+)]
+pub async fn get_training_load_ratio(
+    query: web::Query<TrainingLoadQuery>,
+    pool: web::Data<sqlx::PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    let metric = match query.metric.as_deref() {
+        None | Some("banister") => "banister",
+        Some("edwards") => "edwards",
+        Some(other) => {
+            return HttpResponse::BadRequest().json(
+                ApiResponse::<()>::error(format!("Unknown training load metric '{}', use banister or edwards", other))
+            );
+        }
+    };
+
+    match get_training_load_sums(pool.get_ref(), user_id, metric == "edwards", Utc::now(), ACUTE_DAYS, CHRONIC_DAYS).await {
+        Ok((acute_sum, chronic_sum)) => {
+            let ratio = TrainingLoadRatio::new(metric, acute_sum, chronic_sum);
+            tracing::info!("🏋️ Training load for {}: acute {:.1}, chronic {:.1}, ratio {:?}",
+                claims.username, ratio.acute_load, ratio.chronic_load, ratio.ratio);
+            HttpResponse::Ok().json(ApiResponse::success("Training load retrieved", ratio))
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to fetch training load for {}: {}", claims.username, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch training load")
+            )
+        }
+    }
+}
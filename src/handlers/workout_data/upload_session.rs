@@ -0,0 +1,575 @@
+use std::borrow::Cow;
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use futures::{StreamExt, TryStreamExt};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::scoring_rules::get_user_scoring_rules;
+use crate::db::workout_data::check_workout_uuid_exists;
+use crate::db::workout_jobs::enqueue_upload_session_job;
+use crate::db::workout_upload_sessions::{
+    create_upload_session, finalize_upload_session, get_open_upload_session, get_upload_session_for_user,
+    get_upload_session_sample_range, insert_upload_session_samples, lock_upload_session_for_user,
+    stream_upload_session_samples,
+};
+use crate::game::stats_calculator::StatCalculator;
+use crate::handlers::workout_data::upload_workout_data::{is_duplicate_workout_error, user_max_heart_rate};
+use crate::models::game::ScoringRules;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::workout_data::{
+    HeartRateData, HeartRateZones, UploadSessionStatus, WorkoutDataSyncRequest, WorkoutJobStatus, WorkoutUploadSession,
+};
+use crate::services::workout_queue_service::WorkoutQueueService;
+use crate::workout::workout_analyzer::{GapHandling, HeartRateReserve, IncrementalWorkoutAnalyzer, WorkoutAnalyzer};
+use crate::workout::workout_validator::HeartRateChecks;
+
+/// Most samples one session can stage, 48 hours at one sample per second
+pub const MAX_SESSION_SAMPLES: i32 = 172_800;
+/// Largest body of a single chunk of samples
+pub const MAX_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+/// Longest line of an NDJSON chunk, a single sample is far shorter
+const MAX_SAMPLE_LINE_BYTES: usize = 1024;
+
+/// How a chunk of samples is encoded
+#[derive(Debug, Clone, Copy)]
+enum ChunkFormat {
+    /// One sample object per line, parsed as the body arrives
+    Ndjson,
+    /// A JSON array of sample objects
+    JsonArray,
+}
+
+impl ChunkFormat {
+    fn from_request(req: &HttpRequest) -> Option<Self> {
+        let content_type = req.headers().get(header::CONTENT_TYPE)?.to_str().ok()?;
+        let mime = content_type.split(';').next()?.trim();
+        match mime {
+            "application/x-ndjson" | "application/jsonl" => Some(ChunkFormat::Ndjson),
+            "application/json" => Some(ChunkFormat::JsonArray),
+            _ => None,
+        }
+    }
+}
+
+/// Why a chunk of samples couldn't be read
+enum ChunkError {
+    TooLarge(String),
+    Invalid(String),
+}
+
+/// Parses NDJSON samples as the body arrives, holding at most one partial line besides the samples
+struct NdjsonSampleParser {
+    line: Vec<u8>,
+    line_number: usize,
+    samples: Vec<HeartRateData>,
+}
+
+impl NdjsonSampleParser {
+    fn new() -> Self {
+        Self { line: Vec::new(), line_number: 0, samples: Vec::new() }
+    }
+
+    fn feed(&mut self, mut bytes: &[u8]) -> Result<(), ChunkError> {
+        while let Some(newline) = bytes.iter().position(|byte| *byte == b'\n') {
+            self.line.extend_from_slice(&bytes[..newline]);
+            self.parse_line()?;
+            bytes = &bytes[newline + 1..];
+        }
+
+        if self.line.len() + bytes.len() > MAX_SAMPLE_LINE_BYTES {
+            return Err(ChunkError::Invalid(format!(
+                "Line {} is longer than {} bytes", self.line_number + 1, MAX_SAMPLE_LINE_BYTES
+            )));
+        }
+        self.line.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn parse_line(&mut self) -> Result<(), ChunkError> {
+        self.line_number += 1;
+        if self.line.len() > MAX_SAMPLE_LINE_BYTES {
+            return Err(ChunkError::Invalid(format!(
+                "Line {} is longer than {} bytes", self.line_number, MAX_SAMPLE_LINE_BYTES
+            )));
+        }
+
+        let line = self.line.trim_ascii();
+        if !line.is_empty() {
+            let sample = serde_json::from_slice::<HeartRateData>(line).map_err(|e| {
+                ChunkError::Invalid(format!("Invalid sample on line {}: {}", self.line_number, e))
+            })?;
+            self.samples.push(sample);
+        }
+        self.line.clear();
+        Ok(())
+    }
+
+    /// Parse a last line without a trailing newline
+    fn finish(mut self) -> Result<Vec<HeartRateData>, ChunkError> {
+        if !self.line.is_empty() {
+            self.parse_line()?;
+        }
+        Ok(self.samples)
+    }
+}
+
+/// Read a chunk of samples from the request body without buffering more than `MAX_CHUNK_BYTES`.
+/// NDJSON is parsed line by line, so only the samples are kept.
+async fn read_sample_chunk(
+    mut payload: web::Payload,
+    format: ChunkFormat,
+    capacity: usize,
+) -> Result<Vec<HeartRateData>, ChunkError> {
+    let mut received_bytes = 0;
+    let mut parser = NdjsonSampleParser::new();
+    let mut body = Vec::new();
+
+    while let Some(bytes) = payload.next().await {
+        let bytes = bytes.map_err(|e| ChunkError::Invalid(format!("Failed to read samples: {}", e)))?;
+        received_bytes += bytes.len();
+        if received_bytes > MAX_CHUNK_BYTES {
+            return Err(ChunkError::TooLarge(format!(
+                "A chunk of samples can be at most {} bytes", MAX_CHUNK_BYTES
+            )));
+        }
+
+        match format {
+            ChunkFormat::Ndjson => {
+                parser.feed(&bytes)?;
+                if parser.samples.len() > capacity {
+                    return Err(ChunkError::TooLarge(format!(
+                        "An upload session can hold at most {} samples", MAX_SESSION_SAMPLES
+                    )));
+                }
+            }
+            ChunkFormat::JsonArray => body.extend_from_slice(&bytes),
+        }
+    }
+
+    let samples = match format {
+        ChunkFormat::Ndjson => parser.finish()?,
+        ChunkFormat::JsonArray => serde_json::from_slice::<Vec<HeartRateData>>(&body)
+            .map_err(|e| ChunkError::Invalid(format!("Invalid samples: {}", e)))?,
+    };
+
+    if samples.len() > capacity {
+        return Err(ChunkError::TooLarge(format!(
+            "An upload session can hold at most {} samples", MAX_SESSION_SAMPLES
+        )));
+    }
+    Ok(samples)
+}
+
+/// Outcome of staging a chunk in a session
+enum StageOutcome {
+    NotFound,
+    Finalized,
+    OverCapacity,
+    Staged { stored: u64, sample_count: i32 },
+}
+
+/// Stage samples under the session lock, so the capacity check sees every earlier chunk
+async fn stage_samples(
+    pool: &PgPool,
+    session_id: Uuid,
+    user_id: Uuid,
+    samples: &[HeartRateData],
+) -> Result<StageOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(session) = lock_upload_session_for_user(&mut tx, session_id, user_id).await? else {
+        return Ok(StageOutcome::NotFound);
+    };
+    if session.status != UploadSessionStatus::Open.as_str() {
+        return Ok(StageOutcome::Finalized);
+    }
+    if session.sample_count as usize + samples.len() > MAX_SESSION_SAMPLES as usize {
+        return Ok(StageOutcome::OverCapacity);
+    }
+
+    let stored = insert_upload_session_samples(&mut tx, session_id, samples).await?;
+    tx.commit().await?;
+
+    Ok(StageOutcome::Staged { stored, sample_count: session.sample_count + stored as i32 })
+}
+
+/// Outcome of finalizing a session
+enum FinalizeOutcome {
+    NotFound,
+    Finalized,
+    NoSamples,
+    AlreadyQueued,
+    Queued {
+        job_id: Uuid,
+        session: WorkoutUploadSession,
+    },
+}
+
+/// Duration, heart rate, zone minutes and training load of a session's analyzed samples
+fn heart_rate_summary(analysis: &WorkoutAnalyzer, reserve: &HeartRateReserve) -> serde_json::Value {
+    let zone_minutes: serde_json::Map<String, serde_json::Value> = analysis.zone_durations.iter()
+        .map(|(zone, minutes)| (format!("{:?}", zone), json!(minutes)))
+        .collect();
+    json!({
+        "duration_min": analysis.total_duration_min,
+        "avg_heart_rate": analysis.avg_heart_rate,
+        "peak_heart_rate": analysis.peak_heart_rate,
+        "coverage_percent": analysis.coverage_percent,
+        "zone_minutes": zone_minutes,
+        "training_load": analysis.training_load(reserve),
+    })
+}
+
+/// The heart rate of a session's staged samples, checked and analyzed as they were streamed,
+/// so the queued workout is validated, scored and stored without holding them in memory
+pub struct StagedHeartRate {
+    pub session_id: Uuid,
+    pub checks: HeartRateChecks,
+    /// Analyzed with the gap handling of the workout's season, None if too few samples to analyze
+    pub analysis: Option<WorkoutAnalyzer>,
+    heart_rate_zones: HeartRateZones,
+    heart_rate_reserve: HeartRateReserve,
+}
+
+impl StagedHeartRate {
+    /// Stream a session's staged samples once through the validator's heart rate checks and an
+    /// analysis with the scoring rules of the workout's season
+    pub async fn read(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        session_id: Uuid,
+        data: &WorkoutDataSyncRequest,
+    ) -> Result<Self, sqlx::Error> {
+        let max_heart_rate = user_max_heart_rate(conn, user_id).await?;
+        let (heart_rate_zones, heart_rate_reserve) = StatCalculator::user_heart_rate_zones(conn, user_id).await?;
+        let rules = get_user_scoring_rules(conn, user_id, data.workout_start.unwrap_or(data.timestamp)).await?;
+
+        let mut checks = HeartRateChecks::new(max_heart_rate);
+        let mut analyzer = IncrementalWorkoutAnalyzer::new(&heart_rate_zones, GapHandling::from_rules(&rules));
+        let mut samples = stream_upload_session_samples(conn, session_id);
+        while let Some(sample) = samples.try_next().await? {
+            checks.push(&sample);
+            analyzer.push(&sample);
+        }
+        drop(samples);
+        let analysis = analyzer.finish();
+
+        Ok(Self { session_id, checks, analysis, heart_rate_zones, heart_rate_reserve })
+    }
+
+    /// The samples analyzed with the rules' gap handling. Rules handling gaps differently than the
+    /// season's, like those of a live game in another season, stream the samples again.
+    pub async fn analysis_for(
+        &self,
+        conn: &mut PgConnection,
+        rules: &ScoringRules,
+    ) -> Result<Option<Cow<'_, WorkoutAnalyzer>>, sqlx::Error> {
+        let gaps = GapHandling::from_rules(rules);
+        if let Some(analysis) = self.analysis.as_ref().filter(|analysis| analysis.gap_handling() == gaps) {
+            return Ok(Some(Cow::Borrowed(analysis)));
+        }
+
+        let mut analyzer = IncrementalWorkoutAnalyzer::new(&self.heart_rate_zones, gaps);
+        let mut samples = stream_upload_session_samples(conn, self.session_id);
+        while let Some(sample) = samples.try_next().await? {
+            analyzer.push(&sample);
+        }
+        Ok(analyzer.finish().map(Cow::Owned))
+    }
+
+    /// Summary of the season's analysis, reported with the job's result
+    pub fn summary(&self) -> Option<serde_json::Value> {
+        self.analysis.as_ref().map(|analysis| heart_rate_summary(analysis, &self.heart_rate_reserve))
+    }
+}
+
+/// Queue the session's workout. Its samples are checked and analyzed by the job.
+async fn finalize_session(
+    pool: &PgPool,
+    session_id: Uuid,
+    user_id: Uuid,
+) -> Result<FinalizeOutcome, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let Some(session) = lock_upload_session_for_user(&mut tx, session_id, user_id).await? else {
+        return Ok(FinalizeOutcome::NotFound);
+    };
+    if session.status != UploadSessionStatus::Open.as_str() {
+        return Ok(FinalizeOutcome::Finalized);
+    }
+    if session.sample_count == 0 {
+        return Ok(FinalizeOutcome::NoSamples);
+    }
+
+    let mut data = serde_json::from_value::<WorkoutDataSyncRequest>(session.metadata.clone())
+        .map_err(|e| sqlx::Error::Protocol(format!("Invalid upload session metadata: {}", e)))?;
+
+    // The recording spans the samples unless the client knows better
+    if data.workout_start.is_none() || data.workout_end.is_none() {
+        let (first_sample_at, last_sample_at) = get_upload_session_sample_range(&mut tx, session_id).await?;
+        data.workout_start = data.workout_start.or(first_sample_at);
+        data.workout_end = data.workout_end.or(last_sample_at);
+    }
+
+    let job_id = match enqueue_upload_session_job(&mut tx, user_id, session_id, &data).await {
+        Ok(job_id) => job_id,
+        Err(e) if is_duplicate_workout_error(&e) => return Ok(FinalizeOutcome::AlreadyQueued),
+        Err(e) => return Err(e),
+    };
+    finalize_upload_session(&mut tx, session_id).await?;
+    tx.commit().await?;
+
+    Ok(FinalizeOutcome::Queued { job_id, session })
+}
+
+#[tracing::instrument(
+    name = "Start workout upload session",
+    skip(data, pool, claims),
+    fields(
+        username = %claims.username,
+        workout_uuid = %data.workout_uuid
+    )
+)]
+pub async fn start_upload_session(
+    data: web::Json<WorkoutDataSyncRequest>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    if data.heart_rate.as_ref().is_some_and(|samples| !samples.is_empty()) {
+        return HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Heart rate samples are uploaded to the session in chunks")
+        );
+    }
+
+    match check_workout_uuid_exists(&pool, user_id, &data.workout_uuid).await {
+        Ok(true) => {
+            tracing::warn!("⚠️ Workout {} for {} is already synced", data.workout_uuid, claims.username);
+            return HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Workout UUID already exists")
+            );
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::error!("❌ Failed to check workout UUID for {}: {}", claims.username, e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to start upload session")
+            );
+        }
+    }
+
+    // Starting again after a dropped connection resumes the open session
+    let (session, created) = match create_upload_session(&pool, user_id, &data).await {
+        Ok(Some(session)) => (Ok(Some(session)), true),
+        Ok(None) => (get_open_upload_session(&pool, user_id, &data.workout_uuid).await, false),
+        Err(e) => (Err(e), false),
+    };
+
+    match session {
+        Ok(Some(session)) => {
+            tracing::info!("📡 {} upload session {} for workout {} ({} samples staged)",
+                if created { "Started" } else { "Resumed" }, session.id, session.workout_uuid, session.sample_count);
+            let body = ApiResponse::success(
+                if created { "Upload session started" } else { "Upload session resumed" },
+                json!({
+                    "session_id": session.id,
+                    "workout_uuid": session.workout_uuid,
+                    "sample_count": session.sample_count,
+                    "max_samples": MAX_SESSION_SAMPLES,
+                    "max_chunk_bytes": MAX_CHUNK_BYTES,
+                }),
+            );
+            if created {
+                HttpResponse::Created().json(body)
+            } else {
+                HttpResponse::Ok().json(body)
+            }
+        }
+        // Finalized between the insert and the lookup
+        Ok(None) => HttpResponse::Conflict().json(
+            ApiResponse::<()>::error("Workout is already queued for processing")
+        ),
+        Err(e) => {
+            tracing::error!("❌ Failed to start upload session for {}: {}", claims.username, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to start upload session")
+            )
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Upload workout session samples",
+    skip(req, payload, pool, claims),
+    fields(
+        username = %claims.username,
+        session_id = %session_id
+    )
+)]
+pub async fn upload_session_samples(
+    session_id: web::Path<Uuid>,
+    req: HttpRequest,
+    payload: web::Payload,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+    let session_id = session_id.into_inner();
+
+    let Some(format) = ChunkFormat::from_request(&req) else {
+        return HttpResponse::UnsupportedMediaType().json(
+            ApiResponse::<()>::error("Samples must be sent as application/x-ndjson or a application/json array")
+        );
+    };
+
+    // Check the session before reading the body, so unknown sessions aren't streamed in full
+    let staged = match get_upload_session_for_user(&pool, session_id, user_id).await {
+        Ok(Some(session)) if session.status == UploadSessionStatus::Open.as_str() => session.sample_count,
+        Ok(Some(_)) => {
+            return HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Upload session is already finalized")
+            );
+        }
+        Ok(None) => {
+            return HttpResponse::NotFound().json(
+                ApiResponse::<()>::error("Upload session not found")
+            );
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to fetch upload session {}: {}", session_id, e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to upload samples")
+            );
+        }
+    };
+    let capacity = (MAX_SESSION_SAMPLES - staged).max(0) as usize;
+
+    let samples = match read_sample_chunk(payload, format, capacity).await {
+        Ok(samples) => samples,
+        Err(ChunkError::TooLarge(message)) => {
+            tracing::warn!("⚠️ Chunk for upload session {} rejected: {}", session_id, message);
+            return HttpResponse::PayloadTooLarge().json(ApiResponse::<()>::error(message));
+        }
+        Err(ChunkError::Invalid(message)) => {
+            tracing::warn!("⚠️ Chunk for upload session {} rejected: {}", session_id, message);
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error(message));
+        }
+    };
+
+    match stage_samples(&pool, session_id, user_id, &samples).await {
+        Ok(StageOutcome::Staged { stored, sample_count }) => {
+            tracing::info!("📥 Staged {} of {} samples in upload session {} ({} total)",
+                stored, samples.len(), session_id, sample_count);
+            HttpResponse::Ok().json(ApiResponse::success(
+                "Samples uploaded",
+                json!({
+                    "session_id": session_id,
+                    "received": samples.len(),
+                    "stored": stored,
+                    "duplicates": samples.len() as u64 - stored,
+                    "sample_count": sample_count,
+                }),
+            ))
+        }
+        Ok(StageOutcome::NotFound) => HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Upload session not found")
+        ),
+        Ok(StageOutcome::Finalized) => HttpResponse::Conflict().json(
+            ApiResponse::<()>::error("Upload session is already finalized")
+        ),
+        Ok(StageOutcome::OverCapacity) => HttpResponse::PayloadTooLarge().json(
+            ApiResponse::<()>::error(format!("An upload session can hold at most {} samples", MAX_SESSION_SAMPLES))
+        ),
+        Err(e) => {
+            tracing::error!("❌ Failed to stage samples in upload session {}: {}", session_id, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to upload samples")
+            )
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Finalize workout upload session",
+    skip(pool, workout_queue, claims),
+    fields(
+        username = %claims.username,
+        session_id = %session_id
+    )
+)]
+pub async fn finalize_upload_session_handler(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+    let session_id = session_id.into_inner();
+
+    match finalize_session(&pool, session_id, user_id).await {
+        Ok(FinalizeOutcome::Queued { job_id, session }) => {
+            workout_queue.notify_job_queued();
+            tracing::info!("✅ Finalized upload session {} with {} samples for {} as job {}",
+                session_id, session.sample_count, claims.username, job_id);
+            HttpResponse::Accepted().json(ApiResponse::success(
+                "Upload session finalized and queued for processing",
+                json!({
+                    "job_id": job_id,
+                    "session_id": session_id,
+                    "workout_uuid": session.workout_uuid,
+                    "status": WorkoutJobStatus::Queued,
+                    "sample_count": session.sample_count,
+                }),
+            ))
+        }
+        Ok(FinalizeOutcome::NotFound) => HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Upload session not found")
+        ),
+        Ok(FinalizeOutcome::Finalized) => HttpResponse::Conflict().json(
+            ApiResponse::<()>::error("Upload session is already finalized")
+        ),
+        Ok(FinalizeOutcome::NoSamples) => HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("No heart rate samples were uploaded to this session")
+        ),
+        Ok(FinalizeOutcome::AlreadyQueued) => {
+            tracing::warn!("⚠️ Workout of upload session {} is already queued", session_id);
+            HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Workout is already queued for processing")
+            )
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to finalize upload session {} for {}: {}", session_id, claims.username, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to finalize upload session")
+            )
+        }
+    }
+}
@@ -1,24 +1,107 @@
 // Enhanced src/handlers/workout_data/upload_health_data.rs - Now with game stats!
 
 use actix_web::{web, HttpResponse};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::TryStreamExt;
 use serde_json::json;
+use sqlx::PgConnection;
 use uuid::Uuid;
 use redis::AsyncCommands;
 use std::sync::Arc;
-use crate::game::helper::get_user_profile;
 use crate::middleware::auth::Claims;
-use crate::db::workout_data::insert_workout_data;
-use crate::models::workout_data::WorkoutDataSyncRequest;
+use crate::db::heart_rate_samples::get_heart_rate_samples;
+use crate::db::sensor_samples::get_sensor_streams;
+use crate::db::scoring_rules::{get_game_scoring_rules, get_user_scoring_rules};
+use crate::db::workout_data::{
+    check_workout_uuid_exists, find_overlapping_workouts, insert_workout_data, lock_user_workouts, parse_strength_exercises,
+    OverlappingWorkout,
+};
+use crate::db::workout_jobs::link_workout_to_job;
+use crate::db::workout_upload_sessions::{
+    merge_workout_samples_into_upload_session, store_upload_session_samples, stream_upload_session_samples_between,
+};
+use crate::handlers::workout_data::retract_workout::reverse_and_delete_workout;
+use crate::handlers::workout_data::upload_session::StagedHeartRate;
+use crate::models::workout_data::{
+    OverlapPolicy, ValidationIssue, ValidationReasonCode, ValidationSeverity, WorkoutDataSyncRequest,
+    WorkoutJobStatus, WorkoutReviewStatus, WorkoutType,
+};
 use crate::models::common::ApiResponse;
+use crate::game::helper::{get_user_profile, calc_max_heart_rate};
+use crate::game::stat_caps::apply_stat_caps;
 use crate::game::stats_calculator::StatCalculator;
+use crate::models::game::ScoringRules;
 use crate::models::live_game::{LiveGame, LiveGameScoreUpdate};
 use crate::services::live_game_service::LiveGameService;
+use crate::services::daily_challenge_service::DailyChallengeService;
+use crate::services::avatar_progression_service::AvatarProgressionService;
+use crate::services::personal_record_service::PersonalRecordService;
+use crate::services::workout_queue_service::WorkoutQueueService;
 use crate::game::stats_calculator::StatChanges;
+use crate::workout::overlap_detector::{DuplicateMatch, DuplicateSearch, OverlapDetector};
+use crate::workout::workout_validator::{MaxHeartRate, WorkoutValidation, WorkoutValidator};
+
+/// Result of running a single workout through the upload pipeline
+#[derive(Debug)]
+pub struct ProcessedWorkout {
+    pub sync_id: Uuid,
+    pub stat_changes: StatChanges,
+    pub review_status: WorkoutReviewStatus,
+    /// Validation flags that put the workout into review, empty for accepted workouts
+    pub flags: Vec<ValidationIssue>,
+    /// Stored recording of the same activity from another device that this workout replaced
+    pub replaced_workout_id: Option<Uuid>,
+}
+
+/// A stored recording of the same activity that an upload replaces
+struct ReplacedWorkout {
+    id: Uuid,
+    /// The stored recording's workout_uuid and the ones it replaced earlier, so re-uploads are caught
+    workout_uuids: Vec<String>,
+    /// Whether the stored recording scored, rather than waiting for review
+    accepted: bool,
+}
+
+/// How an upload relates to workouts already recorded on other devices
+enum OverlapResolution {
+    /// No duplicate found, store the upload as it is
+    Separate,
+    /// The policy refuses the upload
+    Rejected(ValidationIssue),
+    /// Store this workout in place of the duplicate. A session upload merged with the stored recording
+    /// comes with its staged samples read again, now that they hold the stored device's too.
+    Replace(Box<WorkoutDataSyncRequest>, ReplacedWorkout, Option<Box<StagedHeartRate>>),
+}
+
+/// An active live game a workout counts towards
+struct LiveGameTarget {
+    live_game: LiveGame,
+    /// The user's team in the game
+    team_id: Uuid,
+    /// Scoring rules of the season the game belongs to
+    rules: ScoringRules,
+    /// The workout scored with the game's rules, when they aren't the ones the avatar scored with
+    stat_changes: Option<StatChanges>,
+}
+
+/// Outcome of the upload pipeline: either the workout was stored, or validation rejected it
+#[derive(Debug)]
+pub enum WorkoutUploadOutcome {
+    Processed(ProcessedWorkout),
+    Rejected(Vec<ValidationIssue>),
+}
+
+/// Check whether a database error is a unique violation, i.e. a duplicate workout_uuid
+pub fn is_duplicate_workout_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(db_err) => db_err.code().as_deref() == Some("23505"),
+        _ => false,
+    }
+}
 
 #[tracing::instrument(
-    name = "Upload workout data with game stats",
-    skip(data, pool, redis, live_game_service, claims),
+    name = "Queue workout data upload",
+    skip(data, pool, workout_queue, claims),
     fields(
         username = %claims.username,
         data_type = %data.device_id
@@ -27,11 +110,10 @@ use crate::game::stats_calculator::StatChanges;
 pub async fn upload_workout_data(
     data: web::Json<WorkoutDataSyncRequest>,
     pool: web::Data<sqlx::PgPool>,
-    redis: Option<web::Data<Arc<redis::Client>>>,
-    live_game_service: Option<web::Data<LiveGameService>>,
+    workout_queue: web::Data<WorkoutQueueService>,
     claims: web::ReqData<Claims>
 ) -> HttpResponse {
-    tracing::info!("🎮 Processing workout data with game mechanics for user: {}", claims.username);
+    tracing::info!("📥 Queueing workout data for user: {}", claims.username);
     
     let user_id = match Uuid::parse_str(&claims.sub) {
         Ok(id) => {
@@ -46,19 +128,594 @@ pub async fn upload_workout_data(
         }
     };
 
+    queue_workout_upload(&pool, &workout_queue, user_id, &claims.username, &data).await
+}
+
+/// Queue a workout for the background workers and answer 202 with the job to poll.
+/// Shared by the endpoints that take a whole workout in one request.
+pub async fn queue_workout_upload(
+    pool: &sqlx::PgPool,
+    workout_queue: &WorkoutQueueService,
+    user_id: Uuid,
+    username: &str,
+    data: &WorkoutDataSyncRequest,
+) -> HttpResponse {
+    // Client retries of stored workouts are answered right away instead of queueing a job that can only fail
+    match check_workout_uuid_exists(pool, user_id, &data.workout_uuid).await {
+        Ok(true) => {
+            tracing::warn!("⚠️ Workout {} for {} is already synced", data.workout_uuid, username);
+            return HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Workout UUID already exists")
+            );
+        }
+        Ok(false) => {}
+        Err(e) => {
+            tracing::error!("❌ Failed to check workout UUID for {}: {}", username, e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error(format!("Failed to queue workout data: {}", e))
+            );
+        }
+    }
+
+    match workout_queue.enqueue(user_id, data).await {
+        Ok(job_id) => {
+            tracing::info!("✅ Queued workout {} for {} as job {}", data.workout_uuid, username, job_id);
+            HttpResponse::Accepted().json(ApiResponse::success(
+                "Workout data queued for processing",
+                json!({
+                    "job_id": job_id,
+                    "workout_uuid": data.workout_uuid,
+                    "status": WorkoutJobStatus::Queued,
+                }),
+            ))
+        }
+        Err(e) if is_duplicate_workout_error(&e) => {
+            tracing::warn!("⚠️ Workout {} for {} is already queued", data.workout_uuid, username);
+            HttpResponse::Conflict().json(
+                ApiResponse::<()>::error("Workout is already queued for processing")
+            )
+        }
+        Err(e) => {
+            tracing::error!("❌ Failed to queue workout data for {}: {}", username, e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error(format!("Failed to queue workout data: {}", e))
+            )
+        }
+    }
+}
+
+/// Response data for a processed workout, shared by the upload responses and queued job results
+pub fn workout_sync_data(processed: &ProcessedWorkout, workout_uuid: &str) -> serde_json::Value {
+    let stat_changes = &processed.stat_changes;
+
+    // 🎉 ENHANCED RESPONSE WITH GAME STATS
+    json!({
+        "sync_id": processed.sync_id,
+        "workout_uuid": workout_uuid,
+        "timestamp": Utc::now(),
+        "review_status": processed.review_status,
+        "validation_flags": processed.flags,
+        "replaced_workout_id": processed.replaced_workout_id,
+        "game_stats": {
+            "stat_changes": {
+                "stamina_change": stat_changes.stamina_change,
+                "strength_change": stat_changes.strength_change,
+            },
+            "reasoning": stat_changes.reasoning,
+            "summary": format!("Gained {} total stat points!", 
+                stat_changes.stamina_change + stat_changes.strength_change
+            )
+        }
+    })
+}
+
+/// Run a single workout through the full pipeline: plausibility validation, cross-device duplicate
+/// detection, stat calculation, avatar update, workout insert, live game attribution, real-time notification,
+/// personal records and daily challenges.
+/// All database writes happen in one transaction, so a failed or duplicate upload changes nothing.
+/// Flagged workouts are stored for admin review without scoring.
+/// When run for a queued job, the job is linked to the stored workout in the same transaction.
+/// A workout uploaded in a session carries no samples, its staged ones are checked, scored and stored
+/// from the database instead.
+#[allow(clippy::too_many_arguments)]
+pub async fn process_workout_data(
+    pool: &sqlx::PgPool,
+    redis: Option<&Arc<redis::Client>>,
+    live_game_service: Option<&LiveGameService>,
+    user_id: Uuid,
+    username: &str,
+    data: &WorkoutDataSyncRequest,
+    staged: Option<&StagedHeartRate>,
+    job_id: Option<Uuid>,
+) -> Result<WorkoutUploadOutcome, sqlx::Error> {
     // workout_uuid is now required - database constraint will prevent duplicates
     tracing::info!("🔍 Processing workout UUID: {}", data.workout_uuid);
 
-    // 🎲 CALCULATE GAME STATS FROM WORKOUT DATA
-    let stat_changes = StatCalculator::calculate_stat_changes(&pool, user_id, &data).await;
-    tracing::info!("📊 Calculated stat changes for {}: +{} stamina, +{} strength", 
-        claims.username, 
-        stat_changes.stamina_change, 
-        stat_changes.strength_change, 
-    );
+    // 🛡️ VALIDATE BEFORE ANYTHING IS SCORED
+    let validation = validate_workout_data(&mut *pool.acquire().await?, user_id, data, staged).await?;
+    if validation.is_rejected() {
+        tracing::warn!("🚫 Workout {} for {} failed validation: {}", data.workout_uuid, username, validation.summary());
+        return Ok(WorkoutUploadOutcome::Rejected(validation.issues));
+    }
+
+    // 💾 PERSIST WORKOUT, AVATAR STATS AND LIVE SCORES AS ONE UNIT
+    // The user's uploads hold this transaction's lock from the overlap check on, so two recordings
+    // of the same activity processed at once can't both be stored. Everything from here on reads
+    // on the transaction, so a worker holding the lock never waits on the pool for a second connection.
+    let mut tx = pool.begin().await?;
+    lock_user_workouts(&mut tx, user_id).await?;
+
+    // 🔁 CHECK FOR THE SAME ACTIVITY RECORDED ON ANOTHER DEVICE
+    let (replacement, merged);
+    let (data, replaced, staged) = match resolve_overlap(&mut tx, user_id, username, data, staged).await? {
+        OverlapResolution::Separate => (data, None, staged),
+        OverlapResolution::Rejected(issue) => return Ok(WorkoutUploadOutcome::Rejected(vec![issue])),
+        OverlapResolution::Replace(workout, replaced, restaged) => {
+            (replacement, merged) = (*workout, restaged);
+            (&replacement, Some(replaced), merged.as_deref().or(staged))
+        }
+    };
+
+    // A merged or kept recording is a payload of its own and has to pass validation too
+    let validation = match &replaced {
+        Some(_) => {
+            let validation = validate_workout_data(&mut tx, user_id, data, staged).await?;
+            if validation.is_rejected() {
+                tracing::warn!("🚫 Replacement of workout {} for {} failed validation: {}",
+                    data.workout_uuid, username, validation.summary());
+                return Ok(WorkoutUploadOutcome::Rejected(validation.issues));
+            }
+            validation
+        }
+        None => validation,
+    };
+
+    let review_status = if validation.is_flagged() {
+        tracing::warn!("🚩 Workout {} for {} held for review: {}", data.workout_uuid, username, validation.summary());
+        WorkoutReviewStatus::PendingReview
+    } else {
+        WorkoutReviewStatus::Accepted
+    };
+
+    // A recording held for review can't take the place of one that already scored
+    if let Some(replaced) = replaced.as_ref().filter(|replaced| replaced.accepted) {
+        if review_status == WorkoutReviewStatus::PendingReview {
+            return Ok(WorkoutUploadOutcome::Rejected(vec![ValidationIssue {
+                code: ValidationReasonCode::OverlappingWorkout,
+                severity: ValidationSeverity::Reject,
+                message: format!(
+                    "Duplicates workout {} that already scored, and would be held for review: {}",
+                    replaced.id, validation.summary()
+                ),
+            }]));
+        }
+    }
+
+    // 🏆 RESOLVE ACTIVE LIVE GAMES THE WORKOUT COUNTS TOWARDS
+    let mut live_game_targets = match review_status {
+        WorkoutReviewStatus::Accepted => resolve_live_game_targets(&mut tx, live_game_service, user_id, username, data).await?,
+        _ => Vec::new(),
+    };
+
+    // 🎲 CALCULATE GAME STATS FROM WORKOUT DATA WITH THE USER'S SEASON RULES
+    let mut stat_changes = if review_status == WorkoutReviewStatus::Accepted {
+        let rules = get_user_scoring_rules(&mut tx, user_id, data.workout_start.unwrap_or(data.timestamp)).await?;
+        let analysis = match staged {
+            Some(staged) => staged.analysis_for(&mut tx, &rules).await?,
+            None => None,
+        };
+        let mut stat_changes = StatCalculator::calculate_stat_changes_with_analysis(&mut tx, user_id, data, analysis.as_deref(), &rules).await?;
+        score_live_game_targets(&mut tx, user_id, data, staged, &rules, &mut live_game_targets).await?;
+        if let Some(replaced) = &replaced {
+            stat_changes.reasoning.push(format!("Replaced duplicate recording {} of the same activity", replaced.id));
+        }
+        tracing::info!("📊 Calculated stat changes for {}: +{} stamina, +{} strength", 
+            username, 
+            stat_changes.stamina_change, 
+            stat_changes.strength_change, 
+        );
+        stat_changes
+    } else {
+        StatChanges {
+            stamina_change: 0,
+            strength_change: 0,
+            reasoning: vec![format!("Held for review: {}", validation.summary())],
+            zone_breakdown: None,
+            training_load: None,
+        }
+    };
+
+    // Take back what the duplicate scored before the replacement is counted
+    let mut corrected_live_games = Vec::new();
+    if let Some(replaced) = &replaced {
+        if let Some(reversed) = reverse_and_delete_workout(&mut tx, user_id, replaced.id).await? {
+            tracing::info!("🔁 Replacing duplicate workout {} for {}: -{} stamina, -{} strength",
+                replaced.id, username, reversed.stamina_reverted, reversed.strength_reverted);
+            corrected_live_games = reversed.corrected_games;
+        }
+    }
+    // 🧢 DIMINISHING RETURNS AGAINST WHAT THE USER ALREADY GAINED THAT DAY AND WEEK
+    if review_status == WorkoutReviewStatus::Accepted {
+        // Without a start time the day is the row's created_at, like retraction and recompute use
+        let stored_at = sqlx::query_scalar!(r#"SELECT NOW() AS "now!""#)
+            .fetch_one(&mut *tx)
+            .await?;
+        apply_stat_caps(
+            &mut tx, user_id, workout_day(data.workout_start, stored_at), &mut stat_changes,
+            live_game_targets.iter_mut().filter_map(|target| target.stat_changes.as_mut()),
+        ).await?;
+    }
+
+    let superseded_workout_uuids = replaced.as_ref()
+        .map(|replaced| replaced.workout_uuids.clone())
+        .unwrap_or_default();
+
+    // Insert the workout first so a duplicate workout_uuid aborts before anything is counted
+    tracing::info!("💾 Inserting workout data into database for user: {} with workout_uuid: {:?}", 
+        username, data.workout_uuid);
+    let sync_id = insert_workout_data(
+        &mut tx, user_id, data, &stat_changes, review_status, &validation.issues, &superseded_workout_uuids,
+    ).await?;
+    tracing::info!("✅ Workout data inserted successfully with sync_id: {} for user: {}", 
+        sync_id, username);
+
+    if let Some(staged) = staged {
+        let stored = store_upload_session_samples(&mut tx, staged.session_id, sync_id).await?;
+        tracing::info!("💾 Stored {} staged heart rate samples of upload session {} with workout {}",
+            stored, staged.session_id, sync_id);
+    }
+
+    if let Some(job_id) = job_id {
+        link_workout_to_job(&mut tx, job_id, sync_id).await?;
+    }
+
+    let updated_live_games = if review_status == WorkoutReviewStatus::Accepted {
+        apply_workout_scoring(&mut tx, live_game_service, &live_game_targets, user_id, username, sync_id, &stat_changes).await?
+    } else {
+        Vec::new()
+    };
+
+    tx.commit().await?;
+    tracing::info!("✅ Committed workout {}, avatar stats and {} live game update(s) for {}", 
+        sync_id, updated_live_games.len(), username);
+
+    broadcast_live_games(live_game_service, &corrected_live_games).await;
+    broadcast_live_games(live_game_service, &updated_live_games).await;
+    publish_workout_processed(redis, user_id, username, sync_id, &stat_changes, review_status, job_id);
+    if review_status == WorkoutReviewStatus::Accepted {
+        update_personal_records(pool, redis, sync_id).await;
+        update_daily_challenges(pool, redis, sync_id).await;
+    }
+    // A replaced workout can take experience away even when the new one is held for review
+    update_avatar_progression(pool, redis, user_id, username).await;
+
+    tracing::info!("✅ Workout data processed successfully with game mechanics for {}: {}", 
+        username, sync_id);
+
+    Ok(WorkoutUploadOutcome::Processed(ProcessedWorkout {
+        sync_id,
+        stat_changes,
+        review_status,
+        flags: validation.issues,
+        replaced_workout_id: replaced.map(|replaced| replaced.id),
+    }))
+}
+
+/// Look for a stored recording of the same activity from another device and apply the upload's
+/// overlap policy: reject it, keep whichever has the denser heart rate data, or merge the two.
+async fn resolve_overlap(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    username: &str,
+    data: &WorkoutDataSyncRequest,
+    staged: Option<&StagedHeartRate>,
+) -> Result<OverlapResolution, sqlx::Error> {
+    let Some((start, end)) = OverlapDetector::workout_window(data) else {
+        return Ok(OverlapResolution::Separate);
+    };
+
+    let candidates = find_overlapping_workouts(conn, user_id, start, end).await?;
+    let duplicate = match staged {
+        Some(staged) => find_staged_duplicate(conn, data, staged, &candidates).await?,
+        None => OverlapDetector::find_duplicate(data, &candidates),
+    };
+    let Some(duplicate) = duplicate else {
+        return Ok(OverlapResolution::Separate);
+    };
+    let existing = duplicate.existing;
+
+    // Re-uploads of the stored workout itself are answered as a duplicate workout_uuid by the insert
+    if existing.workout_uuid == data.workout_uuid {
+        return Ok(OverlapResolution::Separate);
+    }
+
+    tracing::info!("🔁 Workout {} for {} duplicates workout {} from {} ({:.0}% overlap, heart rate difference {:?})",
+        data.workout_uuid, username, existing.id, existing.device_id,
+        duplicate.overlap_ratio * 100.0, duplicate.mean_heart_rate_difference);
+
+    let duplicate_issue = |message: String| ValidationIssue {
+        code: ValidationReasonCode::OverlappingWorkout,
+        severity: ValidationSeverity::Reject,
+        message,
+    };
+
+    let mut restaged = None;
+    let workout = match data.overlap_policy.unwrap_or_default() {
+        OverlapPolicy::Reject => {
+            return Ok(OverlapResolution::Rejected(duplicate_issue(format!(
+                "Duplicates workout {} recorded on {}", existing.id, existing.device_id
+            ))));
+        }
+        OverlapPolicy::KeepBetter => {
+            let sample_count = match staged {
+                Some(staged) => staged.checks.sample_count(),
+                None => data.heart_rate.as_ref().map_or(0, Vec::len),
+            };
+            let new_quality = OverlapDetector::heart_rate_quality(sample_count, start, end);
+            let existing_quality = OverlapDetector::heart_rate_quality(existing.heart_rate.len(), existing.workout_start, existing.workout_end);
+            if existing_quality >= new_quality {
+                return Ok(OverlapResolution::Rejected(duplicate_issue(format!(
+                    "Duplicates workout {} recorded on {} with better heart rate data ({:.1} vs {:.1} samples/min)",
+                    existing.id, existing.device_id, existing_quality, new_quality
+                ))));
+            }
+            // Sets logged with the stored recording survive a better heart rate recording of the session
+            let mut workout = data.clone();
+            if workout.strength_exercises.is_none() {
+                workout.strength_exercises = existing.strength_exercises.clone();
+            }
+            workout
+        }
+        OverlapPolicy::Merge => match staged {
+            // The stored samples join the staged ones on the transaction, which are then checked and analyzed again
+            Some(staged) => {
+                let merged = merge_workout_samples_into_upload_session(conn, staged.session_id, existing.id).await?;
+                tracing::info!("🔀 Merged {} heart rate samples of workout {} into upload session {}",
+                    merged, existing.id, staged.session_id);
+                let workout = OverlapDetector::merge_details(data, existing);
+                restaged = Some(Box::new(StagedHeartRate::read(conn, user_id, staged.session_id, &workout).await?));
+                workout
+            }
+            None => OverlapDetector::merge(data, existing),
+        },
+    };
+
+    let mut workout_uuids = vec![existing.workout_uuid.clone()];
+    workout_uuids.extend(existing.superseded_workout_uuids.iter().cloned());
+
+    Ok(OverlapResolution::Replace(
+        Box::new(workout),
+        ReplacedWorkout { id: existing.id, workout_uuids, accepted: existing.accepted },
+        restaged,
+    ))
+}
+
+/// `OverlapDetector::find_duplicate` for a workout uploaded in a session, comparing the staged
+/// samples in the candidates' windows as they're streamed
+async fn find_staged_duplicate<'a>(
+    conn: &mut PgConnection,
+    data: &WorkoutDataSyncRequest,
+    staged: &StagedHeartRate,
+    candidates: &'a [OverlappingWorkout],
+) -> Result<Option<DuplicateMatch<'a>>, sqlx::Error> {
+    let Some(mut search) = DuplicateSearch::new(data, candidates) else {
+        return Ok(None);
+    };
+    if let Some((from, to)) = search.sample_window() {
+        let mut samples = stream_upload_session_samples_between(conn, staged.session_id, from, to);
+        while let Some(sample) = samples.try_next().await? {
+            search.push(&sample);
+        }
+    }
+    Ok(search.finish())
+}
+
+/// Day a workout's gains count towards for the stat caps. Caps and streaks use UTC days, so a user
+/// changing time zone can't get a day twice. Only daily challenges follow the user's own day, see
+/// `get_accepted_workout_day`, so one workout can count towards a different day for each.
+pub fn workout_day(workout_start: Option<DateTime<Utc>>, fallback: DateTime<Utc>) -> NaiveDate {
+    workout_start.unwrap_or(fallback).date_naive()
+}
+
+/// Run the plausibility validator against the user's max heart rate. Staged samples were already
+/// checked against it as they were streamed.
+pub async fn validate_workout_data(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+    staged: Option<&StagedHeartRate>,
+) -> Result<WorkoutValidation, sqlx::Error> {
+    if let Some(staged) = staged {
+        return Ok(WorkoutValidator::validate_with_heart_rate(data, Some(&staged.checks), Utc::now()));
+    }
+
+    let max_heart_rate = user_max_heart_rate(conn, user_id).await?;
+    Ok(WorkoutValidator::validate(data, max_heart_rate, Utc::now()))
+}
+
+/// The user's measured max heart rate, or else the estimate from their age
+pub async fn user_max_heart_rate(conn: &mut PgConnection, user_id: Uuid) -> Result<MaxHeartRate, sqlx::Error> {
+    let user_profile = get_user_profile(conn, user_id).await?;
+    Ok(match user_profile.max_heart_rate {
+        Some(bpm) => MaxHeartRate { bpm, measured: true },
+        None => MaxHeartRate {
+            bpm: calc_max_heart_rate(user_profile.age, user_profile.gender),
+            measured: false,
+        },
+    })
+}
+
+/// Score a workout that was held for review once an admin approves it.
+/// Updates the stored workout, avatar and any still-active live games in one transaction.
+/// Returns `RowNotFound` if the workout doesn't exist or is no longer pending review.
+pub async fn approve_flagged_workout(
+    pool: &sqlx::PgPool,
+    redis: Option<&Arc<redis::Client>>,
+    live_game_service: Option<&LiveGameService>,
+    workout_id: Uuid,
+    reviewer_id: Uuid,
+) -> Result<StatChanges, sqlx::Error> {
+    let workout = sqlx::query!(
+        r#"
+        SELECT wd.user_id, u.username, wd.device_id, wd.calories_burned,
+               wd.workout_uuid, wd.workout_start, wd.workout_end, wd.created_at,
+               wd.workout_type, wd.strength_exercises
+        FROM workout_data wd
+        JOIN users u ON u.id = wd.user_id
+        WHERE wd.id = $1 AND wd.review_status = 'pending_review'
+        "#,
+        workout_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(sqlx::Error::RowNotFound)?;
+
+    let user_id = workout.user_id;
+    let username = workout.username.as_str();
+    let heart_rate = get_heart_rate_samples(pool, workout_id).await?;
+    let streams = get_sensor_streams(&mut *pool.acquire().await?, workout_id).await?;
+    let data = WorkoutDataSyncRequest {
+        device_id: workout.device_id,
+        timestamp: workout.created_at,
+        heart_rate: (!heart_rate.is_empty()).then_some(heart_rate),
+        calories_burned: workout.calories_burned,
+        workout_uuid: workout.workout_uuid,
+        workout_start: workout.workout_start,
+        workout_end: workout.workout_end,
+        workout_type: workout.workout_type.as_deref().map(WorkoutType::from_name),
+        overlap_policy: None,
+        power: streams.power,
+        cadence: streams.cadence,
+        speed: streams.speed,
+        distance: streams.distance,
+        steps: streams.steps,
+        strength_exercises: parse_strength_exercises(workout.strength_exercises),
+    };
 
-    // 💾 APPLY STAT CHANGES TO DATABASE
-    let update_result = sqlx::query!(
+    // Like uploads, take the user's workout lock before the daily gains and the avatar
+    let mut tx = pool.begin().await?;
+    lock_user_workouts(&mut tx, user_id).await?;
+
+    let mut live_game_targets = resolve_live_game_targets(&mut tx, live_game_service, user_id, username, &data).await?;
+    let rules = get_user_scoring_rules(&mut tx, user_id, data.workout_start.unwrap_or(data.timestamp)).await?;
+    let mut stat_changes = StatCalculator::calculate_stat_changes_with_analysis(&mut tx, user_id, &data, None, &rules).await?;
+    score_live_game_targets(&mut tx, user_id, &data, None, &rules, &mut live_game_targets).await?;
+
+    apply_stat_caps(
+        &mut tx, user_id, workout_day(data.workout_start, data.timestamp), &mut stat_changes,
+        live_game_targets.iter_mut().filter_map(|target| target.stat_changes.as_mut()),
+    ).await?;
+    let zone_breakdown_json = stat_changes.zone_breakdown.as_ref()
+        .map(|breakdown| serde_json::to_value(breakdown).unwrap_or(serde_json::Value::Null));
+
+    let updated = sqlx::query!(
+        r#"
+        UPDATE workout_data
+        SET review_status = 'accepted',
+            reviewed_by = $1,
+            reviewed_at = NOW(),
+            heart_rate_zones = $2,
+            stamina_gained = $3,
+            strength_gained = $4,
+            total_points_gained = $5,
+            banister_trimp = $6,
+            edwards_trimp = $7,
+            intensity_factor = $8,
+            epoc_estimate = $9
+        WHERE id = $10 AND review_status = 'pending_review'
+        "#,
+        reviewer_id,
+        zone_breakdown_json,
+        stat_changes.stamina_change,
+        stat_changes.strength_change,
+        stat_changes.stamina_change + stat_changes.strength_change,
+        stat_changes.training_load.map(|load| load.banister_trimp),
+        stat_changes.training_load.map(|load| load.edwards_trimp),
+        stat_changes.training_load.map(|load| load.intensity_factor),
+        stat_changes.training_load.map(|load| load.epoc_estimate),
+        workout_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Someone else already reviewed this workout
+    if updated.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let updated_live_games = apply_workout_scoring(
+        &mut tx, live_game_service, &live_game_targets, user_id, username, workout_id, &stat_changes,
+    ).await?;
+
+    tx.commit().await?;
+    tracing::info!("✅ Approved workout {} for {}: +{} stamina, +{} strength", 
+        workout_id, username, stat_changes.stamina_change, stat_changes.strength_change);
+
+    broadcast_live_games(live_game_service, &updated_live_games).await;
+    publish_workout_processed(redis, user_id, username, workout_id, &stat_changes, WorkoutReviewStatus::Accepted, None);
+    update_personal_records(pool, redis, workout_id).await;
+    update_daily_challenges(pool, redis, workout_id).await;
+    update_avatar_progression(pool, redis, user_id, username).await;
+
+    Ok(stat_changes)
+}
+
+async fn resolve_live_game_targets(
+    conn: &mut PgConnection,
+    live_game_service: Option<&LiveGameService>,
+    user_id: Uuid,
+    username: &str,
+    data: &WorkoutDataSyncRequest,
+) -> Result<Vec<LiveGameTarget>, sqlx::Error> {
+    match (live_game_service, data.workout_start) {
+        (Some(live_service), Some(workout_start)) => {
+            find_live_game_targets(conn, user_id, username, live_service, &workout_start)
+                .await
+                .map_err(|e| {
+                    tracing::error!("❌ Failed to resolve live games for user {}: {}", username, e);
+                    e
+                })
+        }
+        (Some(_), None) => {
+            tracing::warn!("⚠️ No workout start time found for user {}", username);
+            Ok(Vec::new())
+        }
+        (None, _) => Ok(Vec::new()),
+    }
+}
+
+/// Score the workout with the rules of each game whose season doesn't share the avatar's rules
+async fn score_live_game_targets(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    data: &WorkoutDataSyncRequest,
+    staged: Option<&StagedHeartRate>,
+    avatar_rules: &ScoringRules,
+    live_game_targets: &mut [LiveGameTarget],
+) -> Result<(), sqlx::Error> {
+    for target in live_game_targets.iter_mut().filter(|target| target.rules.season_id != avatar_rules.season_id) {
+        let analysis = match staged {
+            Some(staged) => staged.analysis_for(&mut *conn, &target.rules).await?,
+            None => None,
+        };
+        target.stat_changes = Some(StatCalculator::calculate_stat_changes_with_analysis(
+            &mut *conn, user_id, data, analysis.as_deref(), &target.rules,
+        ).await?);
+    }
+    Ok(())
+}
+
+/// Apply the avatar stat gains and live game score updates for a workout on the caller's transaction
+async fn apply_workout_scoring(
+    conn: &mut PgConnection,
+    live_game_service: Option<&LiveGameService>,
+    live_game_targets: &[LiveGameTarget],
+    user_id: Uuid,
+    username: &str,
+    workout_data_id: Uuid,
+    stat_changes: &StatChanges,
+) -> Result<Vec<LiveGame>, sqlx::Error> {
+    sqlx::query!(
         r#"
         UPDATE user_avatars 
         SET stamina = stamina + $1, 
@@ -69,246 +726,191 @@ pub async fn upload_workout_data(
         stat_changes.strength_change,
         user_id
     )
-    .execute(&**pool)
-    .await;
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| {
+        tracing::error!("❌ Failed to update avatar stats for {}: {}", username, e);
+        e
+    })?;
 
-    match update_result {
-        Ok(_) => {
-            tracing::info!("✅ Successfully updated avatar stats for {}", claims.username);
-        }
-        Err(e) => {
-            tracing::error!("❌ Failed to update avatar stats for {}: {}", claims.username, e);
-            return HttpResponse::InternalServerError().json(
-                ApiResponse::<()>::error("Failed to update avatar stats")
+    let mut updated_live_games = Vec::with_capacity(live_game_targets.len());
+    if let Some(live_service) = live_game_service {
+        for target in live_game_targets {
+            let score_update = build_live_game_score_update(
+                user_id, username, target.team_id, &target.rules,
+                target.stat_changes.as_ref().unwrap_or(stat_changes), live_service, workout_data_id,
             );
+            let updated_game = live_service
+                .apply_score_update(&mut *conn, target.live_game.id, &score_update)
+                .await
+                .map_err(|e| {
+                    tracing::error!("❌ Failed to update live game score for {}: {}", username, e);
+                    e
+                })?;
+            updated_live_games.push(updated_game);
         }
     }
 
-    // Insert workout data into database first to get the ID
-    tracing::info!("💾 Inserting workout data into database for user: {} with workout_uuid: {:?}", 
-        claims.username, data.workout_uuid);
-    let insert_result = insert_workout_data(&pool, user_id, &data).await;
-    
-    match insert_result {
-        Ok(sync_id) => {
-            tracing::info!("✅ Workout data inserted successfully with sync_id: {} for user: {}", 
-                sync_id, claims.username);
-                
-            // 📊 UPDATE WORKOUT DATA WITH CALCULATED STATS
-            let zone_breakdown_json = stat_changes.zone_breakdown.as_ref()
-                .map(|breakdown| serde_json::to_value(breakdown).unwrap_or(serde_json::Value::Null));
-
-            let update_result = sqlx::query!(
-                r#"
-                UPDATE workout_data 
-                SET heart_rate_zones = $1,
-                    stamina_gained = $2,
-                    strength_gained = $3,
-                    total_points_gained = $4
-                WHERE id = $5
-                "#,
-                zone_breakdown_json,
-                stat_changes.stamina_change,
-                stat_changes.strength_change,
-                stat_changes.stamina_change + stat_changes.strength_change,
-                sync_id
-            )
-            .execute(&**pool)
-            .await;
+    Ok(updated_live_games)
+}
 
-            if let Err(e) = update_result {
-                tracing::error!("❌ Failed to update workout data with calculated stats for workout {}: {}", sync_id, e);
-            } else {
-                tracing::info!("✅ Successfully updated workout data with zone breakdown and stat gains for workout {}", sync_id);
+/// Broadcast live scores, only called after the transaction has committed
+async fn broadcast_live_games(live_game_service: Option<&LiveGameService>, updated_live_games: &[LiveGame]) {
+    if let Some(live_service) = live_game_service {
+        for updated_game in updated_live_games {
+            if let Err(e) = live_service.broadcast_live_score_update(updated_game).await {
+                tracing::error!("❌ Failed to broadcast live score update for game {}: {}", updated_game.game_id, e);
             }
+        }
+    }
+}
 
-            // 🏆 CHECK FOR ACTIVE LIVE GAMES AND UPDATE SCORES
-            if let Some(live_service) = &live_game_service {
-                if let Some(workout_start) = data.workout_start {
-                    match check_and_update_live_games(
-                        user_id, 
-                        &claims.username,
-                        sync_id, // Now we have the workout_data_id
-                        &stat_changes,
-                        &live_service,
-                        &workout_start,
-                        &pool,
-                    ).await {
-                        Ok(_) => {
-                            tracing::info!("✅ Successfully updated live game scores for user {}", claims.username);
-                        }
-                        Err(e) => {
-                            tracing::error!("❌ Failed to update live game scores for user {}: {}", claims.username, e);
-                        }
-                    }
-                } else {
-                    tracing::warn!("⚠️ No workout start time found for user {}", claims.username);
-                }
-            }
-            // 🎯 PREPARE GAME EVENT FOR REAL-TIME NOTIFICATION
-            let game_event = json!({
-                "event_type": "workout_data_processed",
-                "user_id": user_id.to_string(),
-                "username": claims.username,
-                "sync_id": sync_id.to_string(),
-                "stat_changes": {
-                    "stamina_change": stat_changes.stamina_change,
-                    "strength_change": stat_changes.strength_change,
-                },
-                "reasoning": stat_changes.reasoning,
-                "timestamp": Utc::now().to_rfc3339()
-            });
+/// Check an accepted workout for personal records. The workout is already stored, so a failure is only logged.
+async fn update_personal_records(pool: &sqlx::PgPool, redis: Option<&Arc<redis::Client>>, workout_id: Uuid) {
+    let service = PersonalRecordService::new(pool.clone(), redis.cloned());
+    if let Err(e) = service.check_workout(workout_id).await {
+        tracing::error!("❌ Failed to update personal records for workout {}: {}", workout_id, e);
+    }
+}
 
-            // 📡 PUBLISH TO REDIS FOR REAL-TIME NOTIFICATION
-            if let Some(redis_client) = &redis {
-                let user_channel = format!("game:events:user:{}", user_id);
-                let global_channel = "game:events:global".to_string();
-                let event_str = serde_json::to_string(&game_event)
-                    .unwrap_or_else(|e| {
-                        tracing::error!("Failed to serialize game event: {}", e);
-                        "{}".to_string()
-                    });
-
-                let redis_client = redis_client.clone();
-                let event_str_clone = event_str.clone();
-                let username_clone = claims.username.clone();
-                
-                tokio::spawn(async move {
-                    match redis_client.get_async_connection().await {
-                        Ok(mut conn) => {
-                            // Publish to user-specific channel
-                            let user_result: Result<i32, redis::RedisError> = 
-                                conn.publish(&user_channel, &event_str).await;
-                            
-                            // Also publish to global channel for leaderboards/social features
-                            let global_result: Result<i32, redis::RedisError> = 
-                                conn.publish(&global_channel, &event_str_clone).await;
-                            
-                            match (user_result, global_result) {
-                                (Ok(user_receivers), Ok(global_receivers)) => {
-                                    tracing::info!("🎮 Published game event for {} to {} user subscribers and {} global subscribers", 
-                                        username_clone, user_receivers, global_receivers);
-                                }
-                                (Err(e), _) | (_, Err(e)) => {
-                                    tracing::error!("❌ Failed to publish game event for {}: {}", username_clone, e);
-                                }
-                            }
-                        },
-                        Err(e) => {
-                            tracing::error!("❌ Redis connection failed during game event publishing: {}", e);
-                        }
-                    }
-                });
-            } else {
-                tracing::warn!("⚠️  Redis not available - game events will not be published in real-time");
-            }
+/// Evaluate the user's daily challenges after an accepted workout. Failures are only logged.
+async fn update_daily_challenges(pool: &sqlx::PgPool, redis: Option<&Arc<redis::Client>>, workout_id: Uuid) {
+    let service = DailyChallengeService::new(pool.clone(), redis.cloned());
+    if let Err(e) = service.evaluate_workout(workout_id).await {
+        tracing::error!("❌ Failed to evaluate daily challenges for workout {}: {}", workout_id, e);
+    }
+}
 
-            // 🎉 ENHANCED RESPONSE WITH GAME STATS
-            let sync_data = json!({
-                "sync_id": sync_id,
-                "timestamp": Utc::now(),
-                "game_stats": {
-                    "stat_changes": {
-                        "stamina_change": stat_changes.stamina_change,
-                        "strength_change": stat_changes.strength_change,
-                    },
-                    "reasoning": stat_changes.reasoning,
-                    "summary": format!("Gained {} total stat points!", 
-                        stat_changes.stamina_change + stat_changes.strength_change
-                    )
-                }
+async fn update_avatar_progression(pool: &sqlx::PgPool, redis: Option<&Arc<redis::Client>>, user_id: Uuid, username: &str) {
+    let service = AvatarProgressionService::new(pool.clone(), redis.cloned());
+    if let Err(e) = service.sync(user_id, username).await {
+        tracing::error!("❌ Failed to update avatar progression for {}: {}", username, e);
+    }
+}
+
+/// Publish the workout_data_processed event to the user and global channels
+fn publish_workout_processed(
+    redis: Option<&Arc<redis::Client>>,
+    user_id: Uuid,
+    username: &str,
+    sync_id: Uuid,
+    stat_changes: &StatChanges,
+    review_status: WorkoutReviewStatus,
+    job_id: Option<Uuid>,
+) {
+    // 🎯 PREPARE GAME EVENT FOR REAL-TIME NOTIFICATION
+    let game_event = json!({
+        "event_type": "workout_data_processed",
+        "user_id": user_id.to_string(),
+        "username": username,
+        "sync_id": sync_id.to_string(),
+        "job_id": job_id,
+        "job_status": job_id.map(|_| WorkoutJobStatus::Completed),
+        "stat_changes": {
+            "stamina_change": stat_changes.stamina_change,
+            "strength_change": stat_changes.strength_change,
+        },
+        "reasoning": stat_changes.reasoning,
+        "review_status": review_status,
+        "timestamp": Utc::now().to_rfc3339()
+    });
+
+    // 📡 PUBLISH TO REDIS FOR REAL-TIME NOTIFICATION
+    if let Some(redis_client) = redis {
+        let user_channel = format!("game:events:user:{}", user_id);
+        let global_channel = "game:events:global".to_string();
+        let event_str = serde_json::to_string(&game_event)
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to serialize game event: {}", e);
+                "{}".to_string()
             });
 
-            tracing::info!("✅ Workout data processed successfully with game mechanics for {}: {}", 
-                claims.username, sync_id);
-            HttpResponse::Ok().json(
-                ApiResponse::success("Workout data synced and game stats calculated!", sync_data)
-            )
-        }
-        Err(e) => {
-            // Check if this is a duplicate workout UUID error
-            if let sqlx::Error::Database(ref db_err) = e {
-                if db_err.code().as_deref() == Some("23505") {
-                    tracing::error!("❌ DUPLICATE WORKOUT UUID: Failed to sync workout data for {} due to duplicate workout_uuid: {:?}. This indicates a potential race condition where the duplicate check passed but another request inserted the same UUID before this one.", 
-                        claims.username, data.workout_uuid);
+        let redis_client = redis_client.clone();
+        let event_str_clone = event_str.clone();
+        let username_clone = username.to_string();
+        
+        tokio::spawn(async move {
+            match redis_client.get_async_connection().await {
+                Ok(mut conn) => {
+                    // Publish to user-specific channel
+                    let user_result: Result<i32, redis::RedisError> = 
+                        conn.publish(&user_channel, &event_str).await;
+                    
+                    // Also publish to global channel for leaderboards/social features
+                    let global_result: Result<i32, redis::RedisError> = 
+                        conn.publish(&global_channel, &event_str_clone).await;
                     
-                    // Return a more specific error response for duplicate UUIDs
-                    return HttpResponse::Conflict().json(
-                        ApiResponse::<()>::error("Workout UUID already exists - possible race condition detected")
-                    );
+                    match (user_result, global_result) {
+                        (Ok(user_receivers), Ok(global_receivers)) => {
+                            tracing::info!("🎮 Published game event for {} to {} user subscribers and {} global subscribers", 
+                                username_clone, user_receivers, global_receivers);
+                        }
+                        (Err(e), _) | (_, Err(e)) => {
+                            tracing::error!("❌ Failed to publish game event for {}: {}", username_clone, e);
+                        }
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("❌ Redis connection failed during game event publishing: {}", e);
                 }
             }
-            
-            tracing::error!("❌ Failed to sync workout data for {}: {}", claims.username, e);
-            HttpResponse::InternalServerError().json(
-                ApiResponse::<()>::error(format!("Failed to sync workout data: {}", e))
-            )
-        }
+        });
+    } else {
+        tracing::warn!("⚠️  Redis not available - game events will not be published in real-time");
     }
 }
 
-/// Check if user is in any active live games and update scores
-async fn check_and_update_live_games(
+/// Find the active live games the workout counts towards, together with the user's team
+/// and the season's scoring rules in each
+async fn find_live_game_targets(
+    conn: &mut PgConnection,
     user_id: Uuid,
     username: &str,
-    workout_data_id: Uuid,
-    stat_changes: &StatChanges,
     live_game_service: &LiveGameService,
     workout_start_time: &DateTime<Utc>,
-    pool: &sqlx::PgPool,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Vec<LiveGameTarget>, sqlx::Error> {
     tracing::info!("🎮 Checking for active live games for user {}", username);
 
-    // Get user's active live games
-    match live_game_service.get_user_active_games(user_id).await {
-        Ok(active_games) => {
-            if active_games.is_empty() {
-                tracing::debug!("No active live games found for user {}", username);
-                return Ok(());
-            }
+    let active_games = live_game_service.get_user_active_games(&mut *conn, user_id).await?;
+    if active_games.is_empty() {
+        tracing::debug!("No active live games found for user {}", username);
+        return Ok(Vec::new());
+    }
 
-            tracing::info!("🏆 Found {} active live game(s) for user {}", active_games.len(), username);
-
-            for live_game in active_games {
-                // Determine which team the user belongs to
-                let user_team_id = if let Ok(team_id) = get_user_team_id(user_id, &live_game, pool).await {
-                    team_id
-                } else {
-                    tracing::error!("Could not determine team for user {} in game {}", username, live_game.game_id);
-                    continue;
-                };
-                // Check if the workout start time is within the game start and end times
-                if &live_game.game_start_time <= workout_start_time && &live_game.game_end_time >= workout_start_time {
-                    tracing::info!("🏆 Workout start time is within the game start and end times for user {}", username);
-                    update_live_game_score(user_id, username, user_team_id, stat_changes, live_game_service, &live_game, workout_data_id).await;
-
-                } else {
-                    tracing::info!("❌ Workout start time is not within the game start and end times for user {}", username);
-                    continue;
-                }
-            }
-            Ok(())
-        }
-        Err(e) => {
-            tracing::error!("❌ Failed to get active games for user {}: {}", username, e);
-            return Err(e);
+    tracing::info!("🏆 Found {} active live game(s) for user {}", active_games.len(), username);
+
+    let mut targets = Vec::new();
+    for live_game in active_games {
+        // Determine which team the user belongs to
+        let Some(user_team_id) = get_user_team_id(&mut *conn, user_id, &live_game).await? else {
+            tracing::error!("Could not determine team for user {} in game {}", username, live_game.game_id);
+            continue;
+        };
+        // Check if the workout start time is within the game start and end times
+        if &live_game.game_start_time <= workout_start_time && &live_game.game_end_time >= workout_start_time {
+            tracing::info!("🏆 Workout start time is within the game start and end times for user {}", username);
+            let rules = get_game_scoring_rules(&mut *conn, live_game.game_id).await?;
+            targets.push(LiveGameTarget { live_game, team_id: user_team_id, rules, stat_changes: None });
+        } else {
+            tracing::info!("❌ Workout start time is not within the game start and end times for user {}", username);
         }
     }
+
+    Ok(targets)
 }
 
-async fn update_live_game_score(
+pub fn build_live_game_score_update(
     user_id: Uuid,
     username: &str,
     user_team_id: Uuid,
+    rules: &ScoringRules,
     stat_changes: &StatChanges,
     live_game_service: &LiveGameService,
-    live_game: &LiveGame,
     workout_data_id: Uuid,
-) {
-    tracing::info!("🏆 Updating live game score for user {}", user_id);
-
+) -> LiveGameScoreUpdate {
     // Calculate score increases based on stat changes
     let score_increase = live_game_service.calculate_score_from_stats(
+        rules,
         stat_changes.stamina_change,
         stat_changes.strength_change,
     );
@@ -321,8 +923,7 @@ async fn update_live_game_score(
         username, stat_changes.stamina_change, stat_changes.strength_change, 
         score_increase, power_increase, user_team_id);
 
-    // Create the score update
-    let score_update = LiveGameScoreUpdate {
+    LiveGameScoreUpdate {
         user_id,
         username: username.to_string(),
         team_id: user_team_id,
@@ -333,34 +934,15 @@ async fn update_live_game_score(
         description: format!("Workout upload: +{} stamina, +{} strength", 
             stat_changes.stamina_change, stat_changes.strength_change),
         workout_data_id: Some(workout_data_id),
-    };
-
-    // Apply the score update
-    match live_game_service.handle_score_update(live_game.game_id, score_update).await {
-        Ok(updated_game) => {
-            tracing::info!("📊 Updated live game {}: {} {} - {} {} (Player: {} +{})", 
-                live_game.game_id,
-                updated_game.home_team_name,
-                updated_game.home_score,
-                updated_game.away_score,
-                updated_game.away_team_name,
-                username,
-                score_increase
-            );
-        }
-        Err(e) => {
-            tracing::error!("❌ Failed to update live game score for {}: {}", username, e);
-        }
     }
- 
 }
 
-/// Helper function to determine which team a user belongs to in a live game
+/// Helper function to determine which team a user belongs to in a live game, `None` if neither
 async fn get_user_team_id(
+    conn: &mut PgConnection,
     user_id: Uuid, 
     live_game: &LiveGame,
-    pool: &sqlx::PgPool
-) -> Result<Uuid, Box<dyn std::error::Error>> {
+) -> Result<Option<Uuid>, sqlx::Error> {
     // Query live_player_contributions to find which team the user belongs to
     let team_info = sqlx::query!(
         r#"
@@ -371,11 +953,11 @@ async fn get_user_team_id(
         live_game.id,
         user_id
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
     
     match team_info {
-        Some(info) => Ok(info.team_id),
+        Some(info) => Ok(Some(info.team_id)),
         None => {
             // If not found in contributions, check team membership directly
             let membership = sqlx::query!(
@@ -390,13 +972,10 @@ async fn get_user_team_id(
                 live_game.home_team_id,
                 live_game.away_team_id
             )
-            .fetch_optional(pool)
+            .fetch_optional(&mut *conn)
             .await?;
             
-            match membership {
-                Some(m) => Ok(m.team_id),
-                None => Err("User does not belong to either team in this game".into())
-            }
+            Ok(membership.map(|m| m.team_id))
         }
     }
 }
@@ -0,0 +1,154 @@
+use actix_web::{web, HttpResponse};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::db::workout_data::get_synced_workout_uuids;
+use crate::handlers::workout_data::upload_workout_data::is_duplicate_workout_error;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+use crate::models::workout_data::{
+    BatchItemStatus, WorkoutBatchItemResult, WorkoutBatchSyncData, WorkoutDataBatchSyncRequest,
+    WorkoutDataSyncRequest,
+};
+use crate::services::workout_queue_service::WorkoutQueueService;
+
+/// Maximum number of workouts accepted in a single batch upload
+pub const MAX_BATCH_SIZE: usize = 100;
+/// Maximum accepted size of a batch upload's JSON body
+pub const MAX_BATCH_BODY_SIZE: usize = 32 * 1024 * 1024;
+
+#[tracing::instrument(
+    name = "Upload workout data batch",
+    skip(data, pool, workout_queue, claims),
+    fields(
+        username = %claims.username,
+        batch_size = data.workouts.len()
+    )
+)]
+pub async fn upload_workout_data_batch(
+    data: web::Json<WorkoutDataBatchSyncRequest>,
+    pool: web::Data<sqlx::PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    tracing::info!("📦 Queueing batch of {} workouts for user: {}", data.workouts.len(), claims.username);
+
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    if data.workouts.is_empty() {
+        return HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error("Batch must contain at least one workout")
+        );
+    }
+
+    if data.workouts.len() > MAX_BATCH_SIZE {
+        return HttpResponse::BadRequest().json(
+            ApiResponse::<()>::error(format!("Batch exceeds maximum size of {} workouts", MAX_BATCH_SIZE))
+        );
+    }
+
+    // Find the workouts that were already synced in a previous upload
+    let workout_uuids: Vec<String> = data.workouts.iter()
+        .map(|workout| workout.workout_uuid.clone())
+        .collect();
+    let already_synced: HashSet<String> = match get_synced_workout_uuids(&pool, user_id, &workout_uuids).await {
+        Ok(uuids) => uuids.into_iter().collect(),
+        Err(e) => {
+            tracing::error!("Database error checking workout sync status: {:?}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to check sync status")
+            );
+        }
+    };
+
+    let mut seen_in_batch = HashSet::new();
+    let mut results = Vec::with_capacity(data.workouts.len());
+
+    // Queued one job per workout, in upload order, so live game attribution follows the order of the batch
+    for workout in &data.workouts {
+        if let Some(reason) = validate_batch_item(workout) {
+            results.push(rejected_item(workout, reason));
+            continue;
+        }
+
+        if already_synced.contains(&workout.workout_uuid) || !seen_in_batch.insert(workout.workout_uuid.clone()) {
+            results.push(duplicate_item(workout, "Workout already synced"));
+            continue;
+        }
+
+        match workout_queue.enqueue(user_id, workout).await {
+            Ok(job_id) => {
+                results.push(WorkoutBatchItemResult {
+                    workout_uuid: workout.workout_uuid.clone(),
+                    status: BatchItemStatus::Queued,
+                    job_id: Some(job_id),
+                    reason: None,
+                });
+            }
+            Err(e) if is_duplicate_workout_error(&e) => {
+                tracing::warn!("⚠️ Workout {} for {} is already queued", workout.workout_uuid, claims.username);
+                results.push(duplicate_item(workout, "Workout is already queued for processing"));
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to queue workout {} for {}: {}", workout.workout_uuid, claims.username, e);
+                results.push(rejected_item(workout, "Failed to queue workout data".to_string()));
+            }
+        }
+    }
+
+    let count = |status: BatchItemStatus| results.iter().filter(|r| r.status == status).count();
+    let batch_data = WorkoutBatchSyncData {
+        queued: count(BatchItemStatus::Queued),
+        duplicates: count(BatchItemStatus::Duplicate),
+        rejected: count(BatchItemStatus::Rejected),
+        results,
+    };
+
+    tracing::info!("✅ Batch queued for {}: {} queued, {} duplicates, {} rejected",
+        claims.username, batch_data.queued, batch_data.duplicates, batch_data.rejected);
+
+    HttpResponse::Accepted().json(
+        ApiResponse::success("Workout batch queued for processing", batch_data)
+    )
+}
+
+/// Basic sanity checks for a single batch item, returns the rejection reason if invalid
+fn validate_batch_item(workout: &WorkoutDataSyncRequest) -> Option<String> {
+    if workout.workout_uuid.trim().is_empty() {
+        return Some("workout_uuid must not be empty".to_string());
+    }
+
+    if let (Some(start), Some(end)) = (workout.workout_start, workout.workout_end) {
+        if end < start {
+            return Some("workout_end is before workout_start".to_string());
+        }
+    }
+
+    None
+}
+
+fn duplicate_item(workout: &WorkoutDataSyncRequest, reason: &str) -> WorkoutBatchItemResult {
+    WorkoutBatchItemResult {
+        workout_uuid: workout.workout_uuid.clone(),
+        status: BatchItemStatus::Duplicate,
+        job_id: None,
+        reason: Some(reason.to_string()),
+    }
+}
+
+fn rejected_item(workout: &WorkoutDataSyncRequest, reason: String) -> WorkoutBatchItemResult {
+    WorkoutBatchItemResult {
+        workout_uuid: workout.workout_uuid.clone(),
+        status: BatchItemStatus::Rejected,
+        job_id: None,
+        reason: Some(reason),
+    }
+}
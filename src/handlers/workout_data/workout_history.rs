@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -5,7 +7,9 @@ use uuid::Uuid;
 use sqlx::PgPool;
 use chrono::{DateTime, Utc, Duration};
 
-use crate::{middleware::auth::Claims, models::workout_data::HeartRateData};
+use crate::db::sensor_samples::get_sensor_streams_for_workouts;
+use crate::db::workout_data::parse_strength_exercises;
+use crate::{middleware::auth::Claims, models::workout_data::{StrengthExercise, TrainingLoad, WorkoutStreams}};
 
 #[derive(Debug, Serialize)]
 pub struct WorkoutHistoryItem {
@@ -13,11 +17,25 @@ pub struct WorkoutHistoryItem {
     pub workout_date: DateTime<Utc>,
     pub workout_start: Option<DateTime<Utc>>,
     pub workout_end: Option<DateTime<Utc>>,
+    pub workout_type: Option<String>,
     pub duration_minutes: Option<i32>,
     pub calories_burned: Option<i32>,
     pub avg_heart_rate: Option<i32>,
     pub max_heart_rate: Option<i32>,
     pub heart_rate_zones: Option<serde_json::Value>,
+    pub avg_power: Option<i32>,
+    pub max_power: Option<i32>,
+    pub avg_cadence: Option<i32>,
+    pub total_distance_meters: Option<f64>,
+    pub total_steps: Option<i32>,
+    /// Raw sensor streams, only included when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub streams: Option<WorkoutStreams>,
+    /// TRIMP, intensity factor and EPOC estimate, missing for workouts without heart rate data
+    pub training_load: Option<TrainingLoad>,
+    /// Logged sets of strength sessions and their summed volume load in kg
+    pub strength_exercises: Option<Vec<StrengthExercise>>,
+    pub strength_volume_load_kg: Option<f64>,
     // Game stats gained from this workout
     pub stamina_gained: i32,
     pub strength_gained: i32,
@@ -27,6 +45,9 @@ pub struct WorkoutHistoryItem {
 pub struct WorkoutHistoryQuery {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Include the power, cadence, speed, distance and step streams of each workout
+    #[serde(default)]
+    pub include_streams: bool,
 }
 
 fn calculate_duration_minutes(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> Option<i32> {
@@ -44,19 +65,6 @@ fn calculate_duration_minutes(start: Option<DateTime<Utc>>, end: Option<DateTime
     }
 }
 
-fn calculate_avg_heart_rate(heart_rate_data: &Vec<HeartRateData>) -> Option<i32> {
-    if heart_rate_data.is_empty() {
-        return None;
-    }
-    let sum: i32 = heart_rate_data.iter().map(|hr| hr.heart_rate).sum();
-    let count = heart_rate_data.len() as i32;
-    Some((sum / count) as i32)
-}
-
-fn calculate_max_heart_rate(heart_rate_data: &Vec<HeartRateData>) -> Option<i32> {
-    heart_rate_data.iter().map(|hr| hr.heart_rate).reduce(i32::max)
-}
-
 #[tracing::instrument(
     name = "Get user workout history",
     skip(pool, claims),
@@ -81,25 +89,36 @@ pub async fn get_workout_history(
     let offset = query.offset.unwrap_or(0);
 
     // Fetch workout history with all stats from workout_data
-    let workouts: Vec<WorkoutHistoryItem> = match sqlx::query!(
+    let rows = match sqlx::query!(
         r#"
         SELECT 
             wd.id,
             COALESCE(wd.workout_start, wd.created_at) as workout_date,
             wd.workout_start,
             wd.workout_end,
+            wd.workout_type,
             wd.created_at,
             wd.calories_burned as calories_burned,
             wd.duration_minutes,
             wd.avg_heart_rate,
             wd.max_heart_rate,
-            wd.heart_rate_data,
             wd.heart_rate_zones,
+            wd.avg_power,
+            wd.max_power,
+            wd.avg_cadence,
+            wd.total_distance_meters,
+            wd.total_steps,
+            wd.banister_trimp,
+            wd.edwards_trimp,
+            wd.intensity_factor,
+            wd.epoc_estimate,
+            wd.strength_exercises,
+            wd.strength_volume_load_kg,
             COALESCE(wd.stamina_gained, 0) as stamina_gained,
             COALESCE(wd.strength_gained, 0) as strength_gained
         FROM workout_data wd
         WHERE wd.user_id = $1
-        AND (wd.calories_burned > 100 OR wd.heart_rate_data IS NOT NULL)
+        AND (wd.calories_burned > 100 OR wd.heart_rate_sample_count > 0 OR wd.strength_volume_load_kg > 0)
         ORDER BY COALESCE(wd.workout_start, wd.created_at) DESC
         LIMIT $2 OFFSET $3
         "#,
@@ -110,40 +129,7 @@ pub async fn get_workout_history(
     .fetch_all(&**pool)
     .await
     {
-        Ok(rows) => {
-            rows.into_iter().map(|row| {
-                // Use pre-calculated values from database when available
-                let duration_minutes = row.duration_minutes
-                    .or_else(|| calculate_duration_minutes(row.workout_start, row.workout_end));
-                
-                // Use pre-calculated heart rate stats or calculate from raw data if needed
-                let (avg_heart_rate, max_heart_rate) = if row.avg_heart_rate.is_some() && row.max_heart_rate.is_some() {
-                    (row.avg_heart_rate, row.max_heart_rate)
-                } else {
-                    // Fallback: calculate from raw data if pre-calculated values missing
-                    let heart_rate_data: Vec<HeartRateData> = 
-                        serde_json::from_value(row.heart_rate_data.clone()).unwrap_or_default();
-                    (
-                        calculate_avg_heart_rate(&heart_rate_data),
-                        calculate_max_heart_rate(&heart_rate_data)
-                    )
-                };
-                
-                WorkoutHistoryItem {
-                    id: row.id,
-                    workout_date: row.workout_date.unwrap_or(row.created_at),
-                    workout_start: row.workout_start,
-                    workout_end: row.workout_end,
-                    duration_minutes,
-                    calories_burned: row.calories_burned,
-                    avg_heart_rate,
-                    max_heart_rate,
-                    heart_rate_zones: row.heart_rate_zones, // Now directly from workout_data
-                    stamina_gained: row.stamina_gained.unwrap_or(0) as i32,
-                    strength_gained: row.strength_gained.unwrap_or(0) as i32,
-                }
-            }).collect()
-        },
+        Ok(rows) => rows,
         Err(e) => {
             tracing::error!("Failed to fetch workout history: {}", e);
             return HttpResponse::InternalServerError().json(json!({
@@ -152,13 +138,72 @@ pub async fn get_workout_history(
         }
     };
 
+    // The raw streams are only read when they were asked for
+    let mut streams = HashMap::new();
+    if query.include_streams {
+        let workout_ids: Vec<Uuid> = rows.iter().map(|row| row.id).collect();
+        let mut conn = match pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::error!("Failed to acquire connection: {}", e);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to fetch workout history"
+                }));
+            }
+        };
+        streams = match get_sensor_streams_for_workouts(&mut conn, &workout_ids).await {
+            Ok(streams) => streams,
+            Err(e) => {
+                tracing::error!("Failed to fetch workout streams: {}", e);
+                return HttpResponse::InternalServerError().json(json!({
+                    "error": "Failed to fetch workout history"
+                }));
+            }
+        };
+    }
+
+    let workouts: Vec<WorkoutHistoryItem> = rows.into_iter().map(|row| {
+        // Use pre-calculated values from database when available
+        let duration_minutes = row.duration_minutes
+            .or_else(|| calculate_duration_minutes(row.workout_start, row.workout_end));
+        
+        WorkoutHistoryItem {
+            id: row.id,
+            workout_date: row.workout_date.unwrap_or(row.created_at),
+            workout_start: row.workout_start,
+            workout_end: row.workout_end,
+            workout_type: row.workout_type,
+            duration_minutes,
+            calories_burned: row.calories_burned,
+            avg_heart_rate: row.avg_heart_rate,
+            max_heart_rate: row.max_heart_rate,
+            heart_rate_zones: row.heart_rate_zones, // Now directly from workout_data
+            avg_power: row.avg_power,
+            max_power: row.max_power,
+            avg_cadence: row.avg_cadence,
+            total_distance_meters: row.total_distance_meters,
+            total_steps: row.total_steps,
+            streams: query.include_streams.then(|| streams.remove(&row.id).unwrap_or_default()),
+            training_load: match (row.banister_trimp, row.edwards_trimp, row.intensity_factor, row.epoc_estimate) {
+                (Some(banister_trimp), Some(edwards_trimp), Some(intensity_factor), Some(epoc_estimate)) => {
+                    Some(TrainingLoad { banister_trimp, edwards_trimp, intensity_factor, epoc_estimate })
+                }
+                _ => None,
+            },
+            strength_exercises: parse_strength_exercises(row.strength_exercises),
+            strength_volume_load_kg: row.strength_volume_load_kg,
+            stamina_gained: row.stamina_gained.unwrap_or(0) as i32,
+            strength_gained: row.strength_gained.unwrap_or(0) as i32,
+        }
+    }).collect();
+
     // Get total count for pagination
     let total_count = match sqlx::query!(
         r#"
         SELECT COUNT(*) as count
         FROM workout_data
         WHERE user_id = $1
-        AND (calories_burned > 100 OR heart_rate_data IS NOT NULL)
+        AND (calories_burned > 100 OR heart_rate_sample_count > 0 OR strength_volume_load_kg > 0)
         "#,
         user_id
     )
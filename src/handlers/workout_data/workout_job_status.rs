@@ -0,0 +1,47 @@
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::workout_jobs::get_workout_job_for_user;
+use crate::middleware::auth::Claims;
+use crate::models::common::ApiResponse;
+
+#[tracing::instrument(
+    name = "Get workout upload job status",
+    skip(pool, claims),
+    fields(
+        username = %claims.username,
+        job_id = %job_id
+    )
+)]
+pub async fn get_workout_job_status(
+    job_id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> HttpResponse {
+    let user_id = match Uuid::parse_str(&claims.sub) {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::error!("Failed to parse user ID: {}", e);
+            return HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Invalid user ID")
+            );
+        }
+    };
+
+    match get_workout_job_for_user(pool.get_ref(), job_id.into_inner(), user_id).await {
+        Ok(Some(job)) => HttpResponse::Ok().json(ApiResponse::success(
+            "Workout job status retrieved successfully",
+            job,
+        )),
+        Ok(None) => HttpResponse::NotFound().json(
+            ApiResponse::<()>::error("Workout job not found")
+        ),
+        Err(e) => {
+            tracing::error!("Database error fetching workout job status: {:?}", e);
+            HttpResponse::InternalServerError().json(
+                ApiResponse::<()>::error("Failed to fetch workout job status")
+            )
+        }
+    }
+}
@@ -228,6 +228,26 @@ impl SeasonService {
         .await
     }
 
+    /// The latest season the user's team has started playing, which is the running one if there is one
+    pub async fn get_current_season_for_user(&self, user_id: Uuid) -> Result<Option<LeagueSeason>, sqlx::Error> {
+        sqlx::query_as!(
+            LeagueSeason,
+            r#"
+            SELECT s.* FROM league_seasons s
+            JOIN league_teams lt ON lt.season_id = s.id
+            JOIN team_members tm ON tm.team_id = lt.team_id
+            WHERE tm.user_id = $1
+            AND tm.status = 'active'
+            AND s.start_date <= NOW()
+            ORDER BY s.start_date DESC
+            LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
     /// Check if a season name already exists
     pub async fn season_name_exists(&self, name: &str) -> Result<bool, sqlx::Error> {
         let count = sqlx::query_scalar!(
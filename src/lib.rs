@@ -19,7 +19,7 @@ mod workout;
 pub mod services;
 use crate::routes::init_routes;
 use crate::config::jwt::JwtSettings;
-use crate::services::{SchedulerService, LiveGameService};
+use crate::services::{SchedulerService, LiveGameService, StatRecomputeService, WorkoutQueueService};
 use std::sync::Arc;
 
 pub fn run(
@@ -38,7 +38,15 @@ pub fn run(
     });
     
     // Create LiveGameService
-    let live_game_service = web::Data::new(LiveGameService::new(db_pool, redis_client));
+    let live_game_service = web::Data::new(LiveGameService::new(db_pool.clone(), redis_client.clone()));
+
+    // Admin-triggered replays of all workouts after scoring changes
+    let stat_recompute_service = web::Data::new(StatRecomputeService::new(db_pool.clone(), redis_client.clone()));
+    stat_recompute_service.clone().into_inner().start();
+
+    // Start the workers that process queued workout uploads
+    let workout_queue = web::Data::new(WorkoutQueueService::new(db_pool, redis_client));
+    workout_queue.clone().into_inner().start();
 
 
     let server = HttpServer::new( move || {
@@ -67,7 +75,9 @@ pub fn run(
             .app_data(db_pool_data.clone())
             .app_data(jwt_settings.clone())
             .app_data(scheduler_service.clone())
-            .app_data(live_game_service.clone());
+            .app_data(live_game_service.clone())
+            .app_data(workout_queue.clone())
+            .app_data(stat_recompute_service.clone());
         if let Some(ref redis) = redis_client_data {
             app = app.app_data(redis.clone());
         }
@@ -1,3 +1,11 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::workout_data::{WorkoutType, ZoneName};
+
+// Default zone points, used by seasons without their own scoring rules
+
 // Stamina gains (cardiovascular endurance)
 pub const ZONE_1_STAMINA_POINTS_PER_MIN: i32 = 2;  // Recovery still builds base
 pub const ZONE_2_STAMINA_POINTS_PER_MIN: i32 = 5;  // Sweet spot for stamina
@@ -10,4 +18,380 @@ pub const ZONE_1_STRENGTH_POINTS_PER_MIN: i32 = 0;  // No strength from recovery
 pub const ZONE_2_STRENGTH_POINTS_PER_MIN: i32 = 1;  // Minimal strength gains
 pub const ZONE_3_STRENGTH_POINTS_PER_MIN: i32 = 3;  // Moderate strength gains
 pub const ZONE_4_STRENGTH_POINTS_PER_MIN: i32 = 5;  // High strength gains
-pub const ZONE_5_STRENGTH_POINTS_PER_MIN: i32 = 8;  // Maximum strength gains
\ No newline at end of file
+pub const ZONE_5_STRENGTH_POINTS_PER_MIN: i32 = 8;  // Maximum strength gains
+
+// Fallback for workouts without heart rate data, scored from calories and duration
+pub const FALLBACK_STAMINA_POINTS_PER_MIN: f32 = 1.0;
+pub const FALLBACK_STRENGTH_POINTS_PER_MIN: f32 = 0.5;
+pub const FALLBACK_STAMINA_POINTS_PER_KCAL: f32 = 0.02;
+pub const FALLBACK_STRENGTH_POINTS_PER_KCAL: f32 = 0.01;
+pub const FALLBACK_MAX_POINTS_PER_MIN: f32 = 1.8;   // Below a minute in zone 1, the lowest zone rate, so heart rate data always pays more
+
+// Strength sessions logged as sets, scored from their volume load (reps x kg)
+pub const STRENGTH_POINTS_PER_VOLUME_KG: f32 = 0.02;  // 10 reps at 50 kg earn 10 points
+pub const STRENGTH_MAX_POINTS_PER_SET: f32 = 25.0;
+/// Sets at this RPE score in full, each point above or below adds or takes 10%
+pub const STRENGTH_REFERENCE_RPE: f32 = 8.0;
+
+// Heart rate gaps: the longest interval a single sample is credited with, and the longest gap interpolated
+pub const MAX_CREDITED_INTERVAL_SEC: f32 = 60.0;
+pub const INTERPOLATE_UP_TO_SEC: f32 = 300.0;
+/// Interpolation walks a gap second by second, so season rules can't stretch it past an hour
+pub const MAX_INTERPOLATE_UP_TO_SEC: f32 = 3600.0;
+
+/// How an activity type adjusts the zone-based points
+#[derive(Debug, Clone, Copy)]
+pub struct ScoringProfile {
+    pub stamina_multiplier: f32,
+    pub strength_multiplier: f32,
+    /// Strength earned per minute on top of the zone points, so lifting counts even at low heart rates
+    pub base_strength_per_min: f32,
+}
+
+impl ScoringProfile {
+    pub fn for_workout_type(workout_type: WorkoutType) -> Self {
+        let (stamina_multiplier, strength_multiplier, base_strength_per_min) = match workout_type {
+            WorkoutType::Run => (1.0, 1.0, 0.0),
+            WorkoutType::Ride => (1.0, 1.0, 0.0),
+            WorkoutType::Swim => (1.0, 1.2, 0.5),   // Full body, upper body load
+            WorkoutType::Walk => (1.0, 0.5, 0.0),
+            WorkoutType::Strength => (0.5, 1.5, 3.0), // Load builds strength whatever the heart rate
+            WorkoutType::Hiit => (0.8, 1.3, 1.0),
+            WorkoutType::Yoga => (0.6, 1.0, 1.0),   // Holds and balance, little cardio
+            WorkoutType::Other => (1.0, 1.0, 0.0),
+        };
+
+        Self { stamina_multiplier, strength_multiplier, base_strength_per_min }
+    }
+}
+
+/// How a season scores workouts: points per minute in each zone and how stat gains turn into live game score.
+/// Fields left out of a request keep their defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(default)]
+pub struct ScoringRules {
+    #[serde(skip_deserializing)]
+    pub season_id: Option<Uuid>,
+    pub zone1_stamina_per_min: f32,
+    pub zone2_stamina_per_min: f32,
+    pub zone3_stamina_per_min: f32,
+    pub zone4_stamina_per_min: f32,
+    pub zone5_stamina_per_min: f32,
+    pub zone1_strength_per_min: f32,
+    pub zone2_strength_per_min: f32,
+    pub zone3_strength_per_min: f32,
+    pub zone4_strength_per_min: f32,
+    pub zone5_strength_per_min: f32,
+    /// Live game score per stamina point gained
+    pub stamina_score_weight: f32,
+    /// Live game score per strength point gained
+    pub strength_score_weight: f32,
+    /// Points per minute and per kcal for workouts without heart rate data
+    pub fallback_stamina_per_min: f32,
+    pub fallback_strength_per_min: f32,
+    pub fallback_stamina_per_kcal: f32,
+    pub fallback_strength_per_kcal: f32,
+    /// Most stamina and strength together a minute without heart rate data can earn
+    pub fallback_max_points_per_min: f32,
+    /// Strength points per kg of volume load for strength sessions logged as sets
+    pub strength_per_volume_kg: f32,
+    /// Most strength a single set can earn
+    pub strength_max_points_per_set: f32,
+    /// Longest interval a heart rate sample is credited with, the rest of a longer gap isn't scored
+    pub max_credited_interval_sec: f32,
+    /// Longer gaps up to this are interpolated between their samples and credited in full. Zero disables interpolation,
+    /// otherwise it's at least `max_credited_interval_sec` and at most `MAX_INTERPOLATE_UP_TO_SEC`.
+    pub interpolate_up_to_sec: f32,
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self {
+            season_id: None,
+            zone1_stamina_per_min: ZONE_1_STAMINA_POINTS_PER_MIN as f32,
+            zone2_stamina_per_min: ZONE_2_STAMINA_POINTS_PER_MIN as f32,
+            zone3_stamina_per_min: ZONE_3_STAMINA_POINTS_PER_MIN as f32,
+            zone4_stamina_per_min: ZONE_4_STAMINA_POINTS_PER_MIN as f32,
+            zone5_stamina_per_min: ZONE_5_STAMINA_POINTS_PER_MIN as f32,
+            zone1_strength_per_min: ZONE_1_STRENGTH_POINTS_PER_MIN as f32,
+            zone2_strength_per_min: ZONE_2_STRENGTH_POINTS_PER_MIN as f32,
+            zone3_strength_per_min: ZONE_3_STRENGTH_POINTS_PER_MIN as f32,
+            zone4_strength_per_min: ZONE_4_STRENGTH_POINTS_PER_MIN as f32,
+            zone5_strength_per_min: ZONE_5_STRENGTH_POINTS_PER_MIN as f32,
+            stamina_score_weight: 1.0,
+            strength_score_weight: 1.0,
+            fallback_stamina_per_min: FALLBACK_STAMINA_POINTS_PER_MIN,
+            fallback_strength_per_min: FALLBACK_STRENGTH_POINTS_PER_MIN,
+            fallback_stamina_per_kcal: FALLBACK_STAMINA_POINTS_PER_KCAL,
+            fallback_strength_per_kcal: FALLBACK_STRENGTH_POINTS_PER_KCAL,
+            fallback_max_points_per_min: FALLBACK_MAX_POINTS_PER_MIN,
+            strength_per_volume_kg: STRENGTH_POINTS_PER_VOLUME_KG,
+            strength_max_points_per_set: STRENGTH_MAX_POINTS_PER_SET,
+            max_credited_interval_sec: MAX_CREDITED_INTERVAL_SEC,
+            interpolate_up_to_sec: INTERPOLATE_UP_TO_SEC,
+            updated_at: None,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Stamina and strength points per minute in a zone
+    pub fn zone_points_per_min(&self, zone: ZoneName) -> (f32, f32) {
+        match zone {
+            ZoneName::Zone1 => (self.zone1_stamina_per_min, self.zone1_strength_per_min),
+            ZoneName::Zone2 => (self.zone2_stamina_per_min, self.zone2_strength_per_min),
+            ZoneName::Zone3 => (self.zone3_stamina_per_min, self.zone3_strength_per_min),
+            ZoneName::Zone4 => (self.zone4_stamina_per_min, self.zone4_strength_per_min),
+            ZoneName::Zone5 => (self.zone5_stamina_per_min, self.zone5_strength_per_min),
+        }
+    }
+
+    /// Live game score for stat gains, weighted by the season's rules
+    pub fn score_from_stats(&self, stamina_gained: i32, strength_gained: i32) -> i32 {
+        (stamina_gained as f32 * self.stamina_score_weight + strength_gained as f32 * self.strength_score_weight).round() as i32
+    }
+
+    /// Reasons the rules can't be used, empty if they're usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let zones = [ZoneName::Zone1, ZoneName::Zone2, ZoneName::Zone3, ZoneName::Zone4, ZoneName::Zone5];
+        if zones.iter().any(|zone| {
+            let (stamina, strength) = self.zone_points_per_min(*zone);
+            !stamina.is_finite() || !strength.is_finite() || stamina < 0.0 || strength < 0.0
+        }) {
+            errors.push("Zone points per minute must be zero or more".to_string());
+        }
+        for (name, weight) in [
+            ("stamina_score_weight", self.stamina_score_weight),
+            ("strength_score_weight", self.strength_score_weight),
+            ("fallback_stamina_per_min", self.fallback_stamina_per_min),
+            ("fallback_strength_per_min", self.fallback_strength_per_min),
+            ("fallback_stamina_per_kcal", self.fallback_stamina_per_kcal),
+            ("fallback_strength_per_kcal", self.fallback_strength_per_kcal),
+            ("fallback_max_points_per_min", self.fallback_max_points_per_min),
+            ("strength_per_volume_kg", self.strength_per_volume_kg),
+            ("strength_max_points_per_set", self.strength_max_points_per_set),
+            ("interpolate_up_to_sec", self.interpolate_up_to_sec),
+        ] {
+            if !weight.is_finite() || weight < 0.0 {
+                errors.push(format!("{} must be zero or more", name));
+            }
+        }
+        let lowest_zone_rate = zones.iter()
+            .map(|zone| {
+                let (stamina, strength) = self.zone_points_per_min(*zone);
+                stamina + strength
+            })
+            .fold(f32::INFINITY, f32::min);
+        if self.fallback_max_points_per_min > 0.0 && self.fallback_max_points_per_min >= lowest_zone_rate {
+            errors.push(format!(
+                "fallback_max_points_per_min must be below {} points per minute, the lowest zone rate, or zero",
+                lowest_zone_rate
+            ));
+        }
+        if !self.max_credited_interval_sec.is_finite() || self.max_credited_interval_sec <= 0.0 {
+            errors.push("max_credited_interval_sec must be more than zero".to_string());
+        }
+        if self.interpolate_up_to_sec > MAX_INTERPOLATE_UP_TO_SEC {
+            errors.push(format!("interpolate_up_to_sec must be at most {}", MAX_INTERPOLATE_UP_TO_SEC));
+        }
+        if self.interpolate_up_to_sec > 0.0 && self.interpolate_up_to_sec < self.max_credited_interval_sec {
+            errors.push("interpolate_up_to_sec must be zero or at least max_credited_interval_sec".to_string());
+        }
+        errors
+    }
+}
+
+/// Soft caps on stat gains: zone minutes past a daily threshold and points past a daily
+/// or weekly cap still count, but at a reduced rate
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StatCaps {
+    pub zone1_daily_minutes: f32,
+    pub zone2_daily_minutes: f32,
+    pub zone3_daily_minutes: f32,
+    pub zone4_daily_minutes: f32,
+    pub zone5_daily_minutes: f32,
+    /// Share of each zone minute past its daily threshold that still scores
+    pub zone_diminished_rate: f32,
+    /// Stamina plus strength points per day scored at the full rate
+    pub daily_soft_cap: i32,
+    /// Stamina plus strength points per Monday-to-Sunday week scored at the full rate
+    pub weekly_soft_cap: i32,
+    /// Share of points past the daily or weekly soft cap that still scores
+    pub over_cap_rate: f32,
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl StatCaps {
+    /// Minutes per day scored at the full rate in a zone, by its position Zone1 to Zone5
+    pub fn zone_daily_minutes(&self, zone_index: usize) -> f32 {
+        match zone_index {
+            0 => self.zone1_daily_minutes,
+            1 => self.zone2_daily_minutes,
+            2 => self.zone3_daily_minutes,
+            3 => self.zone4_daily_minutes,
+            _ => self.zone5_daily_minutes,
+        }
+    }
+
+    /// Reasons the caps can't be applied, empty if they're usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if (0..5).map(|zone_index| self.zone_daily_minutes(zone_index)).any(|minutes| !minutes.is_finite() || minutes < 0.0) {
+            errors.push("Zone daily minutes must be zero or more".to_string());
+        }
+        for (name, rate) in [("zone_diminished_rate", self.zone_diminished_rate), ("over_cap_rate", self.over_cap_rate)] {
+            if !(0.0..=1.0).contains(&rate) {
+                errors.push(format!("{} must be between 0 and 1", name));
+            }
+        }
+        if self.daily_soft_cap < 0 || self.weekly_soft_cap < 0 {
+            errors.push("Soft caps must be zero or more".to_string());
+        }
+        errors
+    }
+}
+
+/// Decay of inactive users' stats: after `idle_days` without an accepted workout, a share of the stats
+/// above the floor is lost every day. Users are warned `warning_days` ahead.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct StatDecaySettings {
+    pub enabled: bool,
+    pub idle_days: i32,
+    pub warning_days: i32,
+    /// Share of the stats above the floor lost per day of decay
+    pub daily_decay_rate: f32,
+    /// Share of the stats a user earned, before any decay, that never decays
+    pub floor_ratio: f32,
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl StatDecaySettings {
+    /// Reasons the settings can't be applied, empty if they're usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.idle_days < 1 {
+            errors.push("idle_days must be at least 1".to_string());
+        }
+        if self.warning_days < 0 {
+            errors.push("warning_days must be zero or more".to_string());
+        }
+        for (name, rate) in [("daily_decay_rate", self.daily_decay_rate), ("floor_ratio", self.floor_ratio)] {
+            if !(0.0..=1.0).contains(&rate) {
+                errors.push(format!("{} must be between 0 and 1", name));
+            }
+        }
+        errors
+    }
+}
+
+/// A decay applied to an inactive user's stats
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StatDecayEvent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub stamina_before: i32,
+    pub strength_before: i32,
+    pub stamina_change: i32,
+    pub strength_change: i32,
+    pub last_active_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Whether a stat recompute job only reports what would change or rewrites the stored stats
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatRecomputeMode {
+    DryRun,
+    Apply,
+}
+
+impl StatRecomputeMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatRecomputeMode::DryRun => "dry_run",
+            StatRecomputeMode::Apply => "apply",
+        }
+    }
+}
+
+/// Lifecycle of a stat recompute job
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatRecomputeStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl StatRecomputeStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatRecomputeStatus::Queued => "queued",
+            StatRecomputeStatus::Running => "running",
+            StatRecomputeStatus::Completed => "completed",
+            StatRecomputeStatus::Failed => "failed",
+        }
+    }
+}
+
+/// An admin-triggered replay of every stored workout with the current scoring
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct StatRecomputeJob {
+    pub id: Uuid,
+    pub requested_by: Option<Uuid>,
+    pub mode: String,
+    pub rebuild_live_games: bool,
+    pub dry_run_job_id: Option<Uuid>,
+    pub status: String,
+    pub report: Option<serde_json::Value>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartStatRecomputeRequest {
+    /// Also rescore live score events in games that were not evaluated yet
+    #[serde(default)]
+    pub rebuild_live_games: bool,
+}
+
+/// What a stat recompute changed, or would change for a dry run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StatRecomputeReport {
+    pub workouts_replayed: usize,
+    pub workouts_changed: usize,
+    /// Users whose avatar totals change, with their totals before and after
+    pub users: Vec<UserStatDiff>,
+    /// Live games whose scores change, empty unless live games are rebuilt
+    pub live_games: Vec<LiveGameScoreDiff>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserStatDiff {
+    pub user_id: Uuid,
+    pub username: String,
+    pub workouts_changed: usize,
+    pub stamina_before: i32,
+    pub stamina_after: i32,
+    pub strength_before: i32,
+    pub strength_after: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LiveGameScoreDiff {
+    pub live_game_id: Uuid,
+    pub game_id: Uuid,
+    pub events_rescored: usize,
+    pub home_score_before: i32,
+    pub home_score_after: i32,
+    pub away_score_before: i32,
+    pub away_score_after: i32,
+}
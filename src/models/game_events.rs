@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::models::common::{MatchResult, PlayerStats, TeamStandings};
+use crate::models::profile::AvatarProgression;
 
 /// Game-specific WebSocket message types
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -28,6 +29,9 @@ pub enum GameEvent {
         username: String,
         stats: PlayerStats,
         position: Position,
+        /// Set when the avatar's level changed
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        progression: Option<AvatarProgression>,
         timestamp: DateTime<Utc>,
     },
 
@@ -67,6 +71,9 @@ pub enum GameEvent {
         user_id: Uuid,
         sync_id: Uuid,
         stat_changes: StatChanges,
+        /// Set when the workout was uploaded through the processing queue
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        job_id: Option<Uuid>,
         timestamp: DateTime<Utc>,
     },
 
@@ -1,13 +1,14 @@
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 
-#[derive(sqlx::FromRow, serde::Serialize)]
+#[derive(serde::Serialize)]
 pub struct UserProfileResponse {
     pub id: Uuid,
     pub username: String,
     pub stats: GameStats,
     pub rank: i32,
     pub avatar_style: String,
+    pub progression: AvatarProgression,
     pub total_stats: i32,
     pub created_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
@@ -28,6 +29,7 @@ pub struct HealthProfileResponse {
     pub resting_heart_rate: Option<i32>,
     pub weight: Option<f32>,
     pub height: Option<f32>,
+    pub auto_apply_heart_rate_estimates: bool,
     pub last_updated: DateTime<Utc>,
 }
 
@@ -38,4 +40,168 @@ pub struct UpdateHealthProfileRequest {
     pub resting_heart_rate: Option<i32>,
     pub weight: Option<f32>,
     pub height: Option<f32>,
-}
\ No newline at end of file
+    /// Apply heart rate estimates from workout history without asking first
+    pub auto_apply_heart_rate_estimates: Option<bool>,
+}
+
+/// Lifecycle of a heart rate estimate
+#[derive(Debug, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartRateEstimateStatus {
+    /// Waiting for the user to accept or dismiss it
+    Proposed,
+    Applied,
+    Dismissed,
+    /// Replaced by a newer estimate before the user decided
+    Superseded,
+}
+
+impl HeartRateEstimateStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeartRateEstimateStatus::Proposed => "proposed",
+            HeartRateEstimateStatus::Applied => "applied",
+            HeartRateEstimateStatus::Dismissed => "dismissed",
+            HeartRateEstimateStatus::Superseded => "superseded",
+        }
+    }
+}
+
+/// Max and resting heart rate estimated from recent workouts, with the zones they give
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct HeartRateEstimate {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub resting_heart_rate: i32,
+    pub max_heart_rate: i32,
+    pub previous_resting_heart_rate: i32,
+    pub previous_max_heart_rate: i32,
+    pub zone1_max: i32,
+    pub zone2_max: i32,
+    pub zone3_max: i32,
+    pub zone4_max: i32,
+    pub zone5_max: i32,
+    pub workouts_considered: i32,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Heart rate zones a user had over a period, `effective_until` is unset for the current ones
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct HeartRateZoneHistoryEntry {
+    pub resting_heart_rate: i32,
+    pub max_heart_rate: i32,
+    pub zone1_max: i32,
+    pub zone2_max: i32,
+    pub zone3_max: i32,
+    pub zone4_max: i32,
+    pub zone5_max: i32,
+    pub source: String,
+    pub estimate_id: Option<Uuid>,
+    pub effective_from: DateTime<Utc>,
+    pub effective_until: Option<DateTime<Utc>>,
+}
+
+/// The best value a user has reached for one kind of record
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct PersonalRecord {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub record_type: String,
+    pub value: f64,
+    pub previous_value: Option<f64>,
+    pub workout_data_id: Option<Uuid>,
+    pub achieved_at: DateTime<Utc>,
+}
+
+/// A cumulative threshold the user has passed, such as their 100th workout
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct Milestone {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub milestone_type: String,
+    pub threshold: i64,
+    pub workout_data_id: Option<Uuid>,
+    pub reached_at: DateTime<Utc>,
+}
+
+/// Consecutive days or weeks with at least one accepted workout
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct TrainingStreak {
+    /// The streak the user is on, 0 once it's broken
+    pub current: i32,
+    pub longest: i32,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PersonalRecordsResponse {
+    pub records: Vec<PersonalRecord>,
+    pub milestones: Vec<Milestone>,
+    pub daily_streak: TrainingStreak,
+    pub weekly_streak: TrainingStreak,
+}
+
+/// A challenge assigned to a user for one day
+#[derive(Debug, sqlx::FromRow, serde::Serialize)]
+pub struct DailyChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub challenge_date: NaiveDate,
+    pub template_key: String,
+    pub title: String,
+    pub description: String,
+    pub target: f64,
+    pub progress: f64,
+    pub stamina_bonus: i32,
+    pub strength_bonus: i32,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub completed_workout_id: Option<Uuid>,
+}
+
+/// Stages an avatar evolves through as it levels up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvolutionStage {
+    Novice,
+    Adept,
+    Veteran,
+    Elite,
+    Legend,
+}
+
+impl EvolutionStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvolutionStage::Novice => "novice",
+            EvolutionStage::Adept => "adept",
+            EvolutionStage::Veteran => "veteran",
+            EvolutionStage::Elite => "elite",
+            EvolutionStage::Legend => "legend",
+        }
+    }
+}
+
+/// Where an avatar stands on its way to the next level
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AvatarProgression {
+    pub level: i32,
+    pub experience: i64,
+    /// Experience at which the current level was reached
+    pub level_experience: i64,
+    pub next_level_experience: i64,
+    pub evolution_stage: EvolutionStage,
+    /// Avatar styles the level unlocked
+    pub unlocked_styles: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct UpdateAvatarStyleRequest {
+    pub avatar_style: String,
+}
+
+#[derive(serde::Deserialize)]
+pub struct UpdateTimezoneRequest {
+    /// IANA name, such as "Europe/Berlin"
+    pub timezone: String,
+}
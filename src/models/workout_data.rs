@@ -22,7 +22,189 @@ pub struct HeartRateData {
     pub heart_rate: i32,
 }
 
-#[derive(Debug, Deserialize)]
+/// Heart rate over one bucket of a downsampled series
+#[derive(Debug, Serialize, Clone)]
+pub struct HeartRateBucket {
+    pub timestamp: DateTime<Utc>,
+    pub avg_heart_rate: i32,
+    pub min_heart_rate: i32,
+    pub max_heart_rate: i32,
+    /// Raw samples in the bucket
+    pub samples: i32,
+}
+
+/// Training load of one workout, from its heart rate
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct TrainingLoad {
+    /// Banister TRIMP: minutes weighted by heart rate reserve, exponentially for harder efforts
+    pub banister_trimp: f32,
+    /// Edwards TRIMP: minutes in each zone times the zone number
+    pub edwards_trimp: f32,
+    /// Average heart rate reserve relative to the threshold at the start of Zone 4
+    pub intensity_factor: f32,
+    /// Estimated peak excess post-exercise oxygen consumption in ml/kg
+    pub epoc_estimate: f32,
+}
+
+/// Power meter sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PowerData {
+    pub timestamp: DateTime<Utc>,
+    pub watts: i32,
+}
+
+/// Cadence sample: pedal revolutions per minute on the bike, steps per minute on foot
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CadenceData {
+    pub timestamp: DateTime<Utc>,
+    pub cadence: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SpeedData {
+    pub timestamp: DateTime<Utc>,
+    pub meters_per_second: f64,
+}
+
+/// Distance covered since the start of the workout
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DistanceData {
+    pub timestamp: DateTime<Utc>,
+    pub meters: f64,
+}
+
+/// Steps taken since the previous sample
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StepsData {
+    pub timestamp: DateTime<Utc>,
+    pub steps: i32,
+}
+
+/// One set of a strength exercise
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StrengthSet {
+    pub reps: i32,
+    /// Load lifted, body weight included for bodyweight exercises
+    #[serde(default)]
+    pub weight_kg: f64,
+    /// Rate of perceived exertion, 1-10
+    pub rpe: Option<f32>,
+}
+
+impl StrengthSet {
+    /// Reps times load
+    pub fn volume_load(&self) -> f64 {
+        self.reps.max(0) as f64 * self.weight_kg.max(0.0)
+    }
+}
+
+/// An exercise of a strength session and the sets done of it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StrengthExercise {
+    pub name: String,
+    pub sets: Vec<StrengthSet>,
+}
+
+/// Summed volume load of a strength session's sets
+pub fn strength_volume_load(exercises: &[StrengthExercise]) -> f64 {
+    exercises.iter()
+        .flat_map(|exercise| &exercise.sets)
+        .map(StrengthSet::volume_load)
+        .sum()
+}
+
+/// Kind of activity, used to pick the scoring profile. Unknown values from newer clients map to `Other`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkoutType {
+    Run,
+    Ride,
+    Swim,
+    Walk,
+    Strength,
+    Hiit,
+    Yoga,
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+impl WorkoutType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkoutType::Run => "run",
+            WorkoutType::Ride => "ride",
+            WorkoutType::Swim => "swim",
+            WorkoutType::Walk => "walk",
+            WorkoutType::Strength => "strength",
+            WorkoutType::Hiit => "hiit",
+            WorkoutType::Yoga => "yoga",
+            WorkoutType::Other => "other",
+        }
+    }
+
+    /// Map a stored value or a sport name from a device export (e.g. TCX `Running`, GPX `cycling`)
+    pub fn from_name(name: &str) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "run" | "running" | "trail_running" | "treadmill" => WorkoutType::Run,
+            "ride" | "biking" | "cycling" | "bike" | "indoor_cycling" | "virtual_ride" => WorkoutType::Ride,
+            "swim" | "swimming" => WorkoutType::Swim,
+            "walk" | "walking" | "hiking" | "hike" => WorkoutType::Walk,
+            "strength" | "strength_training" | "weight_training" | "training" => WorkoutType::Strength,
+            "hiit" | "interval_training" | "crossfit" => WorkoutType::Hiit,
+            "yoga" | "pilates" => WorkoutType::Yoga,
+            _ => WorkoutType::Other,
+        }
+    }
+}
+
+/// A stored workout with its samples, as users export it
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedWorkout {
+    pub id: Uuid,
+    pub workout_uuid: String,
+    pub device_id: String,
+    pub workout_date: DateTime<Utc>,
+    pub workout_start: Option<DateTime<Utc>>,
+    pub workout_end: Option<DateTime<Utc>>,
+    pub workout_type: Option<String>,
+    pub duration_minutes: Option<i32>,
+    pub calories_burned: Option<i32>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
+    pub min_heart_rate: Option<i32>,
+    pub total_distance_meters: Option<f64>,
+    pub stamina_gained: i32,
+    pub strength_gained: i32,
+    pub review_status: String,
+    pub heart_rate: Vec<HeartRateData>,
+    pub streams: WorkoutStreams,
+}
+
+/// What to do when an upload duplicates a workout recorded by another device
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Refuse the new upload
+    Reject,
+    /// Keep whichever recording has the denser heart rate data
+    #[default]
+    KeepBetter,
+    /// Combine both recordings into one workout
+    Merge,
+}
+
+/// Sensor streams stored with a workout besides heart rate
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct WorkoutStreams {
+    pub power: Option<Vec<PowerData>>,
+    pub cadence: Option<Vec<CadenceData>>,
+    pub speed: Option<Vec<SpeedData>>,
+    pub distance: Option<Vec<DistanceData>>,
+    pub steps: Option<Vec<StepsData>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct WorkoutDataSyncRequest {
     pub device_id: String,
     pub timestamp: DateTime<Utc>,
@@ -31,6 +213,16 @@ pub struct WorkoutDataSyncRequest {
     pub workout_uuid: String, // Required: Apple Health workout UUID for duplicate prevention
     pub workout_start: Option<DateTime<Utc>>, // Actual workout start time
     pub workout_end: Option<DateTime<Utc>>, // Actual workout end time
+    pub workout_type: Option<WorkoutType>,
+    /// How to handle a recording of the same activity from another device, `keep_better` if omitted
+    pub overlap_policy: Option<OverlapPolicy>,
+    pub power: Option<Vec<PowerData>>,
+    pub cadence: Option<Vec<CadenceData>>,
+    pub speed: Option<Vec<SpeedData>>,
+    pub distance: Option<Vec<DistanceData>>,
+    pub steps: Option<Vec<StepsData>>,
+    /// Sets, reps, load and RPE of a strength session, scored by volume load instead of heart rate zones
+    pub strength_exercises: Option<Vec<StrengthExercise>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -39,6 +231,173 @@ pub struct WorkoutDataSyncData {
     pub timestamp: DateTime<Utc>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WorkoutDataBatchSyncRequest {
+    pub workouts: Vec<WorkoutDataSyncRequest>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    /// Queued for the background workers, the outcome is reported on the job
+    Queued,
+    Duplicate,
+    Rejected,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkoutBatchItemResult {
+    pub workout_uuid: String,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkoutBatchSyncData {
+    pub queued: usize,
+    pub duplicates: usize,
+    pub rejected: usize,
+    pub results: Vec<WorkoutBatchItemResult>,
+}
+
+/// Reason codes produced by the workout plausibility validator
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationReasonCode {
+    FutureWorkout,
+    HeartRateOutOfBounds,
+    DuplicateTimestamps,
+    NonMonotonicTimestamps,
+    ImplausibleSampleDensity,
+    SparseSamples,
+    ExceedsMaxHeartRate,
+    SustainedMaxEffort,
+    Flatline,
+    SyntheticPattern,
+    StreamOutOfBounds,
+    OverlappingWorkout,
+    StrengthSetOutOfBounds,
+    ImplausibleStrengthVolume,
+    ImplausibleDuration,
+    ImplausibleCalories,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationSeverity {
+    /// The workout cannot be real and is not stored
+    Reject,
+    /// The workout is stored but held for admin review instead of scoring
+    Flag,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationIssue {
+    pub code: ValidationReasonCode,
+    pub severity: ValidationSeverity,
+    pub message: String,
+}
+
+/// Review state of a stored workout
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkoutReviewStatus {
+    Accepted,
+    PendingReview,
+    Rejected,
+}
+
+impl WorkoutReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkoutReviewStatus::Accepted => "accepted",
+            WorkoutReviewStatus::PendingReview => "pending_review",
+            WorkoutReviewStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// Lifecycle of a queued workout upload
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkoutJobStatus {
+    Queued,
+    Processing,
+    /// The workout was stored, either scored or held for review
+    Completed,
+    /// The workout failed plausibility checks and was not stored
+    Rejected,
+    /// Processing kept failing and the job ran out of attempts
+    Failed,
+}
+
+impl WorkoutJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkoutJobStatus::Queued => "queued",
+            WorkoutJobStatus::Processing => "processing",
+            WorkoutJobStatus::Completed => "completed",
+            WorkoutJobStatus::Rejected => "rejected",
+            WorkoutJobStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A workout upload waiting in, or finished by, the processing queue
+#[derive(Debug, Serialize, FromRow)]
+pub struct WorkoutProcessingJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub workout_uuid: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub last_error: Option<String>,
+    pub workout_data_id: Option<Uuid>,
+    pub result: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Lifecycle of a chunked upload session
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadSessionStatus {
+    /// Accepting heart rate samples
+    Open,
+    /// Queued for processing, no more samples are accepted
+    Finalized,
+}
+
+impl UploadSessionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UploadSessionStatus::Open => "open",
+            UploadSessionStatus::Finalized => "finalized",
+        }
+    }
+}
+
+/// A long workout uploaded in chunks of heart rate samples before it's queued
+#[derive(Debug, Serialize, FromRow)]
+pub struct WorkoutUploadSession {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub workout_uuid: String,
+    /// The upload request without its heart rate samples
+    #[serde(skip)]
+    pub metadata: serde_json::Value,
+    pub status: String,
+    pub sample_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finalized_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct UserProfile {
     pub age: i32,
@@ -48,7 +407,7 @@ pub struct UserProfile {
     pub stored_heart_rate_zones: Option<HeartRateZones>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy)]
 pub enum Gender {
     Male,
     Female,
@@ -147,6 +506,12 @@ impl HeartRateZones {
         }
     }
 
+    /// Upper limit of Zone1 to Zone5, as stored in the health profile
+    pub fn zone_maxes(&self) -> [i32; 5] {
+        [ZoneName::Zone1, ZoneName::Zone2, ZoneName::Zone3, ZoneName::Zone4, ZoneName::Zone5]
+            .map(|zone| self.zones.get(&zone).map(|range| range.high).unwrap_or_default())
+    }
+
     pub fn get_zone(&self, heart_rate: f32) -> Option<ZoneName> {
         for (zone_name, zone_range) in &self.zones {
             if heart_rate >= zone_range.low as f32 && heart_rate <= zone_range.high as f32 {
@@ -161,13 +526,15 @@ impl HeartRateZones {
 pub struct ActivitySummaryResponse {
     pub recent_workouts: i32,
     pub total_sessions: i32,
+    /// Minutes per zone over `zone_window`
     pub zone_distribution: HashMap<String, f32>,
+    pub zone_window: String,
     pub last_sync: Option<DateTime<Utc>>,
     pub weekly_stats: WeeklyStats,
     pub monthly_trend: MonthlyTrend,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Default)]
 pub struct WeeklyStats {
     pub total_calories: i32,
     pub total_exercise_time: i32, // in minutes
@@ -175,7 +542,7 @@ pub struct WeeklyStats {
     pub cardio_sessions: i32,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Default)]
 pub struct MonthlyTrend {
     pub stamina_gain: i32,
     pub strength_gain: i32,
@@ -6,6 +6,7 @@ use crate::handlers::admin::{
     league_handler,
     game_management_handler,
     workout_handler,
+    scoring_handler,
 };
 use crate::middleware::admin::AdminMiddleware;
 
@@ -31,6 +32,14 @@ pub fn init_admin_routes(cfg: &mut web::ServiceConfig) {
                 web::resource("/users/{id}/status")
                     .route(web::patch().to(user_handler::update_user_status))
             )
+            .service(
+                web::resource("/users/{id}/stat-decay")
+                    .route(web::get().to(scoring_handler::get_user_decay_events))
+            )
+            .service(
+                web::resource("/heart-rate-estimates/run")
+                    .route(web::post().to(user_handler::run_heart_rate_estimation))
+            )
             
             // Team management routes
             .service(
@@ -84,6 +93,13 @@ pub fn init_admin_routes(cfg: &mut web::ServiceConfig) {
                     .route(web::patch().to(league_handler::update_league_season))
                     .route(web::delete().to(league_handler::delete_league_season))
             )
+            .service(
+                web::resource("/leagues/{id}/seasons/{season_id}/scoring-rules")
+                    .route(web::get().to(scoring_handler::get_season_rules))
+                    .route(web::post().to(scoring_handler::create_season_rules))
+                    .route(web::put().to(scoring_handler::update_season_rules))
+                    .route(web::delete().to(scoring_handler::delete_season_rules))
+            )
             // Game management routes
             .service(
                 web::resource("/games/start-now")
@@ -112,5 +128,36 @@ pub fn init_admin_routes(cfg: &mut web::ServiceConfig) {
                     .route(web::get().to(workout_handler::get_workout_detail))
                     .route(web::delete().to(workout_handler::delete_workout))
             )
+            .service(
+                web::resource("/workouts/{id}/review")
+                    .route(web::post().to(workout_handler::review_workout))
+            )
+            // Scoring routes
+            .service(
+                web::resource("/scoring/caps")
+                    .route(web::get().to(scoring_handler::get_caps))
+                    .route(web::put().to(scoring_handler::update_caps))
+            )
+            .service(
+                web::resource("/scoring/decay")
+                    .route(web::get().to(scoring_handler::get_decay_settings))
+                    .route(web::put().to(scoring_handler::update_decay_settings))
+            )
+            .service(
+                web::resource("/scoring/decay/run")
+                    .route(web::post().to(scoring_handler::run_decay))
+            )
+            .service(
+                web::resource("/scoring/recompute")
+                    .route(web::post().to(scoring_handler::start_recompute))
+            )
+            .service(
+                web::resource("/scoring/recompute/{job_id}")
+                    .route(web::get().to(scoring_handler::get_recompute))
+            )
+            .service(
+                web::resource("/scoring/recompute/{job_id}/apply")
+                    .route(web::post().to(scoring_handler::apply_recompute))
+            )
     );
 }
\ No newline at end of file
@@ -1,16 +1,23 @@
 use actix_web::{web, get, post, HttpResponse};
 use sqlx::PgPool;
+use uuid::Uuid;
 use crate::middleware::auth::Claims;
-use crate::handlers::workout_data::activity::{get_activity_summary, get_zone_analysis};
+use crate::handlers::workout_data::activity::{get_activity_summary, get_zone_analysis, ActivitySummaryQuery};
 use crate::handlers::workout_data::workout_history::get_workout_history;
+use crate::handlers::workout_data::heart_rate_series::{get_heart_rate_series, HeartRateSeriesQuery};
+use crate::handlers::workout_data::training_load::{get_training_load_ratio, TrainingLoadQuery};
+use crate::handlers::workout_data::export_workouts::{
+    export_workout_history, export_workout, SingleWorkoutExportQuery, WorkoutExportQuery,
+};
 use crate::handlers::workout_data::check_workout_sync_status::{check_workout_sync_status, CheckSyncStatusRequest};
 
 #[get("/activity")]
 async fn get_activity_sum(
     pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>
+    claims: web::ReqData<Claims>,
+    query: web::Query<ActivitySummaryQuery>
 ) -> HttpResponse {
-    get_activity_summary(pool, claims).await
+    get_activity_summary(pool, claims, query).await
 }
 
 #[get("/zones")]
@@ -30,6 +37,44 @@ async fn get_workout_hist(
     get_workout_history(pool, claims, query).await
 }
 
+#[get("/workouts/{workout_id}/heart_rate")]
+async fn get_workout_heart_rate(
+    workout_id: web::Path<Uuid>,
+    query: web::Query<HeartRateSeriesQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_heart_rate_series(workout_id, query, pool, claims).await
+}
+
+#[get("/training_load")]
+async fn get_training_load(
+    query: web::Query<TrainingLoadQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_training_load_ratio(query, pool, claims).await
+}
+
+#[get("/export")]
+async fn export_workouts(
+    query: web::Query<WorkoutExportQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    export_workout_history(query, pool, claims).await
+}
+
+#[get("/workouts/{workout_id}/export")]
+async fn export_single_workout(
+    workout_id: web::Path<Uuid>,
+    query: web::Query<SingleWorkoutExportQuery>,
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    export_workout(workout_id, query, pool, claims).await
+}
+
 #[post("/check_sync_status")]
 async fn check_sync_status(
     pool: web::Data<PgPool>,
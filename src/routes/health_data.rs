@@ -1,17 +1,94 @@
-use actix_web::{post, web, HttpResponse};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse};
 use crate::handlers::workout_data::upload_workout_data::upload_workout_data;
+use crate::handlers::workout_data::upload_workout_data_batch::upload_workout_data_batch;
+use crate::handlers::workout_data::import_workout_file::{import_workout_file, WorkoutFileImportQuery};
+use crate::handlers::workout_data::retract_workout::retract_workout;
+use crate::handlers::workout_data::upload_session::{
+    finalize_upload_session_handler, start_upload_session, upload_session_samples,
+};
+use crate::handlers::workout_data::workout_job_status::get_workout_job_status;
 use crate::middleware::auth::Claims;
-use crate::models::workout_data::WorkoutDataSyncRequest;
+use crate::models::workout_data::{WorkoutDataSyncRequest, WorkoutDataBatchSyncRequest};
 use crate::services::live_game_service::LiveGameService;
-use std::sync::Arc;
+use crate::services::workout_queue_service::WorkoutQueueService;
+use uuid::Uuid;
 
 #[post("/upload_health")]
 async fn upload_health(
     data: web::Json<WorkoutDataSyncRequest>,
     pool: web::Data<sqlx::PgPool>,
-    redis: Option<web::Data<Arc<redis::Client>>>,
-    live_game_service: Option<web::Data<LiveGameService>>,
+    workout_queue: web::Data<WorkoutQueueService>,
     claims: web::ReqData<Claims>
 ) -> HttpResponse {
-    upload_workout_data(data, pool, redis, live_game_service, claims).await
+    upload_workout_data(data, pool, workout_queue, claims).await
+}
+
+#[get("/upload_jobs/{job_id}")]
+async fn upload_job_status(
+    job_id: web::Path<Uuid>,
+    pool: web::Data<sqlx::PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_workout_job_status(job_id, pool, claims).await
+}
+
+#[post("/sessions")]
+async fn start_session(
+    data: web::Json<WorkoutDataSyncRequest>,
+    pool: web::Data<sqlx::PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    start_upload_session(data, pool, claims).await
+}
+
+#[post("/sessions/{session_id}/samples")]
+async fn upload_session_chunk(
+    session_id: web::Path<Uuid>,
+    req: HttpRequest,
+    payload: web::Payload,
+    pool: web::Data<sqlx::PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    upload_session_samples(session_id, req, payload, pool, claims).await
+}
+
+#[post("/sessions/{session_id}/finalize")]
+async fn finalize_session(
+    session_id: web::Path<Uuid>,
+    pool: web::Data<sqlx::PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    finalize_upload_session_handler(session_id, pool, workout_queue, claims).await
+}
+
+// POST /health/upload_health_batch, registered with its body limit in `init_routes`
+pub async fn upload_health_batch(
+    data: web::Json<WorkoutDataBatchSyncRequest>,
+    pool: web::Data<sqlx::PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    upload_workout_data_batch(data, pool, workout_queue, claims).await
+}
+
+// POST /health/upload_workout_file, registered with its body limit in `init_routes`
+pub async fn upload_workout_file(
+    body: web::Bytes,
+    query: web::Query<WorkoutFileImportQuery>,
+    pool: web::Data<sqlx::PgPool>,
+    workout_queue: web::Data<WorkoutQueueService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    import_workout_file(body, query, pool, workout_queue, claims).await
+}
+
+#[delete("/workouts/{workout_id}")]
+async fn retract_user_workout(
+    workout_id: web::Path<Uuid>,
+    pool: web::Data<sqlx::PgPool>,
+    live_game_service: web::Data<LiveGameService>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    retract_workout(workout_id, pool, live_game_service, claims).await
 }
\ No newline at end of file
@@ -12,6 +12,8 @@ pub mod health_activity;
 pub mod admin;
 
 use crate::middleware::auth::AuthMiddleware;
+use crate::handlers::workout_data::import_workout_file::MAX_WORKOUT_FILE_SIZE;
+use crate::handlers::workout_data::upload_workout_data_batch::MAX_BATCH_BODY_SIZE;
 
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(registration::register)
@@ -27,9 +29,30 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/health")
             .wrap(AuthMiddleware)
             .service(health_data::upload_health)
+            .service(health_data::upload_job_status)
+            .service(health_data::start_session)
+            .service(health_data::upload_session_chunk)
+            .service(health_data::finalize_session)
+            .service(
+                web::resource("/upload_health_batch")
+                    // Batch uploads carry many workouts with full heart rate series
+                    .app_data(web::JsonConfig::default().limit(MAX_BATCH_BODY_SIZE))
+                    .route(web::post().to(health_data::upload_health_batch))
+            )
+            .service(
+                web::resource("/upload_workout_file")
+                    // GPX/TCX/FIT imports are sent as the raw file body
+                    .app_data(web::PayloadConfig::new(MAX_WORKOUT_FILE_SIZE))
+                    .route(web::post().to(health_data::upload_workout_file))
+            )
+            .service(health_data::retract_user_workout)
             .service(health_activity::get_activity_sum)
             .service(health_activity::get_zone_ana)
             .service(health_activity::get_workout_hist)
+            .service(health_activity::get_workout_heart_rate)
+            .service(health_activity::get_training_load)
+            .service(health_activity::export_workouts)
+            .service(health_activity::export_single_workout)
             .service(health_activity::check_sync_status)
     );
     // Profile routes (require authentication)
@@ -39,6 +62,13 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
             .service(profile::get_user)
             .service(profile::get_health_prof)
             .service(profile::update_health_prof)
+            .service(profile::get_hr_zones)
+            .service(profile::accept_hr_estimate)
+            .service(profile::dismiss_hr_estimate)
+            .service(profile::get_records)
+            .service(profile::get_challenges)
+            .service(profile::update_avatar_style)
+            .service(profile::update_timezone)
     );
     // League routes (require authentication)
     cfg.service(
@@ -1,9 +1,14 @@
-use actix_web::{web, get, put, HttpResponse};
+use actix_web::{web, get, post, put, HttpResponse};
 use sqlx::PgPool;
 use crate::handlers::profile::profile::get_user_profile;
 use crate::handlers::profile::health_profile::{get_health_profile, update_health_profile};
+use crate::handlers::profile::heart_rate_zones::{get_heart_rate_zones, review_heart_rate_estimate};
+use crate::handlers::profile::personal_records::get_user_personal_records;
+use crate::handlers::profile::daily_challenges::get_todays_challenges;
+use crate::handlers::profile::avatar::change_avatar_style;
+use crate::handlers::profile::timezone::change_timezone;
 use crate::middleware::auth::Claims;
-use crate::models::profile::UpdateHealthProfileRequest;
+use crate::models::profile::{UpdateAvatarStyleRequest, UpdateHealthProfileRequest, UpdateTimezoneRequest};
 
 #[get("/user")]
 async fn get_user(
@@ -28,4 +33,62 @@ async fn update_health_prof(
     data: web::Json<UpdateHealthProfileRequest>,
 ) -> HttpResponse {
     update_health_profile(pool, claims, data).await
-}
\ No newline at end of file
+}
+
+#[get("/heart_rate_zones")]
+async fn get_hr_zones(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_heart_rate_zones(pool, claims).await
+}
+
+#[post("/heart_rate_zones/estimate/accept")]
+async fn accept_hr_estimate(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    review_heart_rate_estimate(pool, claims, true).await
+}
+
+#[post("/heart_rate_zones/estimate/dismiss")]
+async fn dismiss_hr_estimate(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    review_heart_rate_estimate(pool, claims, false).await
+}
+
+#[get("/records")]
+async fn get_records(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_user_personal_records(pool, claims).await
+}
+
+#[get("/challenges")]
+async fn get_challenges(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>
+) -> HttpResponse {
+    get_todays_challenges(pool, claims).await
+}
+
+#[put("/avatar/style")]
+async fn update_avatar_style(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+    data: web::Json<UpdateAvatarStyleRequest>,
+) -> HttpResponse {
+    change_avatar_style(pool, claims, data).await
+}
+
+#[put("/timezone")]
+async fn update_timezone(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+    data: web::Json<UpdateTimezoneRequest>,
+) -> HttpResponse {
+    change_timezone(pool, claims, data).await
+}
@@ -346,6 +346,7 @@ impl GameConnection {
                             strength: 50,
                         },
                         position: crate::models::game_events::Position { x, y },
+                        progression: None,
                         timestamp: Utc::now(),
                     };
                     
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::avatar_progression::{get_total_experience, save_avatar_progression, SavedAvatarProgression};
+use crate::game::progression::avatar_progression;
+use crate::models::common::PlayerStats;
+use crate::models::game_events::{GameEvent, Position};
+use crate::models::profile::AvatarProgression;
+use crate::services::user_notifications::publish_user_event;
+
+/// Keeps avatars' experience, level, evolution stage and style in line with what their users earned.
+/// Level-ups are announced to everyone as an `AvatarUpdated` event.
+pub struct AvatarProgressionService {
+    pool: PgPool,
+    redis_client: Option<Arc<redis::Client>>,
+}
+
+impl AvatarProgressionService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self { pool, redis_client }
+    }
+
+    /// Recalculate the user's progression from their earned experience and store it.
+    /// Returns `None` if the user has no avatar.
+    pub async fn sync(&self, user_id: Uuid, username: &str) -> Result<Option<AvatarProgression>, sqlx::Error> {
+        let Some((progression, saved)) = Self::store(&mut *self.pool.acquire().await?, user_id, username).await? else {
+            return Ok(None);
+        };
+        self.announce(user_id, username, &progression, &saved).await;
+        Ok(Some(progression))
+    }
+
+    /// Recalculate and store the user's progression on `conn`, e.g. in the transaction that changed
+    /// their workout gains. Hand the result to `announce` once it's committed.
+    pub async fn store(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        username: &str,
+    ) -> Result<Option<(AvatarProgression, SavedAvatarProgression)>, sqlx::Error> {
+        let experience = get_total_experience(&mut *conn, user_id).await?;
+        let progression = avatar_progression(experience);
+        let Some(saved) = save_avatar_progression(conn, user_id, &progression).await? else {
+            return Ok(None);
+        };
+
+        if saved.avatar_style != saved.previous_avatar_style {
+            tracing::info!("🎨 {} dropped to level {}, which doesn't unlock the {} style, back to {}",
+                username, progression.level,
+                saved.previous_avatar_style.as_deref().unwrap_or_default(),
+                saved.avatar_style.as_deref().unwrap_or_default());
+        }
+
+        Ok(Some((progression, saved)))
+    }
+
+    /// Announce a stored progression if the user levelled up
+    pub async fn announce(&self, user_id: Uuid, username: &str, progression: &AvatarProgression, saved: &SavedAvatarProgression) {
+        if progression.level > saved.previous_level {
+            tracing::info!("⬆️ {} reached level {} ({})", username, progression.level, progression.evolution_stage.as_str());
+            let event = GameEvent::AvatarUpdated {
+                user_id,
+                username: username.to_string(),
+                stats: PlayerStats {
+                    stamina: saved.stamina,
+                    strength: saved.strength,
+                },
+                position: Position { x: 0.0, y: 0.0 },
+                progression: Some(progression.clone()),
+                timestamp: Utc::now(),
+            };
+            publish_user_event(self.redis_client.as_ref(), user_id, &event, true).await;
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use chrono::{NaiveDate, Utc};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::daily_challenges::{
+    assign_daily_challenges, complete_daily_challenge, get_accepted_workout_day, get_daily_challenges,
+    get_day_activity, get_user_today, get_users_due_challenges, reopen_daily_challenge, update_challenge_progress,
+};
+use crate::game::daily_challenges::{find_template, pick_templates};
+use crate::models::game_events::{GameEvent, NotificationType};
+use crate::models::profile::DailyChallenge;
+use crate::services::user_notifications::notify_user;
+
+/// Assigns each user a few challenges per day, in their time zone, and completes them as the day's
+/// workouts come in. Completions add their bonus to the avatar and are announced to the user.
+pub struct DailyChallengeService {
+    pool: PgPool,
+    redis_client: Option<Arc<redis::Client>>,
+}
+
+impl DailyChallengeService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self { pool, redis_client }
+    }
+
+    /// Hand out challenges to every user whose day has started without any, a failure for one
+    /// user doesn't stop the others. Returns how many users got their challenges.
+    pub async fn assign_due_challenges(&self) -> Result<usize, sqlx::Error> {
+        let mut assigned = 0;
+        for (user_id, day) in get_users_due_challenges(&self.pool).await? {
+            match assign_daily_challenges(&self.pool, user_id, day, &pick_templates(user_id, day)).await {
+                Ok(()) => assigned += 1,
+                Err(e) => tracing::error!("❌ Failed to assign daily challenges to user {}: {}", user_id, e),
+            }
+        }
+        Ok(assigned)
+    }
+
+    /// The user's current day and its challenges
+    pub async fn todays_challenges(&self, user_id: Uuid) -> Result<(NaiveDate, Vec<DailyChallenge>), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let today = get_user_today(&mut conn, user_id).await?;
+        let challenges = get_daily_challenges(&mut conn, user_id, today).await?;
+        Ok((today, challenges))
+    }
+
+    /// Re-evaluate today's challenges after an accepted workout. Workouts on earlier days don't count,
+    /// those challenges are over. Returns the challenges the workout completed.
+    pub async fn evaluate_workout(&self, workout_id: Uuid) -> Result<Vec<DailyChallenge>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let Some(workout_day) = get_accepted_workout_day(&mut conn, workout_id).await? else {
+            return Ok(Vec::new());
+        };
+        if workout_day.day != workout_day.today {
+            return Ok(Vec::new());
+        }
+        let (user_id, day) = (workout_day.user_id, workout_day.day);
+
+        let challenges = get_daily_challenges(&mut conn, user_id, day).await?;
+        if challenges.is_empty() {
+            return Ok(Vec::new());
+        }
+        let activity = get_day_activity(&mut conn, user_id, day).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut completed = Vec::new();
+        for challenge in challenges.iter().filter(|challenge| challenge.completed_at.is_none()) {
+            let Some(template) = find_template(&challenge.template_key) else {
+                tracing::warn!("⚠️ Daily challenge {} has unknown template {}", challenge.id, challenge.template_key);
+                continue;
+            };
+
+            let progress = template.metric.progress(&activity);
+            if progress >= challenge.target {
+                if let Some(challenge) = complete_daily_challenge(&mut tx, challenge.id, progress, workout_id).await? {
+                    completed.push(challenge);
+                }
+            } else if progress != challenge.progress {
+                update_challenge_progress(&mut tx, challenge.id, progress).await?;
+            }
+        }
+        tx.commit().await?;
+
+        for challenge in &completed {
+            tracing::info!("🎯 User {} completed daily challenge {}: +{} stamina, +{} strength",
+                user_id, challenge.template_key, challenge.stamina_bonus, challenge.strength_bonus);
+            self.announce(challenge).await;
+        }
+
+        Ok(completed)
+    }
+
+    /// Re-evaluate a day's challenges against the workouts left after one was retracted or replaced,
+    /// on the caller's transaction. Completed challenges the day no longer reaches are reopened and
+    /// their bonus taken back, the others keep their completion.
+    pub async fn reevaluate_day(conn: &mut PgConnection, user_id: Uuid, day: NaiveDate) -> Result<(), sqlx::Error> {
+        let challenges = get_daily_challenges(&mut *conn, user_id, day).await?;
+        if challenges.is_empty() {
+            return Ok(());
+        }
+        let activity = get_day_activity(&mut *conn, user_id, day).await?;
+
+        for challenge in &challenges {
+            let Some(template) = find_template(&challenge.template_key) else {
+                continue;
+            };
+
+            let progress = template.metric.progress(&activity);
+            if challenge.completed_at.is_none() {
+                if progress != challenge.progress {
+                    update_challenge_progress(&mut *conn, challenge.id, progress).await?;
+                }
+            } else if progress < challenge.target {
+                if let Some(reopened) = reopen_daily_challenge(&mut *conn, challenge.id, progress).await? {
+                    tracing::info!("↩️ Reopened daily challenge {} of user {}: -{} stamina, -{} strength",
+                        reopened.template_key, user_id, reopened.stamina_bonus, reopened.strength_bonus);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn announce(&self, challenge: &DailyChallenge) {
+        let notification = GameEvent::Notification {
+            notification_id: Uuid::new_v4(),
+            user_id: challenge.user_id,
+            title: format!("Daily challenge complete: {}", challenge.title),
+            message: format!(
+                "{} done! Bonus: +{} stamina, +{} strength",
+                challenge.description, challenge.stamina_bonus, challenge.strength_bonus
+            ),
+            notification_type: NotificationType::DailyChallenge,
+            action_url: Some("/profile/challenges".to_string()),
+            created_at: Utc::now(),
+        };
+        notify_user(self.redis_client.as_ref(), &notification).await;
+    }
+}
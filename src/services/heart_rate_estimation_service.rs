@@ -0,0 +1,106 @@
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::heart_rate_estimates::{
+    apply_heart_rate_estimate, get_auto_apply_heart_rate_estimates, get_heart_rate_summaries,
+    get_latest_heart_rate_estimate, insert_heart_rate_estimate, resolve_heart_rate_estimate,
+};
+use crate::game::heart_rate_estimator::{estimate_heart_rates, WorkoutHeartRateSummary};
+use crate::game::helper::{calc_max_heart_rate, get_user_profile};
+use crate::models::profile::{HeartRateEstimate, HeartRateEstimateStatus};
+use crate::models::workout_data::HeartRateZones;
+
+/// Only workouts this recent are used, so the estimate follows the user's fitness
+const ESTIMATION_WINDOW_DAYS: i64 = 90;
+
+/// What an estimation run did
+#[derive(Debug, Default, Serialize)]
+pub struct HeartRateEstimationSummary {
+    pub users_checked: usize,
+    pub proposed: usize,
+    pub applied: usize,
+}
+
+/// Estimates max and resting heart rate from recent workouts and proposes zones built on them,
+/// or applies them right away for users who opted in
+pub struct HeartRateEstimationService {
+    pool: PgPool,
+}
+
+impl HeartRateEstimationService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Estimate for every user with recent workouts. A failure for one user doesn't stop the others.
+    pub async fn run(&self) -> Result<HeartRateEstimationSummary, sqlx::Error> {
+        let since = Utc::now() - Duration::days(ESTIMATION_WINDOW_DAYS);
+        let summaries = get_heart_rate_summaries(&self.pool, since).await?;
+
+        let mut summary = HeartRateEstimationSummary::default();
+        for (user_id, workouts) in summaries {
+            summary.users_checked += 1;
+            match self.estimate_for_user(user_id, &workouts).await {
+                Ok(Some(estimate)) if estimate.status == HeartRateEstimateStatus::Applied.as_str() => summary.applied += 1,
+                Ok(Some(_)) => summary.proposed += 1,
+                Ok(None) => {}
+                Err(e) => tracing::error!("❌ Failed to estimate heart rates for user {}: {}", user_id, e),
+            }
+        }
+
+        tracing::info!("💓 Heart rate estimation checked {} users: {} proposed, {} applied",
+            summary.users_checked, summary.proposed, summary.applied);
+        Ok(summary)
+    }
+
+    async fn estimate_for_user(
+        &self,
+        user_id: Uuid,
+        workouts: &[WorkoutHeartRateSummary],
+    ) -> Result<Option<HeartRateEstimate>, sqlx::Error> {
+        let profile = get_user_profile(&mut *self.pool.acquire().await?, user_id).await?;
+        let current_max = profile.max_heart_rate
+            .unwrap_or_else(|| calc_max_heart_rate(profile.age, profile.gender));
+        let current_resting = profile.resting_heart_rate.unwrap_or(60);
+
+        let Some(estimated) = estimate_heart_rates(workouts, current_resting, current_max) else {
+            return Ok(None);
+        };
+
+        // Don't propose again what the user is already looking at or turned down
+        if let Some(latest) = get_latest_heart_rate_estimate(&self.pool, user_id).await? {
+            let same_values = latest.resting_heart_rate == estimated.resting_heart_rate
+                && latest.max_heart_rate == estimated.max_heart_rate;
+            let undecided_or_dismissed = latest.status == HeartRateEstimateStatus::Proposed.as_str()
+                || latest.status == HeartRateEstimateStatus::Dismissed.as_str();
+            if same_values && undecided_or_dismissed {
+                return Ok(None);
+            }
+        }
+
+        let zones = HeartRateZones::new(
+            estimated.max_heart_rate - estimated.resting_heart_rate,
+            estimated.resting_heart_rate,
+            estimated.max_heart_rate,
+        );
+        let auto_apply = get_auto_apply_heart_rate_estimates(&self.pool, user_id).await?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut estimate = insert_heart_rate_estimate(
+            &mut tx, user_id, &estimated, current_resting, current_max, zones.zone_maxes(),
+        ).await?;
+        if auto_apply {
+            resolve_heart_rate_estimate(&mut tx, estimate.id, HeartRateEstimateStatus::Applied).await?;
+            apply_heart_rate_estimate(&mut tx, &estimate).await?;
+            estimate.status = HeartRateEstimateStatus::Applied.as_str().to_string();
+        }
+        tx.commit().await?;
+
+        tracing::info!("💓 Heart rate estimate {} for user {}: max {} -> {}, resting {} -> {} ({})",
+            estimate.id, user_id, current_max, estimate.max_heart_rate,
+            current_resting, estimate.resting_heart_rate, estimate.status);
+        Ok(Some(estimate))
+    }
+}
@@ -1,12 +1,13 @@
 use chrono::Utc;
-use sqlx::{PgPool, Row};
+use sqlx::{PgConnection, PgPool, Row};
 use uuid::Uuid;
 use tracing::{info, error, debug, warn};
 use std::sync::Arc;
 
 use crate::models::live_game::{LiveGame, LiveGameScoreUpdate, LiveGameResponse};
+use crate::models::game::ScoringRules;
 use crate::models::game_events::GameEvent;
-use crate::db::live_game_queries::LiveGameQueries;
+use crate::db::live_game_queries::{LiveGameQueries, RescorableScoreEvent};
 use crate::services::game_evaluation_service::GameEvaluationService;
 use redis::AsyncCommands;
 
@@ -130,25 +131,44 @@ impl LiveGameService {
     ) -> Result<LiveGame, Box<dyn std::error::Error>> {
         debug!("Handling score update for game {} from user {}", game_id, update.username);
 
-        // Get or create live game
-        let live_game = match self.live_game_queries.get_live_game_by_game_id(game_id).await? {
-            Some(game) => game,
+        let live_game = self.get_or_initialize_live_game(game_id).await?;
+
+        // Update the live game score
+        let mut tx = self.pool.begin().await?;
+        let updated_game = self.apply_score_update(&mut tx, live_game.id, &update).await?;
+        tx.commit().await?;
+
+        // Broadcast the score update
+        self.broadcast_live_score_update(&updated_game).await?;
+
+        Ok(updated_game)
+    }
+
+    /// Get the live game for a league game, creating it if it doesn't exist yet
+    pub async fn get_or_initialize_live_game(&self, game_id: Uuid) -> Result<LiveGame, sqlx::Error> {
+        match self.live_game_queries.get_live_game_by_game_id(game_id).await? {
+            Some(game) => Ok(game),
             None => {
                 info!("Live game doesn't exist for game {}, creating it", game_id);
-                self.initialize_live_game(game_id).await?
+                self.initialize_live_game(game_id).await
             }
-        };
+        }
+    }
 
-        // Update the live game score
+    /// Apply a score update on the caller's connection without broadcasting it.
+    /// Callers are expected to broadcast via `broadcast_live_score_update` once their transaction commits.
+    pub async fn apply_score_update(
+        &self,
+        conn: &mut PgConnection,
+        live_game_id: Uuid,
+        update: &LiveGameScoreUpdate,
+    ) -> Result<LiveGame, sqlx::Error> {
         let updated_game = self.live_game_queries
-            .update_live_game_score(live_game.id, &update)
+            .update_live_game_score(conn, live_game_id, update)
             .await?;
 
-        // Broadcast the score update
-        self.broadcast_live_score_update(&updated_game).await?;
-
         info!("Score updated for game {}: {} {} - {} {} (Player: {} +{})", 
-            game_id,
+            updated_game.game_id,
             updated_game.home_team_name,
             updated_game.home_score,
             updated_game.away_score,
@@ -160,8 +180,36 @@ impl LiveGameService {
         Ok(updated_game)
     }
 
+    /// Reverse the live score contributions of a retracted workout on the caller's connection.
+    /// Callers broadcast the returned games once their transaction commits.
+    pub async fn revert_workout_scores(
+        conn: &mut PgConnection,
+        workout_data_id: Uuid,
+    ) -> Result<Vec<LiveGame>, sqlx::Error> {
+        LiveGameQueries::revert_workout_scores(conn, workout_data_id).await
+    }
+
+    /// Workout score events in games that were not evaluated yet, locked on the caller's transaction
+    pub async fn get_rescorable_score_events(
+        &self,
+        conn: &mut PgConnection,
+    ) -> Result<Vec<RescorableScoreEvent>, sqlx::Error> {
+        self.live_game_queries.get_rescorable_score_events(conn).await
+    }
+
+    /// Rescore a workout's score event on the caller's connection.
+    /// Callers broadcast the returned game once their transaction commits.
+    pub async fn rescore_event(
+        &self,
+        conn: &mut PgConnection,
+        event: &RescorableScoreEvent,
+        update: &LiveGameScoreUpdate,
+    ) -> Result<LiveGame, sqlx::Error> {
+        self.live_game_queries.rescore_event(conn, event, update).await
+    }
+
     /// Broadcast live score update to WebSocket clients
-    async fn broadcast_live_score_update(
+    pub async fn broadcast_live_score_update(
         &self,
         live_game: &LiveGame,
     ) -> Result<(), Box<dyn std::error::Error>> {
@@ -233,8 +281,8 @@ impl LiveGameService {
         Ok(())
     }
 
-    /// Check if a user is participating in any active live games
-    pub async fn get_user_active_games(&self, user_id: Uuid) -> Result<Vec<LiveGame>, Box<dyn std::error::Error>> {
+    /// Check if a user is participating in any active live games, on the caller's connection
+    pub async fn get_user_active_games(&self, conn: &mut PgConnection, user_id: Uuid) -> Result<Vec<LiveGame>, sqlx::Error> {
         let games = sqlx::query_as!(
             LiveGame,
             r#"
@@ -252,17 +300,15 @@ impl LiveGameService {
             "#,
             user_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(conn)
         .await?;
 
         Ok(games)
     }
 
-    /// Calculate score increase from stat changes
-    pub fn calculate_score_from_stats(&self, stamina_gained: i32, strength_gained: i32) -> i32 {
-        // Simple scoring: each stat point = 1 score point
-        // Could be made more sophisticated with different weights
-        stamina_gained + strength_gained
+    /// Calculate score increase from stat changes, weighted by the rules of the game's season
+    pub fn calculate_score_from_stats(&self, rules: &ScoringRules, stamina_gained: i32, strength_gained: i32) -> i32 {
+        rules.score_from_stats(stamina_gained, strength_gained)
     }
 
     /// Calculate power increase from stat changes
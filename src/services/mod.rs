@@ -2,8 +2,23 @@ pub mod game_evaluation_service;
 pub mod scheduler;
 pub mod manage_game_service;
 pub mod live_game_service;
+pub mod workout_queue_service;
+pub mod stat_recompute_service;
+pub mod heart_rate_estimation_service;
+pub mod personal_record_service;
+pub mod daily_challenge_service;
+pub mod stat_decay_service;
+pub mod avatar_progression_service;
+pub mod user_notifications;
 
 pub use game_evaluation_service::GameEvaluationService;
 pub use scheduler::SchedulerService;
 pub use manage_game_service::ManageGameService;
-pub use live_game_service::LiveGameService;
\ No newline at end of file
+pub use live_game_service::LiveGameService;
+pub use workout_queue_service::WorkoutQueueService;
+pub use stat_recompute_service::StatRecomputeService;
+pub use heart_rate_estimation_service::HeartRateEstimationService;
+pub use personal_record_service::PersonalRecordService;
+pub use daily_challenge_service::DailyChallengeService;
+pub use stat_decay_service::StatDecayService;
+pub use avatar_progression_service::AvatarProgressionService;
\ No newline at end of file
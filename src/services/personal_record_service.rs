@@ -0,0 +1,175 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::personal_records::{
+    delete_personal_record, delete_unreached_milestones, get_milestone_totals, get_personal_records,
+    get_user_record_metrics, get_workout_days, get_workout_record_metrics, replace_personal_record,
+    save_milestones, save_personal_record, WorkoutRecordMetrics,
+};
+use crate::game::personal_records::{daily_streak, weekly_streak, MilestoneType, RecordType};
+use crate::models::game_events::{GameEvent, NotificationType};
+use crate::models::profile::PersonalRecord;
+use crate::services::user_notifications::notify_user;
+
+/// Keeps users' personal records, training streaks and milestones up to date as workouts are
+/// accepted and retracted, and announces new records and milestones to the user
+pub struct PersonalRecordService {
+    pool: PgPool,
+    redis_client: Option<Arc<redis::Client>>,
+}
+
+impl PersonalRecordService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self { pool, redis_client }
+    }
+
+    /// Compare an accepted workout against the user's records and store any it beats.
+    /// The first value of a record is stored quietly, only beating an earlier record is announced.
+    pub async fn check_workout(&self, workout_id: Uuid) -> Result<Vec<PersonalRecord>, sqlx::Error> {
+        let Some(metrics) = get_workout_record_metrics(&self.pool, workout_id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut conn = self.pool.acquire().await?;
+        let workout_days = get_workout_days(&mut conn, metrics.user_id).await?;
+        let today = Utc::now().date_naive();
+
+        let mut new_records = Vec::new();
+        for record_type in RecordType::ALL {
+            let value = match record_type {
+                RecordType::LongestDailyStreak => daily_streak(&workout_days, today).longest as f64,
+                RecordType::LongestWeeklyStreak => weekly_streak(&workout_days, today).longest as f64,
+                _ => workout_value(record_type, &metrics),
+            };
+            if value <= 0.0 {
+                continue;
+            }
+            let Some(record) = save_personal_record(&self.pool, metrics.user_id, record_type, value, workout_id).await? else {
+                continue;
+            };
+            if let Some(previous_value) = record.previous_value {
+                tracing::info!("🏅 New {} record for user {}: {} (was {})",
+                    record_type.as_str(), metrics.user_id, value, previous_value);
+                self.announce(
+                    metrics.user_id,
+                    format!("New personal record: {}", record_type.title()),
+                    format!(
+                        "{}, beating your previous best of {}",
+                        record_type.format_value(value),
+                        record_type.format_value(previous_value)
+                    ),
+                ).await;
+            }
+            new_records.push(record);
+        }
+
+        self.check_milestones(&mut conn, metrics.user_id, workout_id).await?;
+
+        Ok(new_records)
+    }
+
+    /// Store the milestones the user's totals now reach and announce the highest new one of each kind
+    async fn check_milestones(&self, conn: &mut PgConnection, user_id: Uuid, workout_id: Uuid) -> Result<(), sqlx::Error> {
+        let totals = get_milestone_totals(&mut *conn, user_id).await?;
+        for milestone_type in MilestoneType::ALL {
+            let total = totals.total(milestone_type);
+            let reached: Vec<i64> = milestone_type.thresholds().iter()
+                .copied()
+                .filter(|threshold| *threshold as f64 <= total)
+                .collect();
+            if reached.is_empty() {
+                continue;
+            }
+
+            let new_thresholds = save_milestones(&mut *conn, user_id, milestone_type, &reached, workout_id).await?;
+            if let Some(threshold) = new_thresholds.into_iter().max() {
+                tracing::info!("🎯 User {} reached the {} milestone of {}", user_id, milestone_type.as_str(), threshold);
+                self.announce(
+                    user_id,
+                    "Milestone reached".to_string(),
+                    format!("{} and counting", milestone_type.title(threshold)),
+                ).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuild the user's records from their remaining accepted workouts and drop milestones
+    /// they no longer reach, after a workout was retracted or replaced. Runs on the caller's
+    /// transaction and announces nothing.
+    pub async fn recompute_user(conn: &mut PgConnection, user_id: Uuid) -> Result<(), sqlx::Error> {
+        let workouts = get_user_record_metrics(&mut *conn, user_id).await?;
+        let workout_days = get_workout_days(&mut *conn, user_id).await?;
+        let current = get_personal_records(&mut *conn, user_id).await?;
+        let now = Utc::now();
+
+        for record_type in RecordType::ALL {
+            // The earliest workout reaching the best value holds the record
+            let best = match record_type {
+                RecordType::LongestDailyStreak => Some((daily_streak(&workout_days, now.date_naive()).longest as f64, None)),
+                RecordType::LongestWeeklyStreak => Some((weekly_streak(&workout_days, now.date_naive()).longest as f64, None)),
+                _ => workouts.iter()
+                    .map(|workout| (workout_value(record_type, workout), Some(workout)))
+                    .reduce(|best, candidate| if candidate.0 > best.0 { candidate } else { best }),
+            }
+            .filter(|(value, _)| *value > 0.0);
+
+            let existing = current.iter().find(|record| record.record_type == record_type.as_str());
+            match (best, existing) {
+                (None, None) => {}
+                (None, Some(_)) => delete_personal_record(&mut *conn, user_id, record_type).await?,
+                // Still held by a workout the user kept, or a streak of the same length
+                (Some((value, _)), Some(record))
+                    if record.value == value && (record.workout_data_id.is_some() || is_streak(record_type)) => {}
+                (Some((value, workout)), _) => {
+                    replace_personal_record(
+                        &mut *conn,
+                        user_id,
+                        record_type,
+                        value,
+                        workout.map(|workout| workout.workout_id),
+                        workout.map(|workout| workout.started_at).unwrap_or(now),
+                    ).await?;
+                }
+            }
+        }
+
+        let totals = get_milestone_totals(&mut *conn, user_id).await?;
+        for milestone_type in MilestoneType::ALL {
+            delete_unreached_milestones(&mut *conn, user_id, milestone_type, totals.total(milestone_type)).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send the user an achievement notification
+    async fn announce(&self, user_id: Uuid, title: String, message: String) {
+        let notification = GameEvent::Notification {
+            notification_id: Uuid::new_v4(),
+            user_id,
+            title,
+            message,
+            notification_type: NotificationType::Achievement,
+            action_url: Some("/profile/records".to_string()),
+            created_at: Utc::now(),
+        };
+        notify_user(self.redis_client.as_ref(), &notification).await;
+    }
+}
+
+/// A single workout's value for a record, zero for streaks
+fn workout_value(record_type: RecordType, metrics: &WorkoutRecordMetrics) -> f64 {
+    match record_type {
+        RecordType::LongestWorkout => metrics.duration_minutes,
+        RecordType::MostHighZoneMinutes => metrics.high_zone_minutes,
+        RecordType::HighestWorkoutPoints => metrics.points,
+        RecordType::LongestDailyStreak | RecordType::LongestWeeklyStreak => 0.0,
+    }
+}
+
+fn is_streak(record_type: RecordType) -> bool {
+    matches!(record_type, RecordType::LongestDailyStreak | RecordType::LongestWeeklyStreak)
+}
@@ -7,6 +7,21 @@ use uuid::Uuid;
 use std::error::Error;
 use crate::services::game_evaluation_service::GameEvaluationService;
 use crate::services::manage_game_service::ManageGameService;
+use crate::services::heart_rate_estimation_service::HeartRateEstimationService;
+use crate::services::stat_decay_service::StatDecayService;
+use crate::services::daily_challenge_service::DailyChallengeService;
+use crate::db::workout_upload_sessions::delete_abandoned_upload_sessions;
+
+/// Heart rates are estimated from workout history once a day, at 04:00 UTC
+const HEART_RATE_ESTIMATION_CRON: &str = "0 0 4 * * *";
+/// Inactive users are warned and their stats decayed once a day, at 04:30 UTC
+const STAT_DECAY_CRON: &str = "0 30 4 * * *";
+/// Daily challenges are handed out every 15 minutes, so each user gets theirs soon after their day starts
+const DAILY_CHALLENGE_CRON: &str = "0 */15 * * * *";
+/// Abandoned upload sessions are cleaned up every hour
+const UPLOAD_SESSION_CLEANUP_CRON: &str = "0 15 * * * *";
+/// An upload session untouched for this long, and without a job waiting for its samples, is abandoned
+const UPLOAD_SESSION_IDLE_HOURS: i32 = 24;
 
 pub struct SchedulerService {
     scheduler: Arc<Mutex<JobScheduler>>,
@@ -31,6 +46,56 @@ impl SchedulerService {
     pub async fn start(&self) -> Result<(), Box<dyn Error>> {
         let scheduler = self.scheduler.lock().await;
         
+        let pool = self.pool.clone();
+        let heart_rate_estimation_job = Job::new_async(HEART_RATE_ESTIMATION_CRON, move |_uuid, _l| {
+            let estimation = HeartRateEstimationService::new(pool.clone());
+            Box::pin(async move {
+                if let Err(e) = estimation.run().await {
+                    tracing::error!("❌ Heart rate estimation failed: {}", e);
+                }
+            })
+        })?;
+        scheduler.add(heart_rate_estimation_job).await?;
+
+        let pool = self.pool.clone();
+        let redis_client = self.redis_client.clone();
+        let stat_decay_job = Job::new_async(STAT_DECAY_CRON, move |_uuid, _l| {
+            let decay = StatDecayService::new(pool.clone(), redis_client.clone());
+            Box::pin(async move {
+                if let Err(e) = decay.run().await {
+                    tracing::error!("❌ Stat decay failed: {}", e);
+                }
+            })
+        })?;
+        scheduler.add(stat_decay_job).await?;
+
+        let pool = self.pool.clone();
+        let redis_client = self.redis_client.clone();
+        let daily_challenge_job = Job::new_async(DAILY_CHALLENGE_CRON, move |_uuid, _l| {
+            let challenges = DailyChallengeService::new(pool.clone(), redis_client.clone());
+            Box::pin(async move {
+                match challenges.assign_due_challenges().await {
+                    Ok(0) => {}
+                    Ok(assigned) => tracing::info!("🎯 Assigned daily challenges to {} users", assigned),
+                    Err(e) => tracing::error!("❌ Daily challenge assignment failed: {}", e),
+                }
+            })
+        })?;
+        scheduler.add(daily_challenge_job).await?;
+
+        let pool = self.pool.clone();
+        let upload_session_cleanup_job = Job::new_async(UPLOAD_SESSION_CLEANUP_CRON, move |_uuid, _l| {
+            let pool = pool.clone();
+            Box::pin(async move {
+                match delete_abandoned_upload_sessions(&pool, UPLOAD_SESSION_IDLE_HOURS).await {
+                    Ok(0) => {}
+                    Ok(deleted) => tracing::info!("🧹 Deleted {} abandoned upload sessions", deleted),
+                    Err(e) => tracing::error!("❌ Upload session cleanup failed: {}", e),
+                }
+            })
+        })?;
+        scheduler.add(upload_session_cleanup_job).await?;
+
         // For now, just start the scheduler without loading from DB
         // Seasons will be scheduled when created via the API
         scheduler.start().await?;
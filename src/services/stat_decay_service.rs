@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db::stat_decay::{apply_stat_decay, get_inactive_users, get_stat_decay_settings, mark_decay_warned, InactiveUser};
+use crate::models::game::StatDecaySettings;
+use crate::models::game_events::{GameEvent, NotificationType};
+use crate::services::user_notifications::notify_user;
+
+/// What a decay run did
+#[derive(Debug, Default, Serialize)]
+pub struct StatDecaySummary {
+    pub users_checked: usize,
+    pub warned: usize,
+    pub decayed: usize,
+    pub stamina_removed: i32,
+    pub strength_removed: i32,
+}
+
+/// Lets inactive users' stats decay towards a floor, so long-gone members stop inflating their team's power.
+/// Users are always warned before their first decay, and every decay is recorded.
+pub struct StatDecayService {
+    pool: PgPool,
+    redis_client: Option<Arc<redis::Client>>,
+}
+
+impl StatDecayService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self { pool, redis_client }
+    }
+
+    /// Warn users about to decay and decay those past their idle period, at most once a day each.
+    /// A failure for one user doesn't stop the others.
+    pub async fn run(&self) -> Result<StatDecaySummary, sqlx::Error> {
+        let settings = get_stat_decay_settings(&mut *self.pool.acquire().await?).await?;
+        let mut summary = StatDecaySummary::default();
+        if !settings.enabled {
+            tracing::info!("⏸️ Stat decay is disabled");
+            return Ok(summary);
+        }
+
+        let now = Utc::now();
+        let warn_after_days = (settings.idle_days - settings.warning_days).max(0) as i64;
+        let inactive_users = get_inactive_users(&self.pool, now - Duration::days(warn_after_days)).await?;
+
+        for user in inactive_users {
+            summary.users_checked += 1;
+            if let Err(e) = self.process_user(&user, &settings, now, &mut summary).await {
+                tracing::error!("❌ Failed to decay stats for user {}: {}", user.user_id, e);
+            }
+        }
+
+        tracing::info!("📉 Stat decay checked {} users: {} warned, {} decayed (-{} stamina, -{} strength)",
+            summary.users_checked, summary.warned, summary.decayed, summary.stamina_removed, summary.strength_removed);
+        Ok(summary)
+    }
+
+    async fn process_user(
+        &self,
+        user: &InactiveUser,
+        settings: &StatDecaySettings,
+        now: DateTime<Utc>,
+        summary: &mut StatDecaySummary,
+    ) -> Result<(), sqlx::Error> {
+        let decay_starts_at = user.last_active_at + Duration::days(settings.idle_days as i64);
+
+        // Nobody decays without having been warned since they last trained
+        let warned = user.decay_warned_at.is_some_and(|warned_at| warned_at > user.last_active_at);
+        if !warned {
+            mark_decay_warned(&self.pool, user.user_id).await?;
+            self.warn(user, decay_starts_at, now).await;
+            summary.warned += 1;
+            return Ok(());
+        }
+
+        let decayed_today = user.last_decayed_at.is_some_and(|decayed_at| decayed_at.date_naive() == now.date_naive());
+        if now < decay_starts_at || decayed_today {
+            return Ok(());
+        }
+
+        if let Some(event) = apply_stat_decay(&self.pool, user.user_id, user.last_active_at, settings).await? {
+            tracing::info!("📉 Decayed stats of user {}: {} stamina, {} strength",
+                user.user_id, event.stamina_change, event.strength_change);
+            summary.decayed += 1;
+            summary.stamina_removed -= event.stamina_change;
+            summary.strength_removed -= event.strength_change;
+        }
+        Ok(())
+    }
+
+    async fn warn(&self, user: &InactiveUser, decay_starts_at: DateTime<Utc>, now: DateTime<Utc>) {
+        let idle_days = (now - user.last_active_at).num_days();
+        // Decay never starts before the next run after the warning
+        let days_left = (decay_starts_at - now).num_days().max(1);
+        let notification = GameEvent::Notification {
+            notification_id: Uuid::new_v4(),
+            user_id: user.user_id,
+            title: "Your avatar misses you".to_string(),
+            message: format!(
+                "You haven't trained in {} days. Work out within {} day(s) to keep your stamina and strength from decaying.",
+                idle_days, days_left
+            ),
+            notification_type: NotificationType::System,
+            action_url: None,
+            created_at: now,
+        };
+        notify_user(self.redis_client.as_ref(), &notification).await;
+    }
+}
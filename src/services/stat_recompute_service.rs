@@ -0,0 +1,523 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db::scoring_rules::{get_game_scoring_rules, get_scoring_game_ids, get_user_scoring_rules};
+use crate::db::stat_gains::{add_daily_stat_gains, clear_daily_stat_gains, get_stat_caps, DailyStatGains};
+use crate::db::stat_recompute_jobs::{
+    fail_interrupted_stat_recompute_jobs, fail_stat_recompute_job, finish_stat_recompute_job, get_reviewed_dry_run,
+    start_stat_recompute_job, touch_stat_recompute_job,
+};
+use crate::db::workout_data::{
+    accepted_workouts_fingerprint, get_accepted_workout_ids, get_scored_workout, get_users_with_accepted_workouts,
+    update_workout_stats,
+};
+use crate::game::stat_caps::cap_stat_changes;
+use crate::game::stats_calculator::{StatCalculator, StatChanges};
+use crate::handlers::workout_data::upload_workout_data::{build_live_game_score_update, workout_day};
+use crate::models::game::{
+    LiveGameScoreDiff, ScoringRules, StatRecomputeMode, StatRecomputeReport, UserStatDiff,
+};
+use crate::models::live_game::{LiveGame, LiveGameScoreUpdate};
+use crate::services::avatar_progression_service::AvatarProgressionService;
+use crate::services::live_game_service::LiveGameService;
+
+/// A job whose heartbeat is older than this belongs to an instance that stopped and is failed
+const STALE_JOB_SECONDS: f64 = 300.0;
+/// Running jobs refresh their heartbeat this often, well within `STALE_JOB_SECONDS`
+const JOB_HEARTBEAT_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+/// A workout scored again with the current scoring, next to what it was credited before
+struct ReplayedWorkout {
+    id: Uuid,
+    user_id: Uuid,
+    stamina_before: i32,
+    strength_before: i32,
+    changes: StatChanges,
+    /// The workout scored with the rules of games whose season doesn't share the avatar's rules
+    game_changes: HashMap<Uuid, StatChanges>,
+}
+
+impl ReplayedWorkout {
+    fn changed(&self) -> bool {
+        self.changes.stamina_change != self.stamina_before || self.changes.strength_change != self.strength_before
+    }
+}
+
+/// Every accepted workout scored again, with the daily gains the stat caps were applied against
+#[derive(Default)]
+struct Replay {
+    usernames: HashMap<Uuid, String>,
+    workouts: Vec<ReplayedWorkout>,
+    daily_gains: Vec<(Uuid, NaiveDate, DailyStatGains)>,
+    rules_by_game: HashMap<Uuid, ScoringRules>,
+}
+
+/// The writes applying a dry run makes, stored with it so the apply stores what was reviewed
+#[derive(Default, Serialize, Deserialize)]
+struct RecomputePlan {
+    /// `accepted_workouts_fingerprint` before the replay. The plan is stale once it changes.
+    workouts_fingerprint: String,
+    /// Workouts whose credited stats change
+    workouts: Vec<PlannedWorkout>,
+    /// Every user's daily gains, rebuilt from scratch
+    daily_gains: Vec<PlannedDailyGains>,
+    score_events: Vec<PlannedScoreEvent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlannedWorkout {
+    id: Uuid,
+    changes: StatChanges,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PlannedDailyGains {
+    user_id: Uuid,
+    day: NaiveDate,
+    zone_minutes: [f32; 5],
+    stamina_gained: i32,
+    strength_gained: i32,
+}
+
+/// A live score event rescored, with what it counted for when the dry run saw it
+#[derive(Serialize, Deserialize)]
+struct PlannedScoreEvent {
+    event_id: Uuid,
+    score_points_before: i32,
+    power_contribution_before: i32,
+    update: LiveGameScoreUpdate,
+}
+
+/// Replays every stored workout through the stat calculator after scoring changes.
+/// A dry run reports what would change per user and keeps the writes that make it happen,
+/// applying one stores those writes: workout gains, daily gains, avatar totals and progression, and optionally
+/// the scores of live games that were not evaluated yet.
+pub struct StatRecomputeService {
+    pool: PgPool,
+    live_game_service: LiveGameService,
+    avatar_progression: AvatarProgressionService,
+}
+
+impl StatRecomputeService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self {
+            live_game_service: LiveGameService::new(pool.clone(), redis_client.clone()),
+            avatar_progression: AvatarProgressionService::new(pool.clone(), redis_client),
+            pool,
+        }
+    }
+
+    /// Spawn the task that fails jobs interrupted by a stopped instance. Must be called from within a Tokio runtime.
+    pub fn start(self: &Arc<Self>) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = service.recover_interrupted_jobs().await {
+                    tracing::error!("❌ Failed to recover interrupted stat recompute jobs: {}", e);
+                }
+                tokio::time::sleep(JOB_HEARTBEAT_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Fail jobs left queued or running by an instance that stopped. Returns the number failed.
+    pub async fn recover_interrupted_jobs(&self) -> Result<u64, sqlx::Error> {
+        let failed = fail_interrupted_stat_recompute_jobs(&self.pool, STALE_JOB_SECONDS).await?;
+        if failed > 0 {
+            tracing::warn!("⚠️ Failed {} interrupted stat recompute jobs", failed);
+        }
+        Ok(failed)
+    }
+
+    /// Run a queued job in the background. Must be called from within a Tokio runtime.
+    pub fn spawn_job(self: &Arc<Self>, job_id: Uuid, mode: StatRecomputeMode, rebuild_live_games: bool) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move { service.run_job(job_id, mode, rebuild_live_games).await });
+    }
+
+    async fn run_job(&self, job_id: Uuid, mode: StatRecomputeMode, rebuild_live_games: bool) {
+        tracing::info!("🔄 Starting stat recompute job {} ({})", job_id, mode.as_str());
+        match start_stat_recompute_job(&self.pool, job_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::warn!("⚠️ Stat recompute job {} is no longer queued, not running it", job_id);
+                return;
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to start stat recompute job {}: {}", job_id, e);
+                return;
+            }
+        }
+
+        let heartbeat = self.spawn_heartbeat(job_id);
+        let result = match mode {
+            StatRecomputeMode::DryRun => self.dry_run(rebuild_live_games).await
+                .and_then(|(report, plan)| Ok((report, Some(serialize_plan(&plan)?)))),
+            StatRecomputeMode::Apply => self.apply(job_id).await.map(|report| (report, None)),
+        };
+        heartbeat.abort();
+
+        let recorded = match result {
+            Ok((report, plan)) => {
+                tracing::info!("✅ Stat recompute job {} finished: {} of {} workouts and {} users changed",
+                    job_id, report.workouts_changed, report.workouts_replayed, report.users.len());
+                finish_stat_recompute_job(&self.pool, job_id, &report, plan.as_ref()).await
+            }
+            Err(e) => {
+                tracing::error!("❌ Stat recompute job {} failed: {}", job_id, e);
+                fail_stat_recompute_job(&self.pool, job_id, &e.to_string()).await
+            }
+        };
+        if let Err(e) = recorded {
+            tracing::error!("❌ Failed to record outcome of stat recompute job {}: {}", job_id, e);
+        }
+    }
+
+    /// Refresh the job's heartbeat while it runs, so it isn't failed as interrupted
+    fn spawn_heartbeat(&self, job_id: Uuid) -> tokio::task::JoinHandle<()> {
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(JOB_HEARTBEAT_INTERVAL).await;
+                match touch_stat_recompute_job(&pool, job_id).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => tracing::error!("❌ Failed to refresh heartbeat of stat recompute job {}: {}", job_id, e),
+                }
+            }
+        })
+    }
+
+    /// Report what recomputing would change, with the writes applying it makes, without storing anything
+    async fn dry_run(&self, rebuild_live_games: bool) -> Result<(StatRecomputeReport, RecomputePlan), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let workouts_fingerprint = accepted_workouts_fingerprint(&mut conn).await?;
+        let mut replay = self.replay(&mut conn).await?;
+        let users = self.diff_avatars(&mut conn, &replay).await?;
+
+        let (live_games, score_events) = if rebuild_live_games {
+            self.rescore_live_games(&mut conn, &mut replay).await?
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let report = StatRecomputeReport {
+            workouts_replayed: replay.workouts.len(),
+            workouts_changed: replay.workouts.iter().filter(|workout| workout.changed()).count(),
+            users,
+            live_games,
+        };
+        let plan = RecomputePlan {
+            workouts_fingerprint,
+            workouts: replay.workouts.into_iter()
+                .filter(|workout| workout.changed())
+                .map(|workout| PlannedWorkout { id: workout.id, changes: workout.changes })
+                .collect(),
+            daily_gains: replay.daily_gains.into_iter()
+                .map(|(user_id, day, gains)| PlannedDailyGains {
+                    user_id,
+                    day,
+                    zone_minutes: gains.zone_minutes,
+                    stamina_gained: gains.stamina_gained,
+                    strength_gained: gains.strength_gained,
+                })
+                .collect(),
+            score_events,
+        };
+        Ok((report, plan))
+    }
+
+    /// Store the plan of the dry run the job was approved from as one unit, then broadcast the
+    /// corrected live games. Fails without storing anything if workouts or live score events
+    /// changed since the dry run.
+    async fn apply(&self, job_id: Uuid) -> Result<StatRecomputeReport, sqlx::Error> {
+        let (report, plan) = get_reviewed_dry_run(&self.pool, job_id).await?
+            .ok_or_else(|| sqlx::Error::Protocol("The dry run to apply has no stored plan".to_string()))?;
+        let report: StatRecomputeReport = serde_json::from_value(report)
+            .map_err(|e| sqlx::Error::Protocol(format!("Failed to read the reviewed report: {}", e)))?;
+        let plan: RecomputePlan = serde_json::from_value(plan)
+            .map_err(|e| sqlx::Error::Protocol(format!("Failed to read the reviewed plan: {}", e)))?;
+
+        let mut tx = self.pool.begin().await?;
+
+        // Uploads and retractions wait until the daily gains are rebuilt. Like them, the daily
+        // gains are locked before the avatars.
+        sqlx::query!("LOCK TABLE user_daily_stat_gains IN EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        if accepted_workouts_fingerprint(&mut tx).await? != plan.workouts_fingerprint {
+            return Err(sqlx::Error::Protocol(
+                "Workouts were stored, retracted or rescored since the dry run, start a new one".to_string(),
+            ));
+        }
+
+        let mut users: Vec<&UserStatDiff> = report.users.iter().collect();
+        users.sort_by_key(|user| user.user_id);
+        for user in users {
+            sqlx::query!(
+                r#"
+                UPDATE user_avatars
+                SET stamina = GREATEST(stamina + $1, 0),
+                    strength = GREATEST(strength + $2, 0),
+                    updated_at = NOW()
+                WHERE user_id = $3
+                "#,
+                user.stamina_after - user.stamina_before,
+                user.strength_after - user.strength_before,
+                user.user_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        clear_daily_stat_gains(&mut tx).await?;
+        for planned in &plan.daily_gains {
+            let gains = DailyStatGains {
+                zone_minutes: planned.zone_minutes,
+                stamina_gained: planned.stamina_gained,
+                strength_gained: planned.strength_gained,
+            };
+            add_daily_stat_gains(&mut tx, planned.user_id, planned.day, &gains).await?;
+        }
+        for workout in &plan.workouts {
+            update_workout_stats(&mut tx, workout.id, &workout.changes).await?;
+        }
+
+        // Experience follows the recomputed workout gains
+        let mut progressions = Vec::new();
+        for user in &report.users {
+            if let Some(progression) = AvatarProgressionService::store(&mut tx, user.user_id, &user.username).await? {
+                progressions.push((user, progression));
+            }
+        }
+
+        let mut updated_live_games: Vec<LiveGame> = Vec::new();
+        if !plan.score_events.is_empty() {
+            let events: HashMap<Uuid, _> = self.live_game_service.get_rescorable_score_events(&mut tx).await?
+                .into_iter()
+                .map(|event| (event.id, event))
+                .collect();
+            for planned in &plan.score_events {
+                let event = events.get(&planned.event_id)
+                    .filter(|event| event.score_points == planned.score_points_before
+                        && event.power_contribution == planned.power_contribution_before)
+                    .ok_or_else(|| sqlx::Error::Protocol(format!(
+                        "Live score event {} was evaluated or rescored since the dry run, start a new one", planned.event_id
+                    )))?;
+                let updated_game = self.live_game_service.rescore_event(&mut tx, event, &planned.update).await?;
+                // Keep only the latest state per game
+                updated_live_games.retain(|game| game.id != updated_game.id);
+                updated_live_games.push(updated_game);
+            }
+        }
+
+        tx.commit().await?;
+
+        for (user, (progression, saved)) in &progressions {
+            self.avatar_progression.announce(user.user_id, &user.username, progression, saved).await;
+        }
+        for live_game in &updated_live_games {
+            if let Err(e) = self.live_game_service.broadcast_live_score_update(live_game).await {
+                tracing::error!("❌ Failed to broadcast live score update for game {}: {}", live_game.game_id, e);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Score every accepted workout again, per user in the order they happened, capping each
+    /// against the gains replayed before it. Reads only through `conn`.
+    async fn replay(&self, conn: &mut PgConnection) -> Result<Replay, sqlx::Error> {
+        let caps = get_stat_caps(&mut *conn).await?;
+        let mut replay = Replay::default();
+
+        for (user_id, username) in get_users_with_accepted_workouts(&mut *conn).await? {
+            let workout_ids = get_accepted_workout_ids(&mut *conn, user_id).await?;
+            let scoring_games = get_scoring_game_ids(&mut *conn, &workout_ids).await?;
+            let heart_rate_profile = StatCalculator::user_heart_rate_zones(&mut *conn, user_id).await?;
+
+            let mut days: BTreeMap<NaiveDate, DailyStatGains> = BTreeMap::new();
+            // One workout's samples in memory at a time, however long the user's history
+            for workout_id in workout_ids {
+                let workout = get_scored_workout(&mut *conn, workout_id).await?;
+                let scored_at = workout.data.workout_start.unwrap_or(workout.data.timestamp);
+                let rules = get_user_scoring_rules(&mut *conn, user_id, scored_at).await?;
+                let mut changes = StatCalculator::calculate_stat_changes_with_profile(Some(&heart_rate_profile), None, &workout.data, &rules);
+
+                let day = workout_day(workout.data.workout_start, workout.data.timestamp);
+                let today = days.get(&day).cloned().unwrap_or_default();
+                let week_points = weekly_points(&days, day);
+
+                let mut game_changes = HashMap::new();
+                for game_id in scoring_games.get(&workout.id).into_iter().flatten() {
+                    let game_rules = game_rules(&mut *conn, &mut replay.rules_by_game, *game_id).await?;
+                    if game_rules.season_id == rules.season_id {
+                        continue;
+                    }
+                    let mut changes = StatCalculator::calculate_stat_changes_with_profile(Some(&heart_rate_profile), None, &workout.data, &game_rules);
+                    cap_stat_changes(&mut changes, &caps, &today, week_points);
+                    game_changes.insert(*game_id, changes);
+                }
+
+                let zone_minutes = cap_stat_changes(&mut changes, &caps, &today, week_points);
+
+                let gains = days.entry(day).or_default();
+                for (credited, minutes) in gains.zone_minutes.iter_mut().zip(zone_minutes) {
+                    *credited += minutes;
+                }
+                gains.stamina_gained += changes.stamina_change;
+                gains.strength_gained += changes.strength_change;
+
+                replay.workouts.push(ReplayedWorkout {
+                    id: workout.id,
+                    user_id,
+                    stamina_before: workout.stamina_gained,
+                    strength_before: workout.strength_gained,
+                    changes,
+                    game_changes,
+                });
+            }
+
+            replay.daily_gains.extend(days.into_iter().map(|(day, gains)| (user_id, day, gains)));
+            replay.usernames.insert(user_id, username);
+        }
+
+        Ok(replay)
+    }
+
+    /// Avatar totals before and after for users whose totals change
+    async fn diff_avatars(
+        &self,
+        conn: &mut PgConnection,
+        replay: &Replay,
+    ) -> Result<Vec<UserStatDiff>, sqlx::Error> {
+        // (stamina delta, strength delta, workouts changed) per user
+        let mut deltas: HashMap<Uuid, (i32, i32, usize)> = HashMap::new();
+        for workout in replay.workouts.iter().filter(|workout| workout.changed()) {
+            let delta = deltas.entry(workout.user_id).or_default();
+            delta.0 += workout.changes.stamina_change - workout.stamina_before;
+            delta.1 += workout.changes.strength_change - workout.strength_before;
+            delta.2 += 1;
+        }
+
+        let user_ids: Vec<Uuid> = deltas.keys().copied().collect();
+        let avatars = sqlx::query!(
+            "SELECT user_id, stamina, strength FROM user_avatars WHERE user_id = ANY($1)",
+            &user_ids
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut users: Vec<UserStatDiff> = avatars.into_iter().map(|avatar| {
+            let (stamina_delta, strength_delta, workouts_changed) = deltas[&avatar.user_id];
+            UserStatDiff {
+                user_id: avatar.user_id,
+                username: replay.usernames.get(&avatar.user_id).cloned().unwrap_or_default(),
+                workouts_changed,
+                stamina_before: avatar.stamina,
+                stamina_after: avatar.stamina + stamina_delta,
+                strength_before: avatar.strength,
+                strength_after: avatar.strength + strength_delta,
+            }
+        }).collect();
+
+        users.sort_by(|a, b| a.username.cmp(&b.username));
+        Ok(users)
+    }
+
+    /// Rescore the workout score events of live games that were not evaluated yet with the replayed
+    /// stats and each game's scoring rules. Returns the score changes per game and the planned
+    /// update of each event that changes.
+    async fn rescore_live_games(
+        &self,
+        conn: &mut PgConnection,
+        replay: &mut Replay,
+    ) -> Result<(Vec<LiveGameScoreDiff>, Vec<PlannedScoreEvent>), sqlx::Error> {
+        let workouts: HashMap<Uuid, &ReplayedWorkout> = replay.workouts.iter()
+            .map(|workout| (workout.id, workout))
+            .collect();
+
+        let mut diffs: Vec<LiveGameScoreDiff> = Vec::new();
+        let mut score_events: Vec<PlannedScoreEvent> = Vec::new();
+        for event in self.live_game_service.get_rescorable_score_events(&mut *conn).await? {
+            let Some(workout) = workouts.get(&event.workout_data_id) else {
+                continue;
+            };
+            let rules = game_rules(&mut *conn, &mut replay.rules_by_game, event.game_id).await?;
+            let changes = workout.game_changes.get(&event.game_id).unwrap_or(&workout.changes);
+            let update = build_live_game_score_update(
+                event.user_id, &event.username, event.team_id, &rules, changes,
+                &self.live_game_service, workout.id,
+            );
+            let score_delta = update.score_increase - event.score_points;
+            if score_delta == 0 && update.power_increase == event.power_contribution && !workout.changed() {
+                continue;
+            }
+
+            let diff = match diffs.iter_mut().find(|diff| diff.live_game_id == event.live_game_id) {
+                Some(diff) => diff,
+                None => {
+                    diffs.push(LiveGameScoreDiff {
+                        live_game_id: event.live_game_id,
+                        game_id: event.game_id,
+                        events_rescored: 0,
+                        home_score_before: event.home_score,
+                        home_score_after: event.home_score,
+                        away_score_before: event.away_score,
+                        away_score_after: event.away_score,
+                    });
+                    diffs.last_mut().unwrap()
+                }
+            };
+            diff.events_rescored += 1;
+            if event.team_side == "home" {
+                diff.home_score_after = (diff.home_score_after + score_delta).max(0);
+            } else {
+                diff.away_score_after = (diff.away_score_after + score_delta).max(0);
+            }
+
+            score_events.push(PlannedScoreEvent {
+                event_id: event.id,
+                score_points_before: event.score_points,
+                power_contribution_before: event.power_contribution,
+                update,
+            });
+        }
+
+        Ok((diffs, score_events))
+    }
+}
+
+/// A game's scoring rules, loaded once per replay
+async fn game_rules(
+    conn: &mut PgConnection,
+    rules_by_game: &mut HashMap<Uuid, ScoringRules>,
+    game_id: Uuid,
+) -> Result<ScoringRules, sqlx::Error> {
+    if let Some(rules) = rules_by_game.get(&game_id) {
+        return Ok(rules.clone());
+    }
+    let rules = get_game_scoring_rules(conn, game_id).await?;
+    rules_by_game.insert(game_id, rules.clone());
+    Ok(rules)
+}
+
+fn serialize_plan(plan: &RecomputePlan) -> Result<serde_json::Value, sqlx::Error> {
+    serde_json::to_value(plan)
+        .map_err(|e| sqlx::Error::Protocol(format!("Failed to serialize recompute plan: {}", e)))
+}
+
+/// Points replayed in the Monday-to-Sunday week containing `day`
+fn weekly_points(days: &BTreeMap<NaiveDate, DailyStatGains>, day: NaiveDate) -> i32 {
+    let week_start = day - Duration::days(day.weekday().num_days_from_monday() as i64);
+    days.range(week_start..week_start + Duration::days(7))
+        .map(|(_, gains)| gains.points())
+        .sum()
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::models::game_events::GameEvent;
+
+/// Publish an event to the user's WebSocket channel, and to the global one if `global` is set.
+/// Failures are only logged, the event is a courtesy on top of what's stored.
+pub async fn publish_user_event(
+    redis_client: Option<&Arc<redis::Client>>,
+    user_id: Uuid,
+    event: &GameEvent,
+    global: bool,
+) {
+    let Some(redis_client) = redis_client else {
+        tracing::warn!("⚠️  Redis not available - event for user {} will not be sent in real-time", user_id);
+        return;
+    };
+
+    let message = match serde_json::to_string(event) {
+        Ok(message) => message,
+        Err(e) => {
+            tracing::error!("Failed to serialize game event: {}", e);
+            return;
+        }
+    };
+
+    let mut channels = vec![format!("game:events:user:{}", user_id)];
+    if global {
+        channels.push("game:events:global".to_string());
+    }
+
+    match redis_client.get_async_connection().await {
+        Ok(mut conn) => {
+            for channel in channels {
+                let result: Result<i32, redis::RedisError> = conn.publish(&channel, &message).await;
+                if let Err(e) = result {
+                    tracing::error!("❌ Failed to publish event for user {} to {}: {}", user_id, channel, e);
+                }
+            }
+        }
+        Err(e) => {
+            tracing::error!("❌ Redis connection failed while publishing event for user {}: {}", user_id, e);
+        }
+    }
+}
+
+/// Send a notification to the user it's addressed to
+pub async fn notify_user(redis_client: Option<&Arc<redis::Client>>, notification: &GameEvent) {
+    if let GameEvent::Notification { user_id, .. } = notification {
+        publish_user_event(redis_client, *user_id, notification, false).await;
+    }
+}
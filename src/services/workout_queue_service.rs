@@ -0,0 +1,313 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use redis::AsyncCommands;
+use serde_json::json;
+use sqlx::PgPool;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::db::workout_upload_sessions::delete_upload_session;
+use crate::db::workout_jobs::{
+    claim_next_workout_job, enqueue_workout_job, fail_workout_job, finish_workout_job,
+    retry_workout_job, touch_workout_job_lock, ClaimedWorkoutJob,
+};
+use crate::handlers::workout_data::upload_session::StagedHeartRate;
+use crate::handlers::workout_data::upload_workout_data::{
+    is_duplicate_workout_error, process_workout_data, workout_sync_data, WorkoutUploadOutcome,
+};
+use crate::models::workout_data::{WorkoutDataSyncRequest, WorkoutJobStatus};
+use crate::services::live_game_service::LiveGameService;
+
+/// Number of jobs processed concurrently by one app instance
+const WORKER_COUNT: usize = 4;
+/// How often idle workers look for due retries and jobs queued by other instances
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// A job locked longer than this belongs to a worker that died and is picked up again
+const STALE_LOCK_SECONDS: f64 = 300.0;
+/// Running jobs refresh their lock this often, well within `STALE_LOCK_SECONDS`
+const LOCK_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+/// Retry delay doubles per attempt, up to the cap
+const RETRY_BASE_DELAY_SECONDS: f64 = 5.0;
+const RETRY_MAX_DELAY_SECONDS: f64 = 600.0;
+
+/// Durable Postgres-backed queue for workout uploads.
+/// Uploads are stored as jobs and run through the upload pipeline by background workers, with retries.
+pub struct WorkoutQueueService {
+    pool: PgPool,
+    redis_client: Option<Arc<redis::Client>>,
+    live_game_service: LiveGameService,
+    wake: Notify,
+}
+
+impl WorkoutQueueService {
+    pub fn new(pool: PgPool, redis_client: Option<Arc<redis::Client>>) -> Self {
+        Self {
+            live_game_service: LiveGameService::new(pool.clone(), redis_client.clone()),
+            pool,
+            redis_client,
+            wake: Notify::new(),
+        }
+    }
+
+    /// Spawn the background workers. Must be called from within a Tokio runtime.
+    pub fn start(self: &Arc<Self>) {
+        for worker in 0..WORKER_COUNT {
+            let queue = Arc::clone(self);
+            tokio::spawn(queue.run_worker(worker));
+        }
+        tracing::info!("✅ Workout queue started with {} workers", WORKER_COUNT);
+    }
+
+    /// Queue a workout for processing and wake a worker
+    pub async fn enqueue(&self, user_id: Uuid, data: &WorkoutDataSyncRequest) -> Result<Uuid, sqlx::Error> {
+        let job_id = enqueue_workout_job(&self.pool, user_id, data).await?;
+        self.wake.notify_one();
+        Ok(job_id)
+    }
+
+    /// Wake a worker for a job queued outside of `enqueue`, e.g. in the transaction finalizing an upload session
+    pub fn notify_job_queued(&self) {
+        self.wake.notify_one();
+    }
+
+    async fn run_worker(self: Arc<Self>, worker: usize) {
+        loop {
+            match self.process_next_job().await {
+                // Keep draining while there is work
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => tracing::error!("❌ Workout queue worker {} failed to claim a job: {}", worker, e),
+            }
+
+            tokio::select! {
+                _ = self.wake.notified() => {}
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    }
+
+    /// Claim and run one due job. Returns false if the queue had nothing to do.
+    /// The job runs in a task of its own, so a panic fails the attempt instead of taking the worker down.
+    pub async fn process_next_job(self: &Arc<Self>) -> Result<bool, sqlx::Error> {
+        let Some(job) = claim_next_workout_job(&self.pool, STALE_LOCK_SECONDS).await? else {
+            return Ok(false);
+        };
+
+        tracing::info!("⚙️ Processing workout job {} for {} (attempt {}/{})",
+            job.id, job.username, job.attempts, job.max_attempts);
+
+        let job = Arc::new(job);
+        let heartbeat = self.spawn_lock_heartbeat(&job);
+        let run = {
+            let (queue, job) = (Arc::clone(self), Arc::clone(&job));
+            tokio::spawn(async move { queue.run_job(&job).await })
+        };
+        let result = match run.await {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::error!("💥 Workout job {} panicked on attempt {}: {}", job.id, job.attempts, e);
+                self.retry_or_fail(&job, "Workout processing panicked").await
+            }
+        };
+        // Stop refreshing the lock either way, so the job doesn't stay locked after a panic
+        heartbeat.abort();
+        if let Err(e) = result {
+            tracing::error!("❌ Failed to record outcome of workout job {}: {}", job.id, e);
+        }
+
+        Ok(true)
+    }
+
+    /// Refresh the job's lock while it runs, so a long job isn't re-claimed by another worker
+    fn spawn_lock_heartbeat(&self, job: &ClaimedWorkoutJob) -> tokio::task::JoinHandle<()> {
+        let pool = self.pool.clone();
+        let (job_id, attempt) = (job.id, job.attempts);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(LOCK_HEARTBEAT_INTERVAL).await;
+                match touch_workout_job_lock(&pool, job_id, attempt).await {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(e) => tracing::error!("❌ Failed to refresh lock of workout job {}: {}", job_id, e),
+                }
+            }
+        })
+    }
+
+    async fn run_job(&self, job: &ClaimedWorkoutJob) -> Result<(), sqlx::Error> {
+        // A previous attempt stored the workout but died before finishing the job
+        if let Some(workout_data_id) = job.workout_data_id {
+            tracing::info!("♻️ Workout job {} already stored workout {}, finishing it", job.id, workout_data_id);
+            let result = json!({ "sync_id": workout_data_id, "workout_uuid": job.workout_uuid });
+            self.discard_upload_session(job).await;
+            self.finish(job, WorkoutJobStatus::Completed, &result).await?;
+            return Ok(());
+        }
+
+        if job.attempts > job.max_attempts {
+            return self.fail(job, "Workout processing exceeded its maximum attempts").await;
+        }
+
+        let data = match job.payload.clone().map(serde_json::from_value::<WorkoutDataSyncRequest>) {
+            Some(Ok(data)) => data,
+            Some(Err(e)) => return self.fail(job, &format!("Invalid workout payload: {}", e)).await,
+            None => return self.fail(job, "Workout payload is missing").await,
+        };
+
+        let mut summary = None;
+        let result = match self.read_upload_session(job, &data).await {
+            Ok(staged) => {
+                summary = staged.as_ref().and_then(StagedHeartRate::summary);
+                process_workout_data(
+                    &self.pool,
+                    self.redis_client.as_ref(),
+                    Some(&self.live_game_service),
+                    job.user_id,
+                    &job.username,
+                    &data,
+                    staged.as_ref(),
+                    Some(job.id),
+                ).await
+            }
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(WorkoutUploadOutcome::Processed(processed)) => {
+                let mut result = workout_sync_data(&processed, &job.workout_uuid);
+                if let Some(summary) = summary {
+                    result["heart_rate_summary"] = summary;
+                }
+                self.discard_upload_session(job).await;
+                if self.finish(job, WorkoutJobStatus::Completed, &result).await? {
+                    tracing::info!("✅ Workout job {} completed with sync_id {}", job.id, processed.sync_id);
+                }
+            }
+            Ok(WorkoutUploadOutcome::Rejected(issues)) => {
+                let result = json!({ "workout_uuid": job.workout_uuid, "validation_issues": issues });
+                self.discard_upload_session(job).await;
+                if self.finish(job, WorkoutJobStatus::Rejected, &result).await? {
+                    tracing::warn!("🚫 Workout job {} rejected: {:?}", job.id, issues);
+                    self.publish_job_outcome(job, WorkoutJobStatus::Rejected, result);
+                }
+            }
+            // Retrying can't help if the workout was stored in the meantime
+            Err(e) if is_duplicate_workout_error(&e) => {
+                self.fail(job, "Workout UUID already exists").await?;
+            }
+            Err(e) => {
+                self.retry_or_fail(job, &e.to_string()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Retry a failed attempt after a backoff, or fail the job once it's out of attempts
+    async fn retry_or_fail(&self, job: &ClaimedWorkoutJob, error: &str) -> Result<(), sqlx::Error> {
+        if job.attempts >= job.max_attempts {
+            return self.fail(job, error).await;
+        }
+
+        let delay = (RETRY_BASE_DELAY_SECONDS * 2f64.powi(job.attempts - 1)).min(RETRY_MAX_DELAY_SECONDS);
+        tracing::warn!("⚠️ Workout job {} failed on attempt {}, retrying in {}s: {}", job.id, job.attempts, delay, error);
+        if !retry_workout_job(&self.pool, job.id, job.attempts, error, delay).await? {
+            Self::log_superseded(job);
+        }
+        Ok(())
+    }
+
+    /// Check and analyze the staged samples of a job queued from an upload session as they're streamed,
+    /// with the rules of the workout's season. They stay in the database until the workout is stored.
+    async fn read_upload_session(
+        &self,
+        job: &ClaimedWorkoutJob,
+        data: &WorkoutDataSyncRequest,
+    ) -> Result<Option<StagedHeartRate>, sqlx::Error> {
+        let Some(session_id) = job.upload_session_id else {
+            return Ok(None);
+        };
+
+        let staged = StagedHeartRate::read(&mut *self.pool.acquire().await?, job.user_id, session_id, data).await?;
+        tracing::info!("📦 Streamed {} staged heart rate samples of upload session {} for job {}",
+            staged.checks.sample_count(), session_id, job.id);
+        Ok(Some(staged))
+    }
+
+    /// Staged samples are dropped along with the payload once the workout is stored or rejected
+    async fn discard_upload_session(&self, job: &ClaimedWorkoutJob) {
+        if let Some(session_id) = job.upload_session_id {
+            if let Err(e) = delete_upload_session(&self.pool, session_id).await {
+                tracing::error!("❌ Failed to delete upload session {} of workout job {}: {}", session_id, job.id, e);
+            }
+        }
+    }
+
+    /// Record a final outcome. Returns false if a later attempt took over the job.
+    async fn finish(&self, job: &ClaimedWorkoutJob, status: WorkoutJobStatus, result: &serde_json::Value) -> Result<bool, sqlx::Error> {
+        let finished = finish_workout_job(&self.pool, job.id, job.attempts, status, result).await?;
+        if !finished {
+            Self::log_superseded(job);
+        }
+        Ok(finished)
+    }
+
+    async fn fail(&self, job: &ClaimedWorkoutJob, error: &str) -> Result<(), sqlx::Error> {
+        if !fail_workout_job(&self.pool, job.id, job.attempts, error).await? {
+            Self::log_superseded(job);
+            return Ok(());
+        }
+        // Staged samples stay with the payload for a re-queue, until the session is cleaned up as abandoned
+        tracing::error!("❌ Workout job {} failed after {} attempt(s): {}", job.id, job.attempts, error);
+        self.publish_job_outcome(job, WorkoutJobStatus::Failed, json!({ "error": error }));
+        Ok(())
+    }
+
+    fn log_superseded(job: &ClaimedWorkoutJob) {
+        tracing::warn!("⚠️ Attempt {} of workout job {} lost its lock to a later attempt, dropping its outcome", job.attempts, job.id);
+    }
+
+    /// Tell the user's devices that a queued workout finished without being stored.
+    /// Successful jobs are announced by the upload pipeline itself.
+    fn publish_job_outcome(&self, job: &ClaimedWorkoutJob, status: WorkoutJobStatus, details: serde_json::Value) {
+        let Some(redis_client) = self.redis_client.clone() else {
+            tracing::warn!("⚠️  Redis not available - workout job outcome will not be published in real-time");
+            return;
+        };
+
+        let game_event = json!({
+            "event_type": "workout_data_processed",
+            "user_id": job.user_id,
+            "username": job.username,
+            "sync_id": null,
+            "job_id": job.id,
+            "job_status": status,
+            "workout_uuid": job.workout_uuid,
+            "stat_changes": {
+                "stamina_change": 0,
+                "strength_change": 0,
+            },
+            "details": details,
+            "timestamp": Utc::now().to_rfc3339()
+        });
+        let user_channel = format!("game:events:user:{}", job.user_id);
+        let job_id = job.id;
+
+        tokio::spawn(async move {
+            match redis_client.get_async_connection().await {
+                Ok(mut conn) => {
+                    let result: Result<i32, redis::RedisError> = conn.publish(&user_channel, game_event.to_string()).await;
+                    if let Err(e) = result {
+                        tracing::error!("❌ Failed to publish outcome of workout job {}: {}", job_id, e);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("❌ Redis connection failed during workout job publishing: {}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::models::workout_data::ExportedWorkout;
+
+pub mod tcx;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkoutExportFormat {
+    Csv,
+    Ndjson,
+    /// Recognized only to be refused, as GPX track points need positions and none are recorded
+    Gpx,
+    Tcx,
+}
+
+impl WorkoutExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "csv" => Some(WorkoutExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(WorkoutExportFormat::Ndjson),
+            "gpx" => Some(WorkoutExportFormat::Gpx),
+            "tcx" => Some(WorkoutExportFormat::Tcx),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkoutExportFormat::Csv => "csv",
+            WorkoutExportFormat::Ndjson => "ndjson",
+            WorkoutExportFormat::Gpx => "gpx",
+            WorkoutExportFormat::Tcx => "tcx",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            WorkoutExportFormat::Csv => "text/csv; charset=utf-8",
+            WorkoutExportFormat::Ndjson => "application/x-ndjson",
+            WorkoutExportFormat::Gpx => "application/gpx+xml",
+            WorkoutExportFormat::Tcx => "application/vnd.garmin.tcx+xml",
+        }
+    }
+
+    /// Whether the format holds a single workout rather than a history
+    pub fn is_single_workout(&self) -> bool {
+        matches!(self, WorkoutExportFormat::Gpx | WorkoutExportFormat::Tcx)
+    }
+}
+
+/// Columns of the CSV export: the workout summary repeated on each of its track points
+pub const CSV_HEADER: &str = "workout_id,workout_uuid,device_id,workout_date,workout_start,workout_end,workout_type,\
+duration_minutes,calories_burned,avg_heart_rate,max_heart_rate,min_heart_rate,total_distance_meters,\
+stamina_gained,strength_gained,review_status,sample_time,heart_rate,cadence,power_watts,speed_mps,distance_meters,steps\n";
+
+/// A workout as CSV lines, one per track point of its heart rate and sensor streams, or a single
+/// line with empty sample columns without any
+pub fn csv_rows(workout: &ExportedWorkout) -> String {
+    let summary = [
+        workout.id.to_string(),
+        csv_field(&workout.workout_uuid),
+        csv_field(&workout.device_id),
+        format_time(workout.workout_date),
+        optional(workout.workout_start.map(format_time)),
+        optional(workout.workout_end.map(format_time)),
+        optional(workout.workout_type.as_deref().map(csv_field)),
+        optional(workout.duration_minutes),
+        optional(workout.calories_burned),
+        optional(workout.avg_heart_rate),
+        optional(workout.max_heart_rate),
+        optional(workout.min_heart_rate),
+        optional(workout.total_distance_meters),
+        workout.stamina_gained.to_string(),
+        workout.strength_gained.to_string(),
+        csv_field(&workout.review_status),
+    ].join(",");
+
+    let points = track_points(workout);
+    if points.is_empty() {
+        return format!("{},,,,,,,\n", summary);
+    }
+    points.into_iter()
+        .map(|(timestamp, point)| format!(
+            "{},{},{},{},{},{},{},{}\n",
+            summary,
+            format_time(timestamp),
+            optional(point.heart_rate),
+            optional(point.cadence),
+            optional(point.watts),
+            optional(point.meters_per_second),
+            optional(point.meters),
+            optional(point.steps),
+        ))
+        .collect()
+}
+
+/// A workout with its samples as one line of JSON
+pub fn ndjson_line(workout: &ExportedWorkout) -> String {
+    let mut line = serde_json::to_string(workout).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// Everything recorded at one moment of a workout
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrackPoint {
+    pub heart_rate: Option<i32>,
+    pub cadence: Option<i32>,
+    pub watts: Option<i32>,
+    pub meters_per_second: Option<f64>,
+    pub meters: Option<f64>,
+    pub steps: Option<i32>,
+}
+
+/// Heart rate and sensor streams merged by timestamp, in time order
+pub fn track_points(workout: &ExportedWorkout) -> BTreeMap<DateTime<Utc>, TrackPoint> {
+    let mut points: BTreeMap<DateTime<Utc>, TrackPoint> = BTreeMap::new();
+    for sample in &workout.heart_rate {
+        points.entry(sample.timestamp).or_default().heart_rate = Some(sample.heart_rate);
+    }
+    for sample in workout.streams.cadence.iter().flatten() {
+        points.entry(sample.timestamp).or_default().cadence = Some(sample.cadence);
+    }
+    for sample in workout.streams.power.iter().flatten() {
+        points.entry(sample.timestamp).or_default().watts = Some(sample.watts);
+    }
+    for sample in workout.streams.speed.iter().flatten() {
+        points.entry(sample.timestamp).or_default().meters_per_second = Some(sample.meters_per_second);
+    }
+    for sample in workout.streams.distance.iter().flatten() {
+        points.entry(sample.timestamp).or_default().meters = Some(sample.meters);
+    }
+    for sample in workout.streams.steps.iter().flatten() {
+        points.entry(sample.timestamp).or_default().steps = Some(sample.steps);
+    }
+    points
+}
+
+pub(crate) fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Quote a CSV value if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use crate::models::workout_data::{ExportedWorkout, WorkoutType};
+use super::{format_time, track_points};
+
+/// Write a workout as a Garmin Training Center file with a single lap.
+/// Speed, power and running cadence go in the `TPX` activity extension, the way the importer reads them back.
+pub fn write(workout: &ExportedWorkout) -> String {
+    let workout_type = workout.workout_type.as_deref().map(WorkoutType::from_name).unwrap_or_default();
+    let sport = match workout_type {
+        WorkoutType::Run => "Running",
+        WorkoutType::Ride => "Biking",
+        _ => "Other",
+    };
+    let total_seconds = match (workout.workout_start, workout.workout_end) {
+        (Some(start), Some(end)) => (end - start).num_seconds().max(0),
+        _ => workout.duration_minutes.unwrap_or(0) as i64 * 60,
+    };
+
+    let mut tcx = String::new();
+    tcx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    tcx.push_str("<TrainingCenterDatabase xmlns=\"http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2\" \
+        xmlns:ns3=\"http://www.garmin.com/xmlschemas/ActivityExtension/v2\">\n");
+    tcx.push_str("  <Activities>\n");
+    let _ = writeln!(tcx, "    <Activity Sport=\"{}\">", sport);
+    let _ = writeln!(tcx, "      <Id>{}</Id>", format_time(workout.workout_date));
+    let _ = writeln!(tcx, "      <Lap StartTime=\"{}\">", format_time(workout.workout_start.unwrap_or(workout.workout_date)));
+    let _ = writeln!(tcx, "        <TotalTimeSeconds>{}</TotalTimeSeconds>", total_seconds);
+    let _ = writeln!(tcx, "        <DistanceMeters>{:.1}</DistanceMeters>", workout.total_distance_meters.unwrap_or(0.0));
+    let _ = writeln!(tcx, "        <Calories>{}</Calories>", workout.calories_burned.unwrap_or(0));
+    if let Some(avg_heart_rate) = workout.avg_heart_rate {
+        let _ = writeln!(tcx, "        <AverageHeartRateBpm><Value>{}</Value></AverageHeartRateBpm>", avg_heart_rate);
+    }
+    if let Some(max_heart_rate) = workout.max_heart_rate {
+        let _ = writeln!(tcx, "        <MaximumHeartRateBpm><Value>{}</Value></MaximumHeartRateBpm>", max_heart_rate);
+    }
+    tcx.push_str("        <Intensity>Active</Intensity>\n");
+    tcx.push_str("        <TriggerMethod>Manual</TriggerMethod>\n");
+    tcx.push_str("        <Track>\n");
+
+    for (timestamp, point) in track_points(workout) {
+        let _ = write!(tcx, "          <Trackpoint><Time>{}</Time>", format_time(timestamp));
+        if let Some(meters) = point.meters {
+            let _ = write!(tcx, "<DistanceMeters>{:.1}</DistanceMeters>", meters);
+        }
+        if let Some(heart_rate) = point.heart_rate {
+            let _ = write!(tcx, "<HeartRateBpm><Value>{}</Value></HeartRateBpm>", heart_rate);
+        }
+
+        // Run cadence is stored per foot, TCX counts strides
+        let mut extension = String::new();
+        match point.cadence {
+            Some(cadence) if workout_type == WorkoutType::Run => {
+                let _ = write!(extension, "<ns3:RunCadence>{}</ns3:RunCadence>", cadence / 2);
+            }
+            Some(cadence) => {
+                let _ = write!(tcx, "<Cadence>{}</Cadence>", cadence);
+            }
+            None => {}
+        }
+        if let Some(meters_per_second) = point.meters_per_second {
+            let _ = write!(extension, "<ns3:Speed>{:.3}</ns3:Speed>", meters_per_second);
+        }
+        if let Some(watts) = point.watts {
+            let _ = write!(extension, "<ns3:Watts>{}</ns3:Watts>", watts);
+        }
+        if !extension.is_empty() {
+            let _ = write!(tcx, "<Extensions><ns3:TPX>{}</ns3:TPX></Extensions>", extension);
+        }
+        tcx.push_str("</Trackpoint>\n");
+    }
+
+    tcx.push_str("        </Track>\n      </Lap>\n    </Activity>\n  </Activities>\n</TrainingCenterDatabase>\n");
+    tcx
+}
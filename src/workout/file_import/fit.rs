@@ -0,0 +1,274 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::workout_data::{CadenceData, DistanceData, HeartRateData, PowerData, SpeedData};
+use super::ParsedTrack;
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31T00:00:00Z)
+const FIT_EPOCH_OFFSET: i64 = 631_065_600;
+
+const MESG_SESSION: u16 = 18;
+const MESG_RECORD: u16 = 20;
+
+const FIELD_TIMESTAMP: u8 = 253;
+const FIELD_RECORD_HEART_RATE: u8 = 3;
+const FIELD_RECORD_CADENCE: u8 = 4;
+/// Centimeters since the start of the activity
+const FIELD_RECORD_DISTANCE: u8 = 5;
+/// Millimeters per second
+const FIELD_RECORD_SPEED: u8 = 6;
+const FIELD_RECORD_POWER: u8 = 7;
+const FIELD_SESSION_SPORT: u8 = 5;
+const FIELD_SESSION_SUB_SPORT: u8 = 6;
+const FIELD_SESSION_TOTAL_CALORIES: u8 = 11;
+
+struct FieldDefinition {
+    number: u8,
+    size: usize,
+}
+
+struct MessageDefinition {
+    global_number: u16,
+    big_endian: bool,
+    fields: Vec<FieldDefinition>,
+    developer_data_size: usize,
+}
+
+/// Minimal FIT decoder: walks the record stream and only reads the fields we need
+/// (record timestamp, heart rate, cadence, distance, speed and power, session sport and calories). Everything else is skipped by size.
+pub fn parse(bytes: &[u8]) -> Result<ParsedTrack, String> {
+    let header_size = *bytes.first().ok_or("Empty FIT file")? as usize;
+    if header_size < 12 || bytes.len() < header_size {
+        return Err("Invalid FIT header".to_string());
+    }
+
+    let data_size = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let data_end = header_size
+        .checked_add(data_size)
+        .filter(|end| *end <= bytes.len())
+        .ok_or("FIT file is truncated")?;
+
+    let mut definitions: HashMap<u8, MessageDefinition> = HashMap::new();
+    let mut track = ParsedTrack::default();
+    let mut last_timestamp: Option<u32> = None;
+    let mut offset = header_size;
+
+    while offset < data_end {
+        let record_header = bytes[offset];
+        offset += 1;
+
+        // Compressed timestamp header: a data message with a 5 bit time offset
+        if record_header & 0x80 != 0 {
+            let local_type = (record_header >> 5) & 0x03;
+            let time_offset = (record_header & 0x1F) as u32;
+            let timestamp = match last_timestamp {
+                Some(last) if time_offset < (last & 0x1F) => Some(
+                    ((last & !0x1F) | time_offset)
+                        .checked_add(0x20)
+                        .ok_or("FIT compressed timestamp out of range")?,
+                ),
+                Some(last) => Some((last & !0x1F) | time_offset),
+                None => None,
+            };
+
+            let definition = definitions.get(&local_type).ok_or("FIT data message without definition")?;
+            offset = read_data_message(bytes, offset, data_end, definition, timestamp, &mut last_timestamp, &mut track)?;
+            continue;
+        }
+
+        let local_type = record_header & 0x0F;
+
+        if record_header & 0x40 != 0 {
+            let has_developer_data = record_header & 0x20 != 0;
+            let (definition, next) = read_definition(bytes, offset, data_end, has_developer_data)?;
+            definitions.insert(local_type, definition);
+            offset = next;
+        } else {
+            let definition = definitions.get(&local_type).ok_or("FIT data message without definition")?;
+            offset = read_data_message(bytes, offset, data_end, definition, None, &mut last_timestamp, &mut track)?;
+        }
+    }
+
+    Ok(track)
+}
+
+fn read_definition(
+    bytes: &[u8],
+    offset: usize,
+    data_end: usize,
+    has_developer_data: bool,
+) -> Result<(MessageDefinition, usize), String> {
+    let fixed = take(bytes, offset, 5, data_end)?;
+    let big_endian = fixed[1] == 1;
+    let global_number = if big_endian {
+        u16::from_be_bytes([fixed[2], fixed[3]])
+    } else {
+        u16::from_le_bytes([fixed[2], fixed[3]])
+    };
+    let field_count = fixed[4] as usize;
+    let mut offset = offset + 5;
+
+    let raw_fields = take(bytes, offset, field_count * 3, data_end)?;
+    let fields = raw_fields
+        .chunks_exact(3)
+        .map(|field| FieldDefinition { number: field[0], size: field[1] as usize })
+        .collect();
+    offset += field_count * 3;
+
+    let mut developer_data_size = 0;
+    if has_developer_data {
+        let developer_count = take(bytes, offset, 1, data_end)?[0] as usize;
+        offset += 1;
+        let raw_developer_fields = take(bytes, offset, developer_count * 3, data_end)?;
+        developer_data_size = raw_developer_fields.chunks_exact(3).map(|field| field[1] as usize).sum();
+        offset += developer_count * 3;
+    }
+
+    Ok((MessageDefinition { global_number, big_endian, fields, developer_data_size }, offset))
+}
+
+fn read_data_message(
+    bytes: &[u8],
+    mut offset: usize,
+    data_end: usize,
+    definition: &MessageDefinition,
+    compressed_timestamp: Option<u32>,
+    last_timestamp: &mut Option<u32>,
+    track: &mut ParsedTrack,
+) -> Result<usize, String> {
+    let mut timestamp = compressed_timestamp;
+    let mut heart_rate: Option<u8> = None;
+    let mut cadence: Option<u8> = None;
+    let mut distance: Option<u32> = None;
+    let mut speed: Option<u16> = None;
+    let mut power: Option<u16> = None;
+    let mut total_calories: Option<u16> = None;
+    let mut sport: Option<u8> = None;
+    let mut sub_sport: Option<u8> = None;
+
+    for field in &definition.fields {
+        let value = take(bytes, offset, field.size, data_end)?;
+        offset += field.size;
+
+        match (definition.global_number, field.number, field.size) {
+            (_, FIELD_TIMESTAMP, 4) => {
+                let raw = read_u32(value, definition.big_endian);
+                if raw != u32::MAX {
+                    timestamp = Some(raw);
+                }
+            }
+            (MESG_RECORD, FIELD_RECORD_HEART_RATE, 1) if value[0] != u8::MAX => {
+                heart_rate = Some(value[0]);
+            }
+            (MESG_RECORD, FIELD_RECORD_CADENCE, 1) if value[0] != u8::MAX => {
+                cadence = Some(value[0]);
+            }
+            (MESG_RECORD, FIELD_RECORD_DISTANCE, 4) => {
+                distance = Some(read_u32(value, definition.big_endian)).filter(|raw| *raw != u32::MAX);
+            }
+            (MESG_RECORD, FIELD_RECORD_SPEED, 2) => {
+                speed = Some(read_u16(value, definition.big_endian)).filter(|raw| *raw != u16::MAX);
+            }
+            (MESG_RECORD, FIELD_RECORD_POWER, 2) => {
+                power = Some(read_u16(value, definition.big_endian)).filter(|raw| *raw != u16::MAX);
+            }
+            (MESG_SESSION, FIELD_SESSION_SPORT, 1) if value[0] != u8::MAX => {
+                sport = Some(value[0]);
+            }
+            (MESG_SESSION, FIELD_SESSION_SUB_SPORT, 1) if value[0] != u8::MAX => {
+                sub_sport = Some(value[0]);
+            }
+            (MESG_SESSION, FIELD_SESSION_TOTAL_CALORIES, 2) => {
+                let raw = read_u16(value, definition.big_endian);
+                if raw != u16::MAX {
+                    total_calories = Some(raw);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    take(bytes, offset, definition.developer_data_size, data_end)?;
+    offset += definition.developer_data_size;
+
+    if let Some(raw) = timestamp {
+        *last_timestamp = Some(raw);
+    }
+
+    if definition.global_number == MESG_RECORD {
+        if let Some(time) = timestamp.and_then(fit_time) {
+            track.timestamps.push(time);
+            if let Some(heart_rate) = heart_rate.filter(|hr| *hr > 0) {
+                track.heart_rate.push(HeartRateData { timestamp: time, heart_rate: heart_rate as i32 });
+            }
+            if let Some(cadence) = cadence {
+                track.cadence.push(CadenceData { timestamp: time, cadence: cadence as i32 });
+            }
+            if let Some(distance) = distance {
+                track.distance.push(DistanceData { timestamp: time, meters: distance as f64 / 100.0 });
+            }
+            if let Some(speed) = speed {
+                track.speed.push(SpeedData { timestamp: time, meters_per_second: speed as f64 / 1000.0 });
+            }
+            if let Some(power) = power {
+                track.power.push(PowerData { timestamp: time, watts: power as i32 });
+            }
+        }
+    }
+
+    if let Some(name) = sport.and_then(|sport| sport_name(sport, sub_sport)) {
+        track.sport.get_or_insert_with(|| name.to_string());
+    }
+
+    if let Some(calories) = total_calories {
+        *track.calories_burned.get_or_insert(0) += calories as i32;
+    }
+
+    Ok(offset)
+}
+
+/// Names for the FIT `sport` enum values we score differently.
+/// Training sessions are only specific through their sub sport.
+fn sport_name(sport: u8, sub_sport: Option<u8>) -> Option<&'static str> {
+    match (sport, sub_sport) {
+        (1, _) => Some("running"),
+        (2, _) => Some("cycling"),
+        (5, _) => Some("swimming"),
+        (11, _) => Some("walking"),
+        (17, _) => Some("hiking"),
+        (10, Some(20)) => Some("strength_training"),
+        (10, Some(43)) => Some("yoga"),
+        _ => None,
+    }
+}
+
+fn take(bytes: &[u8], offset: usize, len: usize, data_end: usize) -> Result<&[u8], String> {
+    offset
+        .checked_add(len)
+        .filter(|end| *end <= data_end)
+        .map(|end| &bytes[offset..end])
+        .ok_or_else(|| "FIT file is truncated".to_string())
+}
+
+fn read_u16(value: &[u8], big_endian: bool) -> u16 {
+    let raw = [value[0], value[1]];
+    if big_endian {
+        u16::from_be_bytes(raw)
+    } else {
+        u16::from_le_bytes(raw)
+    }
+}
+
+fn read_u32(value: &[u8], big_endian: bool) -> u32 {
+    let raw = [value[0], value[1], value[2], value[3]];
+    if big_endian {
+        u32::from_be_bytes(raw)
+    } else {
+        u32::from_le_bytes(raw)
+    }
+}
+
+fn fit_time(raw: u32) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(raw as i64 + FIT_EPOCH_OFFSET, 0)
+}
@@ -0,0 +1,75 @@
+use crate::models::workout_data::{CadenceData, DistanceData, HeartRateData, PowerData, SpeedData};
+use super::{descendant_value, parse_xml_time, ParsedTrack};
+
+/// Mean Earth radius used for great-circle distances between track points
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+
+/// Parse a GPX track. Heart rate, cadence and speed come from the Garmin TrackPointExtension
+/// (`<gpxtpx:hr>`, `<gpxtpx:cad>`, `<gpxtpx:speed>`) and power from `<power>`, all matched by
+/// local name so vendor namespace prefixes don't matter. Distance is accumulated from the positions.
+pub fn parse(bytes: &[u8]) -> Result<ParsedTrack, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "GPX file is not valid UTF-8".to_string())?;
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("Invalid GPX file: {}", e))?;
+
+    let mut track = ParsedTrack::default();
+    let mut last_position: Option<(f64, f64)> = None;
+    let mut meters = 0.0;
+
+    for point in document.descendants().filter(|node| node.has_tag_name("trkpt")) {
+        let Some(timestamp) = point.children()
+            .find(|node| node.has_tag_name("time"))
+            .and_then(|node| node.text())
+            .and_then(parse_xml_time)
+        else {
+            continue;
+        };
+        track.timestamps.push(timestamp);
+
+        let heart_rate = point.descendants()
+            .find(|node| node.tag_name().name() == "hr")
+            .and_then(|node| node.text())
+            .and_then(|value| value.trim().parse::<i32>().ok());
+
+        if let Some(heart_rate) = heart_rate.filter(|hr| *hr > 0) {
+            track.heart_rate.push(HeartRateData { timestamp, heart_rate });
+        }
+
+        if let Some(cadence) = descendant_value::<i32>(point, "cad") {
+            track.cadence.push(CadenceData { timestamp, cadence });
+        }
+        if let Some(meters_per_second) = descendant_value::<f64>(point, "speed") {
+            track.speed.push(SpeedData { timestamp, meters_per_second });
+        }
+        if let Some(watts) = descendant_value::<i32>(point, "power") {
+            track.power.push(PowerData { timestamp, watts });
+        }
+
+        let position = point.attribute("lat").and_then(|lat| lat.trim().parse::<f64>().ok())
+            .zip(point.attribute("lon").and_then(|lon| lon.trim().parse::<f64>().ok()));
+        if let Some(position) = position {
+            if let Some(previous) = last_position {
+                meters += haversine_meters(previous, position);
+            }
+            last_position = Some(position);
+            track.distance.push(DistanceData { timestamp, meters });
+        }
+    }
+
+    track.sport = document.descendants()
+        .find(|node| node.has_tag_name("trk"))
+        .and_then(|trk| trk.children().find(|node| node.has_tag_name("type")))
+        .and_then(|node| node.text())
+        .map(|sport| sport.trim().to_string());
+
+    Ok(track)
+}
+
+/// Great-circle distance between two (latitude, longitude) points in degrees
+fn haversine_meters(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
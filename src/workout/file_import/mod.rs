@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::models::workout_data::{CadenceData, DistanceData, HeartRateData, PowerData, SpeedData, WorkoutType};
+
+pub mod fit;
+pub mod gpx;
+pub mod tcx;
+
+/// Fixed namespace for workout UUIDs derived from imported files
+const WORKOUT_FILE_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c_2a5e_9b7d_4e13_8a42_d3c5_f0e7_b914);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkoutFileFormat {
+    Gpx,
+    Tcx,
+    Fit,
+}
+
+impl WorkoutFileFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkoutFileFormat::Gpx => "gpx",
+            WorkoutFileFormat::Tcx => "tcx",
+            WorkoutFileFormat::Fit => "fit",
+        }
+    }
+
+    /// Detect the format from the file contents rather than trusting the file name
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() >= 12 && &bytes[8..12] == b".FIT" {
+            return Some(WorkoutFileFormat::Fit);
+        }
+
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]);
+        if head.contains("<TrainingCenterDatabase") {
+            Some(WorkoutFileFormat::Tcx)
+        } else if head.contains("<gpx") {
+            Some(WorkoutFileFormat::Gpx)
+        } else {
+            None
+        }
+    }
+}
+
+/// Samples extracted by one of the format parsers
+#[derive(Debug, Default)]
+pub struct ParsedTrack {
+    /// Every timestamp seen in the track, used to derive start and end even without heart rate
+    pub timestamps: Vec<DateTime<Utc>>,
+    pub heart_rate: Vec<HeartRateData>,
+    pub power: Vec<PowerData>,
+    pub cadence: Vec<CadenceData>,
+    pub speed: Vec<SpeedData>,
+    pub distance: Vec<DistanceData>,
+    pub calories_burned: Option<i32>,
+    /// Sport named in the file, e.g. TCX `Running` or FIT `cycling`
+    pub sport: Option<String>,
+}
+
+/// A workout file converted into the shape of a regular workout upload
+#[derive(Debug)]
+pub struct ImportedWorkout {
+    pub format: WorkoutFileFormat,
+    pub workout_uuid: String,
+    pub workout_start: DateTime<Utc>,
+    pub workout_end: DateTime<Utc>,
+    pub heart_rate: Vec<HeartRateData>,
+    pub power: Vec<PowerData>,
+    pub cadence: Vec<CadenceData>,
+    pub speed: Vec<SpeedData>,
+    pub distance: Vec<DistanceData>,
+    pub calories_burned: Option<i32>,
+    pub workout_type: Option<WorkoutType>,
+}
+
+/// Parse a GPX, TCX or FIT file into an imported workout of the given user
+pub fn parse_workout_file(bytes: &[u8], user_id: Uuid) -> Result<ImportedWorkout, String> {
+    let format = WorkoutFileFormat::detect(bytes)
+        .ok_or_else(|| "Unsupported file format, expected GPX, TCX or FIT".to_string())?;
+
+    let mut track = match format {
+        WorkoutFileFormat::Gpx => gpx::parse(bytes)?,
+        WorkoutFileFormat::Tcx => tcx::parse(bytes)?,
+        WorkoutFileFormat::Fit => fit::parse(bytes)?,
+    };
+
+    let workout_start = track.timestamps.iter().min().copied()
+        .ok_or_else(|| "File does not contain any timestamped samples".to_string())?;
+    let workout_end = track.timestamps.iter().max().copied().unwrap_or(workout_start);
+
+    sort_samples(&mut track.heart_rate, |sample| sample.timestamp);
+    sort_samples(&mut track.power, |sample| sample.timestamp);
+    sort_samples(&mut track.cadence, |sample| sample.timestamp);
+    sort_samples(&mut track.speed, |sample| sample.timestamp);
+    sort_samples(&mut track.distance, |sample| sample.timestamp);
+
+    let workout_uuid = derive_workout_uuid(user_id, workout_start, workout_end, &track.heart_rate);
+
+    Ok(ImportedWorkout {
+        format,
+        workout_uuid,
+        workout_start,
+        workout_end,
+        heart_rate: track.heart_rate,
+        power: track.power,
+        cadence: track.cadence,
+        speed: track.speed,
+        distance: track.distance,
+        calories_burned: track.calories_burned,
+        workout_type: track.sport.as_deref().map(WorkoutType::from_name),
+    })
+}
+
+/// Order samples by time and keep one sample per timestamp
+fn sort_samples<T>(samples: &mut Vec<T>, timestamp: impl Fn(&T) -> DateTime<Utc>) {
+    samples.sort_by_key(|sample| timestamp(sample));
+    samples.dedup_by_key(|sample| timestamp(sample));
+}
+
+/// Derive a stable UUID from the user and the parsed contents, so the user re-uploading the same
+/// activity (even re-exported in another format) is detected as a duplicate, while another user
+/// importing the same file doesn't collide with them
+fn derive_workout_uuid(
+    user_id: Uuid,
+    workout_start: DateTime<Utc>,
+    workout_end: DateTime<Utc>,
+    heart_rate: &[HeartRateData],
+) -> String {
+    let mut canonical = format!("{}|{}|{}|", user_id, workout_start.timestamp(), workout_end.timestamp());
+    for sample in heart_rate {
+        canonical.push_str(&format!("{}:{};", sample.timestamp.timestamp(), sample.heart_rate));
+    }
+
+    Uuid::new_v5(&WORKOUT_FILE_NAMESPACE, canonical.as_bytes()).to_string()
+}
+
+/// Parse the text of the first descendant with the given local name, ignoring namespace prefixes
+pub(crate) fn descendant_value<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Option<T> {
+    node.descendants()
+        .find(|child| child.tag_name().name() == name)
+        .and_then(|child| child.text())
+        .and_then(|value| value.trim().parse::<T>().ok())
+}
+
+/// Parse an RFC 3339 timestamp as used by GPX and TCX
+pub(crate) fn parse_xml_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
@@ -0,0 +1,69 @@
+use crate::models::workout_data::{CadenceData, DistanceData, HeartRateData, PowerData, SpeedData};
+use super::{descendant_value, parse_xml_time, ParsedTrack};
+
+/// Parse a Garmin Training Center file. Calories are summed over all laps.
+/// Speed, power and running cadence come from the Garmin `TPX` activity extension.
+pub fn parse(bytes: &[u8]) -> Result<ParsedTrack, String> {
+    let text = std::str::from_utf8(bytes).map_err(|_| "TCX file is not valid UTF-8".to_string())?;
+    let document = roxmltree::Document::parse(text).map_err(|e| format!("Invalid TCX file: {}", e))?;
+
+    let mut track = ParsedTrack::default();
+
+    for point in document.descendants().filter(|node| node.tag_name().name() == "Trackpoint") {
+        let Some(timestamp) = point.children()
+            .find(|node| node.tag_name().name() == "Time")
+            .and_then(|node| node.text())
+            .and_then(parse_xml_time)
+        else {
+            continue;
+        };
+        track.timestamps.push(timestamp);
+
+        let heart_rate = point.children()
+            .find(|node| node.tag_name().name() == "HeartRateBpm")
+            .and_then(|node| node.children().find(|child| child.tag_name().name() == "Value"))
+            .and_then(|node| node.text())
+            .and_then(|value| value.trim().parse::<i32>().ok());
+
+        if let Some(heart_rate) = heart_rate.filter(|hr| *hr > 0) {
+            track.heart_rate.push(HeartRateData { timestamp, heart_rate });
+        }
+
+        if let Some(meters) = descendant_value::<f64>(point, "DistanceMeters") {
+            track.distance.push(DistanceData { timestamp, meters });
+        }
+        if let Some(meters_per_second) = descendant_value::<f64>(point, "Speed") {
+            track.speed.push(SpeedData { timestamp, meters_per_second });
+        }
+        if let Some(watts) = descendant_value::<i32>(point, "Watts") {
+            track.power.push(PowerData { timestamp, watts });
+        }
+
+        // Bike cadence is in revolutions per minute, run cadence counts one foot
+        let cadence = descendant_value::<i32>(point, "Cadence")
+            .or_else(|| descendant_value::<i32>(point, "RunCadence").map(|strides| strides * 2));
+        if let Some(cadence) = cadence {
+            track.cadence.push(CadenceData { timestamp, cadence });
+        }
+    }
+
+    track.sport = document.descendants()
+        .find(|node| node.tag_name().name() == "Activity")
+        .and_then(|activity| activity.attribute("Sport"))
+        .map(str::to_string);
+
+    let lap_calories: Vec<i32> = document.descendants()
+        .filter(|node| node.tag_name().name() == "Lap")
+        .filter_map(|lap| {
+            lap.children()
+                .find(|node| node.tag_name().name() == "Calories")
+                .and_then(|node| node.text())
+                .and_then(|value| value.trim().parse::<i32>().ok())
+        })
+        .collect();
+    if !lap_calories.is_empty() {
+        track.calories_burned = Some(lap_calories.iter().sum());
+    }
+
+    Ok(track)
+}
@@ -1 +1,5 @@
-pub mod workout_analyzer;
\ No newline at end of file
+pub mod workout_analyzer;
+pub mod workout_validator;
+pub mod file_import;
+pub mod overlap_detector;
+pub mod file_export;
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::workout_data::OverlappingWorkout;
+use crate::models::workout_data::{HeartRateData, WorkoutDataSyncRequest};
+
+/// Workouts sharing less than this share of the shorter one's time are separate activities
+const MIN_OVERLAP_RATIO: f64 = 0.5;
+/// Without heart rate on both sides, only near-identical windows count as the same activity
+const TIME_ONLY_OVERLAP_RATIO: f64 = 0.8;
+/// Samples from two devices this close in time are compared with each other
+const SAMPLE_MATCH_TOLERANCE_SECONDS: i64 = 10;
+/// Share of the new samples in the overlap that need a counterpart to compare heart rates
+const MIN_MATCHED_SAMPLE_RATIO: f64 = 0.5;
+/// Two devices on the same body read within a few bpm of each other
+const MAX_MEAN_HEART_RATE_DIFFERENCE: f64 = 10.0;
+
+/// Why a stored workout was judged to be the same activity as an upload
+#[derive(Debug)]
+pub struct DuplicateMatch<'a> {
+    pub existing: &'a OverlappingWorkout,
+    /// Overlap as a share of the shorter workout
+    pub overlap_ratio: f64,
+    /// Mean absolute heart rate difference over matched samples, if both have heart rate
+    pub mean_heart_rate_difference: Option<f64>,
+}
+
+/// Detects uploads that record the same activity as a stored workout from another device
+pub struct OverlapDetector;
+
+impl OverlapDetector {
+    /// Time window of an upload, from its start and end or else its heart rate samples
+    pub fn workout_window(workout: &WorkoutDataSyncRequest) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let samples = workout.heart_rate.as_deref().unwrap_or_default();
+        let start = workout.workout_start.or_else(|| samples.iter().map(|sample| sample.timestamp).min())?;
+        let end = workout.workout_end.or_else(|| samples.iter().map(|sample| sample.timestamp).max())?;
+        (end > start).then_some((start, end))
+    }
+
+    /// The best matching stored workout that is a recording of the same activity, if any.
+    /// Overlapping workouts whose heart rates disagree are genuinely separate concurrent activities.
+    pub fn find_duplicate<'a>(
+        workout: &WorkoutDataSyncRequest,
+        candidates: &'a [OverlappingWorkout],
+    ) -> Option<DuplicateMatch<'a>> {
+        let mut search = DuplicateSearch::new(workout, candidates)?;
+        for sample in workout.heart_rate.iter().flatten() {
+            search.push(sample);
+        }
+        search.finish()
+    }
+
+    /// Heart rate samples per minute over the workout window, counting at most one per second
+    pub fn heart_rate_quality(sample_count: usize, start: DateTime<Utc>, end: DateTime<Utc>) -> f64 {
+        let seconds = (end - start).num_seconds().max(1);
+        let samples = (sample_count as i64).min(seconds);
+        samples as f64 / (seconds as f64 / 60.0).max(1.0)
+    }
+
+    /// Combine two recordings of one activity: the union of both windows, heart rate samples
+    /// from both devices at most one per second, and the fuller recording of each stream
+    pub fn merge(workout: &WorkoutDataSyncRequest, existing: &OverlappingWorkout) -> WorkoutDataSyncRequest {
+        let mut heart_rate: Vec<HeartRateData> = existing.heart_rate.iter()
+            .chain(workout.heart_rate.iter().flatten())
+            .cloned()
+            .collect();
+        // Stable sort keeps the stored device's sample when both read at the same moment
+        heart_rate.sort_by_key(|sample| sample.timestamp);
+        let mut merged_heart_rate: Vec<HeartRateData> = Vec::with_capacity(heart_rate.len());
+        for sample in heart_rate {
+            if merged_heart_rate.last().is_none_or(|last| sample.timestamp - last.timestamp >= Duration::seconds(1)) {
+                merged_heart_rate.push(sample);
+            }
+        }
+
+        WorkoutDataSyncRequest {
+            heart_rate: (!merged_heart_rate.is_empty()).then_some(merged_heart_rate),
+            ..Self::merge_details(workout, existing)
+        }
+    }
+
+    /// `merge` without the heart rate, for an upload whose samples are merged in the database
+    pub fn merge_details(workout: &WorkoutDataSyncRequest, existing: &OverlappingWorkout) -> WorkoutDataSyncRequest {
+        let (start, end) = Self::workout_window(workout)
+            .map(|(start, end)| (start.min(existing.workout_start), end.max(existing.workout_end)))
+            .unwrap_or((existing.workout_start, existing.workout_end));
+
+        let streams = &existing.streams;
+        WorkoutDataSyncRequest {
+            device_id: workout.device_id.clone(),
+            timestamp: workout.timestamp,
+            heart_rate: None,
+            calories_burned: workout.calories_burned.max(existing.calories_burned),
+            workout_uuid: workout.workout_uuid.clone(),
+            workout_start: Some(start),
+            workout_end: Some(end),
+            workout_type: workout.workout_type.or(existing.workout_type),
+            overlap_policy: workout.overlap_policy,
+            power: fuller(&workout.power, &streams.power),
+            cadence: fuller(&workout.cadence, &streams.cadence),
+            speed: fuller(&workout.speed, &streams.speed),
+            distance: fuller(&workout.distance, &streams.distance),
+            steps: fuller(&workout.steps, &streams.steps),
+            strength_exercises: workout.strength_exercises.clone().or_else(|| existing.strength_exercises.clone()),
+        }
+    }
+}
+
+/// `OverlapDetector::find_duplicate` fed the upload's samples one at a time, so streamed samples
+/// can be compared without holding them in memory
+pub struct DuplicateSearch<'a> {
+    candidates: Vec<CandidateComparison<'a>>,
+}
+
+/// A stored workout overlapping enough to be a duplicate, and how the upload's heart rate compares with it
+struct CandidateComparison<'a> {
+    existing: &'a OverlappingWorkout,
+    overlap_start: DateTime<Utc>,
+    overlap_end: DateTime<Utc>,
+    overlap_ratio: f64,
+    existing_samples: Vec<&'a HeartRateData>,
+    samples_in_overlap: usize,
+    /// Samples with a stored counterpart, and the sum of their absolute heart rate differences
+    matched_samples: usize,
+    difference_sum: i64,
+}
+
+impl<'a> DuplicateSearch<'a> {
+    /// None if the upload has no time window to compare
+    pub fn new(workout: &WorkoutDataSyncRequest, candidates: &'a [OverlappingWorkout]) -> Option<Self> {
+        let (start, end) = OverlapDetector::workout_window(workout)?;
+
+        let candidates = candidates.iter()
+            .filter_map(|existing| {
+                let overlap_start = start.max(existing.workout_start);
+                let overlap_end = end.min(existing.workout_end);
+                let shorter = (end - start).min(existing.workout_end - existing.workout_start);
+                if overlap_end <= overlap_start || shorter <= Duration::zero() {
+                    return None;
+                }
+
+                let overlap_ratio = (overlap_end - overlap_start).num_seconds() as f64 / shorter.num_seconds() as f64;
+                if overlap_ratio < MIN_OVERLAP_RATIO {
+                    return None;
+                }
+
+                let mut existing_samples: Vec<&HeartRateData> = existing.heart_rate.iter().collect();
+                existing_samples.sort_by_key(|sample| sample.timestamp);
+                Some(CandidateComparison {
+                    existing,
+                    overlap_start,
+                    overlap_end,
+                    overlap_ratio,
+                    existing_samples,
+                    samples_in_overlap: 0,
+                    matched_samples: 0,
+                    difference_sum: 0,
+                })
+            })
+            .collect();
+
+        Some(Self { candidates })
+    }
+
+    /// Span of the upload's samples that overlap a candidate, the only ones worth pushing
+    pub fn sample_window(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = self.candidates.iter().map(|candidate| candidate.overlap_start).min()?;
+        let end = self.candidates.iter().map(|candidate| candidate.overlap_end).max()?;
+        Some((start, end))
+    }
+
+    /// Compare one of the upload's samples with the nearest stored sample of each candidate it overlaps
+    pub fn push(&mut self, sample: &HeartRateData) {
+        let tolerance = Duration::seconds(SAMPLE_MATCH_TOLERANCE_SECONDS);
+        for candidate in &mut self.candidates {
+            if sample.timestamp < candidate.overlap_start || sample.timestamp > candidate.overlap_end {
+                continue;
+            }
+            candidate.samples_in_overlap += 1;
+
+            let existing = &candidate.existing_samples;
+            let index = existing.partition_point(|other| other.timestamp < sample.timestamp);
+            let nearest = [index.checked_sub(1), Some(index)].into_iter()
+                .flatten()
+                .filter_map(|i| existing.get(i))
+                .min_by_key(|other| (other.timestamp - sample.timestamp).num_seconds().abs());
+            if let Some(nearest) = nearest.filter(|nearest| (nearest.timestamp - sample.timestamp).abs() <= tolerance) {
+                candidate.matched_samples += 1;
+                candidate.difference_sum += (nearest.heart_rate - sample.heart_rate).abs() as i64;
+            }
+        }
+    }
+
+    /// The best matching candidate that is a recording of the same activity, if any
+    pub fn finish(self) -> Option<DuplicateMatch<'a>> {
+        self.candidates.into_iter()
+            .filter_map(|candidate| {
+                let mean_heart_rate_difference = candidate.mean_heart_rate_difference();
+                let is_duplicate = match mean_heart_rate_difference {
+                    Some(difference) => difference <= MAX_MEAN_HEART_RATE_DIFFERENCE,
+                    None => candidate.overlap_ratio >= TIME_ONLY_OVERLAP_RATIO,
+                };
+
+                is_duplicate.then_some(DuplicateMatch {
+                    existing: candidate.existing,
+                    overlap_ratio: candidate.overlap_ratio,
+                    mean_heart_rate_difference,
+                })
+            })
+            .max_by(|a, b| a.overlap_ratio.total_cmp(&b.overlap_ratio))
+    }
+}
+
+impl CandidateComparison<'_> {
+    /// Mean absolute difference between the upload's samples in the overlap and the nearest stored sample.
+    /// None if either side has too few samples there to compare.
+    fn mean_heart_rate_difference(&self) -> Option<f64> {
+        if self.samples_in_overlap == 0 || self.existing_samples.is_empty() {
+            return None;
+        }
+        if (self.matched_samples as f64) < self.samples_in_overlap as f64 * MIN_MATCHED_SAMPLE_RATIO {
+            return None;
+        }
+        Some(self.difference_sum as f64 / self.matched_samples as f64)
+    }
+}
+
+fn fuller<T: Clone>(new: &Option<Vec<T>>, existing: &Option<Vec<T>>) -> Option<Vec<T>> {
+    match (new, existing) {
+        (Some(new), Some(existing)) if existing.len() > new.len() => Some(existing.clone()),
+        (Some(new), _) => Some(new.clone()),
+        (None, existing) => existing.clone(),
+    }
+}
+
@@ -1,106 +1,306 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-use crate::models::workout_data::{HeartRateData, HeartRateZones, ZoneName};
+use crate::models::game::{ScoringRules, INTERPOLATE_UP_TO_SEC, MAX_CREDITED_INTERVAL_SEC};
+use crate::models::workout_data::{Gender, HeartRateData, HeartRateZones, TrainingLoad, ZoneName};
 
+/// Share of the heart rate reserve where Zone 4 starts, the threshold the intensity factor is relative to
+const THRESHOLD_RESERVE_FRACTION: f32 = 0.8;
+/// Below this share of the heart rate reserve EPOC recovers instead of building up
+const EPOC_RECOVERY_RESERVE_FRACTION: f32 = 0.3;
+/// EPOC built up per minute at the full heart rate reserve, in ml/kg
+const EPOC_ACCUMULATION_PER_MIN: f32 = 20.0;
+/// Time constant of EPOC recovery in minutes
+const EPOC_RECOVERY_MIN: f32 = 10.0;
+
+/// The heart rates a user's effort is measured against for training load
+#[derive(Debug, Clone, Copy)]
+pub struct HeartRateReserve {
+    pub resting_heart_rate: i32,
+    pub max_heart_rate: i32,
+    /// Picks the Banister TRIMP weighting
+    pub gender: Gender,
+}
+
+impl HeartRateReserve {
+    /// Share of the heart rate reserve a heart rate uses, 0-1
+    fn fraction(&self, heart_rate: f32) -> f32 {
+        let reserve = (self.max_heart_rate - self.resting_heart_rate).max(1) as f32;
+        ((heart_rate - self.resting_heart_rate as f32) / reserve).clamp(0.0, 1.0)
+    }
+
+    /// Coefficient and exponent of the Banister weighting. Women's lactate rises more slowly, so their
+    /// curve is flatter. Other and unknown genders use Banister's original weighting, which was fitted to
+    /// men, like the general max heart rate formula.
+    fn banister_weighting(&self) -> (f32, f32) {
+        match self.gender {
+            Gender::Male | Gender::Other => (0.64, 1.92),
+            Gender::Female => (0.86, 1.67),
+        }
+    }
+}
+
+/// How the time between two heart rate samples is credited to zones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GapHandling {
+    /// Longest interval credited to a sample. The rest of a longer gap is unknown time.
+    pub max_credited_interval_sec: f32,
+    /// Gaps longer than the max credited interval but at most this long are credited in full,
+    /// spread over the zones of a straight line between the two samples. Zero disables interpolation.
+    pub interpolate_up_to_sec: f32,
+}
+
+impl Default for GapHandling {
+    fn default() -> Self {
+        Self {
+            max_credited_interval_sec: MAX_CREDITED_INTERVAL_SEC,
+            interpolate_up_to_sec: INTERPOLATE_UP_TO_SEC,
+        }
+    }
+}
+
+impl GapHandling {
+    /// The gap handling a season's scoring rules ask for
+    pub fn from_rules(rules: &ScoringRules) -> Self {
+        Self {
+            max_credited_interval_sec: rules.max_credited_interval_sec,
+            interpolate_up_to_sec: rules.interpolate_up_to_sec,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct WorkoutAnalyzer {
     pub total_duration_min: i32,
     pub zone_durations: HashMap<ZoneName, f32>,
     pub avg_heart_rate: f32,
     pub peak_heart_rate: f32,
-    time_above_aerobic_threshold: i32,
-    heart_rate_variability: f32,
-    zone_changes: i32,
+    /// Time in sample gaps that wasn't credited to any zone
+    pub unknown_duration_min: f32,
+    /// Share of the recorded time credited to a zone, 0-100
+    pub coverage_percent: f32,
+    /// Minutes in Zone 3 and above
+    pub time_above_aerobic_threshold: f32,
+    /// Standard deviation of the heart rate samples
+    pub heart_rate_variability: f32,
+    /// How often consecutive samples fall in different zones
+    pub zone_changes: i32,
+    /// Credited time as (heart rate, minutes), in order, for the training load
+    credited_intervals: Vec<(f32, f32)>,
+    gaps: GapHandling,
 }
 
 impl WorkoutAnalyzer {
-    pub fn new(heart_rate: &Vec<HeartRateData>, zones: &HeartRateZones) -> Option<Self> {
-        if heart_rate.is_empty() {
-            return None;
+    pub fn with_gap_handling(heart_rate: &[HeartRateData], zones: &HeartRateZones, gaps: GapHandling) -> Option<Self> {
+        // Samples are analyzed in chronological order. Most uploads already are, so only unsorted ones are copied.
+        let sorted_data: Cow<[HeartRateData]> = if heart_rate.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp) {
+            Cow::Borrowed(heart_rate)
+        } else {
+            let mut sorted_data = heart_rate.to_vec();
+            sorted_data.sort_by_key(|sample| sample.timestamp);
+            Cow::Owned(sorted_data)
+        };
+
+        let mut analyzer = IncrementalWorkoutAnalyzer::new(zones, gaps);
+        for hr_data in sorted_data.iter() {
+            analyzer.push(hr_data);
         }
+        analyzer.finish()
+    }
 
-        let mut analyzer = WorkoutAnalyzer {
-            total_duration_min: 0,
-            zone_durations: HashMap::new(),
-            avg_heart_rate: 0.0,
-            peak_heart_rate: 0.0,
-            time_above_aerobic_threshold: 0,
-            heart_rate_variability: 0.0,
-            zone_changes: 0,
-        };
+    /// The gap handling the samples were analyzed with
+    pub fn gap_handling(&self) -> GapHandling {
+        self.gaps
+    }
 
-        // Sort by timestamp to ensure chronological order
-        let mut sorted_data = heart_rate.clone();
-        sorted_data.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-
-        let start_time = sorted_data.first().unwrap().timestamp;
-        let end_time = sorted_data.last().unwrap().timestamp;
-        analyzer.total_duration_min = (end_time - start_time).num_seconds() as i32 / 60; // minutes
-
-        let mut hr_values_for_hrv = Vec::new();
-        let mut prev_zone = None;
-        let mut hr_sum = 0.0;
-
-        // Process each heart rate data point
-        for (index, hr_data) in sorted_data.iter().enumerate() {
-            let hr = hr_data.heart_rate;
-
-            // Statistics
-            hr_sum += hr as f32;
-            analyzer.peak_heart_rate = analyzer.peak_heart_rate.max(hr as f32);
-            hr_values_for_hrv.push(hr);
-
-            let zone = zones.get_zone(hr as f32);
-            // Calc duration for this sample - use interval between first and second point for the first point
-            let duration_sec = if index == 0 {
-                if sorted_data.len() > 1 {
-                    (sorted_data[1].timestamp - sorted_data[0].timestamp).num_seconds() as f32
-                } else {
-                    0.0 // Single data point workout
-                }
+    /// Banister and Edwards TRIMP, intensity factor and an EPOC estimate over the credited time
+    pub fn training_load(&self, reserve: &HeartRateReserve) -> TrainingLoad {
+        let (coefficient, exponent) = reserve.banister_weighting();
+        let mut banister_trimp = 0.0;
+        let mut weighted_fraction = 0.0;
+        let mut credited_min = 0.0;
+        let mut epoc: f32 = 0.0;
+        let mut peak_epoc: f32 = 0.0;
+
+        for &(heart_rate, minutes) in &self.credited_intervals {
+            let fraction = reserve.fraction(heart_rate);
+            banister_trimp += minutes * fraction * coefficient * (exponent * fraction).exp();
+            weighted_fraction += fraction * minutes;
+            credited_min += minutes;
+
+            // EPOC builds up with effort above the recovery level and decays below it
+            if fraction > EPOC_RECOVERY_RESERVE_FRACTION {
+                let effort = (fraction - EPOC_RECOVERY_RESERVE_FRACTION) / (1.0 - EPOC_RECOVERY_RESERVE_FRACTION);
+                epoc += minutes * EPOC_ACCUMULATION_PER_MIN * effort * effort;
             } else {
-                (hr_data.timestamp - sorted_data[index - 1].timestamp).num_seconds() as f32
-            };
-
-            let duration_min = duration_sec / 60.0;
-
-            // Process zone data - now all heart rates should fall into a zone since Zone1 starts at 0
-            if let Some(zone_name) = zone {
-                *analyzer.zone_durations.entry(zone_name).or_insert(0.0) += duration_min;
-                // Count time in aerobic zones
-                match zone_name {
-                    ZoneName::Zone3 | ZoneName::Zone4 | ZoneName::Zone5 => {
-                        analyzer.time_above_aerobic_threshold += duration_min as i32;
+                epoc *= (-minutes / EPOC_RECOVERY_MIN).exp();
+            }
+            peak_epoc = peak_epoc.max(epoc);
+        }
+
+        let edwards_trimp = self.zone_durations.iter()
+            .map(|(zone, minutes)| minutes * zone_number(*zone))
+            .sum();
+        let intensity_factor = if credited_min > 0.0 {
+            weighted_fraction / credited_min / THRESHOLD_RESERVE_FRACTION
+        } else {
+            0.0
+        };
+
+        TrainingLoad { banister_trimp, edwards_trimp, intensity_factor, epoc_estimate: peak_epoc }
+    }
+
+    fn credit(&mut self, zone: ZoneName, heart_rate: f32, minutes: f32) {
+        *self.zone_durations.entry(zone).or_insert(0.0) += minutes;
+        // Consecutive time at the same heart rate adds up to the same training load as one interval,
+        // so a steady stretch of a long recording takes a single entry
+        match self.credited_intervals.last_mut() {
+            Some((last_heart_rate, last_minutes)) if *last_heart_rate == heart_rate => *last_minutes += minutes,
+            _ => self.credited_intervals.push((heart_rate, minutes)),
+        }
+        // Count time in aerobic zones
+        if matches!(zone, ZoneName::Zone3 | ZoneName::Zone4 | ZoneName::Zone5) {
+            self.time_above_aerobic_threshold += minutes;
+        }
+    }
+}
+
+/// Builds a `WorkoutAnalyzer` from heart rate samples pushed one at a time in chronological order,
+/// so a recording can be analyzed as it's read without holding all of its samples
+pub struct IncrementalWorkoutAnalyzer<'a> {
+    zones: &'a HeartRateZones,
+    gaps: GapHandling,
+    analyzer: WorkoutAnalyzer,
+    first_sample: Option<HeartRateData>,
+    previous_sample: Option<HeartRateData>,
+    previous_zone: Option<ZoneName>,
+    sample_count: usize,
+    hr_sum: f32,
+    /// Running mean and sum of squared deviations of the heart rate (Welford) for the HRV
+    hr_mean: f64,
+    hr_squared_deviations: f64,
+    credited_min: f32,
+}
+
+impl<'a> IncrementalWorkoutAnalyzer<'a> {
+    pub fn new(zones: &'a HeartRateZones, gaps: GapHandling) -> Self {
+        Self {
+            zones,
+            gaps,
+            analyzer: WorkoutAnalyzer {
+                total_duration_min: 0,
+                zone_durations: HashMap::new(),
+                avg_heart_rate: 0.0,
+                peak_heart_rate: 0.0,
+                unknown_duration_min: 0.0,
+                coverage_percent: 100.0,
+                time_above_aerobic_threshold: 0.0,
+                heart_rate_variability: 0.0,
+                zone_changes: 0,
+                credited_intervals: Vec::new(),
+                gaps,
+            },
+            first_sample: None,
+            previous_sample: None,
+            previous_zone: None,
+            sample_count: 0,
+            hr_sum: 0.0,
+            hr_mean: 0.0,
+            hr_squared_deviations: 0.0,
+            credited_min: 0.0,
+        }
+    }
+
+    /// Add the next sample. Samples must not be older than the previous one.
+    pub fn push(&mut self, hr_data: &HeartRateData) {
+        let hr = hr_data.heart_rate;
+
+        // Statistics
+        self.sample_count += 1;
+        self.hr_sum += hr as f32;
+        self.analyzer.peak_heart_rate = self.analyzer.peak_heart_rate.max(hr as f32);
+        let delta = hr as f64 - self.hr_mean;
+        self.hr_mean += delta / self.sample_count as f64;
+        self.hr_squared_deviations += delta * (hr as f64 - self.hr_mean);
+
+        let zone = self.zones.get_zone(hr as f32);
+
+        // Each sample is credited with the interval since the previous one, so the credited time
+        // never exceeds the time between the first and the last sample
+        if let Some(previous) = self.previous_sample.clone() {
+            let duration_sec = (hr_data.timestamp - previous.timestamp).num_seconds() as f32;
+
+            // A short gap after the previous sample is spread along the line between the two readings
+            if duration_sec > self.gaps.max_credited_interval_sec && duration_sec <= self.gaps.interpolate_up_to_sec {
+                let previous_hr = previous.heart_rate;
+                let steps = duration_sec.ceil() as i32;
+                for step in 0..steps {
+                    let slice_sec = (duration_sec - step as f32).min(1.0);
+                    let progress = (step as f32 + slice_sec / 2.0) / duration_sec;
+                    let interpolated_hr = previous_hr as f32 + (hr - previous_hr) as f32 * progress;
+                    if let Some(slice_zone) = self.zones.get_zone(interpolated_hr.round()) {
+                        self.analyzer.credit(slice_zone, interpolated_hr, slice_sec / 60.0);
                     }
-                    _ => {}
                 }
+                self.credited_min += duration_sec / 60.0;
+            } else {
+                let credited_sec = duration_sec.min(self.gaps.max_credited_interval_sec);
+                self.credited_min += credited_sec / 60.0;
+                self.analyzer.unknown_duration_min += (duration_sec - credited_sec) / 60.0;
 
-                // Count zone changes
-                if let Some(prev_zone) = prev_zone {
-                    if prev_zone != zone_name {
-                        analyzer.zone_changes += 1;
-                    }
+                // Process zone data - now all heart rates should fall into a zone since Zone1 starts at 0
+                if let Some(zone_name) = zone {
+                    self.analyzer.credit(zone_name, hr as f32, credited_sec / 60.0);
                 }
-                prev_zone = Some(zone_name);
+            }
+        } else {
+            self.first_sample = Some(hr_data.clone());
+        }
 
+        if let Some(zone_name) = zone {
+            // Count zone changes
+            if let Some(previous_zone) = self.previous_zone {
+                if previous_zone != zone_name {
+                    self.analyzer.zone_changes += 1;
+                }
             }
+            self.previous_zone = Some(zone_name);
+        }
+        self.previous_sample = Some(hr_data.clone());
+    }
+
+    /// Finish the analysis, `None` if fewer than two samples were pushed, as a single sample covers no time
+    pub fn finish(self) -> Option<WorkoutAnalyzer> {
+        let first_sample = self.first_sample?;
+        let last_sample = self.previous_sample?;
+        if self.sample_count < 2 {
+            return None;
+        }
+
+        let mut analyzer = self.analyzer;
+        analyzer.total_duration_min = (last_sample.timestamp - first_sample.timestamp).num_seconds() as i32 / 60; // minutes
+
+        let recorded_min = self.credited_min + analyzer.unknown_duration_min;
+        if recorded_min > 0.0 {
+            analyzer.coverage_percent = self.credited_min / recorded_min * 100.0;
         }
 
-        analyzer.avg_heart_rate = hr_sum / sorted_data.len() as f32;
-        analyzer.heart_rate_variability = calc_hrv(&hr_values_for_hrv);
+        analyzer.avg_heart_rate = self.hr_sum / self.sample_count as f32;
+        if self.sample_count >= 2 {
+            analyzer.heart_rate_variability = (self.hr_squared_deviations / self.sample_count as f64).sqrt() as f32;
+        }
 
         Some(analyzer)
     }
 }
 
-fn calc_hrv(hr_values: &Vec<i32>) -> f32 {
-    if hr_values.len() < 2 {
-        return 0.0;
+/// Zone number used as the Edwards TRIMP weight
+fn zone_number(zone: ZoneName) -> f32 {
+    match zone {
+        ZoneName::Zone1 => 1.0,
+        ZoneName::Zone2 => 2.0,
+        ZoneName::Zone3 => 3.0,
+        ZoneName::Zone4 => 4.0,
+        ZoneName::Zone5 => 5.0,
     }
-
-    let mean = hr_values.iter().sum::<i32>() as f32 / hr_values.len() as f32;
-    let variance = hr_values.iter()
-        .map(|hr| (*hr as f32 - mean).powf(2.0))
-        .sum::<f32>() / hr_values.len() as f32;
-    
-    variance.sqrt()
-}
\ No newline at end of file
+}
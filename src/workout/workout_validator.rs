@@ -0,0 +1,520 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::models::workout_data::{
+    HeartRateData, ValidationIssue, ValidationReasonCode, ValidationSeverity, WorkoutDataSyncRequest,
+};
+
+/// Heart rates outside this range can't come from a working sensor on a living person
+const MIN_PLAUSIBLE_HEART_RATE: i32 = 25;
+const MAX_PLAUSIBLE_HEART_RATE: i32 = 240;
+/// Allowed clock skew between the client and the server
+const FUTURE_TOLERANCE_MINUTES: i64 = 5;
+/// Devices record at most one sample per second
+const MAX_SAMPLES_PER_SECOND: f64 = 1.0;
+/// Longer workouts need at least one sample per this many minutes on average
+const SPARSE_SAMPLE_INTERVAL_MINUTES: i64 = 10;
+const SPARSE_MIN_DURATION_MINUTES: i64 = 30;
+/// Tolerance above a measured max heart rate, and above the age-based estimate
+const MEASURED_MAX_HR_TOLERANCE: i32 = 10;
+const ESTIMATED_MAX_HR_TOLERANCE: i32 = 25;
+/// Share of samples allowed above the max heart rate limit before flagging
+const MAX_HR_EXCEEDED_RATIO: f64 = 0.02;
+/// Time allowed at or above 90% of max heart rate before flagging
+const SUSTAINED_MAX_EFFORT_MINUTES: i64 = 90;
+/// A single unchanged heart rate held for this long is a flat-lined sensor or fabricated data
+const FLATLINE_MINUTES: i64 = 10;
+/// Runs of identical non-zero deltas (perfect ramps) of this length look generated
+const SYNTHETIC_RUN_SAMPLES: usize = 30;
+const SYNTHETIC_RUN_MINUTES: i64 = 10;
+/// Upper bounds for the other sensor streams, well above elite sprint and descent values
+const MAX_PLAUSIBLE_POWER_WATTS: i32 = 2500;
+const MAX_PLAUSIBLE_CADENCE: i32 = 300;
+const MAX_PLAUSIBLE_SPEED_MPS: f64 = 40.0;
+/// Faster than a sprinter's cadence, sustained over the whole workout
+const MAX_PLAUSIBLE_STEPS_PER_MINUTE: i64 = 300;
+/// Bounds for logged strength sets, above any lift outside of a strongman competition
+const MAX_PLAUSIBLE_REPS: i32 = 100;
+const MAX_PLAUSIBLE_SET_WEIGHT_KG: f64 = 500.0;
+/// More sets than this in one session get a second look
+const MAX_PLAUSIBLE_STRENGTH_SETS: usize = 60;
+/// A working set and its rest take at least a minute, even in a superset
+const MAX_PLAUSIBLE_SETS_PER_MINUTE: f64 = 1.0;
+/// Workouts without heart rate data are scored from their duration and calories alone, so
+/// longer ones get a second look and ones spanning more than a day can't be a single session
+const UNMONITORED_REVIEW_HOURS: i64 = 4;
+const UNMONITORED_MAX_HOURS: i64 = 24;
+/// More than an elite endurance athlete burns at full effort
+const MAX_PLAUSIBLE_KCAL_PER_MINUTE: f64 = 25.0;
+
+/// The user's max heart rate, and whether it was measured or estimated from age
+#[derive(Debug, Clone, Copy)]
+pub struct MaxHeartRate {
+    pub bpm: i32,
+    pub measured: bool,
+}
+
+impl MaxHeartRate {
+    fn limit(&self) -> i32 {
+        if self.measured {
+            self.bpm + MEASURED_MAX_HR_TOLERANCE
+        } else {
+            self.bpm + ESTIMATED_MAX_HR_TOLERANCE
+        }
+    }
+}
+
+/// Result of validating a workout before it is scored
+#[derive(Debug, Default)]
+pub struct WorkoutValidation {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl WorkoutValidation {
+    pub fn is_rejected(&self) -> bool {
+        self.issues.iter().any(|issue| issue.severity == ValidationSeverity::Reject)
+    }
+
+    pub fn is_flagged(&self) -> bool {
+        self.issues.iter().any(|issue| issue.severity == ValidationSeverity::Flag)
+    }
+
+    /// Human readable summary of all issues, used for error responses and logs
+    pub fn summary(&self) -> String {
+        self.issues.iter()
+            .map(|issue| issue.message.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+
+    fn reject(&mut self, code: ValidationReasonCode, message: String) {
+        self.issues.push(ValidationIssue { code, severity: ValidationSeverity::Reject, message });
+    }
+
+    fn flag(&mut self, code: ValidationReasonCode, message: String) {
+        self.issues.push(ValidationIssue { code, severity: ValidationSeverity::Flag, message });
+    }
+}
+
+/// Plausibility checks run on uploaded workouts before they are scored
+pub struct WorkoutValidator;
+
+impl WorkoutValidator {
+    pub fn validate(
+        workout: &WorkoutDataSyncRequest,
+        max_heart_rate: MaxHeartRate,
+        now: DateTime<Utc>,
+    ) -> WorkoutValidation {
+        let heart_rate = workout.heart_rate.as_ref()
+            .filter(|samples| !samples.is_empty())
+            .map(|samples| HeartRateChecks::of(samples, max_heart_rate));
+        Self::validate_with_heart_rate(workout, heart_rate.as_ref(), now)
+    }
+
+    /// `validate` with the heart rate checked as it was streamed, for uploads whose samples
+    /// aren't carried in the payload
+    pub fn validate_with_heart_rate(
+        workout: &WorkoutDataSyncRequest,
+        heart_rate: Option<&HeartRateChecks>,
+        now: DateTime<Utc>,
+    ) -> WorkoutValidation {
+        let mut validation = WorkoutValidation::default();
+        let latest_allowed = now + Duration::minutes(FUTURE_TOLERANCE_MINUTES);
+
+        let first_sample = heart_rate.and_then(|checks| checks.earliest);
+        if let Some(start) = workout.workout_start.or(first_sample) {
+            if start > latest_allowed {
+                validation.reject(
+                    ValidationReasonCode::FutureWorkout,
+                    format!("Workout starts in the future ({})", start),
+                );
+            }
+        }
+
+        Self::check_streams(workout, &mut validation);
+        Self::check_strength_sets(workout, &mut validation);
+
+        let Some(heart_rate) = heart_rate.filter(|checks| checks.sample_count > 0) else {
+            Self::check_unmonitored_effort(workout, &mut validation);
+            return validation;
+        };
+
+        heart_rate.check_bounds(&mut validation);
+        heart_rate.check_timestamps(&mut validation);
+
+        // The remaining checks assume a clean, ordered series
+        if validation.is_rejected() {
+            return validation;
+        }
+
+        heart_rate.check_density(&mut validation);
+        heart_rate.check_max_heart_rate(&mut validation);
+        heart_rate.check_flatline(&mut validation);
+        heart_rate.check_synthetic_pattern(&mut validation);
+
+        validation
+    }
+
+    /// Power, cadence, speed, distance and step samples must be non-negative and physically possible
+    fn check_streams(workout: &WorkoutDataSyncRequest, validation: &mut WorkoutValidation) {
+        let out_of_bounds = |name: &str, count: usize, range: String| {
+            (count > 0).then(|| format!("{} {} samples outside {}", count, name, range))
+        };
+
+        let problems = [
+            out_of_bounds(
+                "power",
+                workout.power.iter().flatten()
+                    .filter(|sample| !(0..=MAX_PLAUSIBLE_POWER_WATTS).contains(&sample.watts))
+                    .count(),
+                format!("0-{} W", MAX_PLAUSIBLE_POWER_WATTS),
+            ),
+            out_of_bounds(
+                "cadence",
+                workout.cadence.iter().flatten()
+                    .filter(|sample| !(0..=MAX_PLAUSIBLE_CADENCE).contains(&sample.cadence))
+                    .count(),
+                format!("0-{} per minute", MAX_PLAUSIBLE_CADENCE),
+            ),
+            out_of_bounds(
+                "speed",
+                workout.speed.iter().flatten()
+                    .filter(|sample| !(0.0..=MAX_PLAUSIBLE_SPEED_MPS).contains(&sample.meters_per_second))
+                    .count(),
+                format!("0-{} m/s", MAX_PLAUSIBLE_SPEED_MPS),
+            ),
+            out_of_bounds(
+                "distance",
+                workout.distance.iter().flatten()
+                    .filter(|sample| !sample.meters.is_finite() || sample.meters < 0.0)
+                    .count(),
+                "non-negative meters".to_string(),
+            ),
+            out_of_bounds(
+                "step",
+                workout.steps.iter().flatten()
+                    .filter(|sample| sample.steps < 0)
+                    .count(),
+                "non-negative counts".to_string(),
+            ),
+        ];
+
+        for message in problems.into_iter().flatten() {
+            validation.reject(ValidationReasonCode::StreamOutOfBounds, message);
+        }
+
+        Self::check_step_rate(workout, validation);
+    }
+
+    /// The steps summed over the workout must be possible in its duration
+    fn check_step_rate(workout: &WorkoutDataSyncRequest, validation: &mut WorkoutValidation) {
+        let Some(steps) = workout.steps.as_ref().filter(|steps| !steps.is_empty()) else {
+            return;
+        };
+
+        let total_steps: i64 = steps.iter().map(|sample| sample.steps.max(0) as i64).sum();
+        let (start, end) = match (workout.workout_start, workout.workout_end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => (
+                steps.iter().map(|sample| sample.timestamp).min().unwrap_or_default(),
+                steps.iter().map(|sample| sample.timestamp).max().unwrap_or_default(),
+            ),
+        };
+        let minutes = (end - start).num_minutes().max(1);
+
+        if total_steps > MAX_PLAUSIBLE_STEPS_PER_MINUTE * minutes {
+            validation.reject(
+                ValidationReasonCode::StreamOutOfBounds,
+                format!(
+                    "{} steps in {} minutes, more than {} per minute",
+                    total_steps, minutes, MAX_PLAUSIBLE_STEPS_PER_MINUTE
+                ),
+            );
+        }
+    }
+
+    /// Logged strength sets need possible reps, load and RPE, and a session of a believable size
+    fn check_strength_sets(workout: &WorkoutDataSyncRequest, validation: &mut WorkoutValidation) {
+        let sets: Vec<_> = workout.strength_exercises.iter().flatten()
+            .flat_map(|exercise| &exercise.sets)
+            .collect();
+
+        let out_of_bounds = sets.iter()
+            .filter(|set| {
+                !(1..=MAX_PLAUSIBLE_REPS).contains(&set.reps)
+                    || !(0.0..=MAX_PLAUSIBLE_SET_WEIGHT_KG).contains(&set.weight_kg)
+                    || set.rpe.is_some_and(|rpe| !(1.0..=10.0).contains(&rpe))
+            })
+            .count();
+        if out_of_bounds > 0 {
+            validation.reject(
+                ValidationReasonCode::StrengthSetOutOfBounds,
+                format!(
+                    "{} strength sets outside 1-{} reps, 0-{} kg or RPE 1-10",
+                    out_of_bounds, MAX_PLAUSIBLE_REPS, MAX_PLAUSIBLE_SET_WEIGHT_KG
+                ),
+            );
+        }
+
+        if sets.len() > MAX_PLAUSIBLE_STRENGTH_SETS {
+            validation.flag(
+                ValidationReasonCode::ImplausibleStrengthVolume,
+                format!("{} strength sets in one session, more than {}", sets.len(), MAX_PLAUSIBLE_STRENGTH_SETS),
+            );
+        }
+
+        if let (Some(start), Some(end)) = (workout.workout_start, workout.workout_end) {
+            let minutes = ((end - start).num_seconds() as f64 / 60.0).max(1.0);
+            if sets.len() as f64 > MAX_PLAUSIBLE_SETS_PER_MINUTE * minutes {
+                validation.flag(
+                    ValidationReasonCode::ImplausibleStrengthVolume,
+                    format!(
+                        "{} strength sets in {:.0} minutes, more than {} per minute",
+                        sets.len(), minutes, MAX_PLAUSIBLE_SETS_PER_MINUTE
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Without heart rate data the duration and calories are all there is to score, so both must be believable
+    fn check_unmonitored_effort(workout: &WorkoutDataSyncRequest, validation: &mut WorkoutValidation) {
+        let (Some(start), Some(end)) = (workout.workout_start, workout.workout_end) else {
+            return;
+        };
+        if end <= start {
+            return;
+        }
+        let minutes = (end - start).num_seconds() as f64 / 60.0;
+
+        if minutes > (UNMONITORED_MAX_HOURS * 60) as f64 {
+            validation.reject(
+                ValidationReasonCode::ImplausibleDuration,
+                format!(
+                    "{:.0} hours without heart rate data, more than {}",
+                    minutes / 60.0, UNMONITORED_MAX_HOURS
+                ),
+            );
+        } else if minutes > (UNMONITORED_REVIEW_HOURS * 60) as f64 {
+            validation.flag(
+                ValidationReasonCode::ImplausibleDuration,
+                format!(
+                    "{:.1} hours without heart rate data, more than {}",
+                    minutes / 60.0, UNMONITORED_REVIEW_HOURS
+                ),
+            );
+        }
+
+        let calories = workout.calories_burned.unwrap_or(0);
+        if calories as f64 > MAX_PLAUSIBLE_KCAL_PER_MINUTE * minutes.max(1.0) {
+            validation.reject(
+                ValidationReasonCode::ImplausibleCalories,
+                format!(
+                    "{} kcal in {:.0} minutes, more than {} per minute",
+                    calories, minutes, MAX_PLAUSIBLE_KCAL_PER_MINUTE
+                ),
+            );
+        }
+    }
+}
+
+/// The heart rate checks of the validator, tallied one sample at a time in the order they were
+/// recorded, so a long series can be checked while it's streamed instead of held in memory
+#[derive(Debug)]
+pub struct HeartRateChecks {
+    max_heart_rate: MaxHeartRate,
+    sample_count: usize,
+    first: Option<DateTime<Utc>>,
+    earliest: Option<DateTime<Utc>>,
+    previous: Option<HeartRateData>,
+    previous_delta: Option<i32>,
+    out_of_bounds: usize,
+    duplicates: usize,
+    backwards: usize,
+    above_limit: usize,
+    max_effort_seconds: i64,
+    /// Start time and heart rate of the current run of unchanged samples
+    flatline_start: Option<(DateTime<Utc>, i32)>,
+    longest_flatline: Duration,
+    /// Index and time of the first sample of the current run of identical deltas
+    ramp_start: (usize, DateTime<Utc>),
+    longest_ramp_samples: usize,
+    longest_ramp_span: Duration,
+}
+
+impl HeartRateChecks {
+    pub fn new(max_heart_rate: MaxHeartRate) -> Self {
+        Self {
+            max_heart_rate,
+            sample_count: 0,
+            first: None,
+            earliest: None,
+            previous: None,
+            previous_delta: None,
+            out_of_bounds: 0,
+            duplicates: 0,
+            backwards: 0,
+            above_limit: 0,
+            max_effort_seconds: 0,
+            flatline_start: None,
+            longest_flatline: Duration::zero(),
+            ramp_start: (0, DateTime::<Utc>::MIN_UTC),
+            longest_ramp_samples: 0,
+            longest_ramp_span: Duration::zero(),
+        }
+    }
+
+    /// The checks of a whole series at once
+    pub fn of(samples: &[HeartRateData], max_heart_rate: MaxHeartRate) -> Self {
+        let mut checks = Self::new(max_heart_rate);
+        for sample in samples {
+            checks.push(sample);
+        }
+        checks
+    }
+
+    pub fn push(&mut self, sample: &HeartRateData) {
+        let index = self.sample_count;
+        self.sample_count += 1;
+        self.earliest = Some(self.earliest.map_or(sample.timestamp, |earliest| earliest.min(sample.timestamp)));
+
+        if sample.heart_rate < MIN_PLAUSIBLE_HEART_RATE || sample.heart_rate > MAX_PLAUSIBLE_HEART_RATE {
+            self.out_of_bounds += 1;
+        }
+        if sample.heart_rate > self.max_heart_rate.limit() {
+            self.above_limit += 1;
+        }
+
+        let Some(previous) = self.previous.replace(sample.clone()) else {
+            self.first = Some(sample.timestamp);
+            self.flatline_start = Some((sample.timestamp, sample.heart_rate));
+            self.ramp_start = (0, sample.timestamp);
+            return;
+        };
+
+        if sample.timestamp == previous.timestamp {
+            self.duplicates += 1;
+        } else if sample.timestamp < previous.timestamp {
+            self.backwards += 1;
+        }
+
+        if previous.heart_rate >= self.max_effort_threshold() {
+            self.max_effort_seconds += (sample.timestamp - previous.timestamp).num_seconds();
+        }
+
+        match self.flatline_start {
+            Some((start, heart_rate)) if heart_rate == sample.heart_rate => {
+                self.longest_flatline = self.longest_flatline.max(sample.timestamp - start);
+            }
+            _ => self.flatline_start = Some((sample.timestamp, sample.heart_rate)),
+        }
+
+        let delta = sample.heart_rate - previous.heart_rate;
+        if let Some(previous_delta) = self.previous_delta {
+            if delta == 0 || delta != previous_delta {
+                self.ramp_start = (index - 1, previous.timestamp);
+            } else {
+                let run_samples = index - self.ramp_start.0 + 1;
+                if run_samples > self.longest_ramp_samples {
+                    self.longest_ramp_samples = run_samples;
+                    self.longest_ramp_span = sample.timestamp - self.ramp_start.1;
+                }
+            }
+        }
+        self.previous_delta = Some(delta);
+    }
+
+    /// Number of samples checked
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    fn max_effort_threshold(&self) -> i32 {
+        (self.max_heart_rate.bpm as f32 * 0.9) as i32
+    }
+
+    fn check_bounds(&self, validation: &mut WorkoutValidation) {
+        if self.out_of_bounds > 0 {
+            validation.reject(
+                ValidationReasonCode::HeartRateOutOfBounds,
+                format!("{} heart rate samples outside {}-{} bpm", self.out_of_bounds, MIN_PLAUSIBLE_HEART_RATE, MAX_PLAUSIBLE_HEART_RATE),
+            );
+        }
+    }
+
+    fn check_timestamps(&self, validation: &mut WorkoutValidation) {
+        if self.duplicates > 0 {
+            validation.reject(
+                ValidationReasonCode::DuplicateTimestamps,
+                format!("{} heart rate samples share a timestamp with the previous sample", self.duplicates),
+            );
+        }
+
+        if self.backwards > 0 {
+            validation.reject(
+                ValidationReasonCode::NonMonotonicTimestamps,
+                format!("{} heart rate samples are earlier than the previous sample", self.backwards),
+            );
+        }
+    }
+
+    fn check_density(&self, validation: &mut WorkoutValidation) {
+        let (Some(first), Some(last)) = (self.first, self.previous.as_ref()) else {
+            return;
+        };
+        let span = last.timestamp - first;
+        let span_seconds = span.num_seconds().max(1) as f64;
+
+        let samples_per_second = (self.sample_count - 1) as f64 / span_seconds;
+        if self.sample_count > 1 && samples_per_second > MAX_SAMPLES_PER_SECOND {
+            validation.reject(
+                ValidationReasonCode::ImplausibleSampleDensity,
+                format!("{:.1} heart rate samples per second exceeds what devices record", samples_per_second),
+            );
+            return;
+        }
+
+        if span >= Duration::minutes(SPARSE_MIN_DURATION_MINUTES) {
+            let expected_samples = span.num_minutes() / SPARSE_SAMPLE_INTERVAL_MINUTES;
+            if (self.sample_count as i64) < expected_samples {
+                validation.flag(
+                    ValidationReasonCode::SparseSamples,
+                    format!("Only {} heart rate samples over {} minutes", self.sample_count, span.num_minutes()),
+                );
+            }
+        }
+    }
+
+    fn check_max_heart_rate(&self, validation: &mut WorkoutValidation) {
+        if self.above_limit as f64 / self.sample_count as f64 > MAX_HR_EXCEEDED_RATIO {
+            validation.flag(
+                ValidationReasonCode::ExceedsMaxHeartRate,
+                format!("{} samples above the user's max heart rate of {} bpm", self.above_limit, self.max_heart_rate.bpm),
+            );
+        }
+
+        if self.max_effort_seconds > SUSTAINED_MAX_EFFORT_MINUTES * 60 {
+            validation.flag(
+                ValidationReasonCode::SustainedMaxEffort,
+                format!("{} minutes at or above {} bpm", self.max_effort_seconds / 60, self.max_effort_threshold()),
+            );
+        }
+    }
+
+    fn check_flatline(&self, validation: &mut WorkoutValidation) {
+        if self.longest_flatline >= Duration::minutes(FLATLINE_MINUTES) {
+            validation.flag(
+                ValidationReasonCode::Flatline,
+                format!("Heart rate unchanged for {} minutes", self.longest_flatline.num_minutes()),
+            );
+        }
+    }
+
+    fn check_synthetic_pattern(&self, validation: &mut WorkoutValidation) {
+        if self.longest_ramp_samples >= SYNTHETIC_RUN_SAMPLES && self.longest_ramp_span >= Duration::minutes(SYNTHETIC_RUN_MINUTES) {
+            validation.flag(
+                ValidationReasonCode::SyntheticPattern,
+                format!("{} consecutive samples change by exactly the same amount", self.longest_ramp_samples),
+            );
+        }
+    }
+}
@@ -7,7 +7,7 @@ use reqwest::Client;
 
 mod common;
 use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
-use common::workout_data_helpers;
+use common::workout_data_helpers::{self, upload_workout_data_for_user};
 use common::admin_helpers::create_admin_user_and_login;
 
 #[tokio::test]
@@ -22,18 +22,11 @@ async fn test_admin_can_delete_workout() {
     // Create a workout for the user using workout data helpers
     let workout_data = workout_data_helpers::create_advanced_workout_data();
 
-    let workout_response = client
-    .post(&format!("{}/health/upload_health", &test_app.address))
-    .header("Authorization", format!("Bearer {}", user.token))
-    .json(&workout_data)
-    .send()
-    .await
-    .expect("Failed to execute health upload request.");
-
-    assert!(workout_response.status().is_success(), "Workout upload should succeed");
+    let response_data = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout_data.clone())
+        .await
+        .expect("Workout upload should be processed");
 
-    let response_data = workout_response.json::<serde_json::Value>().await.unwrap();
-    let sync_id = response_data["data"]["sync_id"].as_str().unwrap();
+    let sync_id = response_data["result"]["sync_id"].as_str().unwrap();
 
     // Admin deletes the workout
     let delete_response = client
@@ -67,43 +60,24 @@ async fn test_admin_can_bulk_delete_workouts() {
 
     // Create multiple workouts
     let workout1 = workout_data_helpers::create_advanced_workout_data();
-    let workout2 = workout_data_helpers::create_advanced_workout_data();
-    let workout3 = workout_data_helpers::create_advanced_workout_data();
+    let workout2 = workout_data_helpers::workout_hours_ago(workout_data_helpers::create_advanced_workout_data(), 1);
+    let workout3 = workout_data_helpers::workout_hours_ago(workout_data_helpers::create_advanced_workout_data(), 2);
 
-    let workout_response1 = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout1)
-        .send()
+    let response_data1 = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout1.clone())
         .await
-        .expect("Failed to execute health upload request.");
+        .expect("Workout upload should be processed");
 
-    let workout_response2 = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout2)
-        .send()
+    let response_data2 = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout2.clone())
         .await
-        .expect("Failed to execute health upload request.");
+        .expect("Workout upload should be processed");
 
-    let workout_response3 = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout3)
-        .send()
+    let response_data3 = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout3.clone())
         .await
-        .expect("Failed to execute health upload request.");
+        .expect("Workout upload should be processed");
 
-    assert!(workout_response1.status().is_success(), "Workout upload should succeed");
-    assert!(workout_response2.status().is_success(), "Workout upload should succeed");
-    assert!(workout_response3.status().is_success(), "Workout upload should succeed");
-
-    let response_data1 = workout_response1.json::<serde_json::Value>().await.unwrap();
-    let sync_id1 = response_data1["data"]["sync_id"].as_str().unwrap();
-    let response_data2 = workout_response2.json::<serde_json::Value>().await.unwrap();
-    let sync_id2 = response_data2["data"]["sync_id"].as_str().unwrap();
-    let response_data3 = workout_response3.json::<serde_json::Value>().await.unwrap();
-    let sync_id3 = response_data3["data"]["sync_id"].as_str().unwrap();
+    let sync_id1 = response_data1["result"]["sync_id"].as_str().unwrap();
+    let sync_id2 = response_data2["result"]["sync_id"].as_str().unwrap();
+    let sync_id3 = response_data3["result"]["sync_id"].as_str().unwrap();
 
     // Admin bulk deletes workouts 1 and 2
     let delete_response = client
@@ -178,18 +152,11 @@ async fn test_non_admin_cannot_delete_workouts() {
     // Create a workout
     let workout_data = workout_data_helpers::create_advanced_workout_data();
 
-    let workout_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout_data)
-        .send()
+    let response_data = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(workout_response.status().is_success(), "Workout upload should succeed");
+        .expect("Workout upload should be processed");
 
-    let response_data = workout_response.json::<serde_json::Value>().await.unwrap();
-    let sync_id = response_data["data"]["sync_id"].as_str().unwrap();
+    let sync_id = response_data["result"]["sync_id"].as_str().unwrap();
 
     // Regular user tries to delete workout
     let delete_response = client
@@ -226,28 +193,15 @@ async fn test_admin_can_list_workouts() {
     let workout1 = workout_data_helpers::create_advanced_workout_data();
     let workout2 = workout_data_helpers::create_advanced_workout_data();
 
-    let workout_response1 = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user1.token))
-        .json(&workout1)
-        .send()
+    let response_data1 = upload_workout_data_for_user(&client, &test_app.address, &user1.token, workout1.clone())
         .await
-        .expect("Failed to execute health upload request.");
+        .expect("Workout upload should be processed");
 
-    let workout_response2 = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user2.token))
-        .json(&workout2)
-        .send()
+    let response_data2 = upload_workout_data_for_user(&client, &test_app.address, &user2.token, workout2.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(workout_response1.status().is_success(), "Workout upload should succeed");
-    assert!(workout_response2.status().is_success(), "Workout upload should succeed");
+        .expect("Workout upload should be processed");
 
-    let response_data1 = workout_response1.json::<serde_json::Value>().await.unwrap();
-    let sync_id1 = response_data1["data"]["sync_id"].as_str().unwrap();
-    let response_data2 = workout_response2.json::<serde_json::Value>().await.unwrap();
+    let sync_id1 = response_data1["result"]["sync_id"].as_str().unwrap();
     // Admin lists all workouts
     let list_response = client
         .get(&format!("{}/admin/workouts?limit=10", &test_app.address))
@@ -289,18 +243,11 @@ async fn test_admin_can_view_workout_details() {
 
     let workout_data = workout_data_helpers::create_advanced_workout_data();
 
-    let workout_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout_data)
-        .send()
+    let response_data = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(workout_response.status().is_success(), "Workout upload should succeed");
+        .expect("Workout upload should be processed");
 
-    let response_data = workout_response.json::<serde_json::Value>().await.unwrap();
-    let sync_id1 = response_data["data"]["sync_id"].as_str().unwrap();
+    let sync_id1 = response_data["result"]["sync_id"].as_str().unwrap();
 
     // Admin views workout details
     let get_response = client
@@ -335,18 +282,11 @@ async fn test_workout_cascade_deletes_with_user() {
     // Create workouts
     let workout_data = workout_data_helpers::create_advanced_workout_data();
 
-    let workout_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout_data)
-        .send()
+    let response_data = upload_workout_data_for_user(&client, &test_app.address, &user.token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");    
-
-    assert!(workout_response.status().is_success(), "Workout upload should succeed");
+        .expect("Workout upload should be processed");    
 
-    let response_data = workout_response.json::<serde_json::Value>().await.unwrap();
-    let sync_id1 = response_data["data"]["sync_id"].as_str().unwrap();
+    let sync_id1 = response_data["result"]["sync_id"].as_str().unwrap();
 
     // Delete the user
     let delete_response = client
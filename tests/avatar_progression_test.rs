@@ -0,0 +1,143 @@
+use futures_util::StreamExt;
+use reqwest::Client;
+use secrecy::ExposeSecret;
+use serde_json::json;
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request, parse_user_id_from_jwt_token};
+use common::workout_data_helpers::upload_workout_data_for_user;
+use evolveme_backend::config::settings::get_config;
+use evolveme_backend::game::progression::level_for_experience;
+
+async fn get_progression(client: &Client, address: &str, token: &str) -> serde_json::Value {
+    let response = make_authenticated_request(
+        client, reqwest::Method::GET, &format!("{}/profile/user", address), token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["data"]["progression"].clone()
+}
+
+async fn set_avatar_style(client: &Client, address: &str, token: &str, style: &str) -> reqwest::StatusCode {
+    make_authenticated_request(
+        client, reqwest::Method::PUT, &format!("{}/profile/avatar/style", address), token,
+        Some(json!({ "avatar_style": style })),
+    ).await.status()
+}
+
+#[tokio::test]
+async fn new_avatars_start_as_level_one_novices_with_the_default_style() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let progression = get_progression(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(progression["level"], 1);
+    assert_eq!(progression["experience"], 0);
+    assert_eq!(progression["level_experience"], 0);
+    assert_eq!(progression["next_level_experience"], 100);
+    assert_eq!(progression["evolution_stage"], "novice");
+    assert_eq!(progression["unlocked_styles"], json!(["warrior"]));
+
+    assert_eq!(set_avatar_style(&client, &test_app.address, &test_user.token, "dragon").await, 400);
+    assert_eq!(set_avatar_style(&client, &test_app.address, &test_user.token, "ranger").await, 403);
+    assert_eq!(set_avatar_style(&client, &test_app.address, &test_user.token, "warrior").await, 200);
+}
+
+/// An hour without heart rate burning 400 kcal: 68 stamina and 34 strength, under the fallback ceiling and the caps
+fn hour_without_heart_rate(ended_hours_ago: i64) -> serde_json::Value {
+    let start = Utc::now() - Duration::hours(ended_hours_ago + 1);
+    json!({
+        "device_id": format!("device-{}", Uuid::new_v4()),
+        "timestamp": start,
+        "calories_burned": 400,
+        "workout_start": start,
+        "workout_end": start + Duration::hours(1),
+        "workout_uuid": Uuid::new_v4().to_string()
+    })
+}
+
+async fn avatar_style(pool: &sqlx::PgPool, user_id: Uuid) -> Option<String> {
+    sqlx::query_scalar("SELECT avatar_style FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn earned_experience_levels_up_the_avatar_and_unlocks_styles() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let user_id = parse_user_id_from_jwt_token(&test_user.token);
+
+    let config = get_config().expect("Failed to read config");
+    let redis_url = format!("redis://:{}@localhost:{}", config.redis.password.expose_secret(), config.redis.port);
+    let redis_client = redis::Client::open(redis_url).expect("Failed to create Redis client");
+    let mut pubsub = redis_client.get_async_connection().await.expect("Failed to connect to Redis").into_pubsub();
+    let user_channel = format!("game:events:user:{}", user_id);
+    pubsub.subscribe(&user_channel).await.expect("Failed to subscribe to user channel");
+
+    // Three workouts on different days are worth 306 experience, just past level 3
+    let mut workout_ids = Vec::new();
+    for ended_hours_ago in [50, 26, 2] {
+        let job = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, hour_without_heart_rate(ended_hours_ago))
+            .await
+            .expect("Upload should succeed");
+        assert_eq!(job["result"]["game_stats"]["stat_changes"]["stamina_change"], 68, "{}", job);
+        assert_eq!(job["result"]["game_stats"]["stat_changes"]["strength_change"], 34, "{}", job);
+        workout_ids.push(job["result"]["sync_id"].as_str().unwrap().to_string());
+    }
+
+    let progression = get_progression(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(progression["experience"], 306);
+    assert_eq!(progression["level"], 3);
+    assert_eq!(level_for_experience(306), 3);
+    assert_eq!(progression["level_experience"], 300);
+    assert_eq!(progression["next_level_experience"], 600);
+    assert_eq!(progression["evolution_stage"], "novice");
+    assert_eq!(progression["unlocked_styles"], json!(["warrior", "ranger"]));
+
+    // Reaching level 2 and then level 3 are both announced
+    let mut messages = pubsub.on_message();
+    let mut level_ups = Vec::new();
+    while let Ok(Some(msg)) = tokio::time::timeout(StdDuration::from_secs(2), messages.next()).await {
+        let payload: serde_json::Value = serde_json::from_str(&msg.get_payload::<String>().unwrap()).unwrap();
+        if payload["event_type"] == "avatar_updated" {
+            level_ups.push(payload);
+        }
+    }
+    assert_eq!(level_ups.len(), 2, "{:?}", level_ups);
+    assert_eq!(level_ups[0]["user_id"], user_id.to_string());
+    assert_eq!(level_ups[0]["progression"]["level"], 2);
+    assert_eq!(level_ups[1]["progression"], progression);
+
+    // Stat decay doesn't cost experience
+    sqlx::query("UPDATE user_avatars SET stamina = 0, strength = 0 WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(get_progression(&client, &test_app.address, &test_user.token).await, progression);
+
+    assert_eq!(set_avatar_style(&client, &test_app.address, &test_user.token, "monk").await, 403);
+    assert_eq!(set_avatar_style(&client, &test_app.address, &test_user.token, "ranger").await, 200);
+    assert_eq!(avatar_style(&test_app.db_pool, user_id).await.as_deref(), Some("ranger"));
+
+    // Retracting a workout takes the avatar back to level 2, which doesn't unlock the ranger
+    let response = make_authenticated_request(
+        &client, reqwest::Method::DELETE, &format!("{}/health/workouts/{}", test_app.address, workout_ids[2]),
+        &test_user.token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+
+    let progression = get_progression(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(progression["experience"], 204);
+    assert_eq!(progression["level"], 2);
+    assert_eq!(progression["unlocked_styles"], json!(["warrior"]));
+    assert_eq!(avatar_style(&test_app.db_pool, user_id).await.as_deref(), Some("warrior"));
+}
@@ -1,3 +1,4 @@
+use chrono::{Duration, NaiveTime, Utc, Weekday};
 use evolveme_backend::league::league;
 use reqwest::Client;
 use serde_json::json;
@@ -5,7 +6,10 @@ use uuid::Uuid;
 use sqlx::PgPool;
 
 use crate::common::utils::{
+    TestApp,
     UserRegLoginResponse,
+    create_test_user_and_login,
+    get_next_date,
     parse_user_id_from_jwt_token,
     make_authenticated_request
 };
@@ -279,4 +283,46 @@ pub async fn add_user_to_team(app_address: &str, admin_token: &str, team_id: &st
     ).await;
 
     assert!(response.status().is_success());
-}
\ No newline at end of file
+}
+
+/// Two teams of one user each playing a game that is live right now. Returns the first team's player and the game
+pub async fn setup_live_game(test_app: &TestApp, season_name: &str) -> (UserRegLoginResponse, Uuid) {
+    let admin = create_admin_user_and_login(&test_app.address).await;
+    let league_id = create_league(&test_app.address, &admin.token, 2).await;
+    let team_ids = create_teams_for_test(&test_app.address, &admin.token, 2).await;
+
+    let player = create_test_user_and_login(&test_app.address).await;
+    let opponent = create_test_user_and_login(&test_app.address).await;
+    add_user_to_team(&test_app.address, &admin.token, &team_ids[0], player.user_id).await;
+    add_user_to_team(&test_app.address, &admin.token, &team_ids[1], opponent.user_id).await;
+    add_team_to_league(&test_app.address, &admin.token, &league_id, &team_ids[0]).await;
+    add_team_to_league(&test_app.address, &admin.token, &league_id, &team_ids[1]).await;
+
+    let start_date = get_next_date(Weekday::Sat, NaiveTime::from_hms_opt(22, 0, 0).unwrap());
+    let season_id = create_league_season(&test_app.address, &admin.token, &league_id, season_name, &start_date.to_rfc3339()).await;
+
+    let game_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM league_games WHERE season_id = $1 ORDER BY week_number LIMIT 1"
+    )
+    .bind(Uuid::parse_str(&season_id).unwrap())
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to find generated game");
+
+    sqlx::query(
+        "UPDATE league_games SET status = 'in_progress', scheduled_time = $1, week_start_date = $1, week_end_date = $2 WHERE id = $3"
+    )
+    .bind(Utc::now() - Duration::hours(2))
+    .bind(Utc::now() + Duration::hours(2))
+    .bind(game_id)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to start game");
+
+    let live_game_service = evolveme_backend::services::LiveGameService::new(test_app.db_pool.clone(), None);
+    live_game_service.initialize_live_game(game_id)
+        .await
+        .expect("Failed to initialize live game");
+
+    (player, game_id)
+}
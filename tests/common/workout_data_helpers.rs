@@ -164,7 +164,25 @@ pub fn create_elite_workout_data() -> serde_json::Value {
     })
 }
 
-/// Helper function to upload health data for a user
+/// Move a generated workout and all its samples back in time, so several workouts for one user don't overlap
+pub fn workout_hours_ago(mut workout_data: serde_json::Value, hours: i64) -> serde_json::Value {
+    let shift = |value: &mut serde_json::Value| {
+        if let Ok(time) = serde_json::from_value::<chrono::DateTime<Utc>>(value.clone()) {
+            *value = json!(time - Duration::hours(hours));
+        }
+    };
+
+    for field in ["timestamp", "workout_start", "workout_end"] {
+        shift(&mut workout_data[field]);
+    }
+    if let Some(samples) = workout_data["heart_rate"].as_array_mut() {
+        samples.iter_mut().for_each(|sample| shift(&mut sample["timestamp"]));
+    }
+    workout_data
+}
+
+/// Helper function to upload health data for a user and wait for the queue to process it.
+/// Returns the finished upload job, or an error if the upload wasn't accepted or stored.
 pub async fn upload_workout_data_for_user(
     client: &reqwest::Client,
     app_address: &str,
@@ -180,11 +198,83 @@ pub async fn upload_workout_data_for_user(
     ).await;
 
     let status = response.status();
-    if !status.is_success() {
+    if status != reqwest::StatusCode::ACCEPTED {
         let error_body = response.text().await.map_err(|e| e.to_string())?;
         return Err(format!("Health data upload failed with status {}: {}", status, error_body));
     }
 
     let response_data: serde_json::Value = response.json().await.map_err(|e| e.to_string())?;
-    Ok(response_data)
-}
\ No newline at end of file
+    let job_id = response_data["data"]["job_id"].as_str().ok_or("Upload response has no job_id")?;
+
+    let job = wait_for_workout_job(client, app_address, token, job_id).await;
+    if job["status"] != "completed" {
+        return Err(format!("Workout job did not complete: {}", job));
+    }
+    Ok(job)
+}
+
+/// Upload health data and wait for the queue to finish with it, whatever the outcome
+pub async fn upload_workout_and_wait(
+    client: &reqwest::Client,
+    app_address: &str,
+    token: &str,
+    health_data: serde_json::Value,
+) -> serde_json::Value {
+    let response = crate::common::utils::make_authenticated_request(
+        client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", app_address),
+        token,
+        Some(health_data),
+    ).await;
+    assert_eq!(response.status(), 202, "Workout upload should be queued");
+
+    let response_data: serde_json::Value = response.json().await.expect("Failed to parse upload response");
+    let job_id = response_data["data"]["job_id"].as_str().expect("Upload response has no job_id");
+    wait_for_workout_job(client, app_address, token, job_id).await
+}
+
+/// Poll an upload job until the workout queue has finished with it
+pub async fn wait_for_workout_job(
+    client: &reqwest::Client,
+    app_address: &str,
+    token: &str,
+    job_id: &str,
+) -> serde_json::Value {
+    for _ in 0..150 {
+        let response = crate::common::utils::make_authenticated_request(
+            client,
+            reqwest::Method::GET,
+            &format!("{}/health/upload_jobs/{}", app_address, job_id),
+            token,
+            None,
+        ).await;
+        assert_eq!(response.status(), 200, "Failed to fetch workout job");
+
+        let body: serde_json::Value = response.json().await.expect("Failed to parse workout job");
+        let job = body["data"].clone();
+        if job["status"] != "queued" && job["status"] != "processing" {
+            return job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("Workout job {} was not processed in time", job_id);
+}
+
+/// Avatar stamina and strength gained from workouts, leaving out the bonuses of completed daily challenges
+pub async fn avatar_stats_from_workouts(pool: &sqlx::PgPool, user_id: Uuid) -> (i32, i32) {
+    sqlx::query_as(
+        r#"
+        SELECT a.stamina - COALESCE(SUM(c.stamina_bonus), 0)::int,
+               a.strength - COALESCE(SUM(c.strength_bonus), 0)::int
+        FROM user_avatars a
+        LEFT JOIN daily_challenges c ON c.user_id = a.user_id AND c.completed_at IS NOT NULL
+        WHERE a.user_id = $1
+        GROUP BY a.stamina, a.strength
+        "#
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to fetch avatar")
+}
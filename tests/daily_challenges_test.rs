@@ -0,0 +1,202 @@
+use chrono::{Duration, NaiveDate, Timelike, Utc};
+use evolveme_backend::services::DailyChallengeService;
+use reqwest::Client;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request, parse_user_id_from_jwt_token};
+use common::workout_data_helpers::{create_elite_workout_data, upload_workout_and_wait, upload_workout_data_for_user, workout_hours_ago};
+
+async fn get_challenges(client: &Client, address: &str, token: &str) -> Vec<serde_json::Value> {
+    get_challenge_day(client, address, token).await.1
+}
+
+/// The user's current day and its challenges
+async fn get_challenge_day(client: &Client, address: &str, token: &str) -> (NaiveDate, Vec<serde_json::Value>) {
+    let response = make_authenticated_request(
+        client, reqwest::Method::GET, &format!("{}/profile/challenges", address), token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    (
+        serde_json::from_value(body["data"]["date"].clone()).unwrap(),
+        body["data"]["challenges"].as_array().unwrap().clone(),
+    )
+}
+
+async fn set_timezone(client: &Client, address: &str, token: &str, timezone: &str) -> reqwest::Response {
+    make_authenticated_request(
+        client, reqwest::Method::PUT, &format!("{}/profile/timezone", address), token, Some(json!({ "timezone": timezone })),
+    ).await
+}
+
+/// What the scheduled job does every 15 minutes
+async fn run_challenge_job(pool: &sqlx::PgPool) -> usize {
+    DailyChallengeService::new(pool.clone(), None)
+        .assign_due_challenges()
+        .await
+        .expect("Failed to assign daily challenges")
+}
+
+#[tokio::test]
+async fn workouts_complete_daily_challenges_and_award_their_bonus() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let user_id = parse_user_id_from_jwt_token(&test_user.token);
+
+    // Challenges are handed out by the scheduled job, not on first look
+    assert!(get_challenges(&client, &test_app.address, &test_user.token).await.is_empty());
+    assert_eq!(run_challenge_job(&test_app.db_pool).await, 1);
+
+    let challenges = get_challenges(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(challenges.len(), 3);
+    assert!(challenges.iter().all(|challenge| challenge["completed_at"].is_null() && challenge["progress"] == 0.0));
+    assert_eq!(run_challenge_job(&test_app.db_pool).await, 0);
+    let again = get_challenges(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(
+        challenges.iter().map(|challenge| &challenge["id"]).collect::<Vec<_>>(),
+        again.iter().map(|challenge| &challenge["id"]).collect::<Vec<_>>(),
+        "Challenges are assigned once per day"
+    );
+
+    let (stamina_before, strength_before) = avatar_stats(&test_app.db_pool, user_id).await;
+
+    // 35 minutes mostly in Zone 4-5 and 720 kcal
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, create_elite_workout_data())
+        .await
+        .expect("Upload should succeed");
+
+    let challenges = get_challenges(&client, &test_app.address, &test_user.token).await;
+    let mut stamina_bonus = 0;
+    let mut strength_bonus = 0;
+    for challenge in &challenges {
+        let progress = challenge["progress"].as_f64().unwrap();
+        let completed = progress >= challenge["target"].as_f64().unwrap();
+        match challenge["template_key"].as_str().unwrap() {
+            "zone4_10" | "calories_400" => assert!(completed, "{}", challenge),
+            "two_workouts" | "active_45" => assert!(!completed, "{}", challenge),
+            _ => {}
+        }
+        assert_eq!(!challenge["completed_at"].is_null(), completed, "{}", challenge);
+        assert!(progress > 0.0, "Every challenge makes progress: {}", challenge);
+        if completed {
+            stamina_bonus += challenge["stamina_bonus"].as_i64().unwrap();
+            strength_bonus += challenge["strength_bonus"].as_i64().unwrap();
+        }
+    }
+
+    // The avatar gained the workout's stats plus the completed challenges' bonus
+    let workout = sqlx::query("SELECT stamina_gained::int8 AS stamina, strength_gained::int8 AS strength FROM workout_data WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch workout");
+    let (stamina_after, strength_after) = avatar_stats(&test_app.db_pool, user_id).await;
+    assert_eq!(stamina_after - stamina_before, workout.get::<i64, _>("stamina") + stamina_bonus);
+    assert_eq!(strength_after - strength_before, workout.get::<i64, _>("strength") + strength_bonus);
+}
+
+async fn avatar_stats(pool: &sqlx::PgPool, user_id: uuid::Uuid) -> (i64, i64) {
+    let row = sqlx::query("SELECT stamina::int8 AS stamina, strength::int8 AS strength FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to fetch avatar");
+    (row.get("stamina"), row.get("strength"))
+}
+
+#[tokio::test]
+async fn challenges_follow_the_users_own_day() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = set_timezone(&client, &test_app.address, &test_user.token, "Mars/Olympus_Mons").await;
+    assert_eq!(response.status(), 400);
+
+    // Kiritimati is 14 hours ahead of UTC, Pago Pago 11 hours behind, so their days never match
+    for (timezone, offset_hours) in [("Pacific/Kiritimati", 14), ("Pacific/Pago_Pago", -11)] {
+        let response = set_timezone(&client, &test_app.address, &test_user.token, timezone).await;
+        assert_eq!(response.status(), 200);
+
+        run_challenge_job(&test_app.db_pool).await;
+        let (day, challenges) = get_challenge_day(&client, &test_app.address, &test_user.token).await;
+        assert_eq!(day, (Utc::now() + Duration::hours(offset_hours)).date_naive(), "{}", timezone);
+        assert_eq!(challenges.len(), 3, "{}", timezone);
+        assert!(challenges.iter().all(|challenge| challenge["challenge_date"] == json!(day)));
+    }
+}
+
+#[tokio::test]
+async fn retracting_a_workout_reevaluates_the_days_challenges() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let user_id = parse_user_id_from_jwt_token(&test_user.token);
+
+    // Around noon for the user, so workouts of the last few hours are on their today
+    let utc_offset = 12 - Utc::now().hour() as i32;
+    let timezone = format!("Etc/GMT{:+}", -utc_offset);
+    let response = set_timezone(&client, &test_app.address, &test_user.token, &timezone).await;
+    assert_eq!(response.status(), 200, "{}", timezone);
+    let (today, _) = get_challenge_day(&client, &test_app.address, &test_user.token).await;
+
+    // 400 kcal, which each of the day's workouts burns on its own
+    sqlx::query(
+        "INSERT INTO daily_challenges (id, user_id, challenge_date, template_key, title, description, target, stamina_bonus, strength_bonus)
+         VALUES ($1, $2, $3, 'calories_400', 'Burn it', 'Burn 400 kcal in workouts', 400, 2, 4)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(today)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to add challenge");
+
+    let mut workout_ids = Vec::new();
+    for hours_ago in [3, 1] {
+        let job = upload_workout_and_wait(
+            &client, &test_app.address, &test_user.token, workout_hours_ago(create_elite_workout_data(), hours_ago),
+        ).await;
+        assert_eq!(job["status"], "completed");
+        workout_ids.push(job["result"]["sync_id"].as_str().unwrap().to_string());
+    }
+    let challenge = &get_challenges(&client, &test_app.address, &test_user.token).await[0];
+    assert_eq!(challenge["completed_workout_id"], workout_ids[0].as_str());
+
+    // The later workout still burned enough, so the challenge and its bonus stay
+    retract(&client, &test_app.address, &test_user.token, &workout_ids[0]).await;
+    let challenge = &get_challenges(&client, &test_app.address, &test_user.token).await[0];
+    assert!(!challenge["completed_at"].is_null());
+    let (stamina, strength) = workout_gains(&test_app.db_pool, user_id).await;
+    assert_eq!(avatar_stats(&test_app.db_pool, user_id).await, (stamina + 2, strength + 4));
+
+    // Without any workouts left it's reopened and the bonus taken back
+    retract(&client, &test_app.address, &test_user.token, &workout_ids[1]).await;
+    let challenge = &get_challenges(&client, &test_app.address, &test_user.token).await[0];
+    assert!(challenge["completed_at"].is_null());
+    assert_eq!(challenge["progress"], 0.0);
+    assert_eq!(avatar_stats(&test_app.db_pool, user_id).await, (0, 0));
+}
+
+async fn retract(client: &Client, address: &str, token: &str, workout_id: &str) {
+    let response = make_authenticated_request(
+        client, reqwest::Method::DELETE, &format!("{}/health/workouts/{}", address, workout_id), token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+}
+
+async fn workout_gains(pool: &sqlx::PgPool, user_id: Uuid) -> (i64, i64) {
+    let row = sqlx::query(
+        "SELECT COALESCE(SUM(stamina_gained), 0)::int8 AS stamina, COALESCE(SUM(strength_gained), 0)::int8 AS strength
+         FROM workout_data WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to sum workout gains");
+    (row.get("stamina"), row.get("strength"))
+}
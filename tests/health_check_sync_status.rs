@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 mod common;
 use common::utils::{spawn_app, create_test_user_and_login};
+use common::workout_data_helpers::upload_workout_data_for_user;
 
 #[tokio::test]
 async fn test_check_workout_sync_status() {
@@ -28,15 +29,9 @@ async fn test_check_workout_sync_status() {
     });
 
     // Upload the workout
-    let response = client
-        .post(&format!("{}/health/upload_health", &app.address))
-        .header("Authorization", format!("Bearer {}", user.token))
-        .json(&workout_data)
-        .send()
+    upload_workout_data_for_user(&client, &app.address, &user.token, workout_data.clone())
         .await
-        .expect("Failed to execute request");
-
-    assert!(response.status().is_success());
+        .expect("Workout upload should be processed");
 
     // Check sync status for multiple UUIDs
     let check_request = json!({
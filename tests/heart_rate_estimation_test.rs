@@ -0,0 +1,168 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request, UserRegLoginResponse};
+use common::admin_helpers::create_admin_user_and_login;
+use common::workout_data_helpers::{upload_workout_data_for_user, workout_hours_ago};
+
+/// 20 minutes ramping from 72 to about 190 bpm, above the 185 bpm max of a 35 year old
+fn create_hard_workout_data() -> serde_json::Value {
+    let base_time = Utc::now();
+    let heart_rate_readings: Vec<serde_json::Value> = (0..1200)
+        .map(|i| {
+            let progress = i as f64 / 1200.0;
+            let heart_rate = (72.0 + 118.0 * progress + 3.0 * (i as f64 * 0.3).sin()) as i32;
+            json!({
+                "timestamp": base_time + Duration::seconds(i),
+                "heart_rate": heart_rate
+            })
+        })
+        .collect();
+
+    json!({
+        "device_id": format!("device-{}", Uuid::new_v4()),
+        "timestamp": base_time,
+        "heart_rate": heart_rate_readings,
+        "calories_burned": 400,
+        "workout_start": base_time,
+        "workout_end": base_time + Duration::seconds(1200),
+        "workout_uuid": &Uuid::new_v4().to_string()[..8]
+    })
+}
+
+async fn create_user_with_hard_workouts(client: &Client, address: &str, auto_apply: bool) -> UserRegLoginResponse {
+    let user = create_test_user_and_login(address).await;
+
+    let response = make_authenticated_request(
+        client,
+        reqwest::Method::PUT,
+        &format!("{}/profile/health_profile", address),
+        &user.token,
+        Some(json!({
+            "age": 35,
+            "gender": "male",
+            "resting_heart_rate": 60,
+            "auto_apply_heart_rate_estimates": auto_apply
+        })),
+    ).await;
+    assert!(response.status().is_success(), "Failed to create health profile");
+
+    for hours_ago in [2, 26, 50] {
+        upload_workout_data_for_user(client, address, &user.token, workout_hours_ago(create_hard_workout_data(), hours_ago))
+            .await
+            .expect("Upload should succeed");
+    }
+    user
+}
+
+async fn run_estimation(client: &Client, address: &str) {
+    let admin = create_admin_user_and_login(address).await;
+    let response = make_authenticated_request(
+        client,
+        reqwest::Method::POST,
+        &format!("{}/admin/heart-rate-estimates/run", address),
+        &admin.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200, "Heart rate estimation should run");
+}
+
+async fn get_heart_rate_zones(client: &Client, address: &str, token: &str) -> serde_json::Value {
+    let response = make_authenticated_request(
+        client,
+        reqwest::Method::GET,
+        &format!("{}/profile/heart_rate_zones", address),
+        token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    response.json::<serde_json::Value>().await.unwrap()["data"].clone()
+}
+
+#[tokio::test]
+async fn estimated_max_heart_rate_is_proposed_and_applied_when_accepted() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let user = create_user_with_hard_workouts(&client, &test_app.address, false).await;
+
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    assert_eq!(zones["current"]["max_heart_rate"], 185);
+    assert!(zones["pending_estimate"].is_null());
+    assert_eq!(zones["history"].as_array().unwrap().len(), 1, "Profile zones should start the history");
+
+    run_estimation(&client, &test_app.address).await;
+
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    let estimate = &zones["pending_estimate"];
+    let estimated_max = estimate["max_heart_rate"].as_i64().expect("An estimate should be proposed");
+    assert!((188..=192).contains(&estimated_max), "Estimated max was {}", estimated_max);
+    assert_eq!(estimate["previous_max_heart_rate"], 185);
+    assert_eq!(estimate["resting_heart_rate"], 60, "Workouts never got near the resting heart rate");
+    assert_eq!(estimate["workouts_considered"], 3);
+    assert_eq!(zones["current"]["max_heart_rate"], 185, "Proposals wait for the user");
+
+    // The same estimate isn't proposed twice
+    run_estimation(&client, &test_app.address).await;
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    assert_eq!(zones["pending_estimate"]["id"], estimate["id"]);
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/profile/heart_rate_zones/estimate/accept", &test_app.address),
+        &user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200);
+
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    assert_eq!(zones["current"]["max_heart_rate"], estimated_max);
+    assert!(zones["pending_estimate"].is_null());
+    let history = zones["history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["source"], "estimate");
+    assert_eq!(history[0]["estimate_id"], estimate["id"]);
+    assert!(history[0]["effective_until"].is_null());
+    assert_eq!(history[1]["source"], "profile");
+    assert!(!history[1]["effective_until"].is_null());
+
+    // Updating the profile keeps the observed max instead of going back to 220 - age
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::PUT,
+        &format!("{}/profile/health_profile", &test_app.address),
+        &user.token,
+        Some(json!({ "weight": 80.0 })),
+    ).await;
+    assert!(response.status().is_success());
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    assert_eq!(zones["current"]["max_heart_rate"], estimated_max);
+    assert_eq!(zones["history"].as_array().unwrap().len(), 2);
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/profile/heart_rate_zones/estimate/dismiss", &test_app.address),
+        &user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 404, "Nothing left to dismiss");
+}
+
+#[tokio::test]
+async fn estimates_are_applied_right_away_for_users_who_opted_in() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let user = create_user_with_hard_workouts(&client, &test_app.address, true).await;
+
+    run_estimation(&client, &test_app.address).await;
+
+    let zones = get_heart_rate_zones(&client, &test_app.address, &user.token).await;
+    assert!(zones["pending_estimate"].is_null());
+    let max_heart_rate = zones["current"]["max_heart_rate"].as_i64().unwrap();
+    assert!(max_heart_rate > 185, "Max was {}", max_heart_rate);
+    assert_eq!(zones["history"][0]["source"], "estimate");
+}
@@ -30,6 +30,9 @@ async fn create_test_user_with_health_profile(test_app: &TestApp, client: &Clien
 }
 use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request, TestApp, get_next_date, UserRegLoginResponse};
 use common::admin_helpers::{create_admin_user_and_login, create_league_season, create_teams_for_test, create_league, add_team_to_league, add_user_to_team};
+use common::workout_data_helpers::upload_workout_and_wait;
+use evolveme_backend::game::progression::level_for_experience;
+use evolveme_backend::services::StatRecomputeService;
 
 
 #[tokio::test]
@@ -162,6 +165,296 @@ async fn test_complete_live_game_workflow() {
     );
 }
 
+#[tokio::test]
+async fn test_live_scoring_uses_season_scoring_rules() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin_session = create_admin_user_and_login(&test_app.address).await;
+
+    let (home_user, _away_user_1, _away_user_2, game_id) = setup_live_game_environment(&test_app).await;
+    update_game_times_to_now(&test_app, game_id).await;
+    start_test_game(&test_app, game_id).await;
+    initialize_live_game(&test_app, game_id).await;
+
+    let season_id = get_season_id_for_game(&test_app, game_id).await;
+    let league_id = sqlx::query_scalar!("SELECT league_id FROM league_seasons WHERE id = $1", season_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to get league for season");
+    let rules_url = format!("{}/admin/leagues/{}/seasons/{}/scoring-rules", test_app.address, league_id, season_id);
+
+    let response = make_authenticated_request(&client, reqwest::Method::GET, &rules_url, &admin_session.token, None).await;
+    assert_eq!(response.status(), 404, "Seasons start without their own rules");
+
+    let response = make_authenticated_request(&client, reqwest::Method::POST, &rules_url, &admin_session.token,
+        Some(json!({ "stamina_score_weight": 2.0, "strength_score_weight": 0.0 }))).await;
+    assert_eq!(response.status(), 201);
+    let created: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(created["data"]["season_id"], season_id.to_string());
+    assert_eq!(created["data"]["zone2_stamina_per_min"], 5.0, "Fields left out keep their defaults");
+
+    let response = make_authenticated_request(&client, reqwest::Method::POST, &rules_url, &admin_session.token, Some(json!({}))).await;
+    assert_eq!(response.status(), 409);
+
+    let response = make_authenticated_request(&client, reqwest::Method::PUT, &rules_url, &admin_session.token,
+        Some(json!({ "zone3_stamina_per_min": -1.0 }))).await;
+    assert_eq!(response.status(), 400);
+
+    // Scoring without heart rate data may never pay as much as a minute in the lowest zone
+    let response = make_authenticated_request(&client, reqwest::Method::PUT, &rules_url, &admin_session.token,
+        Some(json!({ "fallback_max_points_per_min": 2.0 }))).await;
+    assert_eq!(response.status(), 400);
+
+    // Every second of a gap up to the interpolation limit is walked, so it can't be stretched without bound
+    let response = make_authenticated_request(&client, reqwest::Method::PUT, &rules_url, &admin_session.token,
+        Some(json!({ "interpolate_up_to_sec": 1e9 }))).await;
+    assert_eq!(response.status(), 400);
+
+    // Interpolation picks up where crediting a single sample stops
+    let response = make_authenticated_request(&client, reqwest::Method::PUT, &rules_url, &admin_session.token,
+        Some(json!({ "max_credited_interval_sec": 120.0, "interpolate_up_to_sec": 90.0 }))).await;
+    assert_eq!(response.status(), 400);
+
+    let response = make_authenticated_request(&client, reqwest::Method::PUT, &rules_url, &admin_session.token,
+        Some(json!({ "stamina_score_weight": 3.0, "strength_score_weight": 0.0 }))).await;
+    assert_eq!(response.status(), 200);
+
+    // Only stamina scores, three points each
+    let (stamina, _strength) = upload_workout_data(&test_app, &client, &home_user, WorkoutType::Intense).await;
+    let live_game = get_live_game_state(&test_app, game_id).await;
+    assert!(stamina > 0);
+    assert_eq!(live_game.home_score, stamina * 3);
+
+    let response = make_authenticated_request(&client, reqwest::Method::DELETE, &rules_url, &admin_session.token, None).await;
+    assert_eq!(response.status(), 200);
+    let response = make_authenticated_request(&client, reqwest::Method::GET, &rules_url, &admin_session.token, None).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_avatar_and_each_live_game_score_with_their_own_season_rules() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin_session = create_admin_user_and_login(&test_app.address).await;
+
+    let (home_user, away_user, _away_user_2, game_id) = setup_live_game_environment(&test_app).await;
+    update_game_times_to_now(&test_app, game_id).await;
+    start_test_game(&test_app, game_id).await;
+    let live_game = initialize_live_game(&test_app, game_id).await;
+
+    // The game's season credits no strength, and only stamina scores, three points each
+    let season_id = get_season_id_for_game(&test_app, game_id).await;
+    let league_id = sqlx::query_scalar!("SELECT league_id FROM league_seasons WHERE id = $1", season_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to get league for season");
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/admin/leagues/{}/seasons/{}/scoring-rules", test_app.address, league_id, season_id),
+        &admin_session.token,
+        Some(json!({
+            "zone1_strength_per_min": 0.0, "zone2_strength_per_min": 0.0, "zone3_strength_per_min": 0.0,
+            "zone4_strength_per_min": 0.0, "zone5_strength_per_min": 0.0, "fallback_max_points_per_min": 0.9,
+            "stamina_score_weight": 3.0, "strength_score_weight": 0.0
+        })),
+    ).await;
+    assert_eq!(response.status(), 201);
+
+    // The season hasn't started, so the avatar still scores with the default rules while the game uses its own
+    let (stamina, strength) = upload_workout_data(&test_app, &client, &home_user, WorkoutType::Intense).await;
+    assert!(stamina > 0 && strength > 0);
+    let event = sqlx::query!(
+        "SELECT stamina_gained, strength_gained, score_points FROM live_score_events WHERE live_game_id = $1",
+        live_game.id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to get score event");
+    assert_eq!((event.stamina_gained, event.strength_gained), (stamina, 0));
+    assert_eq!(event.score_points, stamina * 3);
+    assert_eq!(get_live_game_state(&test_app, game_id).await.home_score, stamina * 3);
+
+    // Once the season runs, avatars score with its rules too
+    start_season_now(&test_app, season_id).await;
+    let (stamina, strength) = upload_workout_data(&test_app, &client, &away_user, WorkoutType::Intense).await;
+    assert!(stamina > 0);
+    assert_eq!(strength, 0);
+}
+
+#[tokio::test]
+async fn test_stat_recompute_rescores_workouts_and_live_games() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin_session = create_admin_user_and_login(&test_app.address).await;
+
+    let (home_user, _away_user_1, _away_user_2, game_id) = setup_live_game_environment(&test_app).await;
+    update_game_times_to_now(&test_app, game_id).await;
+    start_test_game(&test_app, game_id).await;
+    let live_game = initialize_live_game(&test_app, game_id).await;
+
+    let (stamina, strength) = upload_workout_data(&test_app, &client, &home_user, WorkoutType::Intense).await;
+    assert!(stamina > 0 && strength > 0);
+    let avatar_before = get_avatar_stats(&test_app, home_user.user_id).await;
+
+    // The season stops crediting strength after the workout was scored, and only stamina scores, three points each
+    let season_id = get_season_id_for_game(&test_app, game_id).await;
+    let league_id = sqlx::query_scalar!("SELECT league_id FROM league_seasons WHERE id = $1", season_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to get league for season");
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/admin/leagues/{}/seasons/{}/scoring-rules", test_app.address, league_id, season_id),
+        &admin_session.token,
+        Some(json!({
+            "zone1_strength_per_min": 0.0, "zone2_strength_per_min": 0.0, "zone3_strength_per_min": 0.0,
+            "zone4_strength_per_min": 0.0, "zone5_strength_per_min": 0.0, "fallback_max_points_per_min": 0.9,
+            "stamina_score_weight": 3.0, "strength_score_weight": 0.0
+        })),
+    ).await;
+    assert_eq!(response.status(), 201);
+    start_season_now(&test_app, season_id).await;
+
+    let recompute_url = format!("{}/admin/scoring/recompute", test_app.address);
+    let dry_run = run_recompute_job(&client, &recompute_url, &admin_session.token, Some(json!({ "rebuild_live_games": true }))).await;
+    assert_eq!(dry_run["status"], "completed", "Dry run failed: {:?}", dry_run);
+    assert_eq!(dry_run["mode"], "dry_run");
+
+    let report = &dry_run["report"];
+    let user_diff = report["users"].as_array().unwrap().iter()
+        .find(|diff| diff["user_id"] == home_user.user_id.to_string())
+        .expect("The home user's totals change");
+    assert_eq!(user_diff["workouts_changed"], 1);
+    assert_eq!(user_diff["stamina_before"], user_diff["stamina_after"]);
+    assert_eq!(user_diff["strength_before"], avatar_before.1);
+    assert_eq!(user_diff["strength_after"], avatar_before.1 - strength);
+    let game_diff = report["live_games"].as_array().unwrap().iter()
+        .find(|diff| diff["live_game_id"] == live_game.id.to_string())
+        .expect("The live game is rescored");
+    assert_eq!(game_diff["events_rescored"], 1);
+    assert_eq!(game_diff["home_score_after"], stamina * 3);
+
+    // A dry run stores nothing
+    assert_eq!(get_avatar_stats(&test_app, home_user.user_id).await, avatar_before);
+    assert_ne!(get_live_game_state(&test_app, game_id).await.home_score, stamina * 3);
+
+    // Rules changed after the review don't change what's applied
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::PUT,
+        &format!("{}/admin/leagues/{}/seasons/{}/scoring-rules", test_app.address, league_id, season_id),
+        &admin_session.token,
+        Some(json!({
+            "zone1_strength_per_min": 0.0, "zone2_strength_per_min": 0.0, "zone3_strength_per_min": 0.0,
+            "zone4_strength_per_min": 0.0, "zone5_strength_per_min": 0.0, "fallback_max_points_per_min": 0.9,
+            "stamina_score_weight": 5.0, "strength_score_weight": 0.0
+        })),
+    ).await;
+    assert_eq!(response.status(), 200);
+
+    let avatar_experience = |pool: sqlx::PgPool| async move {
+        sqlx::query_as::<_, (i64, i32)>("SELECT experience::int8, level FROM user_avatars WHERE user_id = $1")
+            .bind(home_user.user_id)
+            .fetch_one(&pool)
+            .await
+            .expect("Failed to get avatar experience")
+    };
+    let (experience_before, _) = avatar_experience(test_app.db_pool.clone()).await;
+
+    let apply_url = format!("{}/{}/apply", recompute_url, dry_run["id"].as_str().unwrap());
+    let applied = run_recompute_job(&client, &apply_url, &admin_session.token, None).await;
+    assert_eq!(applied["status"], "completed", "Apply failed: {:?}", applied);
+    assert_eq!(applied["mode"], "apply");
+    assert_eq!(applied["rebuild_live_games"], true);
+
+    assert_eq!(get_avatar_stats(&test_app, home_user.user_id).await, (avatar_before.0, avatar_before.1 - strength));
+    // Experience and level follow the recomputed gains
+    let (experience, level) = avatar_experience(test_app.db_pool.clone()).await;
+    assert_eq!(experience, experience_before - strength as i64);
+    assert_eq!(level, level_for_experience(experience));
+    let workout = sqlx::query!(
+        "SELECT stamina_gained, strength_gained, total_points_gained FROM workout_data WHERE user_id = $1",
+        home_user.user_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to get workout");
+    assert_eq!((workout.stamina_gained, workout.strength_gained, workout.total_points_gained), (stamina, 0, stamina));
+
+    assert_eq!(get_live_game_state(&test_app, game_id).await.home_score, stamina * 3);
+    let events = get_recent_score_events(&test_app, live_game.id).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].score_points, stamina * 3);
+
+    let response = make_authenticated_request(&client, reqwest::Method::POST, &apply_url, &admin_session.token, None).await;
+    assert_eq!(response.status(), 409, "A dry run is applied once");
+    let response = make_authenticated_request(
+        &client, reqwest::Method::POST, &format!("{}/{}/apply", recompute_url, applied["id"].as_str().unwrap()), &admin_session.token, None,
+    ).await;
+    assert_eq!(response.status(), 409, "Only dry runs can be applied");
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/{}", recompute_url, Uuid::new_v4()), &admin_session.token, None,
+    ).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_stat_recompute_refuses_stale_dry_runs_and_fails_interrupted_jobs() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin_session = create_admin_user_and_login(&test_app.address).await;
+    let user = create_test_user_with_health_profile(&test_app, &client).await;
+    upload_workout_data(&test_app, &client, &user, WorkoutType::Intense).await;
+    let avatar_before = get_avatar_stats(&test_app, user.user_id).await;
+
+    let recompute_url = format!("{}/admin/scoring/recompute", test_app.address);
+    let dry_run = run_recompute_job(&client, &recompute_url, &admin_session.token, None).await;
+    assert_eq!(dry_run["status"], "completed", "Dry run failed: {:?}", dry_run);
+
+    // A workout stored after the review makes the dry run stale
+    let workout_data = WorkoutData::new_with_custom_time(WorkoutType::Intense, 30, Utc::now() - Duration::hours(3));
+    let job = upload_workout_and_wait(&client, &test_app.address, &user.token, workout_data.to_json()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    let avatar_after_upload = get_avatar_stats(&test_app, user.user_id).await;
+    assert_ne!(avatar_after_upload, avatar_before);
+
+    let apply_url = format!("{}/{}/apply", recompute_url, dry_run["id"].as_str().unwrap());
+    let applied = run_recompute_job(&client, &apply_url, &admin_session.token, None).await;
+    assert_eq!(applied["status"], "failed");
+    assert!(applied["error"].as_str().unwrap().contains("since the dry run"), "{:?}", applied);
+    assert_eq!(get_avatar_stats(&test_app, user.user_id).await, avatar_after_upload);
+
+    // Jobs of an instance that stopped are failed, jobs that keep their heartbeat up keep running
+    let (interrupted_id, alive_id) = (Uuid::new_v4(), Uuid::new_v4());
+    sqlx::query(
+        r#"
+        INSERT INTO stat_recompute_jobs (id, mode, status, started_at, heartbeat_at)
+        VALUES ($1, 'dry_run', 'running', NOW() - INTERVAL '1 hour', NOW() - INTERVAL '10 minutes'),
+               ($2, 'dry_run', 'running', NOW() - INTERVAL '1 hour', NOW())
+        "#
+    )
+    .bind(interrupted_id)
+    .bind(alive_id)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert running jobs");
+
+    StatRecomputeService::new(test_app.db_pool.clone(), None)
+        .recover_interrupted_jobs()
+        .await
+        .expect("Failed to recover interrupted jobs");
+
+    for (job_id, status) in [(interrupted_id, "failed"), (alive_id, "running")] {
+        let response = make_authenticated_request(
+            &client, reqwest::Method::GET, &format!("{}/{}", recompute_url, job_id), &admin_session.token, None,
+        ).await;
+        let job: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(job["data"]["status"], status, "{:?}", job);
+    }
+}
+
 #[tokio::test]
 async fn test_live_game_edge_cases() {
     let test_app = spawn_app().await;
@@ -352,8 +645,8 @@ async fn test_workout_timing_validation_for_live_games() {
     // Setup test environment
     let (home_user, away_user, _, game_id) = setup_live_game_environment(&test_app).await;
     
-    // Update game times for precise testing
-    update_game_times_to_now(&test_app, game_id).await;
+    // Game started two hours ago so workouts during the game are already in the past
+    update_game_times_to_started_hours_ago(&test_app, game_id, 2).await;
     start_test_game(&test_app, game_id).await;
     
     let live_game = initialize_live_game(&test_app, game_id).await;
@@ -370,14 +663,8 @@ async fn test_workout_timing_validation_for_live_games() {
         game_start - Duration::hours(2)
     );
     
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &home_user.token,
-        Some(before_game_workout.to_json()),
-    ).await;
-    assert!(response.status().is_success(), "Workout upload should succeed");
+    let job = upload_workout_and_wait(&client, &test_app.address, &home_user.token, before_game_workout.to_json()).await;
+    assert_eq!(job["status"], "completed", "Workout upload should succeed");
     
     // Check that score didn't increase
     let game_state_1 = get_live_game_state(&test_app, game_id).await;
@@ -392,14 +679,8 @@ async fn test_workout_timing_validation_for_live_games() {
         game_start + Duration::hours(1)
     );
     
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &home_user.token,
-        Some(during_game_workout.to_json()),
-    ).await;
-    assert!(response.status().is_success(), "Workout upload should succeed");
+    let job = upload_workout_and_wait(&client, &test_app.address, &home_user.token, during_game_workout.to_json()).await;
+    assert_eq!(job["status"], "completed", "Workout upload should succeed");
     
     // Check that score increased
     let game_state_2 = get_live_game_state(&test_app, game_id).await;
@@ -415,14 +696,9 @@ async fn test_workout_timing_validation_for_live_games() {
         game_end + Duration::hours(1)
     );
     
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &home_user.token,
-        Some(after_game_workout.to_json()),
-    ).await;
-    assert!(response.status().is_success(), "Workout upload should succeed");
+    let job = upload_workout_and_wait(&client, &test_app.address, &home_user.token, after_game_workout.to_json()).await;
+    // The game is still running, so a workout after its end lies in the future and is rejected outright
+    assert_eq!(job["status"], "rejected", "Future workout upload should be rejected");
     
     // Check that score didn't increase further
     let game_state_3 = get_live_game_state(&test_app, game_id).await;
@@ -437,44 +713,56 @@ async fn test_workout_timing_validation_for_live_games() {
         game_start
     );
     
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &away_user.token,
-        Some(at_start_workout.to_json()),
-    ).await;
-    assert!(response.status().is_success(), "Workout upload should succeed");
+    let job = upload_workout_and_wait(&client, &test_app.address, &away_user.token, at_start_workout.to_json()).await;
+    assert_eq!(job["status"], "completed", "Workout upload should succeed");
     
     // Check that away team score increased
     let game_state_4 = get_live_game_state(&test_app, game_id).await;
     assert!(game_state_4.away_score > 0, "Score should increase for workout at game start");
     println!("✅ Workout at game start correctly counted: +{} points", game_state_4.away_score);
     
-    // Test 5: Workout exactly at game end - should count
-    println!("\n🔬 Test 5: Uploading workout exactly at game end...");
+    // Test 5: Workout that just finished - should count
+    println!("\n🔬 Test 5: Uploading workout that just finished...");
     let at_end_workout = WorkoutData::new_with_custom_time(
         WorkoutType::Moderate, 
         30, 
-        game_end
+        Utc::now() - Duration::minutes(30)
     );
     
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &away_user.token,
-        Some(at_end_workout.to_json()),
-    ).await;
-    assert!(response.status().is_success(), "Workout upload should succeed");
+    let job = upload_workout_and_wait(&client, &test_app.address, &away_user.token, at_end_workout.to_json()).await;
+    assert_eq!(job["status"], "completed", "Workout upload should succeed");
     
     // Check that away team score increased
     let game_state_5 = get_live_game_state(&test_app, game_id).await;
-    assert!(game_state_5.away_score > game_state_4.away_score, "Score should increase for workout at game end");
-    println!("✅ Workout at game end correctly counted: +{} points", game_state_5.away_score - game_state_4.away_score);
+    assert!(game_state_5.away_score > game_state_4.away_score, "Score should increase for workout that just finished");
+    println!("✅ Workout that just finished correctly counted: +{} points", game_state_5.away_score - game_state_4.away_score);
+    
+    // Test 6: Workout exactly at game end - should count
+    println!("\n🔬 Test 6: Uploading workout exactly at game end...");
+    // Pull the game end in so a workout starting there is within the upload's clock tolerance
+    sqlx::query("UPDATE live_games SET game_end_time = $1 WHERE game_id = $2")
+        .bind(Utc::now() + Duration::minutes(3))
+        .bind(game_id)
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to move game end");
+    let game_end = get_live_game_state(&test_app, game_id).await.game_end_time;
+    let exact_end_workout = WorkoutData::new_with_custom_time(
+        WorkoutType::Moderate, 
+        30, 
+        game_end
+    );
+    
+    let job = upload_workout_and_wait(&client, &test_app.address, &away_user.token, exact_end_workout.to_json()).await;
+    assert_eq!(job["status"], "completed", "Workout upload should succeed");
+    
+    // Check that away team score increased
+    let game_state_6 = get_live_game_state(&test_app, game_id).await;
+    assert!(game_state_6.away_score > game_state_5.away_score, "Score should increase for workout at game end");
+    println!("✅ Workout at game end correctly counted: +{} points", game_state_6.away_score - game_state_5.away_score);
     
     println!("\n🎉 All workout timing validation tests passed!");
-    println!("Final scores - Home: {}, Away: {}", game_state_5.home_score, game_state_5.away_score);
+    println!("Final scores - Home: {}, Away: {}", game_state_6.home_score, game_state_6.away_score);
 }
 
 async fn test_live_scoring_history_api(
@@ -803,6 +1091,32 @@ async fn update_game_times_to_now(test_app: &TestApp, game_id: Uuid) {
     .expect("Failed to update game times to current");
 }
 
+async fn update_game_times_to_started_hours_ago(test_app: &TestApp, game_id: Uuid, hours: i64) {
+    let game_start = Utc::now() - Duration::hours(hours);
+    let game_end = Utc::now() + Duration::hours(2);
+    
+    sqlx::query(
+        "UPDATE league_games SET scheduled_time = $1, week_start_date = $1, week_end_date = $2 WHERE id = $3"
+    )
+    .bind(game_start)
+    .bind(game_end)
+    .bind(game_id)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to update game times");
+}
+
+/// Move a season's start to now, so workouts from now on score with its rules
+async fn start_season_now(test_app: &TestApp, season_id: Uuid) {
+    sqlx::query!(
+        "UPDATE league_seasons SET start_date = LEAST(start_date, NOW() - INTERVAL '1 minute') WHERE id = $1",
+        season_id
+    )
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to start season");
+}
+
 async fn start_test_game(test_app: &TestApp, game_id: Uuid) {
     sqlx::query!(
         "UPDATE league_games SET status = 'in_progress' WHERE id = $1",
@@ -907,19 +1221,11 @@ async fn upload_workout_data(
     let workout_data = WorkoutData::new(workout_type, 30);
 
 
-    let response = make_authenticated_request(
-        client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", test_app.address),
-        &user.token,
-        Some(workout_data.to_json()),
-    ).await;
-
-    assert!(response.status().is_success(), "Health data upload should succeed");
+    let job = upload_workout_and_wait(client, &test_app.address, &user.token, workout_data.to_json()).await;
+    assert_eq!(job["status"], "completed", "Health data upload should succeed");
     
-    // Return actual calculated values based on the response
-    let response_data: serde_json::Value = response.json().await.unwrap();
-    if let Some(game_stats) = response_data["data"]["game_stats"].as_object() {
+    // Return actual calculated values based on the processed job
+    if let Some(game_stats) = job["result"]["game_stats"].as_object() {
         if let Some(stat_changes) = game_stats["stat_changes"].as_object() {
             let stamina = stat_changes["stamina_change"].as_i64().unwrap_or(0) as i32;
             let strength = stat_changes["strength_change"].as_i64().unwrap_or(0) as i32;
@@ -928,7 +1234,34 @@ async fn upload_workout_data(
     }
     
     // Fallback - this shouldn't happen if the response is successful
-    panic!("Failed to extract stat changes from response: {:?}", response_data);
+    panic!("Failed to extract stat changes from job: {:?}", job);
+}
+
+/// Start a stat recompute job and wait until it finishes
+async fn run_recompute_job(client: &Client, url: &str, token: &str, body: Option<serde_json::Value>) -> serde_json::Value {
+    let response = make_authenticated_request(client, reqwest::Method::POST, url, token, body).await;
+    assert_eq!(response.status(), 202);
+    let job: serde_json::Value = response.json().await.unwrap();
+    let job_url = format!("{}/admin/scoring/recompute/{}", url.split("/admin/").next().unwrap(), job["data"]["id"].as_str().unwrap());
+
+    for _ in 0..120 {
+        let response = make_authenticated_request(client, reqwest::Method::GET, &job_url, token, None).await;
+        assert_eq!(response.status(), 200);
+        let job: serde_json::Value = response.json().await.unwrap();
+        if job["data"]["status"] == "completed" || job["data"]["status"] == "failed" {
+            return job["data"].clone();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+    panic!("Stat recompute job did not finish in time");
+}
+
+async fn get_avatar_stats(test_app: &TestApp, user_id: Uuid) -> (i32, i32) {
+    let avatar = sqlx::query!("SELECT stamina, strength FROM user_avatars WHERE user_id = $1", user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to get avatar");
+    (avatar.stamina, avatar.strength)
 }
 
 async fn get_player_contributions(test_app: &TestApp, live_game_id: Uuid) -> (Vec<PlayerContribution>, Vec<PlayerContribution>) {
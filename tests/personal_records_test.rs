@@ -0,0 +1,156 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{
+    create_advanced_workout_data, create_beginner_workout_data, create_elite_workout_data,
+    upload_workout_and_wait, upload_workout_data_for_user, workout_hours_ago,
+};
+
+async fn get_records(client: &Client, address: &str, token: &str) -> serde_json::Value {
+    let response = make_authenticated_request(
+        client, reqwest::Method::GET, &format!("{}/profile/records", address), token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["data"].clone()
+}
+
+fn record<'a>(records: &'a serde_json::Value, record_type: &str) -> &'a serde_json::Value {
+    records["records"].as_array().unwrap().iter()
+        .find(|record| record["record_type"] == record_type)
+        .unwrap_or_else(|| panic!("No {} record", record_type))
+}
+
+fn milestone_thresholds(records: &serde_json::Value, milestone_type: &str) -> Vec<i64> {
+    let mut thresholds: Vec<i64> = records["milestones"].as_array().unwrap().iter()
+        .filter(|milestone| milestone["milestone_type"] == milestone_type)
+        .map(|milestone| milestone["threshold"].as_i64().unwrap())
+        .collect();
+    thresholds.sort();
+    thresholds
+}
+
+async fn upload(client: &Client, address: &str, token: &str, workout: serde_json::Value) -> String {
+    let job = upload_workout_and_wait(client, address, token, workout).await;
+    assert_eq!(job["status"], "completed");
+    job["result"]["sync_id"].as_str().unwrap().to_string()
+}
+
+async fn retract(client: &Client, address: &str, token: &str, workout_id: &str) {
+    let response = make_authenticated_request(
+        client, reqwest::Method::DELETE, &format!("{}/health/workouts/{}", address, workout_id), token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn workouts_set_personal_records_and_streaks() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let records = get_records(&client, &test_app.address, &test_user.token).await;
+    assert_eq!(records["records"].as_array().unwrap().len(), 0);
+    assert_eq!(records["milestones"].as_array().unwrap().len(), 0);
+    assert_eq!(records["daily_streak"]["current"], 0);
+
+    // A short, easy workout two days ago sets the first records
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, workout_hours_ago(create_beginner_workout_data(), 48))
+        .await
+        .expect("Upload should succeed");
+    let records = get_records(&client, &test_app.address, &test_user.token).await;
+    let first_longest = record(&records, "longest_workout")["value"].as_f64().unwrap();
+    assert!(record(&records, "longest_workout")["previous_value"].is_null(), "The first record beats nothing");
+    assert_eq!(record(&records, "longest_daily_streak")["value"], 1.0);
+    assert_eq!(records["daily_streak"]["current"], 0, "A workout two days ago doesn't keep the streak alive");
+    assert_eq!(milestone_thresholds(&records, "workouts"), vec![1]);
+
+    // Longer, harder workouts yesterday and today beat them and build a streak
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, workout_hours_ago(create_advanced_workout_data(), 24))
+        .await
+        .expect("Upload should succeed");
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, create_elite_workout_data())
+        .await
+        .expect("Upload should succeed");
+
+    let records = get_records(&client, &test_app.address, &test_user.token).await;
+    let longest = record(&records, "longest_workout");
+    assert!(longest["value"].as_f64().unwrap() > first_longest);
+    assert!(longest["previous_value"].as_f64().unwrap() >= first_longest);
+    assert!(record(&records, "most_high_zone_minutes")["value"].as_f64().unwrap() > 0.0);
+    assert!(record(&records, "highest_workout_points")["value"].as_f64().unwrap() > 0.0);
+    assert_eq!(record(&records, "longest_daily_streak")["value"], 3.0);
+    assert_eq!(record(&records, "longest_daily_streak")["previous_value"], 2.0);
+    assert_eq!(records["daily_streak"]["current"], 3);
+    assert_eq!(records["daily_streak"]["longest"], 3);
+    assert!(records["weekly_streak"]["current"].as_i64().unwrap() >= 1);
+
+    // Records are per user
+    let other_user = create_test_user_and_login(&test_app.address).await;
+    let records = get_records(&client, &test_app.address, &other_user.token).await;
+    assert_eq!(records["records"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn retracted_and_replaced_workouts_give_their_records_and_milestones_back() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let address = &test_app.address;
+
+    // A short workout yesterday, then a longer one the day before
+    let short_id = upload(&client, address, &test_user.token, workout_hours_ago(create_beginner_workout_data(), 24)).await;
+    let records = get_records(&client, address, &test_user.token).await;
+    let short_minutes = record(&records, "longest_workout")["value"].as_f64().unwrap();
+
+    let long_id = upload(&client, address, &test_user.token, workout_hours_ago(create_advanced_workout_data(), 48)).await;
+    let records = get_records(&client, address, &test_user.token).await;
+    assert_eq!(record(&records, "longest_workout")["workout_data_id"], long_id.as_str());
+    assert_eq!(record(&records, "longest_daily_streak")["value"], 2.0);
+
+    // Retracting the longer one hands its records back to the short one
+    retract(&client, address, &test_user.token, &long_id).await;
+    let records = get_records(&client, address, &test_user.token).await;
+    let longest = record(&records, "longest_workout");
+    assert_eq!(longest["workout_data_id"], short_id.as_str());
+    assert_eq!(longest["value"].as_f64().unwrap(), short_minutes);
+    assert!(longest["previous_value"].is_null());
+    assert_eq!(record(&records, "highest_workout_points")["workout_data_id"], short_id.as_str());
+    assert_eq!(record(&records, "longest_daily_streak")["value"], 1.0);
+    assert_eq!(milestone_thresholds(&records, "workouts"), vec![1]);
+
+    // Without any workouts there's nothing left to hold a record or milestone
+    retract(&client, address, &test_user.token, &short_id).await;
+    let records = get_records(&client, address, &test_user.token).await;
+    assert_eq!(records["records"].as_array().unwrap().len(), 0);
+    assert_eq!(records["milestones"].as_array().unwrap().len(), 0);
+
+    // A denser recording of the same activity replaces the first and takes over its records
+    let start = Utc::now() - Duration::hours(3);
+    let recording = |device_id: &str, interval_seconds: i64| json!({
+        "device_id": device_id,
+        "timestamp": start + Duration::minutes(30),
+        "heart_rate": (0..1800 / interval_seconds).map(|i| {
+            let timestamp = start + Duration::seconds(i * interval_seconds);
+            json!({ "timestamp": timestamp, "heart_rate": 125 + (25.0 * (timestamp.timestamp() as f64 / 300.0).sin()) as i32 })
+        }).collect::<Vec<_>>(),
+        "calories_burned": 300,
+        "workout_start": start,
+        "workout_end": start + Duration::minutes(30),
+        "workout_uuid": uuid::Uuid::new_v4().to_string(),
+    });
+    let watch_id = upload(&client, address, &test_user.token, recording("watch", 5)).await;
+    let records = get_records(&client, address, &test_user.token).await;
+    assert_eq!(record(&records, "longest_workout")["workout_data_id"], watch_id.as_str());
+
+    let strap_id = upload(&client, address, &test_user.token, recording("chest-strap", 1)).await;
+    assert_ne!(strap_id, watch_id);
+    let records = get_records(&client, address, &test_user.token).await;
+    for record_type in ["longest_workout", "highest_workout_points"] {
+        assert_eq!(record(&records, record_type)["workout_data_id"], strap_id.as_str(), "{} moves to the kept recording", record_type);
+    }
+    assert_eq!(milestone_thresholds(&records, "workouts"), vec![1], "The replacement is still one workout");
+}
@@ -0,0 +1,119 @@
+use chrono::{Duration, NaiveTime, Utc};
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::admin_helpers::create_admin_user_and_login;
+use common::workout_data_helpers::{create_advanced_workout_data, upload_workout_data_for_user, workout_hours_ago};
+
+/// An advanced workout starting around `hour` o'clock UTC yesterday, so several of them share a day
+fn workout_yesterday_at(hour: u32) -> serde_json::Value {
+    let target = (Utc::now() - Duration::days(1)).date_naive().and_time(NaiveTime::from_hms_opt(hour, 0, 0).unwrap()).and_utc();
+    workout_hours_ago(create_advanced_workout_data(), (Utc::now() - target).num_hours())
+}
+
+fn total_gain(job: &serde_json::Value) -> i64 {
+    let changes = &job["result"]["game_stats"]["stat_changes"];
+    changes["stamina_change"].as_i64().unwrap() + changes["strength_change"].as_i64().unwrap()
+}
+
+fn reasoning(job: &serde_json::Value) -> Vec<String> {
+    job["result"]["game_stats"]["reasoning"].as_array().unwrap().iter()
+        .map(|line| line.as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn set_caps(client: &Client, address: &str, token: &str, caps: serde_json::Value) -> reqwest::Response {
+    make_authenticated_request(client, reqwest::Method::PUT, &format!("{}/admin/scoring/caps", address), token, Some(caps)).await
+}
+
+async fn daily_points(test_app: &common::utils::TestApp, user: &common::utils::UserRegLoginResponse) -> i64 {
+    sqlx::query_scalar!(
+        r#"SELECT (stamina_gained + strength_gained)::bigint AS "points!" FROM user_daily_stat_gains WHERE user_id = $1"#,
+        user.user_id
+    )
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch daily gains")
+}
+
+fn caps(zone_daily_minutes: f32, zone_diminished_rate: f32, daily_soft_cap: i32) -> serde_json::Value {
+    json!({
+        "zone1_daily_minutes": zone_daily_minutes,
+        "zone2_daily_minutes": zone_daily_minutes,
+        "zone3_daily_minutes": zone_daily_minutes,
+        "zone4_daily_minutes": zone_daily_minutes,
+        "zone5_daily_minutes": zone_daily_minutes,
+        "zone_diminished_rate": zone_diminished_rate,
+        "daily_soft_cap": daily_soft_cap,
+        "weekly_soft_cap": 1_000_000,
+        "over_cap_rate": 0.25,
+    })
+}
+
+/// Caps are a single row per database, so the scenarios run one after another, each setting the caps it needs
+#[tokio::test]
+async fn stat_caps_reduce_gains_past_daily_limits() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin = create_admin_user_and_login(&test_app.address).await;
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/admin/scoring/caps", &test_app.address), &admin.token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let defaults: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(defaults["daily_soft_cap"], 800);
+
+    let mut invalid = caps(1000.0, 0.5, 20);
+    invalid["over_cap_rate"] = json!(1.5);
+    assert_eq!(set_caps(&client, &test_app.address, &admin.token, invalid).await.status(), 400);
+
+    let uncapped_user = create_test_user_and_login(&test_app.address).await;
+    let uncapped = upload_workout_data_for_user(&client, &test_app.address, &uncapped_user.token, workout_yesterday_at(10))
+        .await
+        .expect("Upload should succeed");
+
+    // Every zone minute is past a zero threshold and scores half
+    assert_eq!(set_caps(&client, &test_app.address, &admin.token, caps(0.0, 0.5, 100_000)).await.status(), 200);
+    let zone_capped_user = create_test_user_and_login(&test_app.address).await;
+    let zone_capped = upload_workout_data_for_user(&client, &test_app.address, &zone_capped_user.token, workout_yesterday_at(10)).await;
+
+    // A low daily cap leaves the same workout later that day scoring at the over cap rate from the first point
+    assert_eq!(set_caps(&client, &test_app.address, &admin.token, caps(1000.0, 0.5, 20)).await.status(), 200);
+    let daily_capped_user = create_test_user_and_login(&test_app.address).await;
+    let first = upload_workout_data_for_user(&client, &test_app.address, &daily_capped_user.token, workout_yesterday_at(10)).await;
+    let second = upload_workout_data_for_user(&client, &test_app.address, &daily_capped_user.token, workout_yesterday_at(13)).await;
+
+    assert_eq!(set_caps(&client, &test_app.address, &admin.token, defaults).await.status(), 200);
+    let zone_capped = zone_capped.expect("Upload should succeed");
+    let first = first.expect("First upload should succeed");
+    let second = second.expect("Second upload should succeed");
+
+    assert!(!reasoning(&uncapped).iter().any(|line| line.contains("past")), "Default caps leave a single workout alone");
+    assert!(reasoning(&zone_capped).iter().any(|line| line.contains("past 0 min today") && line.ends_with("scored at 50%")));
+    let expected = total_gain(&uncapped) / 2;
+    assert!((total_gain(&zone_capped) - expected).abs() <= 5, "Expected about {}, got {}", expected, total_gain(&zone_capped));
+
+    let expected = 20 + (total_gain(&uncapped) - 20) / 4;
+    assert!((total_gain(&first) - expected).abs() <= 1, "Only the first 20 points score in full, got {}", total_gain(&first));
+    assert!(reasoning(&first).iter().any(|line| line.starts_with("Daily soft cap of 20 points reached")));
+    let expected = total_gain(&uncapped) / 4;
+    assert!((total_gain(&second) - expected).abs() <= 1, "Expected about {}, got {}", expected, total_gain(&second));
+
+    assert_eq!(daily_points(&test_app, &daily_capped_user).await, total_gain(&first) + total_gain(&second));
+
+    // Retracting both frees up the day again
+    for job in [&first, &second] {
+        let response = make_authenticated_request(
+            &client,
+            reqwest::Method::DELETE,
+            &format!("{}/health/workouts/{}", &test_app.address, job["result"]["sync_id"].as_str().unwrap()),
+            &daily_capped_user.token,
+            None,
+        ).await;
+        assert_eq!(response.status(), 200);
+    }
+    assert_eq!(daily_points(&test_app, &daily_capped_user).await, 0);
+}
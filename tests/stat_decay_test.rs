@@ -0,0 +1,117 @@
+use reqwest::Client;
+use serde_json::json;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request, parse_user_id_from_jwt_token};
+use common::admin_helpers::create_admin_user_and_login;
+use common::workout_data_helpers::{create_advanced_workout_data, upload_workout_data_for_user};
+
+async fn run_decay(client: &Client, address: &str, admin_token: &str) -> serde_json::Value {
+    let response = make_authenticated_request(
+        client, reqwest::Method::POST, &format!("{}/admin/scoring/decay/run", address), admin_token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    body["data"].clone()
+}
+
+async fn avatar_stats(pool: &PgPool, user_id: Uuid) -> (i32, i32) {
+    let row = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to fetch avatar");
+    (row.get("stamina"), row.get("strength"))
+}
+
+#[tokio::test]
+async fn inactive_users_are_warned_then_decay_down_to_a_floor() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin = create_admin_user_and_login(&test_app.address).await;
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::PUT, &format!("{}/admin/scoring/decay", &test_app.address), &admin.token,
+        Some(json!({ "enabled": true, "idle_days": 14, "warning_days": 3, "daily_decay_rate": 1.5, "floor_ratio": 0.5 })),
+    ).await;
+    assert_eq!(response.status(), 400, "The decay rate is a share");
+
+    // Registered a month ago and never trained
+    let idle_user = create_test_user_and_login(&test_app.address).await;
+    let idle_user_id = parse_user_id_from_jwt_token(&idle_user.token);
+    sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '30 days' WHERE id = $1")
+        .bind(idle_user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE user_avatars SET stamina = 200, strength = 100 WHERE user_id = $1")
+        .bind(idle_user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+
+    // Also registered a month ago, but trained today
+    let active_user = create_test_user_and_login(&test_app.address).await;
+    let active_user_id = parse_user_id_from_jwt_token(&active_user.token);
+    sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '30 days' WHERE id = $1")
+        .bind(active_user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    upload_workout_data_for_user(&client, &test_app.address, &active_user.token, create_advanced_workout_data())
+        .await
+        .expect("Upload should succeed");
+    let active_stats = avatar_stats(&test_app.db_pool, active_user_id).await;
+
+    // The first run only warns
+    let summary = run_decay(&client, &test_app.address, &admin.token).await;
+    assert!(summary["warned"].as_i64().unwrap() >= 1);
+    assert_eq!(avatar_stats(&test_app.db_pool, idle_user_id).await, (200, 100));
+
+    // The next one takes 2% of what's above the floor at half the earned stats, once a day
+    run_decay(&client, &test_app.address, &admin.token).await;
+    assert_eq!(avatar_stats(&test_app.db_pool, idle_user_id).await, (198, 99));
+    run_decay(&client, &test_app.address, &admin.token).await;
+    assert_eq!(avatar_stats(&test_app.db_pool, idle_user_id).await, (198, 99));
+    assert_eq!(avatar_stats(&test_app.db_pool, active_user_id).await, active_stats);
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/admin/users/{}/stat-decay", &test_app.address, idle_user_id),
+        &admin.token, None,
+    ).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    let events = body["data"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["stamina_before"], 200);
+    assert_eq!(events[0]["stamina_change"], -2);
+    assert_eq!(events[0]["strength_change"], -1);
+
+    // However steep the decay, the floor holds half of what was earned
+    let response = make_authenticated_request(
+        &client, reqwest::Method::PUT, &format!("{}/admin/scoring/decay", &test_app.address), &admin.token,
+        Some(json!({ "enabled": true, "idle_days": 14, "warning_days": 3, "daily_decay_rate": 1.0, "floor_ratio": 0.5 })),
+    ).await;
+    assert_eq!(response.status(), 200);
+    sqlx::query("UPDATE stat_decay_events SET created_at = created_at - INTERVAL '1 day' WHERE user_id = $1")
+        .bind(idle_user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    run_decay(&client, &test_app.address, &admin.token).await;
+    assert_eq!(avatar_stats(&test_app.db_pool, idle_user_id).await, (100, 50));
+
+    // Training again resets the idle period
+    upload_workout_data_for_user(&client, &test_app.address, &idle_user.token, create_advanced_workout_data())
+        .await
+        .expect("Upload should succeed");
+    let trained_stats = avatar_stats(&test_app.db_pool, idle_user_id).await;
+    sqlx::query("UPDATE stat_decay_events SET created_at = created_at - INTERVAL '1 day' WHERE user_id = $1")
+        .bind(idle_user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    run_decay(&client, &test_app.address, &admin.token).await;
+    assert_eq!(avatar_stats(&test_app.db_pool, idle_user_id).await, trained_stats);
+}
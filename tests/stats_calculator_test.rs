@@ -1,5 +1,6 @@
 use evolveme_backend::game::stats_calculator::StatCalculator;
-use evolveme_backend::models::workout_data::{HeartRateData, WorkoutDataSyncRequest};
+use evolveme_backend::models::game::ScoringRules;
+use evolveme_backend::models::workout_data::{HeartRateData, StrengthExercise, StrengthSet, WorkoutDataSyncRequest, WorkoutType};
 use chrono::{Duration, Utc};
 use uuid::Uuid;
 
@@ -61,9 +62,10 @@ async fn test_zone_1_active_recovery() {
         workout_end: Some(workout_end),
         heart_rate: Some(heart_rate_data),
         calories_burned: Some(150),
+        ..Default::default()
     };
 
-    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data).await;
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
     
     // Around 5 minutes * 2 points per minute ≈ 10 stamina points (9-10 due to rounding)
     assert!(changes.stamina_change >= 9 && changes.stamina_change <= 10);
@@ -128,9 +130,10 @@ async fn test_zone_2_aerobic_base() {
         workout_end: Some(workout_end),
         heart_rate: Some(heart_rate_data),
         calories_burned: Some(225),
+        ..Default::default()
     };
 
-    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data).await;
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
     
     // Around 3 minutes * 5 stamina + 1 strength points per minute (14-15 stamina, 2-3 strength due to rounding)
     assert!(changes.stamina_change >= 14 && changes.stamina_change <= 15);
@@ -195,9 +198,10 @@ async fn test_zone_4_lactate_threshold() {
         workout_end: Some(workout_end),
         heart_rate: Some(heart_rate_data),
         calories_burned: Some(300),
+        ..Default::default()
     };
 
-    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data).await;
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
     
     // Around 2 minutes * 2 stamina + 5 strength points per minute (3-4 stamina, 9-10 strength due to rounding)
     assert!(changes.stamina_change >= 3 && changes.stamina_change <= 4);
@@ -262,9 +266,10 @@ async fn test_zone_5_neuromuscular_power() {
         workout_end: Some(workout_end),
         heart_rate: Some(heart_rate_data),
         calories_burned: Some(400),
+        ..Default::default()
     };
 
-    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data).await;
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
     
     // Around 1.5 minutes * 1 stamina + 8 strength points per minute (1-2 stamina, 11-12 strength due to rounding)
     assert!(changes.stamina_change >= 1 && changes.stamina_change <= 2);
@@ -276,7 +281,7 @@ async fn test_zone_5_neuromuscular_power() {
 }
 
 #[tokio::test]
-async fn test_no_heart_rate_no_gains() {
+async fn test_no_heart_rate_falls_back_to_calories_and_duration() {
     let test_app = spawn_app().await;
     
     // Create a test user with health profile
@@ -318,10 +323,394 @@ async fn test_no_heart_rate_no_gains() {
         workout_end: Some(workout_end),
         heart_rate: None,
         calories_burned: Some(200),
+        ..Default::default()
     };
 
-    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data).await;
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    // 30 min * 1 + 200 kcal * 0.02 stamina, 30 min * 0.5 + 200 kcal * 0.01 strength
+    assert_eq!(changes.stamina_change, 34);
+    assert_eq!(changes.strength_change, 17);
+    assert!(changes.zone_breakdown.is_none());
+    assert!(changes.training_load.is_none());
+    assert_eq!(changes.reasoning[0], "No heart rate data: scored from calories and duration");
+
+    // Without a duration there's nothing to score
+    let no_duration = WorkoutDataSyncRequest { workout_end: None, ..workout_data };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &no_duration, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
     assert_eq!(changes.stamina_change, 0);
     assert_eq!(changes.strength_change, 0);
-    assert_eq!(changes.reasoning.len(), 0);
-}
\ No newline at end of file
+    assert!(changes.reasoning.iter().any(|r| r == "No workout duration, nothing scored"));
+}
+
+#[tokio::test]
+async fn test_fallback_scoring_is_limited_per_minute() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 30 minutes of lifting logged with calories only
+    let now = Utc::now();
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(now - Duration::minutes(30)),
+        workout_end: Some(now),
+        calories_burned: Some(1500),
+        workout_type: Some(WorkoutType::Strength),
+        ..Default::default()
+    };
+
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    let total = changes.stamina_change + changes.strength_change;
+    assert!((53..=54).contains(&total), "total was {}", total);
+    assert!(changes.strength_change > changes.stamina_change);
+    assert!(changes.reasoning.iter().any(|r| r == "Limited to 1.8 points per minute without heart rate data"));
+
+    // Seasons can tune the fallback
+    let season_rules = ScoringRules {
+        season_id: Some(Uuid::new_v4()),
+        fallback_max_points_per_min: 1.0,
+        ..Default::default()
+    };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &season_rules).await.expect("Failed to calculate stat changes");
+    let total = changes.stamina_change + changes.strength_change;
+    assert!((29..=30).contains(&total), "total was {}", total);
+}
+
+#[tokio::test]
+async fn test_single_heart_rate_sample_falls_back_to_calories_and_duration() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // One sample covers no time, so the workout scores as if it had no heart rate
+    let now = Utc::now();
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(now - Duration::minutes(30)),
+        workout_end: Some(now),
+        heart_rate: Some(vec![HeartRateData { timestamp: now - Duration::minutes(15), heart_rate: 130 }]),
+        calories_burned: Some(200),
+        ..Default::default()
+    };
+
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    assert_eq!(changes.stamina_change, 34);
+    assert_eq!(changes.strength_change, 17);
+    assert!(changes.zone_breakdown.is_none());
+    assert!(changes.reasoning.iter().any(|r| r == "Too few heart rate samples to analyze"));
+}
+
+/// A 25 year old male with a resting heart rate of 60, so 130 bpm is Zone 1
+async fn create_user_with_health_profile(pool: &sqlx::PgPool) -> Uuid {
+    let user_id = Uuid::new_v4();
+    let username = format!("testuser_{}", &user_id.to_string()[..8]);
+    let email = format!("test_{}@test.com", &user_id.to_string()[..8]);
+
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, created_at, updated_at) VALUES ($1, $2, $3, $4, NOW(), NOW())"
+    )
+    .bind(user_id)
+    .bind(username)
+    .bind(email)
+    .bind("dummy_hash")
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        "INSERT INTO user_health_profiles (user_id, age, gender, resting_heart_rate) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(user_id)
+    .bind(25)
+    .bind("male")
+    .bind(60)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    user_id
+}
+
+#[tokio::test]
+async fn test_strength_workout_earns_strength_at_low_heart_rate() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 5 minutes in Zone 1, the same session scored as a run and as lifting
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(30);
+    let heart_rate_data: Vec<HeartRateData> = (0..300)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::seconds(i), heart_rate: 130 })
+        .collect();
+
+    let workout = |workout_type| WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data.clone()),
+        workout_type: Some(workout_type),
+        ..Default::default()
+    };
+
+    let run = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout(WorkoutType::Run), &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    assert_eq!(run.strength_change, 0);
+
+    let strength = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout(WorkoutType::Strength), &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    // Around 5 minutes * 3 base strength points per minute, with half the stamina of the run
+    assert!(strength.strength_change >= 14 && strength.strength_change <= 15);
+    assert!(strength.stamina_change < run.stamina_change);
+    assert!(strength.reasoning.iter().any(|r| r.contains("strength")));
+}
+
+#[tokio::test]
+async fn test_long_heart_rate_gap_is_not_credited_to_one_zone() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 5 minutes of Zone 1, the watch loses contact for 40 minutes, then 5 more minutes
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(60);
+    let before_gap = (0..300).map(|i| workout_start + Duration::seconds(i));
+    let after_gap = (0..300).map(|i| workout_start + Duration::seconds(300 + 2400 + i));
+    let heart_rate_data: Vec<HeartRateData> = before_gap.chain(after_gap)
+        .map(|timestamp| HeartRateData { timestamp, heart_rate: 130 })
+        .collect();
+
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        ..Default::default()
+    };
+
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+
+    // About 11 credited minutes (10 sampled plus one capped interval) at 2 stamina per minute,
+    // instead of 50 minutes with the gap credited to Zone 1
+    assert!(changes.stamina_change >= 20 && changes.stamina_change <= 22, "stamina was {}", changes.stamina_change);
+    let breakdown = changes.zone_breakdown.expect("Should have a zone breakdown");
+    let zone_1_minutes: f32 = breakdown.iter().filter(|zone| zone.zone == "Zone1").map(|zone| zone.minutes).sum();
+    assert!((zone_1_minutes - 11.0).abs() < 0.1, "Zone 1 minutes were {}", zone_1_minutes);
+    assert!(
+        changes.reasoning.iter().any(|r| r.contains("39.0 min without heart rate data")),
+        "Reasoning should state the discarded time: {:?}", changes.reasoning
+    );
+}
+
+#[tokio::test]
+async fn test_short_heart_rate_gap_is_interpolated() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // Samples every 3 minutes: longer than one sample is credited with, short enough to interpolate
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(40);
+    let heart_rate_data: Vec<HeartRateData> = (0..11)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::minutes(i * 3), heart_rate: 130 })
+        .collect();
+
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        ..Default::default()
+    };
+
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+
+    // 30 interpolated minutes, all in Zone 1
+    let breakdown = changes.zone_breakdown.clone().expect("Should have a zone breakdown");
+    let zone_1_minutes: f32 = breakdown.iter().filter(|zone| zone.zone == "Zone1").map(|zone| zone.minutes).sum();
+    assert!((zone_1_minutes - 30.0).abs() < 0.01, "Zone 1 minutes were {}", zone_1_minutes);
+    assert!(changes.stamina_change >= 59 && changes.stamina_change <= 60, "stamina was {}", changes.stamina_change);
+    assert!(!changes.reasoning.iter().any(|r| r.contains("without heart rate data")));
+}
+
+#[tokio::test]
+async fn test_season_rules_set_how_heart_rate_gaps_are_credited() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // Samples every 3 minutes
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(40);
+    let heart_rate_data: Vec<HeartRateData> = (0..11)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::minutes(i * 3), heart_rate: 130 })
+        .collect();
+
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        ..Default::default()
+    };
+
+    // Without interpolation each 3 minute gap is credited with one minute
+    let no_interpolation = ScoringRules { interpolate_up_to_sec: 0.0, ..Default::default() };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &no_interpolation).await.expect("Failed to calculate stat changes");
+    assert_eq!(changes.stamina_change, 20);
+    assert!(
+        changes.reasoning.iter().any(|r| r.contains("20.0 min without heart rate data")),
+        "Reasoning should state the discarded time: {:?}", changes.reasoning
+    );
+
+    // A sample credited with up to 3 minutes covers the whole workout without interpolating
+    let sparse_samples = ScoringRules { max_credited_interval_sec: 180.0, interpolate_up_to_sec: 0.0, ..Default::default() };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &sparse_samples).await.expect("Failed to calculate stat changes");
+    assert_eq!(changes.stamina_change, 60);
+    assert!(!changes.reasoning.iter().any(|r| r.contains("without heart rate data")));
+
+    let invalid = ScoringRules { max_credited_interval_sec: 0.0, interpolate_up_to_sec: -1.0, ..Default::default() };
+    assert_eq!(invalid.validate().len(), 2);
+
+    // Gaps are interpolated second by second, so the limit is bounded
+    let unbounded = ScoringRules { interpolate_up_to_sec: 1e9, ..Default::default() };
+    assert_eq!(unbounded.validate().len(), 1);
+
+    // A gap interpolated only up to less than a single sample is credited with can never be interpolated
+    let below_credited = ScoringRules { max_credited_interval_sec: 120.0, interpolate_up_to_sec: 90.0, ..Default::default() };
+    assert_eq!(below_credited.validate().len(), 1);
+}
+
+#[tokio::test]
+async fn test_season_scoring_rules_replace_default_zone_points() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 5 minutes in Zone 1
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(30);
+    let heart_rate_data: Vec<HeartRateData> = (0..=300)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::seconds(i), heart_rate: 130 })
+        .collect();
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        ..Default::default()
+    };
+
+    let defaults = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+
+    let season_rules = ScoringRules {
+        season_id: Some(Uuid::new_v4()),
+        zone1_stamina_per_min: 6.0,
+        zone1_strength_per_min: 2.0,
+        ..Default::default()
+    };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &season_rules).await.expect("Failed to calculate stat changes");
+
+    // Three times the default 2 stamina per minute, and strength where Zone 1 gives none by default
+    assert_eq!(defaults.strength_change, 0);
+    assert!((changes.stamina_change - defaults.stamina_change * 3).abs() <= 1, "stamina was {}", changes.stamina_change);
+    assert!(changes.strength_change >= 9 && changes.strength_change <= 10, "strength was {}", changes.strength_change);
+    assert!(changes.reasoning.iter().any(|r| r == "Scored with the season's scoring rules"));
+}
+
+#[tokio::test]
+async fn test_training_load_from_heart_rate_reserve() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 10 minutes at 157 bpm: 75% of the 60-190 bpm heart rate reserve, in Zone 3
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(30);
+    let heart_rate_data: Vec<HeartRateData> = (0..600)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::seconds(i), heart_rate: 157 })
+        .collect();
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        ..Default::default()
+    };
+
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    let load = changes.training_load.expect("Should have a training load");
+
+    // 10 min * 0.746 * 0.64 * e^(1.92 * 0.746)
+    assert!((load.banister_trimp - 20.0).abs() < 0.5, "Banister TRIMP was {}", load.banister_trimp);
+    // 10 min in Zone 3
+    assert!((load.edwards_trimp - 30.0).abs() < 0.1, "Edwards TRIMP was {}", load.edwards_trimp);
+    assert!((load.intensity_factor - 0.933).abs() < 0.01, "Intensity factor was {}", load.intensity_factor);
+    assert!(load.epoc_estimate > 75.0 && load.epoc_estimate < 85.0, "EPOC was {}", load.epoc_estimate);
+
+    // Women's weighting has its own coefficient as well as its own exponent
+    sqlx::query("UPDATE user_health_profiles SET gender = 'female' WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    let load = changes.training_load.expect("Should have a training load");
+    // Her max heart rate is 184 bpm, so 157 bpm is 78% of the reserve: 10 min * 0.782 * 0.86 * e^(1.67 * 0.782)
+    assert!((load.banister_trimp - 24.8).abs() < 0.5, "Banister TRIMP was {}", load.banister_trimp);
+}
+
+#[tokio::test]
+async fn test_logged_sets_replace_zone_strength_with_volume_load() {
+    let test_app = spawn_app().await;
+    let user_id = create_user_with_health_profile(&test_app.db_pool).await;
+
+    // 5 minutes in Zone 1 while lifting
+    let now = Utc::now();
+    let workout_start = now - Duration::minutes(30);
+    let heart_rate_data: Vec<HeartRateData> = (0..300)
+        .map(|i| HeartRateData { timestamp: workout_start + Duration::seconds(i), heart_rate: 130 })
+        .collect();
+    let set = |reps, weight_kg, rpe| StrengthSet { reps, weight_kg, rpe };
+    let workout_data = WorkoutDataSyncRequest {
+        workout_uuid: Uuid::new_v4().to_string(),
+        device_id: "test".to_string(),
+        timestamp: now,
+        workout_start: Some(workout_start),
+        workout_end: Some(now),
+        heart_rate: Some(heart_rate_data),
+        workout_type: Some(WorkoutType::Strength),
+        strength_exercises: Some(vec![
+            StrengthExercise { name: "Deadlift".to_string(), sets: vec![set(5, 140.0, Some(6.0)), set(20, 200.0, None)] },
+        ]),
+        ..Default::default()
+    };
+
+    let without_sets = WorkoutDataSyncRequest { strength_exercises: None, ..workout_data.clone() };
+    let zones_only = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &without_sets, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+
+    // 700 kg at RPE 6 is worth 14 at 80%, 4000 kg would be worth 80 but a set earns at most 25
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &ScoringRules::default()).await.expect("Failed to calculate stat changes");
+    assert_eq!(changes.strength_change, 36);
+    assert_eq!(changes.stamina_change, zones_only.stamina_change, "Stamina still comes from heart rate");
+    assert!(changes.zone_breakdown.unwrap().iter().all(|zone| zone.strength_gained == 0));
+    assert!(changes.reasoning.iter().any(|r| r == "1 sets limited to 25 strength each"));
+    let replaced = format!("Logged sets replace the {} strength from heart rate zones", zones_only.strength_change);
+    assert!(changes.reasoning.contains(&replaced), "{:?}", changes.reasoning);
+
+    // Seasons can tune the volume load model
+    let season_rules = ScoringRules {
+        season_id: Some(Uuid::new_v4()),
+        strength_per_volume_kg: 0.01,
+        strength_max_points_per_set: 100.0,
+        ..Default::default()
+    };
+    let changes = StatCalculator::calculate_stat_changes(&test_app.db_pool, user_id, &workout_data, &season_rules).await.expect("Failed to calculate stat changes");
+    assert_eq!(changes.strength_change, 45);
+}
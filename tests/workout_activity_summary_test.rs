@@ -0,0 +1,100 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{create_advanced_workout_data, upload_workout_data_for_user, workout_hours_ago};
+
+async fn get_activity_summary(client: &Client, address: &str, token: &str, window: Option<&str>) -> reqwest::Response {
+    let url = match window {
+        Some(window) => format!("{}/health/activity?window={}", address, window),
+        None => format!("{}/health/activity", address),
+    };
+    make_authenticated_request(client, reqwest::Method::GET, &url, token, None).await
+}
+
+fn zone_minutes(summary: &serde_json::Value) -> f64 {
+    summary["zone_distribution"].as_object().unwrap().values().map(|minutes| minutes.as_f64().unwrap()).sum()
+}
+
+#[tokio::test]
+async fn activity_summary_is_aggregated_from_stored_workouts() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = get_activity_summary(&client, &test_app.address, &test_user.token, None).await;
+    assert_eq!(response.status(), 200);
+    let summary = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    assert_eq!(summary["zone_window"], "30d");
+    assert_eq!(summary["zone_distribution"].as_object().unwrap().len(), 5);
+    assert_eq!(zone_minutes(&summary), 0.0);
+    assert_eq!(summary["weekly_stats"]["total_calories"], 0);
+
+    // A run today, a run 10 days ago and a lifting session without heart rate data today
+    for hours_ago in [0, 10 * 24] {
+        let mut run = workout_hours_ago(create_advanced_workout_data(), hours_ago);
+        run["workout_type"] = json!("run");
+        upload_workout_data_for_user(&client, &test_app.address, &test_user.token, run)
+            .await
+            .expect("Upload should succeed");
+    }
+    let lifting_start = Utc::now() - Duration::hours(3);
+    let lifting = json!({
+        "device_id": format!("device-{}", Uuid::new_v4()),
+        "timestamp": lifting_start,
+        "calories_burned": 300,
+        "workout_start": lifting_start,
+        "workout_end": lifting_start + Duration::minutes(45),
+        "workout_type": "strength",
+        "workout_uuid": &Uuid::new_v4().to_string()[..8]
+    });
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, lifting)
+        .await
+        .expect("Upload should succeed");
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/history", &test_app.address), &test_user.token, None,
+    ).await;
+    let history: serde_json::Value = response.json().await.unwrap();
+    let workouts = history["data"]["workouts"].as_array().unwrap().clone();
+    assert_eq!(workouts.len(), 3);
+    let sum = |field: &str| workouts.iter().map(|workout| workout[field].as_i64().unwrap()).sum::<i64>();
+    let run_zone_minutes = |workout: &serde_json::Value| -> f64 {
+        workout["heart_rate_zones"].as_array().unwrap().iter().map(|zone| zone["minutes"].as_f64().unwrap()).sum()
+    };
+    let recent_run = workouts.iter().find(|workout| workout["calories_burned"] == 520).unwrap();
+
+    let response = get_activity_summary(&client, &test_app.address, &test_user.token, Some("30d")).await;
+    let summary = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    let run_minutes: f64 = workouts.iter()
+        .filter(|workout| workout["heart_rate_zones"].is_array())
+        .map(run_zone_minutes)
+        .sum();
+    assert!((zone_minutes(&summary) - run_minutes).abs() < 0.1, "Both runs count towards 30 days");
+    assert!(summary["zone_distribution"]["Zone 4 (Threshold)"].as_f64().unwrap() > 0.0);
+
+    // The weekly stats leave out the older run
+    let weekly_stats = &summary["weekly_stats"];
+    assert_eq!(weekly_stats["total_calories"], 820);
+    assert_eq!(weekly_stats["total_exercise_time"], recent_run["duration_minutes"].as_i64().unwrap() + 45);
+    assert_eq!(weekly_stats["strength_sessions"], 1);
+    assert_eq!(weekly_stats["cardio_sessions"], 1);
+
+    assert_eq!(summary["monthly_trend"]["stamina_gain"], sum("stamina_gained"));
+    assert_eq!(summary["monthly_trend"]["strength_gain"], sum("strength_gained"));
+
+    let response = get_activity_summary(&client, &test_app.address, &test_user.token, Some("7d")).await;
+    let summary = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    assert_eq!(summary["zone_window"], "7d");
+    assert!((zone_minutes(&summary) - run_zone_minutes(recent_run)).abs() < 0.1, "Only today's run counts towards 7 days");
+
+    // Not on a team, so there's no season to summarize
+    let response = get_activity_summary(&client, &test_app.address, &test_user.token, Some("season")).await;
+    assert_eq!(response.status(), 404);
+
+    let response = get_activity_summary(&client, &test_app.address, &test_user.token, Some("year")).await;
+    assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,101 @@
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{
+    avatar_stats_from_workouts, create_beginner_workout_data, create_intermediate_workout_data,
+    upload_workout_data_for_user, wait_for_workout_job,
+};
+
+#[tokio::test]
+async fn upload_workout_batch_reports_per_item_results() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // One workout was already synced before the batch
+    let already_synced = create_beginner_workout_data();
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, already_synced.clone())
+        .await
+        .expect("Initial upload failed");
+
+    let (stamina_before, strength_before) = avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await;
+
+    let new_workout = create_intermediate_workout_data();
+    let mut invalid_workout = create_beginner_workout_data();
+    invalid_workout["workout_uuid"] = json!("");
+
+    let batch = json!({
+        "workouts": [
+            new_workout.clone(),
+            already_synced.clone(),
+            new_workout.clone(),
+            invalid_workout
+        ]
+    });
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health_batch", &test_app.address),
+        &test_user.token,
+        Some(batch),
+    ).await;
+
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    let data = &body["data"];
+
+    assert_eq!(data["queued"], 1);
+    assert_eq!(data["duplicates"], 2);
+    assert_eq!(data["rejected"], 1);
+
+    let results = data["results"].as_array().expect("Results should be an array");
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["status"], "queued");
+    assert_eq!(results[0]["workout_uuid"], new_workout["workout_uuid"]);
+    assert_eq!(results[1]["status"], "duplicate");
+    assert_eq!(results[2]["status"], "duplicate");
+    assert_eq!(results[3]["status"], "rejected");
+    assert!(results[3]["reason"].is_string());
+
+    // The queued workout is processed by the background workers
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, results[0]["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    assert_eq!(job["result"]["workout_uuid"], new_workout["workout_uuid"]);
+
+    // Only the queued workout should have been stored and counted towards the avatar
+    let stored_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_data WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count workouts");
+    assert_eq!(stored_count, 2);
+
+    let (stamina_after, strength_after) = avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await;
+    let stamina_gain = stamina_after - stamina_before;
+    let strength_gain = strength_after - strength_before;
+    let stat_changes = &job["result"]["game_stats"]["stat_changes"];
+    assert_eq!(stamina_gain, stat_changes["stamina_change"].as_i64().unwrap() as i32);
+    assert_eq!(strength_gain, stat_changes["strength_change"].as_i64().unwrap() as i32);
+}
+
+#[tokio::test]
+async fn upload_workout_batch_rejects_empty_batch() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health_batch", &test_app.address),
+        &test_user.token,
+        Some(json!({ "workouts": [] })),
+    ).await;
+
+    assert_eq!(response.status(), 400);
+}
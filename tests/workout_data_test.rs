@@ -5,6 +5,7 @@ use sqlx::Row;
 
 mod common;
 use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{create_intermediate_workout_data, upload_workout_and_wait};
 use uuid::Uuid;
 
 #[tokio::test]
@@ -63,26 +64,13 @@ async fn upload_workout_data_working() {
         }
     });
 
-    // Upload health data
-    let response = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", &test_app.address),
-        &test_user.token,
-        Some(workout_data),
-    ).await;
-
-    let status = response.status();
-    if !status.is_success() {
-        let error_body = response.text().await.expect("Failed to read error response");
-        panic!("Health data upload failed with status {}: {}", status, error_body);
-    }
-
-    assert!(status.is_success());
+    // Upload health data and wait for the queue to process it
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data).await;
+    assert_eq!(job["status"], "completed", "Workout job should complete: {}", job);
 
     // Verify the data was stored correctly
     let saved = sqlx::query(
-        "SELECT device_id, heart_rate_data, calories_burned FROM workout_data WHERE device_id = $1"
+        "SELECT id, device_id, heart_rate_sample_count, calories_burned FROM workout_data WHERE device_id = $1"
     )
     .bind("test-device-123")
     .fetch_one(&test_app.db_pool)
@@ -90,41 +78,35 @@ async fn upload_workout_data_working() {
     .expect("Failed to fetch saved health data.");
 
     let device_id: String = saved.get("device_id");
-    let heart_rate_data: Option<serde_json::Value> = saved.get("heart_rate_data");
     let calories_burned: Option<i32> = saved.get("calories_burned");
 
     assert_eq!(device_id, "test-device-123");
-    assert!(heart_rate_data.is_some());
     assert_eq!(calories_burned, Some(450));
+    // Should have 600 heart rate readings (10 minutes of data)
+    assert_eq!(saved.get::<i32, _>("heart_rate_sample_count"), 600);
+
+    // Verify the heart rate samples in the time-series table
+    let workout_id: uuid::Uuid = saved.get("id");
+    let samples: Vec<i16> = sqlx::query_scalar(
+        "SELECT heart_rate FROM workout_heart_rate_samples WHERE workout_data_id = $1 ORDER BY recorded_at"
+    )
+    .bind(workout_id)
+    .fetch_all(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch heart rate samples");
+    assert_eq!(samples.len(), 600);
+
+    // Verify heart rate progression makes sense
+    let first_hr = samples[0];
+    let mid_hr = samples[300]; // Middle of workout
+    let last_hr = samples[599];
     
-    // Verify the heart rate data structure and content
-    if let Some(hr_data) = heart_rate_data {
-        assert!(hr_data.is_array());
-        let hr_array = hr_data.as_array().unwrap();
-        
-        // Should have 600 heart rate readings (10 minutes of data)
-        assert_eq!(hr_array.len(), 600);
-        
-        // Verify structure of first reading
-        assert!(hr_array[0]["heart_rate"].as_i64().is_some());
-        assert!(hr_array[0]["timestamp"].as_str().is_some());
-        
-        // Verify structure of last reading
-        assert!(hr_array[599]["heart_rate"].as_i64().is_some());
-        assert!(hr_array[599]["timestamp"].as_str().is_some());
-        
-        // Verify heart rate progression makes sense
-        let first_hr = hr_array[0]["heart_rate"].as_i64().unwrap();
-        let mid_hr = hr_array[300]["heart_rate"].as_i64().unwrap(); // Middle of workout
-        let last_hr = hr_array[599]["heart_rate"].as_i64().unwrap();
-        
-        // First should be resting (65-70), middle should be high intensity (>120), last should be cooling down
-        assert!(first_hr >= 65 && first_hr <= 70, "Resting HR should be 65-70 bpm, got {}", first_hr);
-        assert!(mid_hr > 120, "Peak HR should be >120 bpm, got {}", mid_hr);
-        assert!(last_hr < first_hr + 50, "Cooldown HR should not be too high, got {}", last_hr);
-        
-        println!("Heart rate progression: start={:.1}, peak={:.1}, end={:.1}", first_hr, mid_hr, last_hr);
-    }
+    // First should be resting (65-70), middle should be high intensity (>120), last should be cooling down
+    assert!(first_hr >= 65 && first_hr <= 70, "Resting HR should be 65-70 bpm, got {}", first_hr);
+    assert!(mid_hr > 120, "Peak HR should be >120 bpm, got {}", mid_hr);
+    assert!(last_hr < first_hr + 50, "Cooldown HR should not be too high, got {}", last_hr);
+    
+    println!("Heart rate progression: start={:.1}, peak={:.1}, end={:.1}", first_hr, mid_hr, last_hr);
 }
 
 #[tokio::test]
@@ -156,19 +138,9 @@ async fn duplicate_workout_uuid_prevention() {
     });
 
     // First upload - should succeed
-    let response1 = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", &test_app.address),
-        &test_user.token,
-        Some(workout_data.clone()),
-    ).await;
-
-    assert!(response1.status().is_success(), "First upload should succeed");
-    
-    let response1_body: serde_json::Value = response1.json().await.expect("Failed to parse response");
-    assert_eq!(response1_body["success"], true);
-    assert!(response1_body["data"]["game_stats"].is_object(), "Should contain game stats");
+    let job1 = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data.clone()).await;
+    assert_eq!(job1["status"], "completed", "First upload should succeed");
+    assert!(job1["result"]["game_stats"].is_object(), "Should contain game stats");
 
     // Second upload with same UUID - should be rejected as duplicate
     let response2 = make_authenticated_request(
@@ -203,19 +175,9 @@ async fn duplicate_workout_uuid_prevention() {
     let mut workout_data_different = workout_data.clone();
     workout_data_different["workout_uuid"] = json!(different_uuid);
 
-    let response3 = make_authenticated_request(
-        &client,
-        reqwest::Method::POST,
-        &format!("{}/health/upload_health", &test_app.address),
-        &test_user.token,
-        Some(workout_data_different),
-    ).await;
-
-    assert!(response3.status().is_success(), "Different UUID upload should succeed");
-    
-    let response3_body: serde_json::Value = response3.json().await.expect("Failed to parse third response");
-    assert_eq!(response3_body["success"], true);
-    assert!(response3_body["data"]["game_stats"].is_object(), "Should contain game stats for new workout");
+    let job3 = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data_different).await;
+    assert_eq!(job3["status"], "completed", "Different UUID upload should succeed");
+    assert!(job3["result"]["game_stats"].is_object(), "Should contain game stats for new workout");
 
     // Verify now we have two records with different UUIDs
     let total_count = sqlx::query_scalar::<_, i64>(
@@ -228,4 +190,51 @@ async fn duplicate_workout_uuid_prevention() {
     .expect("Failed to count total health data records");
 
     assert_eq!(total_count, 2, "Should have two records with different workout UUIDs");
-} 
\ No newline at end of file
+} 
+#[tokio::test]
+async fn retried_workout_upload_does_not_double_count_stats() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let workout_data = create_intermediate_workout_data();
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data.clone()).await;
+    assert_eq!(job["status"], "completed", "First upload should succeed");
+
+    let avatar_after_first = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch avatar");
+    let stamina_after_first: i32 = avatar_after_first.get("stamina");
+    let strength_after_first: i32 = avatar_after_first.get("strength");
+
+    // Stat gains are stored with the workout in the same write
+    let stored = sqlx::query("SELECT stamina_gained, strength_gained, total_points_gained FROM workout_data WHERE workout_uuid = $1")
+        .bind(workout_data["workout_uuid"].as_str().unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch stored workout");
+    assert_eq!(stored.get::<i32, _>("stamina_gained"), stamina_after_first);
+    assert_eq!(stored.get::<i32, _>("strength_gained"), strength_after_first);
+    assert_eq!(stored.get::<i32, _>("total_points_gained"), stamina_after_first + strength_after_first);
+
+    // A client retry of the same workout must be rejected without touching the avatar
+    let response2 = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", &test_app.address),
+        &test_user.token,
+        Some(workout_data.clone()),
+    ).await;
+    assert_eq!(response2.status(), 409, "Retried upload should return 409 Conflict");
+
+    let avatar_after_retry = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch avatar");
+    assert_eq!(avatar_after_retry.get::<i32, _>("stamina"), stamina_after_first);
+    assert_eq!(avatar_after_retry.get::<i32, _>("strength"), strength_after_first);
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Duration, SubsecRound, Utc};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{
+    create_advanced_workout_data, upload_workout_data_for_user, wait_for_workout_job, workout_hours_ago,
+};
+
+async fn export(client: &Client, url: &str, token: &str) -> reqwest::Response {
+    make_authenticated_request(client, reqwest::Method::GET, url, token, None).await
+}
+
+async fn upload_file(client: &Client, address: &str, token: &str, body: String) -> reqwest::Response {
+    client.post(format!("{}/health/upload_workout_file", address))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request")
+}
+
+#[tokio::test]
+async fn workout_history_exports_as_csv_and_ndjson_within_a_date_range() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Two runs a day apart and a session without heart rate data
+    for hours_ago in [0, 24] {
+        upload_workout_data_for_user(&client, &test_app.address, &test_user.token, workout_hours_ago(create_advanced_workout_data(), hours_ago))
+            .await
+            .expect("Upload should succeed");
+    }
+    let lifting_start = Utc::now() - Duration::hours(4);
+    let lifting = json!({
+        "device_id": "gym, machine 3",
+        "timestamp": lifting_start,
+        "calories_burned": 250,
+        "workout_start": lifting_start,
+        "workout_end": lifting_start + Duration::minutes(40),
+        "workout_type": "strength",
+        "workout_uuid": &Uuid::new_v4().to_string()[..8]
+    });
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, lifting)
+        .await
+        .expect("Upload should succeed");
+
+    let response = export(&client, &format!("{}/health/export", &test_app.address), &test_user.token).await;
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/csv"));
+    assert!(response.headers()["content-disposition"].to_str().unwrap().contains("workouts.csv"));
+    let csv = response.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert!(lines[0].starts_with("workout_id,workout_uuid,device_id,"));
+    assert!(lines[0].ends_with(",sample_time,heart_rate,cadence,power_watts,speed_mps,distance_meters,steps"));
+    // A line per heart rate sample of each run, one for the session without samples
+    assert_eq!(lines.len(), 1 + 1500 * 2 + 1);
+    let lifting_line = lines[1..].iter().find(|line| line.contains(",strength,")).unwrap();
+    assert!(lifting_line.contains("\"gym, machine 3\""), "Fields with commas are quoted");
+    assert!(lifting_line.ends_with(",,,,,,,"), "No sample columns without samples");
+
+    // Only the last 12 hours
+    let from = (Utc::now() - Duration::hours(12)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let response = export(
+        &client, &format!("{}/health/export?format=ndjson&from={}", &test_app.address, from), &test_user.token,
+    ).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+    let ndjson = response.text().await.unwrap();
+    let workouts: Vec<serde_json::Value> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(workouts.len(), 2);
+    // Oldest first
+    assert_eq!(workouts[0]["workout_type"], "strength");
+    assert_eq!(workouts[0]["heart_rate"].as_array().unwrap().len(), 0);
+    assert_eq!(workouts[1]["heart_rate"].as_array().unwrap().len(), 1500);
+    assert_eq!(workouts[1]["calories_burned"], 520);
+
+    let response = export(&client, &format!("{}/health/export?format=tcx", &test_app.address), &test_user.token).await;
+    assert_eq!(response.status(), 400, "TCX holds a single workout");
+
+    let to = (Utc::now() - Duration::hours(24)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let response = export(
+        &client, &format!("{}/health/export?from={}&to={}", &test_app.address, from, to), &test_user.token,
+    ).await;
+    assert_eq!(response.status(), 400, "The range must not be empty");
+}
+
+#[tokio::test]
+async fn single_workout_exports_as_tcx_that_imports_elsewhere() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Cadence, power, speed and steps recorded alongside each heart rate sample
+    let mut run = create_advanced_workout_data();
+    run["workout_type"] = json!("run");
+    // Whole seconds, as devices record them, so the streams line up with the stored heart rate
+    for sample in run["heart_rate"].as_array_mut().unwrap() {
+        let timestamp: DateTime<Utc> = serde_json::from_value(sample["timestamp"].clone()).unwrap();
+        sample["timestamp"] = json!(timestamp.trunc_subsecs(0));
+    }
+    let timestamps: Vec<serde_json::Value> = run["heart_rate"].as_array().unwrap().iter()
+        .map(|sample| sample["timestamp"].clone())
+        .collect();
+    run["cadence"] = json!(timestamps.iter().map(|t| json!({ "timestamp": t, "cadence": 170 })).collect::<Vec<_>>());
+    run["power"] = json!(timestamps.iter().map(|t| json!({ "timestamp": t, "watts": 250 })).collect::<Vec<_>>());
+    run["speed"] = json!(timestamps.iter().map(|t| json!({ "timestamp": t, "meters_per_second": 3.5 })).collect::<Vec<_>>());
+    run["steps"] = json!(timestamps.iter().map(|t| json!({ "timestamp": t, "steps": 3 })).collect::<Vec<_>>());
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, run)
+        .await
+        .expect("Upload should succeed");
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/history", &test_app.address), &test_user.token, None,
+    ).await;
+    let history: serde_json::Value = response.json().await.unwrap();
+    let workout_id = history["data"]["workouts"][0]["id"].as_str().unwrap().to_string();
+
+    // Without positions the track points wouldn't be valid GPX
+    let response = export(
+        &client, &format!("{}/health/workouts/{}/export?format=gpx", &test_app.address, workout_id), &test_user.token,
+    ).await;
+    assert_eq!(response.status(), 422);
+
+    let response = export(&client, &format!("{}/health/workouts/{}/export", &test_app.address, workout_id), &test_user.token).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "application/vnd.garmin.tcx+xml");
+    let tcx = response.text().await.unwrap();
+    assert!(tcx.contains("<Activity Sport=\"Running\">"));
+    assert!(tcx.contains("<HeartRateBpm><Value>"));
+    assert!(tcx.contains("<ns3:RunCadence>85</ns3:RunCadence>"));
+    assert!(tcx.contains("<ns3:Watts>250</ns3:Watts>"));
+
+    // The CSV history carries the sensor streams next to the heart rate
+    let response = export(&client, &format!("{}/health/export", &test_app.address), &test_user.token).await;
+    let csv = response.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 1 + 1500, "Streams recorded at the same moment share a line");
+    assert!(lines[1].ends_with(",170,250,3.5,,3"), "Unexpected sample columns in {}", lines[1]);
+
+    // The TCX carries the whole heart rate series to another account
+    let other_user = create_test_user_and_login(&test_app.address).await;
+    let response = upload_file(&client, &test_app.address, &other_user.token, tcx.clone()).await;
+    assert_eq!(response.status(), 202, "Exported TCX should import");
+    let body: serde_json::Value = response.json().await.unwrap();
+    let job = wait_for_workout_job(&client, &test_app.address, &other_user.token, body["data"]["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    let stored = sqlx::query(
+        "SELECT workout_type, heart_rate_sample_count, avg_cadence FROM workout_data WHERE workout_uuid = $1"
+    )
+    .bind(job["result"]["workout_uuid"].as_str().unwrap())
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch imported workout");
+    assert_eq!(stored.get::<Option<String>, _>("workout_type").as_deref(), Some("run"));
+    assert_eq!(stored.get::<i32, _>("heart_rate_sample_count"), 1500);
+    assert_eq!(stored.get::<Option<i32>, _>("avg_cadence"), Some(170));
+
+    // Importing it again is recognized as the same activity
+    let response = upload_file(&client, &test_app.address, &other_user.token, tcx).await;
+    assert_eq!(response.status(), 409);
+
+    // Other users' workouts can't be exported
+    let other_user = create_test_user_and_login(&test_app.address).await;
+    let response = export(&client, &format!("{}/health/workouts/{}/export", &test_app.address, workout_id), &other_user.token).await;
+    assert_eq!(response.status(), 404);
+
+    let response = export(
+        &client, &format!("{}/health/workouts/{}/export?format=csv", &test_app.address, workout_id), &test_user.token,
+    ).await;
+    assert_eq!(response.status(), 400);
+}
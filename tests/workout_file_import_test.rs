@@ -0,0 +1,290 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login};
+use common::workout_data_helpers::wait_for_workout_job;
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31T00:00:00Z)
+const FIT_EPOCH_OFFSET: i64 = 631_065_600;
+
+/// A 20 minute track with one sample every 10 seconds, starting at a unique second
+/// so the content-derived workout_uuid doesn't collide across test runs
+fn sample_track() -> Vec<(DateTime<Utc>, i32)> {
+    let jitter = (Uuid::new_v4().as_u128() % 86_400) as i64;
+    let start = DateTime::from_timestamp(Utc::now().timestamp() - 2 * 86_400 - jitter, 0).unwrap();
+
+    (0..120i32)
+        .map(|i| {
+            let heart_rate = if i < 20 { 100 + i * 2 } else { 145 + (i % 10) };
+            (start + Duration::seconds(i as i64 * 10), heart_rate)
+        })
+        .collect()
+}
+
+fn build_gpx(track: &[(DateTime<Utc>, i32)]) -> String {
+    let points: String = track.iter()
+        .map(|(time, hr)| format!(
+            r#"<trkpt lat="47.0" lon="8.0"><time>{}</time><extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>{}</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions></trkpt>"#,
+            time.to_rfc3339(), hr
+        ))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Garmin Connect" xmlns="http://www.topografix.com/GPX/1/1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+<trk><name>Morning Run</name><trkseg>{}</trkseg></trk>
+</gpx>"#,
+        points
+    )
+}
+
+fn build_tcx(track: &[(DateTime<Utc>, i32)], calories: i32) -> String {
+    let points: String = track.iter()
+        .map(|(time, hr)| format!(
+            "<Trackpoint><Time>{}</Time><HeartRateBpm><Value>{}</Value></HeartRateBpm></Trackpoint>",
+            time.to_rfc3339(), hr
+        ))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+<Activities><Activity Sport="Running"><Id>{}</Id>
+<Lap StartTime="{}"><TotalTimeSeconds>1200</TotalTimeSeconds><Calories>{}</Calories><Track>{}</Track></Lap>
+</Activity></Activities>
+</TrainingCenterDatabase>"#,
+        track[0].0.to_rfc3339(), track[0].0.to_rfc3339(), calories, points
+    )
+}
+
+/// A ride with cumulative distance, cadence and the TPX speed and power extension on every point
+fn build_cycling_tcx(track: &[(DateTime<Utc>, i32)]) -> String {
+    let points: String = track.iter()
+        .enumerate()
+        .map(|(i, (time, hr))| format!(
+            "<Trackpoint><Time>{}</Time><DistanceMeters>{}</DistanceMeters><HeartRateBpm><Value>{}</Value></HeartRateBpm>\
+             <Cadence>85</Cadence><Extensions><ns3:TPX><ns3:Speed>8.5</ns3:Speed><ns3:Watts>{}</ns3:Watts></ns3:TPX></Extensions></Trackpoint>",
+            time.to_rfc3339(), i as f64 * 85.0, hr, 180 + (i % 5) * 10
+        ))
+        .collect();
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2" xmlns:ns3="http://www.garmin.com/xmlschemas/ActivityExtension/v2">
+<Activities><Activity Sport="Biking"><Id>{}</Id>
+<Lap StartTime="{}"><TotalTimeSeconds>1200</TotalTimeSeconds><DistanceMeters>10115</DistanceMeters><Track>{}</Track></Lap>
+</Activity></Activities>
+</TrainingCenterDatabase>"#,
+        track[0].0.to_rfc3339(), track[0].0.to_rfc3339(), points
+    )
+}
+
+fn build_fit(track: &[(DateTime<Utc>, i32)], calories: u16) -> Vec<u8> {
+    let mut records = Vec::new();
+
+    // Definition: local 0 = record (20) with timestamp (253, uint32) and heart_rate (3, uint8)
+    records.extend_from_slice(&[0x40, 0, 0, 20, 0, 2, 253, 4, 0x86, 3, 1, 0x02]);
+    for (time, hr) in track {
+        records.push(0x00);
+        records.extend_from_slice(&((time.timestamp() - FIT_EPOCH_OFFSET) as u32).to_le_bytes());
+        records.push(*hr as u8);
+    }
+
+    // Definition: local 1 = session (18) with total_calories (11, uint16)
+    records.extend_from_slice(&[0x41, 0, 0, 18, 0, 1, 11, 2, 0x84]);
+    records.push(0x01);
+    records.extend_from_slice(&calories.to_le_bytes());
+
+    let mut file = vec![14, 0x20, 0x54, 0x08];
+    file.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    file.extend_from_slice(b".FIT");
+    file.extend_from_slice(&[0, 0]);
+    file.extend_from_slice(&records);
+    // File CRC, not validated by the importer
+    file.extend_from_slice(&[0, 0]);
+    file
+}
+
+async fn upload_file(client: &Client, address: &str, token: &str, body: Vec<u8>) -> reqwest::Response {
+    client.post(format!("{}/health/upload_workout_file", address))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request")
+}
+
+/// Wait for the queued import to be processed, returning the finished job
+async fn wait_for_import(client: &Client, address: &str, token: &str, response: reqwest::Response) -> serde_json::Value {
+    assert_eq!(response.status(), 202, "Imported workouts should be queued");
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    let job = wait_for_workout_job(client, address, token, body["data"]["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    job
+}
+
+#[tokio::test]
+async fn import_gpx_file_creates_workout_and_rejects_reimport() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let track = sample_track();
+
+    let response = upload_file(&client, &test_app.address, &test_user.token, build_gpx(&track).into_bytes()).await;
+    let job = wait_for_import(&client, &test_app.address, &test_user.token, response).await;
+    assert!(job["result"]["game_stats"].is_object(), "Should contain game stats");
+
+    let workout_uuid = job["result"]["workout_uuid"].as_str().expect("Should return the derived workout_uuid");
+
+    let stored = sqlx::query(
+        "SELECT device_id, workout_start, workout_end, heart_rate_sample_count AS samples, stamina_gained
+         FROM workout_data WHERE workout_uuid = $1"
+    )
+    .bind(workout_uuid)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch imported workout");
+
+    assert_eq!(stored.get::<String, _>("device_id"), "gpx");
+    assert_eq!(stored.get::<Option<DateTime<Utc>>, _>("workout_start"), Some(track[0].0));
+    assert_eq!(stored.get::<Option<DateTime<Utc>>, _>("workout_end"), Some(track[track.len() - 1].0));
+    assert_eq!(stored.get::<Option<i32>, _>("samples"), Some(track.len() as i32));
+    assert!(stored.get::<i32, _>("stamina_gained") > 0, "Heart rate track should earn stamina");
+
+    // The same activity exported as TCX derives the same workout_uuid
+    let response = upload_file(&client, &test_app.address, &test_user.token, build_tcx(&track, 300).into_bytes()).await;
+    assert_eq!(response.status(), 409, "Re-importing the same activity should be rejected");
+
+    // Another user importing the same file gets a workout of their own
+    let other_user = create_test_user_and_login(&test_app.address).await;
+    let response = upload_file(&client, &test_app.address, &other_user.token, build_gpx(&track).into_bytes()).await;
+    let job = wait_for_import(&client, &test_app.address, &other_user.token, response).await;
+    assert_ne!(job["result"]["workout_uuid"].as_str(), Some(workout_uuid));
+}
+
+#[tokio::test]
+async fn import_tcx_file_reads_heart_rate_and_calories() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let track = sample_track();
+
+    let response = upload_file(&client, &test_app.address, &test_user.token, build_tcx(&track, 312).into_bytes()).await;
+    let job = wait_for_import(&client, &test_app.address, &test_user.token, response).await;
+    let workout_uuid = job["result"]["workout_uuid"].as_str().unwrap();
+
+    let stored = sqlx::query(
+        "SELECT device_id, calories_burned, max_heart_rate FROM workout_data WHERE workout_uuid = $1"
+    )
+    .bind(workout_uuid)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch imported workout");
+
+    assert_eq!(stored.get::<String, _>("device_id"), "tcx");
+    assert_eq!(stored.get::<Option<i32>, _>("calories_burned"), Some(312));
+    assert_eq!(stored.get::<Option<i32>, _>("max_heart_rate"), track.iter().map(|(_, hr)| *hr).max());
+}
+
+#[tokio::test]
+async fn import_tcx_file_reads_sport_power_cadence_speed_and_distance() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let track = sample_track();
+
+    let response = upload_file(&client, &test_app.address, &test_user.token, build_cycling_tcx(&track).into_bytes()).await;
+    let job = wait_for_import(&client, &test_app.address, &test_user.token, response).await;
+    let workout_uuid = job["result"]["workout_uuid"].as_str().unwrap();
+
+    let stored = sqlx::query(
+        "SELECT workout_type, avg_power, max_power, avg_cadence, total_distance_meters,
+                (SELECT COUNT(speed_mps)::int FROM workout_sensor_samples WHERE workout_data_id = wd.id) AS speed_samples,
+                (SELECT speed_mps FROM workout_sensor_samples WHERE workout_data_id = wd.id
+                 ORDER BY recorded_at LIMIT 1) AS first_speed
+         FROM workout_data wd WHERE workout_uuid = $1"
+    )
+    .bind(workout_uuid)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch imported workout");
+
+    assert_eq!(stored.get::<Option<String>, _>("workout_type").as_deref(), Some("ride"));
+    assert_eq!(stored.get::<Option<i32>, _>("avg_power"), Some(200));
+    assert_eq!(stored.get::<Option<i32>, _>("max_power"), Some(220));
+    assert_eq!(stored.get::<Option<i32>, _>("avg_cadence"), Some(85));
+    assert_eq!(stored.get::<Option<f64>, _>("total_distance_meters"), Some((track.len() - 1) as f64 * 85.0));
+    assert_eq!(stored.get::<Option<i32>, _>("speed_samples"), Some(track.len() as i32));
+    assert_eq!(stored.get::<Option<f64>, _>("first_speed"), Some(8.5));
+}
+
+#[tokio::test]
+async fn import_fit_file_decodes_records_and_session() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let track = sample_track();
+
+    let response = client.post(format!("{}/health/upload_workout_file?device_id=garmin-fenix", &test_app.address))
+        .header("Authorization", format!("Bearer {}", test_user.token))
+        .body(build_fit(&track, 275))
+        .send()
+        .await
+        .expect("Failed to execute request");
+    let job = wait_for_import(&client, &test_app.address, &test_user.token, response).await;
+    let workout_uuid = job["result"]["workout_uuid"].as_str().unwrap();
+
+    let stored = sqlx::query(
+        "SELECT device_id, calories_burned, workout_start, heart_rate_sample_count AS samples
+         FROM workout_data WHERE workout_uuid = $1"
+    )
+    .bind(workout_uuid)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch imported workout");
+
+    assert_eq!(stored.get::<String, _>("device_id"), "garmin-fenix");
+    assert_eq!(stored.get::<Option<i32>, _>("calories_burned"), Some(275));
+    assert_eq!(stored.get::<Option<DateTime<Utc>>, _>("workout_start"), Some(track[0].0));
+    assert_eq!(stored.get::<Option<i32>, _>("samples"), Some(track.len() as i32));
+}
+
+#[tokio::test]
+async fn import_rejects_unsupported_file() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = upload_file(&client, &test_app.address, &test_user.token, b"not a workout file".to_vec()).await;
+    assert_eq!(response.status(), 400);
+
+    // Truncated FIT file
+    let mut fit = build_fit(&sample_track(), 100);
+    fit.truncate(40);
+    let response = upload_file(&client, &test_app.address, &test_user.token, fit).await;
+    assert_eq!(response.status(), 400);
+
+    // A compressed timestamp rolling over past the largest FIT timestamp
+    let mut fit = build_fit(&[], 100);
+    let records_end = fit.len() - 2;
+    let mut records = vec![0x00];
+    records.extend_from_slice(&(u32::MAX - 1).to_le_bytes());
+    records.push(120);
+    records.push(0x81);
+    records.extend_from_slice(&0u32.to_le_bytes());
+    records.push(120);
+    fit.splice(records_end..records_end, records.iter().copied());
+    let data_size = u32::from_le_bytes(fit[4..8].try_into().unwrap()) + records.len() as u32;
+    fit[4..8].copy_from_slice(&data_size.to_le_bytes());
+    let response = upload_file(&client, &test_app.address, &test_user.token, fit).await;
+    assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,63 @@
+use reqwest::Client;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{create_intermediate_workout_data, upload_workout_data_for_user};
+
+#[tokio::test]
+async fn heart_rate_series_is_downsampled_for_charts() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let other_user = create_test_user_and_login(&test_app.address).await;
+
+    // 900 one-second samples
+    let job = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, create_intermediate_workout_data())
+        .await
+        .expect("Upload should succeed");
+    let workout_id = job["result"]["sync_id"].as_str().unwrap().to_string();
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/workouts/{}/heart_rate?points=60", &test_app.address, workout_id),
+        &test_user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse heart rate series");
+    let data = &body["data"];
+    assert_eq!(data["sample_count"], 900);
+    assert_eq!(data["bucket_seconds"], 15);
+
+    let series = data["series"].as_array().unwrap();
+    assert!((60..=61).contains(&series.len()), "Expected about 60 points, got {}", series.len());
+    assert_eq!(series.iter().map(|point| point["samples"].as_i64().unwrap()).sum::<i64>(), 900);
+    assert!(series.iter().all(|point| {
+        point["min_heart_rate"].as_i64() <= point["avg_heart_rate"].as_i64()
+            && point["avg_heart_rate"].as_i64() <= point["max_heart_rate"].as_i64()
+    }));
+
+    // Summaries in the history don't carry the raw series
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/history", &test_app.address),
+        &test_user.token,
+        None,
+    ).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse history");
+    let workout = &body["data"]["workouts"][0];
+    assert!(workout["avg_heart_rate"].as_i64().unwrap() > 0);
+    assert!(workout.get("heart_rate").is_none());
+
+    // Other users can't read someone else's series
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/workouts/{}/heart_rate", &test_app.address, workout_id),
+        &other_user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 404);
+}
@@ -10,6 +10,8 @@ use crate::common::{
     workout_data_helpers::{
         create_advanced_workout_data,
         create_elite_workout_data,
+        upload_workout_data_for_user,
+        workout_hours_ago,
     },
     utils::create_test_user_and_login,
 };
@@ -53,15 +55,9 @@ async fn test_workout_history_with_data() {
 
     let workout_data = create_advanced_workout_data();
 
-    let health_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", token))
-        .json(&workout_data)
-        .send()
+    upload_workout_data_for_user(&client, &test_app.address, &token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(health_response.status().is_success(), "Health upload should succeed");
+        .expect("Workout upload should be processed");
 
     // Wait a bit for processing
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -115,18 +111,13 @@ async fn test_workout_history_pagination() {
     let token = test_user.token;
 
     // Upload multiple health data entries
-    for _ in 0..5 {
-        let workout_data = create_advanced_workout_data();
-
-        let health_response = client
-            .post(&format!("{}/health/upload_health", &test_app.address))
-            .header("Authorization", format!("Bearer {}", token))
-            .json(&workout_data)
-            .send()
-            .await
-            .expect("Failed to execute health upload request.");
-
-        assert!(health_response.status().is_success(), "Health upload should succeed");
+    for hours_ago in 0..5 {
+        let workout_data = workout_hours_ago(create_advanced_workout_data(), hours_ago);
+
+        upload_workout_data_for_user(&client, &test_app.address, &token, workout_data.clone())
+        .await
+        .expect("Workout upload should be processed");
+
     }
 
     // Wait for processing
@@ -210,15 +201,9 @@ async fn test_workout_history_with_stats() {
     // Upload workout data with high intensity to generate stats
     let workout_data = create_elite_workout_data();
 
-    let health_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", token))
-        .json(&workout_data)
-        .send()
+    upload_workout_data_for_user(&client, &test_app.address, &token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(health_response.status().is_success(), "Health upload should succeed");
+        .expect("Workout upload should be processed");
 
     // Wait for processing
     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
@@ -274,15 +259,9 @@ async fn test_workout_history_zone_breakdown() {
     // Upload workout data with high intensity to generate zone breakdown
     let workout_data = create_elite_workout_data();
 
-    let health_response = client
-        .post(&format!("{}/health/upload_health", &test_app.address))
-        .header("Authorization", format!("Bearer {}", token))
-        .json(&workout_data)
-        .send()
+    upload_workout_data_for_user(&client, &test_app.address, &token, workout_data.clone())
         .await
-        .expect("Failed to execute health upload request.");
-
-    assert!(health_response.status().is_success(), "Health upload should succeed");
+        .expect("Workout upload should be processed");
 
     // Wait for processing
     tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
@@ -354,4 +333,42 @@ async fn test_workout_history_zone_breakdown() {
     } else {
         panic!("❌ Zone breakdown is null - this indicates the zone calculation/storage is not working");
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_workout_history_shows_workout_type() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let mut strength_workout = create_advanced_workout_data();
+    strength_workout["workout_type"] = json!("strength");
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, strength_workout)
+        .await
+        .expect("Strength workout upload should be processed");
+
+    // Types this server doesn't know yet are accepted and scored as other
+    let mut unknown_workout = workout_hours_ago(create_advanced_workout_data(), 1);
+    unknown_workout["workout_type"] = json!("climbing");
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, unknown_workout)
+        .await
+        .expect("Workout with an unknown type should be processed");
+
+    let history_body: serde_json::Value = client
+        .get(&format!("{}/health/history", &test_app.address))
+        .header("Authorization", format!("Bearer {}", test_user.token))
+        .send()
+        .await
+        .expect("Failed to execute workout history request.")
+        .json()
+        .await
+        .expect("Failed to parse workout history response");
+
+    let mut workout_types: Vec<&str> = history_body["data"]["workouts"].as_array().unwrap()
+        .iter()
+        .map(|workout| workout["workout_type"].as_str().unwrap())
+        .collect();
+    workout_types.sort();
+    assert_eq!(workout_types, vec!["other", "strength"]);
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{upload_workout_and_wait, wait_for_workout_job};
+
+/// A 30 minute workout sampled every `interval_seconds`, with heart rate shifted by `heart_rate_offset`.
+/// Heart rate follows wall-clock time, so devices recording the same activity read alike.
+fn recording(
+    device_id: &str,
+    start: DateTime<Utc>,
+    interval_seconds: i64,
+    heart_rate_offset: i32,
+) -> serde_json::Value {
+    let heart_rate: Vec<serde_json::Value> = (0..1800 / interval_seconds)
+        .map(|i| {
+            let timestamp = start + Duration::seconds(i * interval_seconds);
+            let heart_rate = 125 + (25.0 * (timestamp.timestamp() as f64 / 300.0).sin()) as i32 + heart_rate_offset;
+            json!({ "timestamp": timestamp, "heart_rate": heart_rate })
+        })
+        .collect();
+
+    json!({
+        "device_id": device_id,
+        "timestamp": start + Duration::minutes(30),
+        "heart_rate": heart_rate,
+        "calories_burned": 300,
+        "workout_start": start,
+        "workout_end": start + Duration::minutes(30),
+        "workout_uuid": &Uuid::new_v4().to_string()[..8],
+    })
+}
+
+async fn stored_workouts(pool: &sqlx::PgPool, user_id: Uuid) -> Vec<(Uuid, String, i32, i32)> {
+    sqlx::query("SELECT id, device_id, stamina_gained, strength_gained FROM workout_data WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .expect("Failed to fetch workouts")
+        .iter()
+        .map(|row| (row.get("id"), row.get("device_id"), row.get("stamina_gained"), row.get("strength_gained")))
+        .collect()
+}
+
+async fn avatar_stats(pool: &sqlx::PgPool, user_id: Uuid) -> (i32, i32) {
+    let row = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to fetch avatar");
+    (row.get("stamina"), row.get("strength"))
+}
+
+#[tokio::test]
+async fn denser_duplicate_replaces_the_stored_recording_without_double_counting() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    let watch = recording("watch", start, 5, 0);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, watch.clone()).await;
+    assert_eq!(job["status"], "completed");
+    let watch_id = job["result"]["sync_id"].as_str().unwrap().to_string();
+
+    // A chest strap recording the same run, started a little later and reading a few bpm higher
+    let strap = recording("chest-strap", start + Duration::seconds(20), 1, 3);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, strap).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["replaced_workout_id"], watch_id.as_str());
+    assert!(job["result"]["game_stats"]["reasoning"].as_array().unwrap().iter()
+        .any(|line| line.as_str().unwrap().contains("Replaced duplicate recording")));
+
+    let workouts = stored_workouts(&test_app.db_pool, test_user.user_id).await;
+    assert_eq!(workouts.len(), 1);
+    let (_, device_id, stamina_gained, strength_gained) = &workouts[0];
+    assert_eq!(device_id, "chest-strap");
+    assert_eq!(
+        avatar_stats(&test_app.db_pool, test_user.user_id).await,
+        (*stamina_gained, *strength_gained),
+        "Only the kept recording is counted"
+    );
+
+    // The sparser recording no longer wins, even under a new uuid
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, recording("watch", start, 5, 0)).await;
+    assert_eq!(job["status"], "rejected");
+    assert_eq!(job["result"]["validation_issues"][0]["code"], "overlapping_workout");
+
+    // Retrying the replaced upload is answered as already synced
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", &test_app.address),
+        &test_user.token,
+        Some(watch),
+    ).await;
+    assert_eq!(response.status(), 409);
+}
+
+#[tokio::test]
+async fn reject_policy_refuses_a_duplicate() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, recording("watch", start, 5, 0)).await;
+    assert_eq!(job["status"], "completed");
+
+    let mut strap = recording("chest-strap", start, 1, -2);
+    strap["overlap_policy"] = json!("reject");
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, strap).await;
+    assert_eq!(job["status"], "rejected");
+    let issue = &job["result"]["validation_issues"][0];
+    assert_eq!(issue["code"], "overlapping_workout");
+    assert!(issue["message"].as_str().unwrap().contains("watch"));
+
+    assert_eq!(stored_workouts(&test_app.db_pool, test_user.user_id).await.len(), 1);
+}
+
+#[tokio::test]
+async fn merge_policy_combines_both_recordings() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, recording("watch", start, 10, 0)).await;
+    let watch_id = job["result"]["sync_id"].as_str().unwrap().to_string();
+
+    let mut phone = recording("phone", start + Duration::minutes(5), 10, 2);
+    phone["overlap_policy"] = json!("merge");
+    phone["workout_type"] = json!("run");
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, phone).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["replaced_workout_id"], watch_id.as_str());
+
+    let row = sqlx::query(
+        "SELECT workout_start, workout_end, heart_rate_sample_count AS samples, workout_type, stamina_gained, strength_gained \
+         FROM workout_data WHERE user_id = $1"
+    )
+    .bind(test_user.user_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Merged workout should be the only one stored");
+
+    let merged_start: DateTime<Utc> = row.get("workout_start");
+    let merged_end: DateTime<Utc> = row.get("workout_end");
+    assert_eq!(merged_start.timestamp(), start.timestamp());
+    assert_eq!(merged_end.timestamp(), (start + Duration::minutes(35)).timestamp());
+    assert!(row.get::<i32, _>("samples") > 180, "Samples from both devices are kept");
+    assert_eq!(row.get::<Option<String>, _>("workout_type").as_deref(), Some("run"));
+
+    assert_eq!(
+        avatar_stats(&test_app.db_pool, test_user.user_id).await,
+        (row.get("stamina_gained"), row.get("strength_gained")),
+        "The merged workout is not counted on top of the original"
+    );
+}
+
+#[tokio::test]
+async fn concurrent_activity_with_different_heart_rate_is_kept() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, recording("watch", start, 5, 0)).await;
+    assert_eq!(job["status"], "completed");
+
+    // Same time window, but a heart rate no device on the same body would read
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, recording("bike-computer", start, 1, 30)).await;
+    assert_eq!(job["status"], "completed");
+    assert!(job["result"]["replaced_workout_id"].is_null());
+
+    assert_eq!(stored_workouts(&test_app.db_pool, test_user.user_id).await.len(), 2);
+}
+
+#[tokio::test]
+async fn recordings_of_one_activity_uploaded_at_once_are_stored_once() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    let batch_url = format!("{}/health/upload_health_batch", &test_app.address);
+    let upload = |recording: serde_json::Value| make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &batch_url,
+        &test_user.token,
+        Some(json!({ "workouts": [recording] })),
+    );
+    let (watch, strap) = tokio::join!(
+        upload(recording("watch", start, 5, 0)),
+        upload(recording("chest-strap", start + Duration::seconds(20), 1, 3)),
+    );
+    for response in [watch, strap] {
+        assert_eq!(response.status(), 202);
+        let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+        let job_id = body["data"]["results"][0]["job_id"].as_str().expect("Workout should be queued");
+        // Whichever job runs second either replaces the first recording or is rejected as the worse one
+        let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, job_id).await;
+        assert!(job["status"] == "completed" || job["status"] == "rejected", "{}", job);
+    }
+
+    let workouts = stored_workouts(&test_app.db_pool, test_user.user_id).await;
+    assert_eq!(workouts.len(), 1, "Only one recording of the activity is kept");
+    let (_, _, stamina_gained, strength_gained) = &workouts[0];
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, (*stamina_gained, *strength_gained));
+}
+
+#[tokio::test]
+async fn merged_recording_held_for_review_does_not_replace_a_scored_workout() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let start = Utc::now() - Duration::hours(2);
+
+    // Each device holds a steady 130 bpm for 8 minutes, which passes on its own
+    let steady = |device_id: &str, start: DateTime<Utc>| {
+        let heart_rate: Vec<serde_json::Value> = (0..96)
+            .map(|i| json!({ "timestamp": start + Duration::seconds(i * 5), "heart_rate": 130 }))
+            .collect();
+        json!({
+            "device_id": device_id,
+            "timestamp": start + Duration::minutes(8),
+            "heart_rate": heart_rate,
+            "workout_start": start,
+            "workout_end": start + Duration::minutes(8),
+            "workout_uuid": &Uuid::new_v4().to_string()[..8],
+        })
+    };
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, steady("watch", start)).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["review_status"], "accepted");
+    let avatar_before = avatar_stats(&test_app.db_pool, test_user.user_id).await;
+
+    // Merged, the two make 11 unchanged minutes, which is flagged
+    let mut phone = steady("phone", start + Duration::minutes(3));
+    phone["overlap_policy"] = json!("merge");
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, phone).await;
+    assert_eq!(job["status"], "rejected", "{}", job);
+    let issue = &job["result"]["validation_issues"][0];
+    assert_eq!(issue["code"], "overlapping_workout");
+    assert!(issue["message"].as_str().unwrap().contains("unchanged"), "{}", issue);
+
+    let workouts = stored_workouts(&test_app.db_pool, test_user.user_id).await;
+    assert_eq!(workouts.len(), 1);
+    assert_eq!(workouts[0].1, "watch");
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, avatar_before);
+}
@@ -0,0 +1,189 @@
+use reqwest::Client;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{create_intermediate_workout_data, wait_for_workout_job};
+
+async fn avatar_stats(pool: &sqlx::PgPool, user_id: Uuid) -> (i32, i32) {
+    let row = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to fetch avatar");
+    (row.get("stamina"), row.get("strength"))
+}
+
+#[tokio::test]
+async fn upload_is_queued_and_processed_in_the_background() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let other_user = create_test_user_and_login(&test_app.address).await;
+    let workout_data = create_intermediate_workout_data();
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", &test_app.address),
+        &test_user.token,
+        Some(workout_data.clone()),
+    ).await;
+    assert_eq!(response.status(), 202);
+
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    assert_eq!(body["data"]["status"], "queued");
+    assert_eq!(body["data"]["workout_uuid"], workout_data["workout_uuid"]);
+    let job_id = body["data"]["job_id"].as_str().expect("Should return a job id").to_string();
+
+    // Jobs are private to their owner
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/upload_jobs/{}", &test_app.address, job_id),
+        &other_user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 404);
+
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, &job_id).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["attempts"], 1);
+    assert_eq!(job["workout_data_id"], job["result"]["sync_id"]);
+    assert!(job["result"]["game_stats"]["stat_changes"]["stamina_change"].is_number());
+
+    let (stamina, strength) = avatar_stats(&test_app.db_pool, test_user.user_id).await;
+    assert_eq!(job["result"]["game_stats"]["stat_changes"]["stamina_change"], stamina);
+    assert_eq!(job["result"]["game_stats"]["stat_changes"]["strength_change"], strength);
+
+    // The stored workout holds the data, so the queued copy is dropped
+    let payload: Option<serde_json::Value> = sqlx::query_scalar("SELECT payload FROM workout_processing_jobs WHERE id = $1")
+        .bind(Uuid::parse_str(&job_id).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch job");
+    assert!(payload.is_none());
+
+    // Re-uploading a processed workout is refused without queueing another job
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", &test_app.address),
+        &test_user.token,
+        Some(workout_data),
+    ).await;
+    assert_eq!(response.status(), 409);
+
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_processing_jobs WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count jobs");
+    assert_eq!(jobs, 1);
+}
+
+#[tokio::test]
+async fn job_abandoned_by_a_dead_worker_is_picked_up_again() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let workout_data = create_intermediate_workout_data();
+
+    // A worker claimed the job and died without finishing it
+    let job_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO workout_processing_jobs (id, user_id, workout_uuid, payload, status, attempts, locked_at)
+        VALUES ($1, $2, $3, $4, 'processing', 1, NOW() - INTERVAL '10 minutes')
+        "#
+    )
+    .bind(job_id)
+    .bind(test_user.user_id)
+    .bind(workout_data["workout_uuid"].as_str().unwrap())
+    .bind(&workout_data)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert job");
+
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, &job_id.to_string()).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["attempts"], 2);
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_data WHERE workout_uuid = $1")
+        .bind(workout_data["workout_uuid"].as_str().unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count workouts");
+    assert_eq!(stored, 1);
+}
+
+#[tokio::test]
+async fn job_that_already_stored_its_workout_is_not_scored_twice() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::POST,
+        &format!("{}/health/upload_health", &test_app.address),
+        &test_user.token,
+        Some(create_intermediate_workout_data()),
+    ).await;
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    let job_id = body["data"]["job_id"].as_str().unwrap().to_string();
+
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, &job_id).await;
+    assert_eq!(job["status"], "completed");
+    let stats_after_processing = avatar_stats(&test_app.db_pool, test_user.user_id).await;
+
+    // Pretend the worker died after committing the workout but before finishing the job
+    sqlx::query(
+        "UPDATE workout_processing_jobs SET status = 'processing', locked_at = NOW() - INTERVAL '10 minutes' WHERE id = $1"
+    )
+    .bind(Uuid::parse_str(&job_id).unwrap())
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to reset job");
+
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, &job_id).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["attempts"], 2);
+    assert_eq!(job["result"]["sync_id"], job["workout_data_id"]);
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, stats_after_processing);
+}
+
+#[tokio::test]
+async fn job_held_by_a_running_worker_is_not_claimed_again() {
+    let test_app = spawn_app().await;
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let workout_data = create_intermediate_workout_data();
+
+    // A worker is still on the job and refreshed its lock a minute ago
+    let job_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO workout_processing_jobs (id, user_id, workout_uuid, payload, status, attempts, locked_at)
+        VALUES ($1, $2, $3, $4, 'processing', 1, NOW() - INTERVAL '1 minute')
+        "#
+    )
+    .bind(job_id)
+    .bind(test_user.user_id)
+    .bind(workout_data["workout_uuid"].as_str().unwrap())
+    .bind(&workout_data)
+    .execute(&test_app.db_pool)
+    .await
+    .expect("Failed to insert job");
+
+    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+
+    let row = sqlx::query("SELECT status, attempts FROM workout_processing_jobs WHERE id = $1")
+        .bind(job_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch job");
+    assert_eq!(row.get::<String, _>("status"), "processing");
+    assert_eq!(row.get::<i32, _>("attempts"), 1);
+}
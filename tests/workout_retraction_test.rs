@@ -0,0 +1,179 @@
+use reqwest::Client;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::admin_helpers::{create_admin_user_and_login, setup_live_game};
+use common::workout_data_helpers::{create_intermediate_workout_data, upload_workout_data_for_user, workout_hours_ago};
+
+async fn retract(client: &Client, address: &str, token: &str, workout_id: &str) -> reqwest::Response {
+    make_authenticated_request(
+        client,
+        reqwest::Method::DELETE,
+        &format!("{}/health/workouts/{}", address, workout_id),
+        token,
+        None,
+    ).await
+}
+
+async fn avatar_stats(pool: &sqlx::PgPool, user_id: Uuid) -> (i32, i32) {
+    let row = sqlx::query("SELECT stamina, strength FROM user_avatars WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .expect("Failed to fetch avatar");
+    (row.get("stamina"), row.get("strength"))
+}
+
+#[tokio::test]
+async fn retracting_a_workout_reverses_its_stat_gains() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let other_user = create_test_user_and_login(&test_app.address).await;
+
+    let first = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, create_intermediate_workout_data())
+        .await
+        .expect("First upload should succeed");
+    let stats_after_first = avatar_stats(&test_app.db_pool, test_user.user_id).await;
+
+    let second = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, workout_hours_ago(create_intermediate_workout_data(), 1))
+        .await
+        .expect("Second upload should succeed");
+    let workout_id = second["result"]["sync_id"].as_str().unwrap().to_string();
+    assert_ne!(avatar_stats(&test_app.db_pool, test_user.user_id).await, stats_after_first);
+
+    // Someone else can't retract it
+    let response = retract(&client, &test_app.address, &other_user.token, &workout_id).await;
+    assert_eq!(response.status(), 404);
+
+    let response = retract(&client, &test_app.address, &test_user.token, &workout_id).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    assert_eq!(body["data"]["stamina_reverted"], second["result"]["game_stats"]["stat_changes"]["stamina_change"]);
+    assert_eq!(body["data"]["strength_reverted"], second["result"]["game_stats"]["stat_changes"]["strength_change"]);
+
+    // Only the second workout's gains are gone
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, stats_after_first);
+
+    let remaining: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM workout_data WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_all(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch workouts");
+    assert_eq!(remaining, vec![Uuid::parse_str(first["result"]["sync_id"].as_str().unwrap()).unwrap()]);
+
+    // Retracting twice doesn't subtract twice
+    let response = retract(&client, &test_app.address, &test_user.token, &workout_id).await;
+    assert_eq!(response.status(), 404);
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, stats_after_first);
+}
+
+#[tokio::test]
+async fn retracting_a_workout_after_decay_stops_at_the_decay_floor() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin = create_admin_user_and_login(&test_app.address).await;
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let upload = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, create_intermediate_workout_data())
+        .await
+        .expect("Upload should succeed");
+    let workout_id = upload["result"]["sync_id"].as_str().unwrap().to_string();
+    let (stamina_gained, strength_gained) = avatar_stats(&test_app.db_pool, test_user.user_id).await;
+    assert!(stamina_gained > 0, "Workout should earn stamina");
+
+    // 100 of each stat earned elsewhere, and nothing trained for a month
+    sqlx::query("UPDATE user_avatars SET stamina = stamina + 100, strength = strength + 100 WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE users SET created_at = NOW() - INTERVAL '30 days' WHERE id = $1")
+        .bind(test_user.user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE workout_data SET workout_start = workout_start - INTERVAL '30 days', created_at = created_at - INTERVAL '30 days' WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .execute(&test_app.db_pool)
+        .await
+        .unwrap();
+
+    // Decay everything above the floor at half of what was earned: the first run warns, the second decays
+    let response = make_authenticated_request(
+        &client, reqwest::Method::PUT, &format!("{}/admin/scoring/decay", &test_app.address), &admin.token,
+        Some(json!({ "enabled": true, "idle_days": 14, "warning_days": 3, "daily_decay_rate": 1.0, "floor_ratio": 0.5 })),
+    ).await;
+    assert_eq!(response.status(), 200);
+    for _ in 0..2 {
+        let response = make_authenticated_request(
+            &client, reqwest::Method::POST, &format!("{}/admin/scoring/decay/run", &test_app.address), &admin.token, None,
+        ).await;
+        assert_eq!(response.status(), 200);
+    }
+    assert_eq!(
+        avatar_stats(&test_app.db_pool, test_user.user_id).await,
+        ((stamina_gained + 101) / 2, (strength_gained + 101) / 2)
+    );
+
+    // Decay already took half the workout's gains, so taking all of them back stops at half of the rest
+    let response = retract(&client, &test_app.address, &test_user.token, &workout_id).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(avatar_stats(&test_app.db_pool, test_user.user_id).await, (50, 50));
+}
+
+#[tokio::test]
+async fn retracting_a_workout_removes_its_live_game_score() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (player, game_id) = setup_live_game(&test_app, "Retraction Season").await;
+
+    let upload = upload_workout_data_for_user(&client, &test_app.address, &player.token, create_intermediate_workout_data())
+        .await
+        .expect("Upload should succeed");
+    let workout_id = upload["result"]["sync_id"].as_str().unwrap().to_string();
+
+    let live_game = sqlx::query("SELECT id, home_score + away_score AS total_score FROM live_games WHERE game_id = $1")
+        .bind(game_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch live game");
+    let live_game_id: Uuid = live_game.get("id");
+    assert!(live_game.get::<i32, _>("total_score") > 0, "Workout should score in the live game");
+
+    let response = retract(&client, &test_app.address, &player.token, &workout_id).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse response");
+    assert_eq!(body["data"]["live_games_corrected"], 1);
+
+    let live_game = sqlx::query("SELECT home_score, away_score, home_power, away_power FROM live_games WHERE id = $1")
+        .bind(live_game_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch live game");
+    assert_eq!(live_game.get::<i32, _>("home_score"), 0);
+    assert_eq!(live_game.get::<i32, _>("away_score"), 0);
+    assert_eq!(live_game.get::<i32, _>("home_power"), 0);
+    assert_eq!(live_game.get::<i32, _>("away_power"), 0);
+
+    let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM live_score_events WHERE live_game_id = $1")
+        .bind(live_game_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count score events");
+    assert_eq!(events, 0);
+
+    let contribution = sqlx::query(
+        "SELECT total_score_contribution, contribution_count FROM live_player_contributions WHERE live_game_id = $1 AND user_id = $2"
+    )
+    .bind(live_game_id)
+    .bind(player.user_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch contribution");
+    assert_eq!(contribution.get::<i32, _>("total_score_contribution"), 0);
+    assert_eq!(contribution.get::<i32, _>("contribution_count"), 0);
+}
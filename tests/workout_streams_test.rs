@@ -0,0 +1,129 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::admin_helpers::create_admin_user_and_login;
+use common::workout_data_helpers::{create_intermediate_workout_data, upload_workout_and_wait, upload_workout_data_for_user};
+
+/// Add one power, cadence, speed, distance and step sample per minute of the workout
+fn with_sensor_streams(mut workout_data: serde_json::Value) -> serde_json::Value {
+    let start: DateTime<Utc> = serde_json::from_value(workout_data["workout_start"].clone()).unwrap();
+    let minutes = 0..15i64;
+
+    workout_data["power"] = minutes.clone()
+        .map(|i| json!({ "timestamp": start + Duration::minutes(i), "watts": 200 + i * 10 }))
+        .collect();
+    workout_data["cadence"] = minutes.clone()
+        .map(|i| json!({ "timestamp": start + Duration::minutes(i), "cadence": if i == 0 { 0 } else { 90 } }))
+        .collect();
+    workout_data["speed"] = minutes.clone()
+        .map(|i| json!({ "timestamp": start + Duration::minutes(i), "meters_per_second": 3.5 }))
+        .collect();
+    workout_data["distance"] = minutes.clone()
+        .map(|i| json!({ "timestamp": start + Duration::minutes(i), "meters": i as f64 * 210.0 }))
+        .collect();
+    workout_data["steps"] = minutes
+        .map(|i| json!({ "timestamp": start + Duration::minutes(i), "steps": 170 }))
+        .collect();
+    workout_data
+}
+
+#[tokio::test]
+async fn sensor_streams_are_stored_and_exposed() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let admin = create_admin_user_and_login(&test_app.address).await;
+
+    let job = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, with_sensor_streams(create_intermediate_workout_data()))
+        .await
+        .expect("Upload with sensor streams should succeed");
+    let workout_id = job["result"]["sync_id"].as_str().unwrap().to_string();
+
+    // History carries the summaries, and the raw streams only when asked for
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/history", &test_app.address),
+        &test_user.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.expect("Failed to parse history");
+    let workout = &body["data"]["workouts"][0];
+    assert_eq!(workout["avg_power"], 270);
+    assert_eq!(workout["max_power"], 340);
+    assert_eq!(workout["avg_cadence"], 90, "Zero cadence samples are ignored in the average");
+    assert_eq!(workout["total_distance_meters"], 2940.0);
+    assert_eq!(workout["total_steps"], 2550);
+    assert!(workout.get("streams").is_none());
+
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/health/history?include_streams=true", &test_app.address),
+        &test_user.token,
+        None,
+    ).await;
+    let body: serde_json::Value = response.json().await.expect("Failed to parse history");
+    let streams = &body["data"]["workouts"][0]["streams"];
+    assert_eq!(streams["power"].as_array().unwrap().len(), 15);
+    assert_eq!(streams["speed"][0]["meters_per_second"], 3.5);
+    assert_eq!(streams["distance"][14]["meters"], 2940.0);
+
+    // Admins see the full streams in the workout detail
+    let response = make_authenticated_request(
+        &client,
+        reqwest::Method::GET,
+        &format!("{}/admin/workouts/{}", &test_app.address, workout_id),
+        &admin.token,
+        None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let detail: serde_json::Value = response.json().await.expect("Failed to parse workout detail");
+    assert_eq!(detail["power"][1]["watts"], 210);
+    assert_eq!(detail["cadence"].as_array().unwrap().len(), 15);
+    assert_eq!(detail["steps"][0]["steps"], 170);
+    assert_eq!(detail["max_power"], 340);
+    assert!(detail["heart_rate"].is_array());
+}
+
+#[tokio::test]
+async fn impossible_sensor_values_are_rejected() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let mut workout_data = with_sensor_streams(create_intermediate_workout_data());
+    workout_data["power"][3]["watts"] = json!(-50);
+    workout_data["speed"][5]["meters_per_second"] = json!(95.0);
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data).await;
+    assert_eq!(job["status"], "rejected");
+
+    let issues = job["result"]["validation_issues"].as_array().unwrap();
+    assert_eq!(issues.len(), 2);
+    assert!(issues.iter().all(|issue| issue["code"] == "stream_out_of_bounds"));
+}
+
+#[tokio::test]
+async fn step_counts_beyond_a_possible_cadence_are_rejected() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Together these would overflow a 32-bit total
+    let mut workout_data = with_sensor_streams(create_intermediate_workout_data());
+    workout_data["steps"][0]["steps"] = json!(i32::MAX);
+    workout_data["steps"][1]["steps"] = json!(i32::MAX);
+
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_data).await;
+    assert_eq!(job["status"], "rejected");
+
+    let issues = job["result"]["validation_issues"].as_array().unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0]["code"], "stream_out_of_bounds");
+    assert!(issues[0]["message"].as_str().unwrap().contains("per minute"), "{}", issues[0]);
+}
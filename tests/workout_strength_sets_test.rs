@@ -0,0 +1,112 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::admin_helpers::setup_live_game;
+use common::workout_data_helpers::{avatar_stats_from_workouts, upload_workout_and_wait, upload_workout_data_for_user};
+
+/// A lifting session of the given length logged without heart rate
+fn strength_session(minutes: i64, exercises: serde_json::Value) -> serde_json::Value {
+    let workout_end = Utc::now() - Duration::minutes(5);
+    json!({
+        "device_id": "strength-log",
+        "timestamp": workout_end,
+        "workout_uuid": format!("strength-{}", Uuid::new_v4()),
+        "workout_start": workout_end - Duration::minutes(minutes),
+        "workout_end": workout_end,
+        "workout_type": "strength",
+        "strength_exercises": exercises,
+    })
+}
+
+#[tokio::test]
+async fn logged_sets_score_strength_from_volume_load_and_count_in_live_games() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let (player, game_id) = setup_live_game(&test_app, "Strength Season").await;
+
+    let exercises = json!([
+        {
+            "name": "Back squat",
+            "sets": [
+                { "reps": 5, "weight_kg": 100.0, "rpe": 8 },
+                { "reps": 5, "weight_kg": 100.0, "rpe": 8 },
+                { "reps": 5, "weight_kg": 100.0, "rpe": 10 }
+            ]
+        },
+        {
+            "name": "Bench press",
+            "sets": [
+                { "reps": 8, "weight_kg": 60.0 },
+                { "reps": 8, "weight_kg": 60.0 }
+            ]
+        }
+    ]);
+    let job = upload_workout_data_for_user(&client, &test_app.address, &player.token, strength_session(45, exercises.clone()))
+        .await
+        .expect("Upload should succeed");
+
+    // 500 kg at RPE 8 is worth 10, at RPE 10 it's worth 20% more, 480 kg without RPE is worth 9.6
+    let stat_changes = &job["result"]["game_stats"]["stat_changes"];
+    assert_eq!(stat_changes["strength_change"], 51, "{}", stat_changes);
+    assert!(job["result"]["game_stats"]["reasoning"].as_array().unwrap().iter()
+        .any(|reason| reason == "Strength from volume load: 2460 kg over 5 sets of 2 exercises"));
+    let stamina = stat_changes["stamina_change"].as_i64().unwrap();
+
+    let total_score: i32 = sqlx::query_scalar("SELECT home_score + away_score FROM live_games WHERE game_id = $1")
+        .bind(game_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch live game");
+    assert_eq!(total_score as i64, stamina + 51, "The sets count towards the live game");
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/history", &test_app.address), &player.token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let history: serde_json::Value = response.json().await.unwrap();
+    let workout = &history["data"]["workouts"][0];
+    assert_eq!(workout["strength_gained"], 51);
+    assert_eq!(workout["strength_volume_load_kg"], 2460.0);
+    assert_eq!(workout["strength_exercises"][0]["name"], "Back squat");
+    assert_eq!(workout["strength_exercises"][1]["sets"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn impossible_sets_are_rejected() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let exercises = json!([
+        { "name": "Deadlift", "sets": [{ "reps": 5, "weight_kg": 180.0, "rpe": 9 }, { "reps": 3, "weight_kg": 900.0 }] }
+    ]);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, strength_session(45, exercises)).await;
+    assert_eq!(job["status"], "rejected");
+    assert_eq!(job["result"]["validation_issues"][0]["code"], "strength_set_out_of_bounds");
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_data WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count workouts");
+    assert_eq!(stored, 0);
+}
+
+#[tokio::test]
+async fn more_sets_than_minutes_are_held_for_review() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let sets: Vec<_> = (0..60).map(|_| json!({ "reps": 5, "weight_kg": 60.0 })).collect();
+    let exercises = json!([{ "name": "Bench press", "sets": sets }]);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, strength_session(1, exercises)).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["review_status"], "pending_review");
+    assert_eq!(job["result"]["validation_flags"][0]["code"], "implausible_strength_volume");
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+}
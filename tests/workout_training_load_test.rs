@@ -0,0 +1,63 @@
+use reqwest::Client;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{create_advanced_workout_data, upload_workout_data_for_user, workout_hours_ago};
+
+#[tokio::test]
+async fn training_load_is_stored_and_compared_acute_to_chronic() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/training_load", &test_app.address), &test_user.token, None,
+    ).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["status"], "no_baseline");
+    assert!(body["data"]["ratio"].is_null());
+
+    // One workout now and one three weeks ago: inside the chronic window, outside the acute one
+    for hours_ago in [0, 21 * 24] {
+        upload_workout_data_for_user(&client, &test_app.address, &test_user.token, workout_hours_ago(create_advanced_workout_data(), hours_ago))
+            .await
+            .expect("Upload should succeed");
+    }
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/history", &test_app.address), &test_user.token, None,
+    ).await;
+    let history: serde_json::Value = response.json().await.unwrap();
+    let workouts = history["data"]["workouts"].as_array().unwrap();
+    assert_eq!(workouts.len(), 2);
+    let load = &workouts[0]["training_load"];
+    let banister_trimp = load["banister_trimp"].as_f64().expect("History should include the Banister TRIMP");
+    assert!(banister_trimp > 0.0);
+    assert!(load["edwards_trimp"].as_f64().unwrap() > banister_trimp, "25 minutes mostly in zones 3 and 4");
+    assert!(load["intensity_factor"].as_f64().unwrap() > 0.5);
+    assert!(load["epoc_estimate"].as_f64().unwrap() > 0.0);
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/training_load", &test_app.address), &test_user.token, None,
+    ).await;
+    let data = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    assert_eq!(data["metric"], "banister");
+    // Both workouts carry the same load, one of them in the last 7 of 28 days
+    let ratio = data["ratio"].as_f64().unwrap();
+    assert!((ratio - 2.0).abs() < 0.01, "Ratio was {}", ratio);
+    assert!((data["acute_load"].as_f64().unwrap() - banister_trimp / 7.0).abs() < 0.01);
+    assert_eq!(data["status"], "high_risk");
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/training_load?metric=edwards", &test_app.address), &test_user.token, None,
+    ).await;
+    let data = response.json::<serde_json::Value>().await.unwrap()["data"].clone();
+    assert_eq!(data["metric"], "edwards");
+    assert!((data["ratio"].as_f64().unwrap() - 2.0).abs() < 0.01);
+
+    let response = make_authenticated_request(
+        &client, reqwest::Method::GET, &format!("{}/health/training_load?metric=tss", &test_app.address), &test_user.token, None,
+    ).await;
+    assert_eq!(response.status(), 400);
+}
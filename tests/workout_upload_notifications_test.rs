@@ -74,10 +74,11 @@ async fn test_workout_upload_sends_redis_notifications() {
         Some(workout_data),
     ).await;
     
-    assert_eq!(upload_response.status(), 200, "Workout upload should succeed");
+    assert_eq!(upload_response.status(), 202, "Workout upload should be queued");
     let upload_result = upload_response.json::<serde_json::Value>().await.unwrap();
     assert!(upload_result["success"].as_bool().unwrap_or(false));
-    println!("✅ Workout uploaded successfully");
+    let job_id = upload_result["data"]["job_id"].as_str().expect("Upload should return a job id").to_string();
+    println!("✅ Workout queued as job {}", job_id);
     
     // Listen for Redis messages
     println!("🔍 Listening for Redis notifications...");
@@ -104,6 +105,8 @@ async fn test_workout_upload_sends_redis_notifications() {
                         assert_eq!(json["user_id"].as_str().unwrap_or(""), user_uuid.to_string());
                         assert_eq!(json["username"].as_str().unwrap_or(""), test_user.username);
                         assert!(json["sync_id"].is_string());
+                        assert_eq!(json["job_id"].as_str().unwrap_or(""), job_id);
+                        assert_eq!(json["job_status"], "completed");
                         assert!(json["stat_changes"]["stamina_change"].is_number());
                         assert!(json["stat_changes"]["strength_change"].is_number());
                         assert!(json["timestamp"].is_string());
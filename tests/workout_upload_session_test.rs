@@ -0,0 +1,314 @@
+use chrono::{DateTime, Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login, make_authenticated_request};
+use common::workout_data_helpers::{upload_workout_data_for_user, wait_for_workout_job};
+
+/// Three hours of samples every two seconds, wandering between 120 and 157 bpm
+fn long_workout_samples(start: DateTime<Utc>) -> Vec<serde_json::Value> {
+    (0..5400)
+        .map(|i| json!({
+            "timestamp": start + Duration::seconds(i * 2),
+            "heart_rate": 120 + ((i * 7) % 23) + ((i / 60) % 15),
+        }))
+        .collect()
+}
+
+fn ndjson(samples: &[serde_json::Value]) -> String {
+    samples.iter().map(|sample| format!("{}\n", sample)).collect()
+}
+
+async fn start_session(client: &Client, address: &str, token: &str, metadata: serde_json::Value) -> reqwest::Response {
+    make_authenticated_request(client, reqwest::Method::POST, &format!("{}/health/sessions", address), token, Some(metadata)).await
+}
+
+async fn send_chunk(client: &Client, address: &str, token: &str, session_id: &str, content_type: &str, body: String) -> reqwest::Response {
+    client
+        .post(format!("{}/health/sessions/{}/samples", address, session_id))
+        .header("Authorization", format!("Bearer {}", token))
+        .header("Content-Type", content_type)
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to send chunk")
+}
+
+async fn finalize(client: &Client, address: &str, token: &str, session_id: &str) -> reqwest::Response {
+    make_authenticated_request(
+        client, reqwest::Method::POST, &format!("{}/health/sessions/{}/finalize", address, session_id), token, None,
+    ).await
+}
+
+#[tokio::test]
+async fn long_workout_is_streamed_in_chunks_and_queued_on_finalize() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let workout_start = Utc::now() - Duration::hours(4);
+    let workout_uuid = format!("ultra-{}", Uuid::new_v4());
+    let response = start_session(&client, &test_app.address, &test_user.token, json!({
+        "device_id": "ultra-watch",
+        "timestamp": workout_start + Duration::hours(3),
+        "workout_uuid": workout_uuid,
+        "calories_burned": 2100,
+    })).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["data"]["session_id"].as_str().unwrap().to_string();
+
+    let samples = long_workout_samples(workout_start);
+    for chunk in samples[..4000].chunks(2000) {
+        let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", ndjson(chunk)).await;
+        assert_eq!(response.status(), 200);
+    }
+
+    // A resent chunk doesn't duplicate samples
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", ndjson(&samples[2000..4000])).await;
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["stored"], 0);
+    assert_eq!(body["data"]["duplicates"], 2000);
+    assert_eq!(body["data"]["sample_count"], 4000);
+
+    // The rest as a JSON batch
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/json",
+        serde_json::to_string(&samples[4000..]).unwrap()).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["sample_count"], 5400);
+
+    let response = finalize(&client, &test_app.address, &test_user.token, &session_id).await;
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let data = &body["data"];
+    assert_eq!(data["sample_count"], 5400);
+    assert!(data["summary"].is_null(), "The samples are analyzed by the job, not on finalize");
+
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, data["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    assert!(job["result"]["game_stats"]["stat_changes"]["stamina_change"].as_i64().unwrap() > 0);
+    let summary = &job["result"]["heart_rate_summary"];
+    assert_eq!(summary["duration_min"], 179);
+    assert!(summary["avg_heart_rate"].as_f64().unwrap() > 120.0);
+    assert_eq!(summary["coverage_percent"], 100.0);
+    assert!(summary["training_load"]["banister_trimp"].as_f64().unwrap() > 0.0);
+
+    let workout_id = Uuid::parse_str(job["result"]["sync_id"].as_str().unwrap()).unwrap();
+    let (sample_count, started_at, avg_heart_rate, max_heart_rate): (i32, Option<DateTime<Utc>>, Option<i32>, Option<i32>) = sqlx::query_as(
+        "SELECT heart_rate_sample_count, workout_start, avg_heart_rate, max_heart_rate FROM workout_data WHERE id = $1"
+    )
+    .bind(workout_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch stored workout");
+    assert_eq!(sample_count, 5400);
+    assert!(avg_heart_rate.unwrap() > 120);
+    assert!(max_heart_rate.unwrap() <= 157);
+    assert_eq!(started_at.unwrap().timestamp(), workout_start.timestamp(), "The workout spans the samples");
+
+    // Staged samples are dropped once the workout is stored
+    let staged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_upload_session_samples WHERE session_id = $1")
+        .bind(Uuid::parse_str(&session_id).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count staged samples");
+    assert_eq!(staged, 0);
+}
+
+#[tokio::test]
+async fn session_upload_merged_with_a_stored_recording_keeps_both_devices_samples() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Half an hour every 10 seconds on each device, heart rate following wall-clock time
+    let start = DateTime::from_timestamp(Utc::now().timestamp() - 2 * 3600, 0).unwrap();
+    let recording = |start: DateTime<Utc>, offset: i64| -> Vec<serde_json::Value> {
+        (0..180)
+            .map(|i| {
+                let timestamp = start + Duration::seconds(i * 10);
+                let heart_rate = 125 + (25.0 * (timestamp.timestamp() as f64 / 300.0).sin()) as i64 + offset;
+                json!({ "timestamp": timestamp, "heart_rate": heart_rate })
+            })
+            .collect()
+    };
+
+    let job = upload_workout_data_for_user(&client, &test_app.address, &test_user.token, json!({
+        "device_id": "watch",
+        "timestamp": start + Duration::minutes(30),
+        "heart_rate": recording(start, 0),
+        "calories_burned": 300,
+        "workout_start": start,
+        "workout_end": start + Duration::minutes(30),
+        "workout_uuid": &Uuid::new_v4().to_string()[..8],
+    })).await.expect("Upload failed");
+    let watch_id = job["result"]["sync_id"].as_str().unwrap().to_string();
+
+    // The chest strap's samples fall between the watch's
+    let strap_start = start + Duration::seconds(305);
+    let response = start_session(&client, &test_app.address, &test_user.token, json!({
+        "device_id": "chest-strap",
+        "timestamp": strap_start + Duration::minutes(30),
+        "workout_uuid": format!("strap-{}", Uuid::new_v4()),
+        "workout_start": strap_start,
+        "workout_end": strap_start + Duration::minutes(30),
+        "overlap_policy": "merge",
+    })).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["data"]["session_id"].as_str().unwrap().to_string();
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson",
+        ndjson(&recording(strap_start, 2))).await;
+    assert_eq!(response.status(), 200);
+
+    let response = finalize(&client, &test_app.address, &test_user.token, &session_id).await;
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, body["data"]["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "completed", "{}", job);
+    assert_eq!(job["result"]["replaced_workout_id"], watch_id.as_str());
+
+    let (workouts, sample_count, stored_samples, workout_start, workout_end): (i64, i32, i64, DateTime<Utc>, DateTime<Utc>) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*) OVER (), wd.heart_rate_sample_count,
+               (SELECT COUNT(*) FROM workout_heart_rate_samples s WHERE s.workout_data_id = wd.id),
+               wd.workout_start, wd.workout_end
+        FROM workout_data wd
+        WHERE wd.user_id = $1
+        "#
+    )
+    .bind(test_user.user_id)
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed to fetch merged workout");
+    assert_eq!(workouts, 1, "The merged recording replaces the stored one");
+    assert_eq!(sample_count, 360, "Samples from both devices are kept");
+    assert_eq!(stored_samples, 360);
+    assert_eq!(workout_start, start);
+    assert_eq!(workout_end, strap_start + Duration::minutes(30));
+}
+
+#[tokio::test]
+async fn upload_sessions_are_bounded_and_validated() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+    let other_user = create_test_user_and_login(&test_app.address).await;
+
+    let workout_start = Utc::now() - Duration::hours(2);
+    let metadata = json!({
+        "device_id": "ultra-watch",
+        "timestamp": workout_start + Duration::hours(1),
+        "workout_uuid": format!("ultra-{}", Uuid::new_v4()),
+    });
+
+    let mut with_samples = metadata.clone();
+    with_samples["heart_rate"] = json!(long_workout_samples(workout_start)[..10]);
+    let response = start_session(&client, &test_app.address, &test_user.token, with_samples).await;
+    assert_eq!(response.status(), 400, "Samples go to the session, not the start request");
+
+    let response = start_session(&client, &test_app.address, &test_user.token, metadata.clone()).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["data"]["session_id"].as_str().unwrap().to_string();
+
+    // Starting again resumes the open session
+    let response = start_session(&client, &test_app.address, &test_user.token, metadata.clone()).await;
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["session_id"], session_id.as_str());
+
+    let response = finalize(&client, &test_app.address, &test_user.token, &session_id).await;
+    assert_eq!(response.status(), 400, "An empty session can't be finalized");
+
+    let samples = long_workout_samples(workout_start);
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "text/csv", ndjson(&samples[..10])).await;
+    assert_eq!(response.status(), 415);
+
+    let broken = format!("{}{{\"timestamp\": \"yesterday\"}}\n", ndjson(&samples[..3]));
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", broken).await;
+    assert_eq!(response.status(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("line 4"), "{}", body);
+
+    let oversized = "\n".repeat(4 * 1024 * 1024 + 1);
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", oversized).await;
+    assert_eq!(response.status(), 413);
+
+    let response = send_chunk(&client, &test_app.address, &other_user.token, &session_id, "application/x-ndjson", ndjson(&samples[..10])).await;
+    assert_eq!(response.status(), 404, "Sessions are private to their user");
+
+    // Nothing of the rejected chunks was staged
+    let staged: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_upload_session_samples WHERE session_id = $1")
+        .bind(Uuid::parse_str(&session_id).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count staged samples");
+    assert_eq!(staged, 0);
+
+    // A session that is almost full only takes what fits
+    sqlx::query("UPDATE workout_upload_sessions SET sample_count = 172799 WHERE id = $1")
+        .bind(Uuid::parse_str(&session_id).unwrap())
+        .execute(&test_app.db_pool)
+        .await
+        .expect("Failed to fill session");
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", ndjson(&samples[..2])).await;
+    assert_eq!(response.status(), 413);
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", ndjson(&samples[..1])).await;
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn failed_session_jobs_keep_their_staged_samples() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    let workout_start = Utc::now() - Duration::hours(4);
+    let workout_uuid = format!("ultra-{}", Uuid::new_v4());
+    let response = start_session(&client, &test_app.address, &test_user.token, json!({
+        "device_id": "ultra-watch",
+        "timestamp": workout_start + Duration::hours(3),
+        "workout_uuid": workout_uuid,
+    })).await;
+    assert_eq!(response.status(), 201);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let session_id = body["data"]["session_id"].as_str().unwrap().to_string();
+    let samples = long_workout_samples(workout_start);
+    let response = send_chunk(&client, &test_app.address, &test_user.token, &session_id, "application/x-ndjson", ndjson(&samples[..600])).await;
+    assert_eq!(response.status(), 200);
+
+    // Another upload of the same workout is stored while the session is still open
+    let earlier = Utc::now() - Duration::hours(30);
+    upload_workout_data_for_user(&client, &test_app.address, &test_user.token, json!({
+        "device_id": "phone",
+        "timestamp": earlier,
+        "workout_uuid": workout_uuid,
+        "workout_start": earlier - Duration::minutes(30),
+        "workout_end": earlier,
+        "calories_burned": 250,
+    }))
+    .await
+    .expect("Upload should succeed");
+
+    let response = finalize(&client, &test_app.address, &test_user.token, &session_id).await;
+    assert_eq!(response.status(), 202);
+    let body: serde_json::Value = response.json().await.unwrap();
+    let job = wait_for_workout_job(&client, &test_app.address, &test_user.token, body["data"]["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "failed", "{}", job);
+
+    // The session and its staged samples are kept for a re-queue, until the session is cleaned up as abandoned
+    let (status, staged): (String, i64) = sqlx::query_as(
+        "SELECT status, (SELECT COUNT(*) FROM workout_upload_session_samples WHERE session_id = $1) \
+         FROM workout_upload_sessions WHERE id = $1"
+    )
+    .bind(Uuid::parse_str(&session_id).unwrap())
+    .fetch_one(&test_app.db_pool)
+    .await
+    .expect("Failed session should be kept");
+    assert_eq!(status, "finalized");
+    assert_eq!(staged, 600);
+}
@@ -0,0 +1,234 @@
+use chrono::{Duration, Utc};
+use reqwest::Client;
+use serde_json::json;
+use sqlx::Row;
+use uuid::Uuid;
+
+mod common;
+use common::utils::{spawn_app, create_test_user_and_login};
+use common::admin_helpers::create_admin_user_and_login;
+use common::workout_data_helpers::{avatar_stats_from_workouts, create_intermediate_workout_data, upload_workout_and_wait};
+
+/// A workout with a heart rate sample every `interval_seconds`, generated by `heart_rate_at`
+fn workout_with_samples(
+    minutes: i64,
+    interval_seconds: i64,
+    heart_rate_at: impl Fn(i64) -> i32,
+) -> serde_json::Value {
+    let start = Utc::now() - Duration::minutes(minutes + 5);
+    let samples: Vec<serde_json::Value> = (0..=(minutes * 60 / interval_seconds))
+        .map(|i| json!({
+            "timestamp": start + Duration::seconds(i * interval_seconds),
+            "heart_rate": heart_rate_at(i)
+        }))
+        .collect();
+
+    json!({
+        "device_id": format!("device-{}", Uuid::new_v4()),
+        "timestamp": start,
+        "heart_rate": samples,
+        "calories_burned": 400,
+        "workout_start": start,
+        "workout_end": start + Duration::minutes(minutes),
+        "workout_uuid": Uuid::new_v4().to_string()
+    })
+}
+
+fn reason_codes(result: &serde_json::Value, key: &str) -> Vec<String> {
+    result[key].as_array()
+        .expect("Should list validation issues")
+        .iter()
+        .map(|issue| issue["code"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn implausible_workouts_are_rejected_with_reason_codes() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Starts tomorrow
+    let mut future = create_intermediate_workout_data();
+    let tomorrow = Utc::now() + Duration::days(1);
+    future["workout_start"] = json!(tomorrow);
+    future["heart_rate"][0]["timestamp"] = json!(tomorrow);
+
+    // Every sample sent twice
+    let mut duplicated = workout_with_samples(20, 5, |i| 120 + (i % 30) as i32);
+    let samples = duplicated["heart_rate"].as_array().unwrap().clone();
+    duplicated["heart_rate"] = json!(samples.iter().flat_map(|s| [s.clone(), s.clone()]).collect::<Vec<_>>());
+
+    // Beyond what a human heart can do
+    let out_of_bounds = workout_with_samples(20, 5, |i| if i % 50 == 0 { 300 } else { 140 + (i % 7) as i32 });
+
+    for (workout, expected_code) in [
+        (future, "future_workout"),
+        (duplicated, "duplicate_timestamps"),
+        (out_of_bounds, "heart_rate_out_of_bounds"),
+    ] {
+        let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout).await;
+        assert_eq!(job["status"], "rejected", "Expected {} to be rejected", expected_code);
+        assert!(reason_codes(&job["result"], "validation_issues").contains(&expected_code.to_string()));
+    }
+
+    // Nothing was stored or scored
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workout_data WHERE user_id = $1")
+        .bind(test_user.user_id)
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to count workouts");
+    assert_eq!(stored, 0);
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+}
+
+#[tokio::test]
+async fn flagged_workout_is_held_for_review_until_admin_approves() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin = create_admin_user_and_login(&test_app.address).await;
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // An hour at exactly 150 bpm
+    let flatline = workout_with_samples(60, 10, |_| 150);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, flatline).await;
+    assert_eq!(job["status"], "completed");
+
+    let result = &job["result"];
+    assert_eq!(result["review_status"], "pending_review");
+    assert!(reason_codes(result, "validation_flags").contains(&"flatline".to_string()));
+    assert_eq!(result["game_stats"]["stat_changes"]["stamina_change"], 0);
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+
+    let workout_id = result["sync_id"].as_str().unwrap().to_string();
+
+    // It shows up in the admin review queue
+    let queue: serde_json::Value = client
+        .get(format!("{}/admin/workouts?review_status=pending_review&user_id={}", &test_app.address, test_user.user_id))
+        .header("Authorization", format!("Bearer {}", admin.token))
+        .send()
+        .await
+        .expect("Failed to fetch review queue")
+        .json()
+        .await
+        .expect("Failed to parse review queue");
+    assert_eq!(queue["total"], 1);
+    assert_eq!(queue["workouts"][0]["id"], workout_id);
+    assert_eq!(queue["workouts"][0]["validation_flags"][0]["code"], "flatline");
+
+    // Approving scores it
+    let response = client
+        .post(format!("{}/admin/workouts/{}/review", &test_app.address, workout_id))
+        .header("Authorization", format!("Bearer {}", admin.token))
+        .json(&json!({ "decision": "approve" }))
+        .send()
+        .await
+        .expect("Failed to approve workout");
+    assert_eq!(response.status(), 200);
+    let approval: serde_json::Value = response.json().await.expect("Failed to parse approval");
+
+    let (stamina, strength) = avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await;
+    assert!(stamina + strength > 0, "Approved workout should be scored");
+    assert_eq!(approval["stamina_change"], stamina);
+    assert_eq!(approval["strength_change"], strength);
+
+    let row = sqlx::query("SELECT review_status, reviewed_by, stamina_gained FROM workout_data WHERE id = $1")
+        .bind(Uuid::parse_str(&workout_id).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch workout");
+    assert_eq!(row.get::<String, _>("review_status"), "accepted");
+    assert_eq!(row.get::<Option<Uuid>, _>("reviewed_by"), Some(admin.user_id));
+    assert_eq!(row.get::<i32, _>("stamina_gained"), stamina);
+
+    // A second review is refused so the workout can't be scored twice
+    let response = client
+        .post(format!("{}/admin/workouts/{}/review", &test_app.address, workout_id))
+        .header("Authorization", format!("Bearer {}", admin.token))
+        .json(&json!({ "decision": "approve" }))
+        .send()
+        .await
+        .expect("Failed to send second review");
+    assert_eq!(response.status(), 404);
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (stamina, strength));
+}
+
+#[tokio::test]
+async fn rejected_review_keeps_workout_unscored() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let admin = create_admin_user_and_login(&test_app.address).await;
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // A perfect one bpm per sample ramp looks generated
+    let synthetic = workout_with_samples(30, 10, |i| 100 + (i % 90) as i32);
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, synthetic).await;
+    assert_eq!(job["status"], "completed");
+    let result = &job["result"];
+    assert_eq!(result["review_status"], "pending_review");
+    assert!(reason_codes(result, "validation_flags").contains(&"synthetic_pattern".to_string()));
+
+    let workout_id = result["sync_id"].as_str().unwrap().to_string();
+
+    let response = client
+        .post(format!("{}/admin/workouts/{}/review", &test_app.address, workout_id))
+        .header("Authorization", format!("Bearer {}", admin.token))
+        .json(&json!({ "decision": "reject" }))
+        .send()
+        .await
+        .expect("Failed to reject workout");
+    assert_eq!(response.status(), 200);
+
+    let review_status: String = sqlx::query_scalar("SELECT review_status FROM workout_data WHERE id = $1")
+        .bind(Uuid::parse_str(&workout_id).unwrap())
+        .fetch_one(&test_app.db_pool)
+        .await
+        .expect("Failed to fetch workout");
+    assert_eq!(review_status, "rejected");
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+}
+
+/// A workout without heart rate data ending `ended_minutes_ago`, scored from its duration and calories alone
+fn workout_without_heart_rate(minutes: i64, calories: i32, ended_minutes_ago: i64) -> serde_json::Value {
+    let start = Utc::now() - Duration::minutes(minutes + ended_minutes_ago);
+    json!({
+        "device_id": format!("device-{}", Uuid::new_v4()),
+        "timestamp": start,
+        "calories_burned": calories,
+        "workout_start": start,
+        "workout_end": start + Duration::minutes(minutes),
+        "workout_uuid": Uuid::new_v4().to_string()
+    })
+}
+
+#[tokio::test]
+async fn workouts_without_heart_rate_need_a_believable_duration_and_calories() {
+    let test_app = spawn_app().await;
+    let client = Client::new();
+    let test_user = create_test_user_and_login(&test_app.address).await;
+
+    // Started a month ago, or burned 5000 kcal in half an hour
+    for (workout, expected_code) in [
+        (workout_without_heart_rate(30 * 24 * 60, 500, 5), "implausible_duration"),
+        (workout_without_heart_rate(30, 5000, 5), "implausible_calories"),
+    ] {
+        let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout).await;
+        assert_eq!(job["status"], "rejected", "Expected {} to be rejected", expected_code);
+        assert!(reason_codes(&job["result"], "validation_issues").contains(&expected_code.to_string()));
+    }
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+
+    // Six hours is possible but held for review
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_without_heart_rate(6 * 60, 1500, 5)).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["review_status"], "pending_review");
+    assert!(reason_codes(&job["result"], "validation_flags").contains(&"implausible_duration".to_string()));
+    assert_eq!(avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await, (0, 0));
+
+    // An ordinary session the day before scores
+    let job = upload_workout_and_wait(&client, &test_app.address, &test_user.token, workout_without_heart_rate(45, 400, 24 * 60)).await;
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["result"]["review_status"], "accepted");
+    let (stamina, strength) = avatar_stats_from_workouts(&test_app.db_pool, test_user.user_id).await;
+    assert!(stamina + strength > 0);
+}